@@ -0,0 +1,103 @@
+// atmosphere.rs
+// 🌅 Tabla de transmitancia precalculada (al estilo de Bruneton) para la dispersión de Rayleigh.
+// Se integra una vez al arrancar la profundidad óptica desde cada altitud y ángulo cenital hasta
+// el techo de la atmósfera; los shaders solo leen la tabla con interpolación bilineal.
+use raylib::math::Vector3;
+
+// Resolución (filas de altitud = columnas de ángulo) con la que se precalcula la tabla
+pub const TRANSMITTANCE_RESOLUTION: u32 = 128;
+// Profundidad óptica vertical desde el suelo (R, G, B) de la atmósfera terrestre. Rayleigh
+// escala con 1/λ⁴: el azul se dispersa unas 6 veces más que el rojo.
+const RAYLEIGH_ZENITH_OPTICAL_DEPTH: Vector3 = Vector3::new(0.046, 0.108, 0.265);
+// Muestras por rayo al integrar la densidad
+const INTEGRATION_STEPS: u32 = 64;
+
+/// Tabla de transmitancia RGB de `resolution × resolution` texeles (3 floats cada uno).
+/// Fila = altitud de 0 (suelo) a `atmosphere_height` (techo); columna = coseno del ángulo
+/// cenital de la visual, de -1 (hacia abajo) a 1 (hacia arriba). `rayleigh_scale` es la altura
+/// de escala de la densidad (exp(-h / H)), en las mismas unidades que `planet_radius`.
+/// Las visuales que chocan con el planeta tienen transmitancia 0.
+pub fn precompute_transmittance_table(planet_radius: f32, atmosphere_height: f32, rayleigh_scale: f32, resolution: u32) -> Vec<f32> {
+    let resolution = resolution.max(2);
+    let top_radius = planet_radius + atmosphere_height;
+    // Coeficiente de extinción al nivel del suelo: profundidad vertical / altura de escala
+    let beta = RAYLEIGH_ZENITH_OPTICAL_DEPTH * (1.0 / rayleigh_scale.max(1e-6));
+    let mut table = Vec::with_capacity((resolution * resolution * 3) as usize);
+    for row in 0..resolution {
+        let altitude = atmosphere_height * row as f32 / (resolution - 1) as f32;
+        let r = planet_radius + altitude;
+        for col in 0..resolution {
+            let cos_angle = -1.0 + 2.0 * col as f32 / (resolution - 1) as f32;
+            let transmittance = if ray_hits_ground(r, cos_angle, planet_radius) {
+                Vector3::zero()
+            } else {
+                let depth = optical_length(r, cos_angle, planet_radius, top_radius, rayleigh_scale);
+                Vector3::new((-beta.x * depth).exp(), (-beta.y * depth).exp(), (-beta.z * depth).exp())
+            };
+            table.extend_from_slice(&[transmittance.x, transmittance.y, transmittance.z]);
+        }
+    }
+    table
+}
+
+// La recta desde el radio `r` con coseno cenital `mu` corta la esfera del planeta
+fn ray_hits_ground(r: f32, mu: f32, planet_radius: f32) -> bool {
+    mu < 0.0 && r * r * (mu * mu - 1.0) + planet_radius * planet_radius >= 0.0
+}
+
+// ∫ exp(-h / H) ds desde el radio `r` hasta el techo de la atmósfera (regla del punto medio)
+fn optical_length(r: f32, mu: f32, planet_radius: f32, top_radius: f32, scale_height: f32) -> f32 {
+    let discriminant = (r * r * (mu * mu - 1.0) + top_radius * top_radius).max(0.0);
+    let distance = (-r * mu + discriminant.sqrt()).max(0.0);
+    let step = distance / INTEGRATION_STEPS as f32;
+    let mut sum = 0.0;
+    for i in 0..INTEGRATION_STEPS {
+        let t = (i as f32 + 0.5) * step;
+        let radius = (r * r + t * t + 2.0 * r * mu * t).sqrt();
+        sum += (-(radius - planet_radius).max(0.0) / scale_height.max(1e-6)).exp();
+    }
+    sum * step
+}
+
+/// Transmitancia RGB desde `altitude` (fracción del alto de la atmósfera: 0 suelo, 1 techo)
+/// mirando con coseno cenital `cos_angle`, interpolada bilinealmente en la tabla.
+/// Con una tabla vacía o de otro tamaño devuelve 1 (atmósfera transparente).
+pub fn sample_transmittance(table: &[f32], resolution: u32, altitude: f32, cos_angle: f32) -> Vector3 {
+    let resolution = resolution as usize;
+    if resolution < 2 || table.len() < resolution * resolution * 3 {
+        return Vector3::one();
+    }
+    let max_index = (resolution - 1) as f32;
+    let fy = altitude.clamp(0.0, 1.0) * max_index;
+    let fx = ((cos_angle.clamp(-1.0, 1.0) + 1.0) * 0.5) * max_index;
+    let (x0, y0) = (fx.floor() as usize, fy.floor() as usize);
+    let (x1, y1) = ((x0 + 1).min(resolution - 1), (y0 + 1).min(resolution - 1));
+    let (tx, ty) = (fx - x0 as f32, fy - y0 as f32);
+    let texel = |x: usize, y: usize| {
+        let i = (y * resolution + x) * 3;
+        Vector3::new(table[i], table[i + 1], table[i + 2])
+    };
+    let top = texel(x0, y0) * (1.0 - tx) + texel(x1, y0) * tx;
+    let bottom = texel(x0, y1) * (1.0 - tx) + texel(x1, y1) * tx;
+    top * (1.0 - ty) + bottom * ty
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zenith_is_clearer_than_the_horizon() {
+        let resolution = 32;
+        let table = precompute_transmittance_table(1.0, 0.1, 0.025, resolution);
+        let zenith = sample_transmittance(&table, resolution, 0.0, 1.0);
+        let horizon = sample_transmittance(&table, resolution, 0.0, 0.0);
+        for (up, side) in [(zenith.x, horizon.x), (zenith.y, horizon.y), (zenith.z, horizon.z)] {
+            assert!(up > side && side > 0.0, "cenit {} horizonte {}", up, side);
+        }
+        // Rayleigh: en el horizonte se pierde más azul que rojo
+        assert!(horizon.z < horizon.x);
+        // Hacia el suelo no llega nada
+        assert_eq!(sample_transmittance(&table, resolution, 0.0, -1.0), Vector3::zero());
+    }
+}
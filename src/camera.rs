@@ -2,8 +2,48 @@
 #![allow(dead_code)]
 
 use raylib::prelude::*;
+use crate::config::load_toml;
 use crate::matrix::create_view_matrix;
+use serde::Deserialize;
 use std::f32::consts::PI;
+use std::io;
+
+/// Near/far clip distances shared by the perspective and orthographic projections. Every pass
+/// that turns stored depth back into distance must use the same pair the view was drawn with.
+#[derive(Clone, Copy, PartialEq, Debug, Deserialize)]
+#[serde(default)]
+pub struct ClipPlanes {
+    pub near: f32,
+    pub far: f32,
+}
+
+impl Default for ClipPlanes {
+    fn default() -> Self {
+        ClipPlanes { near: crate::NEAR_PLANE, far: crate::FAR_PLANE }
+    }
+}
+
+impl ClipPlanes {
+    /// Reads `near` and `far` from a TOML file; missing keys keep their default.
+    /// Requires 0 < near < far.
+    pub fn load(path: &str) -> io::Result<Self> {
+        let planes: ClipPlanes = load_toml(path)?;
+        if !(planes.near > 0.0 && planes.near < planes.far) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("need 0 < near < far, found near {} far {}", planes.near, planes.far)));
+        }
+        Ok(planes)
+    }
+}
+
+/// How the camera reacts when it gets close to a body
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CameraMode {
+    /// Bounced away by the collision sphere around each body
+    Free,
+    /// Low-altitude flight: the camera may skim the surface but is kept a minimum altitude
+    /// above the body's sphere
+    TerrainFollowMode,
+}
 
 pub struct Camera {
     // Camera position/orientation
@@ -20,6 +60,8 @@ pub struct Camera {
     pub rotation_speed: f32,
     pub zoom_speed: f32,
     pub pan_speed: f32,
+
+    pub mode: CameraMode,
 }
 
 impl Camera {
@@ -45,6 +87,7 @@ impl Camera {
             rotation_speed: 0.05,
             zoom_speed: 0.5,
             pan_speed: 0.1,
+            mode: CameraMode::Free,
         }
     }
 
@@ -62,6 +105,20 @@ impl Camera {
         self.eye.z = self.target.z + self.distance * self.pitch.cos() * self.yaw.sin();
     }
 
+    /// Normalized direction of the ray leaving the eye through pixel (sx, sy) of a perspective
+    /// view drawn in `viewport` (x, y, width, height) with vertical field of view `fov`
+    pub fn ray_through_pixel(&self, sx: i32, sy: i32, viewport: (i32, i32, i32, i32), fov: f32) -> Vector3 {
+        let (vx, vy, vw, vh) = viewport;
+        let aspect = vw as f32 / vh as f32;
+        let tan_half = (fov * 0.5).tan();
+        let ndc_x = (sx - vx) as f32 / vw as f32 * 2.0 - 1.0;
+        let ndc_y = 1.0 - (sy - vy) as f32 / vh as f32 * 2.0;
+        let forward = (self.target - self.eye).normalized();
+        let right = forward.cross(self.up).normalized();
+        let up = right.cross(forward);
+        (forward + right * (ndc_x * tan_half * aspect) + up * (ndc_y * tan_half)).normalized()
+    }
+
     /// Get the view matrix for this camera
     pub fn get_view_matrix(&self) -> Matrix {
         create_view_matrix(self.eye, self.target, self.up)
@@ -156,4 +213,22 @@ impl Camera {
             self.update_eye_position();
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clip_planes_load_from_toml() {
+        let planes = ClipPlanes::load(crate::PROJECTION_CONFIG_PATH).unwrap();
+        assert_eq!(planes, ClipPlanes { near: 0.5, far: 600.0 });
+
+        let path = std::env::temp_dir().join("proyecto3_projection_bad.toml");
+        let path = path.to_string_lossy().into_owned();
+        std::fs::write(&path, "near = 10.0\nfar = 5.0\n").unwrap();
+        let error = ClipPlanes::load(&path).err().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,251 @@
+// catalog.rs
+// 📖 Catálogo de cuerpos celestes en JSON (`assets/bodies.json`): añadir planetas, lunas o
+// asteroides es cuestión de datos. Cada entrada es un `BodyEntry`; los satélites indican
+// `"parent": "Earth"` y se resuelven a índices al cargar.
+use crate::{body_position, update_orbit_centers, CelestialBody, OrbitTrail};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use raylib::prelude::{Color, Vector3};
+use serde::Deserialize;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+
+#[derive(Deserialize)]
+struct TrailEntry {
+    length: usize,
+    color: [u8; 3],
+}
+
+#[derive(Deserialize)]
+struct BodyEntry {
+    name: String,
+    // Shader con el que se pinta; por defecto el del nombre
+    #[serde(default)]
+    shader_name: Option<String>,
+    // Cuerpo alrededor del que orbita (el Sol si no se indica)
+    #[serde(default)]
+    parent: Option<String>,
+    scale: f32,
+    orbit_radius: f32,
+    orbit_speed: f32,
+    rotation_speed: f32,
+    color: [u8; 3],
+    #[serde(default)]
+    trail: Option<TrailEntry>,
+    #[serde(default)]
+    atmosphere_height: f32,
+    #[serde(default)]
+    atmosphere_density: f32,
+    #[serde(default)]
+    inclination_deg: f32,
+    #[serde(default)]
+    ascending_node_deg: f32,
+    // Masa que curva la luz (unidades de escena con G = c = 1); 0 = sin lente gravitatoria
+    #[serde(default)]
+    lensing_mass: f32,
+    // Masa en masas terrestres, para el baricentro; 0 = no cuenta
+    #[serde(default)]
+    mass: f32,
+    // Casilla de `assets/planet_atlas.png` que se mezcla con su shader, si tiene
+    #[serde(default)]
+    atlas_tile: Option<u32>,
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Lee el catálogo de `path`. Los padres tienen que aparecer antes que sus satélites, así la
+/// posición de un cuerpo siempre se puede calcular a partir de las ya conocidas.
+pub fn load_body_catalog(path: &str) -> io::Result<Vec<CelestialBody>> {
+    let text = fs::read_to_string(path)?;
+    let entries: Vec<BodyEntry> = serde_json::from_str(&text).map_err(|err| invalid_data(format!("{}: {}", path, err)))?;
+    let mut bodies: Vec<CelestialBody> = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let parent = match &entry.parent {
+            Some(parent_name) => Some(
+                bodies
+                    .iter()
+                    .position(|body| &body.name == parent_name)
+                    .ok_or_else(|| invalid_data(format!("{}: parent '{}' of '{}' must be listed before it", path, parent_name, entry.name)))?,
+            ),
+            None => None,
+        };
+        bodies.push(body_from_entry(entry, parent));
+    }
+    Ok(bodies)
+}
+
+fn body_from_entry(entry: BodyEntry, parent: Option<usize>) -> CelestialBody {
+    let [r, g, b] = entry.color;
+    CelestialBody {
+        shader_name: entry.shader_name.unwrap_or_else(|| entry.name.clone()),
+        name: entry.name,
+        translation: Vector3::zero(),
+        scale: entry.scale,
+        rotation: Vector3::zero(),
+        orbit_radius: entry.orbit_radius,
+        orbit_speed: entry.orbit_speed,
+        rotation_speed: entry.rotation_speed,
+        color: Color::new(r, g, b, 255),
+        trail: entry.trail.map(|trail| {
+            let [r, g, b] = trail.color;
+            OrbitTrail::new(trail.length, Color::new(r, g, b, 255))
+        }),
+        atmosphere_height: entry.atmosphere_height,
+        atmosphere_density: entry.atmosphere_density,
+        inclination: entry.inclination_deg.to_radians(),
+        ascending_node: entry.ascending_node_deg.to_radians(),
+        node_precession_rate: 0.0,
+        lensing_mass: entry.lensing_mass,
+        mass: entry.mass,
+        atlas_tile: entry.atlas_tile,
+        parent,
+        orbit_center: Vector3::zero(),
+    }
+}
+
+/// Catálogo mínimo por si falta `assets/bodies.json`: el Sol y la Tierra.
+pub fn fallback_body_catalog() -> Vec<CelestialBody> {
+    let entry = |name: &str, scale: f32, orbit_radius: f32, orbit_speed: f32, color: [u8; 3]| BodyEntry {
+        name: name.to_string(),
+        shader_name: None,
+        parent: None,
+        scale,
+        orbit_radius,
+        orbit_speed,
+        rotation_speed: 1.0,
+        color,
+        trail: None,
+        atmosphere_height: 0.0,
+        atmosphere_density: 0.0,
+        inclination_deg: 0.0,
+        ascending_node_deg: 0.0,
+        lensing_mass: 0.0,
+        mass: 0.0,
+        atlas_tile: None,
+    };
+    let mut sun = entry("Sun", 15.0, 0.0, 0.0, [255, 255, 0]);
+    sun.mass = 333000.0;
+    let mut earth = entry("Earth", 3.0, 25.0, 0.5, [0, 100, 200]);
+    earth.atmosphere_height = 0.02;
+    earth.atmosphere_density = 1.8;
+    earth.mass = 1.0;
+    vec![
+        body_from_entry(sun, None),
+        body_from_entry(earth, None),
+    ]
+}
+
+// Paso (s simulados) de la diferencia central con la que se estima la velocidad al exportar
+const EXPORT_VELOCITY_STEP: f32 = 0.01;
+
+/// Avanza el modelo kepleriano de `time_start` a `time_end` en pasos de `dt` y guarda la
+/// posición y velocidad de cada cuerpo en cada paso como CSV (`time,body_name,x,y,z,vx,vy,vz`).
+/// Si `path` acaba en `.gz` se comprime con gzip. La velocidad es la diferencia central de la
+/// posición, así que las lunas la llevan ya sumada a la de su planeta.
+pub fn export_orbital_data(bodies: &[CelestialBody], time_start: f32, time_end: f32, dt: f32, path: &str) -> io::Result<()> {
+    if !(dt > 0.0 && time_end >= time_start) {
+        return Err(invalid_data(format!("need dt > 0 and time_end >= time_start, found dt {} from {} to {}", dt, time_start, time_end)));
+    }
+    let file = BufWriter::new(File::create(path)?);
+    if path.ends_with(".gz") {
+        let mut encoder = GzEncoder::new(file, Compression::default());
+        write_orbital_csv(&mut encoder, bodies, time_start, time_end, dt)?;
+        encoder.finish()?.flush()
+    } else {
+        let mut file = file;
+        write_orbital_csv(&mut file, bodies, time_start, time_end, dt)?;
+        file.flush()
+    }
+}
+
+fn write_orbital_csv(out: &mut impl Write, bodies: &[CelestialBody], time_start: f32, time_end: f32, dt: f32) -> io::Result<()> {
+    // Copia propia: los centros de las órbitas de los satélites cambian con el tiempo
+    let mut bodies = bodies.to_vec();
+    let names: Vec<String> = bodies.iter().map(|body| body.name.clone()).collect();
+    let mut positions_at = |time: f32| -> Vec<Vector3> {
+        update_orbit_centers(&mut bodies, time);
+        bodies.iter().map(|body| body_position(body, time)).collect()
+    };
+
+    writeln!(out, "time,body_name,x,y,z,vx,vy,vz")?;
+    // Cada instante desde el inicio (sin acumular `dt`, que arrastraría error de redondeo)
+    let steps = ((time_end - time_start) / dt).floor() as u32;
+    for step in 0..=steps {
+        let time = time_start + step as f32 * dt;
+        let positions = positions_at(time);
+        let before = positions_at(time - EXPORT_VELOCITY_STEP);
+        let after = positions_at(time + EXPORT_VELOCITY_STEP);
+        for (i, name) in names.iter().enumerate() {
+            let (p, v) = (positions[i], (after[i] - before[i]) / (2.0 * EXPORT_VELOCITY_STEP));
+            writeln!(out, "{},{},{},{},{},{},{},{}", time, name, p.x, p.y, p.z, v.x, v.y, v.z)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catalog_with_five_bodies_resolves_parents() {
+        let json = r#"[
+            {"name": "Sun", "scale": 15.0, "orbit_radius": 0.0, "orbit_speed": 0.0, "rotation_speed": 0.5, "color": [255, 255, 0]},
+            {"name": "Earth", "scale": 3.0, "orbit_radius": 25.0, "orbit_speed": 0.5, "rotation_speed": 1.5, "color": [0, 100, 200]},
+            {"name": "Moon", "parent": "Earth", "scale": 0.8, "orbit_radius": 5.0, "orbit_speed": 2.0, "rotation_speed": 0.5, "color": [200, 200, 200]},
+            {"name": "Mars", "scale": 2.5, "orbit_radius": 35.0, "orbit_speed": 0.4, "rotation_speed": 1.4, "color": [200, 80, 40]},
+            {"name": "Phobos", "parent": "Mars", "shader_name": "Moon", "scale": 0.3, "orbit_radius": 3.0, "orbit_speed": 3.0, "rotation_speed": 1.0, "color": [150, 130, 110]}
+        ]"#;
+        let path = std::env::temp_dir().join("proyecto3_catalog_five.json");
+        let path = path.to_string_lossy().into_owned();
+        fs::write(&path, json).unwrap();
+        let bodies = load_body_catalog(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(bodies.len(), 5);
+        let parents: Vec<Option<usize>> = bodies.iter().map(|body| body.parent).collect();
+        assert_eq!(parents, vec![None, None, Some(1), None, Some(3)]);
+        assert_eq!(bodies[4].shader_name, "Moon");
+        assert_eq!(bodies[2].shader_name, "Moon");
+    }
+
+    #[test]
+    fn satellite_listed_before_its_parent_is_rejected() {
+        let json = r#"[{"name": "Moon", "parent": "Earth", "scale": 0.8, "orbit_radius": 5.0, "orbit_speed": 2.0, "rotation_speed": 0.5, "color": [200, 200, 200]}]"#;
+        let path = std::env::temp_dir().join("proyecto3_catalog_orphan.json");
+        let path = path.to_string_lossy().into_owned();
+        fs::write(&path, json).unwrap();
+        let error = load_body_catalog(&path).err().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn exported_earth_rows_stay_on_the_orbit_radius() {
+        use std::io::Read;
+
+        let bodies = load_body_catalog("./assets/bodies.json").unwrap();
+        let earth_radius = bodies.iter().find(|body| body.name == "Earth").unwrap().orbit_radius;
+        let path = std::env::temp_dir().join("proyecto3_orbits.csv.gz");
+        let path = path.to_string_lossy().into_owned();
+        export_orbital_data(&bodies, 0.0, 100.0, 0.5, &path).unwrap();
+        let mut csv = String::new();
+        flate2::read::GzDecoder::new(File::open(&path).unwrap()).read_to_string(&mut csv).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("time,body_name,x,y,z,vx,vy,vz"));
+        let earth_rows: Vec<Vec<f32>> = lines
+            .map(|line| line.split(',').collect::<Vec<_>>())
+            .filter(|fields| fields[1] == "Earth")
+            .map(|fields| fields[2..5].iter().map(|field| field.parse().unwrap()).collect())
+            .collect();
+        assert_eq!(earth_rows.len(), 201);
+        for row in &earth_rows {
+            let r_squared = row[0] * row[0] + row[1] * row[1] + row[2] * row[2];
+            assert!((r_squared / (earth_radius * earth_radius) - 1.0).abs() < 1e-4, "r² = {} en vez de {}", r_squared, earth_radius * earth_radius);
+        }
+    }
+}
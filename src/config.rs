@@ -0,0 +1,13 @@
+// config.rs
+// ⚙️ Lectura de los archivos de configuración TOML (`projection.toml`, `docking.toml`,
+// `postprocess.toml`) sobre structs con serde, igual que el catálogo en JSON
+use serde::de::DeserializeOwned;
+use std::fs;
+use std::io;
+
+/// Lee `path` como TOML en `T`. Si el archivo no es TOML válido o no encaja con `T`, el error es
+/// `InvalidData` con la ruta y lo que falló.
+pub fn load_toml<T: DeserializeOwned>(path: &str) -> io::Result<T> {
+    let text = fs::read_to_string(path)?;
+    toml::from_str(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, err)))
+}
@@ -0,0 +1,229 @@
+// docking.rs
+// 🛰️ Estación espacial en órbita baja de la Tierra y acoplamiento de la nave: al acercarse a menos
+// de DOCKING_RANGE la nave saca tres puntales articulados (IK de dos huesos) hasta el casco de
+// la estación. Los puntos de anclaje se leen de `docking.toml`.
+use crate::config::load_toml;
+use crate::matrix::{create_model_matrix, create_model_matrix_with_rotation, multiply_matrix_vector4, new_matrix3};
+use crate::mesh::{generate_box, generate_cube_sphere, generate_cylinder_mesh};
+use crate::vertex::Vertex;
+use crate::{ease_in_out, lerp_vec3};
+use raylib::prelude::*;
+use serde::Deserialize;
+use std::io;
+
+pub const DOCKING_CONFIG_PATH: &str = "docking.toml";
+// Distancia (centro de la estación a la nave) a la que empiezan a salir los puntales
+pub const DOCKING_RANGE: f32 = 5.0;
+// Segundos que tardan los puntales en desplegarse del todo
+pub const DOCKING_DURATION: f32 = 2.0;
+
+// Órbita de la estación alrededor de la Tierra (unidades de mundo, rad / s simulado) y radio
+// del módulo central, que es la esfera contra la que apoyan los puntales
+const STATION_ORBIT_RADIUS: f32 = 4.0;
+const STATION_ORBIT_SPEED: f32 = 0.15;
+const STATION_HUB_RADIUS: f32 = 0.8;
+// Sección de los puntales
+const STRUT_HALF_WIDTH: f32 = 0.035;
+
+/// IK analítica de dos huesos por el teorema del coseno. Devuelve (raíz, codo, extremo). El codo
+/// se dobla hacia el +Y del mundo (o +X si el brazo es vertical). Si el objetivo queda fuera de
+/// alcance (`distance > upper_length + lower_length`) el brazo se estira del todo hacia él; si
+/// está demasiado cerca (`distance < |upper - lower|`), se pliega del todo.
+pub fn ik_2joint(root: Vector3, target: Vector3, upper_length: f32, lower_length: f32) -> (Vector3, Vector3, Vector3) {
+    let to_target = target - root;
+    let distance = to_target.length();
+    let dir = if distance > 1e-6 { to_target / distance } else { Vector3::new(0.0, -1.0, 0.0) };
+    let reach = upper_length + lower_length;
+    if distance >= reach {
+        return (root, root + dir * upper_length, root + dir * reach);
+    }
+    // Con un hueso de largo nulo no hay codo que doblar (y el teorema del coseno dividiría por
+    // cero): queda un brazo recto con lo que mida el otro
+    if upper_length <= 1e-6 || lower_length <= 1e-6 {
+        return (root, root + dir * upper_length.max(0.0).min(distance), root + dir * reach.max(0.0).min(distance));
+    }
+
+    // Dirección de doblado: la parte de la referencia perpendicular al brazo
+    let reference = if dir.y.abs() > 0.99 { Vector3::new(1.0, 0.0, 0.0) } else { Vector3::new(0.0, 1.0, 0.0) };
+    let bend = (reference - dir * reference.dot(dir)).normalized();
+    let distance = distance.max((upper_length - lower_length).abs()).max(1e-4);
+    // Ángulo en la raíz entre el brazo y el hueso superior
+    let cos_root = ((upper_length * upper_length + distance * distance - lower_length * lower_length) / (2.0 * upper_length * distance)).clamp(-1.0, 1.0);
+    let sin_root = (1.0 - cos_root * cos_root).sqrt();
+    let elbow = root + (dir * cos_root + bend * sin_root) * upper_length;
+    (root, elbow, root + dir * distance)
+}
+
+// Puntales de acoplamiento: anclajes en espacio del modelo de la nave (+X al frente) y largo
+// de cada hueso
+pub struct DockingStruts {
+    pub attachments: Vec<Vector3>,
+    pub upper_length: f32,
+    pub lower_length: f32,
+}
+
+impl Default for DockingStruts {
+    fn default() -> Self {
+        DockingStruts {
+            attachments: vec![Vector3::new(0.5, -0.25, 0.0), Vector3::new(-0.4, -0.25, 0.35), Vector3::new(-0.4, -0.25, -0.35)],
+            upper_length: 0.9,
+            lower_length: 0.9,
+        }
+    }
+}
+
+// Contenido de `docking.toml`; lo que falta se queda como en `DockingStruts::default`
+#[derive(Deserialize)]
+struct DockingConfig {
+    #[serde(default)]
+    struts: Vec<[f32; 3]>,
+    upper_length: Option<f32>,
+    lower_length: Option<f32>,
+}
+
+impl DockingStruts {
+    /// Lee `struts = [[x, y, z], ...]` (si hay alguno sustituyen a los de fábrica),
+    /// `upper_length` y `lower_length`.
+    pub fn load(path: &str) -> io::Result<Self> {
+        let config: DockingConfig = load_toml(path)?;
+        let mut struts = DockingStruts::default();
+        if !config.struts.is_empty() {
+            struts.attachments = config.struts.iter().map(|&[x, y, z]| Vector3::new(x, y, z)).collect();
+        }
+        struts.upper_length = config.upper_length.unwrap_or(struts.upper_length);
+        struts.lower_length = config.lower_length.unwrap_or(struts.lower_length);
+        if !(struts.upper_length > 0.0 && struts.lower_length > 0.0) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("strut lengths must be positive, found {} and {}", struts.upper_length, struts.lower_length)));
+        }
+        Ok(struts)
+    }
+}
+
+/// Centro de la estación en `time`: órbita circular ecuatorial alrededor de `earth_position`.
+pub fn station_position(earth_position: Vector3, time: f32) -> Vector3 {
+    let angle = time * STATION_ORBIT_SPEED;
+    earth_position + Vector3::new(angle.cos(), 0.0, angle.sin()) * STATION_ORBIT_RADIUS
+}
+
+/// La estación gira sobre su eje Y una vez por órbita: siempre muestra la misma cara a la Tierra.
+pub fn station_model_matrix(position: Vector3, time: f32) -> Matrix {
+    create_model_matrix(position, 1.0, Vector3::new(0.0, -time * STATION_ORBIT_SPEED, 0.0))
+}
+
+/// Malla de la estación: módulo central esférico, viga a lo largo de X y dos paneles solares.
+pub fn generate_station_mesh() -> Vec<Vertex> {
+    let hull = Vector3::new(0.75, 0.75, 0.78);
+    let panel = Vector3::new(0.15, 0.2, 0.45);
+    let mut mesh = generate_cube_sphere(6);
+    for vertex in mesh.iter_mut() {
+        vertex.position *= STATION_HUB_RADIUS;
+        vertex.color = hull;
+    }
+    // Viga: el cilindro va por +Y, se tumba sobre X
+    for mut vertex in generate_cylinder_mesh(0.12, 4.0, 10) {
+        vertex.position = Vector3::new(vertex.position.y - 2.0, -vertex.position.x, vertex.position.z);
+        vertex.normal = Vector3::new(vertex.normal.y, -vertex.normal.x, vertex.normal.z);
+        vertex.color = hull;
+        mesh.push(vertex);
+    }
+    for side in [-1.0_f32, 1.0] {
+        let (inner, outer) = (side * 1.1, side * 2.0);
+        mesh.extend(generate_box(Vector3::new(inner.min(outer), -0.02, -0.6), Vector3::new(inner.max(outer), 0.02, 0.6), panel));
+    }
+    mesh
+}
+
+/// Tramo de puntal de largo 1 a lo largo de +Y (se estira con `strut_segment_matrix`).
+pub fn generate_strut_segment() -> Vec<Vertex> {
+    generate_box(Vector3::new(-STRUT_HALF_WIDTH, 0.0, -STRUT_HALF_WIDTH), Vector3::new(STRUT_HALF_WIDTH, 1.0, STRUT_HALF_WIDTH), Vector3::new(0.6, 0.6, 0.62))
+}
+
+/// Matriz que lleva el tramo de `generate_strut_segment` de `from` a `to`.
+pub fn strut_segment_matrix(from: Vector3, to: Vector3) -> Matrix {
+    let along = to - from;
+    let dir = along.normalized();
+    let side = if dir.y.abs() > 0.99 { Vector3::new(1.0, 0.0, 0.0) } else { Vector3::new(0.0, 1.0, 0.0) };
+    let right = dir.cross(side).normalized();
+    let third = right.cross(dir);
+    // Columnas: X -> right, Y -> el tramo entero (con su largo), Z -> third
+    let rotation = new_matrix3(
+        right.x, along.x, third.x,
+        right.y, along.y, third.y,
+        right.z, along.z, third.z,
+    );
+    create_model_matrix_with_rotation(from, 1.0, rotation)
+}
+
+// Progreso del despliegue de los puntales: 0 recogidos, 1 apoyados en la estación
+#[derive(Default)]
+pub struct DockingAnimation {
+    pub progress: f32,
+}
+
+impl DockingAnimation {
+    /// Despliega los puntales mientras la nave está a menos de DOCKING_RANGE y los recoge al
+    /// alejarse, en DOCKING_DURATION segundos en cada sentido.
+    pub fn update(&mut self, in_range: bool, dt: f32) {
+        let step = dt / DOCKING_DURATION;
+        self.progress = if in_range { (self.progress + step).min(1.0) } else { (self.progress - step).max(0.0) };
+    }
+
+    /// (raíz, codo, extremo) de cada puntal en mundo; vacío con los puntales recogidos. El extremo
+    /// va de su anclaje al punto del casco de la estación más cercano.
+    pub fn strut_joints(&self, struts: &DockingStruts, ship_matrix: &Matrix, station_center: Vector3) -> Vec<(Vector3, Vector3, Vector3)> {
+        if self.progress <= 0.0 {
+            return Vec::new();
+        }
+        let t = ease_in_out(self.progress);
+        struts
+            .attachments
+            .iter()
+            .map(|attachment| {
+                let root = multiply_matrix_vector4(ship_matrix, &Vector4::new(attachment.x, attachment.y, attachment.z, 1.0));
+                let root = Vector3::new(root.x, root.y, root.z);
+                let contact = station_center + (root - station_center).normalized() * STATION_HUB_RADIUS;
+                ik_2joint(root, lerp_vec3(root, contact, t), struts.upper_length, struts.lower_length)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ik_2joint_reaches_a_reachable_target() {
+        let (root, target) = (Vector3::new(1.0, 2.0, 3.0), Vector3::new(2.0, 1.5, 3.4));
+        let (start, elbow, end) = ik_2joint(root, target, 0.9, 0.9);
+        assert_eq!(start, root);
+        assert!((end - target).length() < 1e-4);
+        assert!(((elbow - root).length() - 0.9).abs() < 1e-4);
+        assert!(((end - elbow).length() - 0.9).abs() < 1e-4);
+    }
+
+    #[test]
+    fn ik_2joint_with_a_zero_length_bone_is_finite() {
+        let target = Vector3::new(0.5, -0.5, 0.0);
+        for (upper, lower) in [(0.0, 0.9), (0.9, 0.0), (0.0, 0.0)] {
+            let (_, elbow, end) = ik_2joint(Vector3::zero(), target, upper, lower);
+            for v in [elbow, end] {
+                assert!(v.x.is_finite() && v.y.is_finite() && v.z.is_finite());
+            }
+        }
+    }
+
+    #[test]
+    fn docking_config_is_valid_toml() {
+        let struts = DockingStruts::load(DOCKING_CONFIG_PATH).unwrap();
+        assert_eq!(struts.attachments, DockingStruts::default().attachments);
+        assert_eq!((struts.upper_length, struts.lower_length), (0.9, 0.9));
+
+        let path = std::env::temp_dir().join("proyecto3_docking_zero.toml");
+        let path = path.to_string_lossy().into_owned();
+        std::fs::write(&path, "struts = [[0.0, 1.0, 2.0]]\nupper_length = 0.0\n").unwrap();
+        let error = DockingStruts::load(&path).err().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
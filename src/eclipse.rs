@@ -0,0 +1,114 @@
+// eclipse.rs
+// 🌑 Conos de sombra de un cuerpo iluminado por el Sol. La umbra (sombra total) se cierra detrás
+// del cuerpo en un vértice; la penumbra (sombra parcial) se abre desde un vértice entre el Sol y
+// el cuerpo. Con ellos se detectan los eclipses y se dibujan los conos.
+use raylib::math::Vector3;
+
+// Solo se dibujan los conos si la cámara está a menos de esto del volumen de sombra
+pub const SHADOW_CONE_VISIBLE_DISTANCE: f32 = 1000.0;
+
+/// Distancia del centro del cuerpo al vértice de su umbra, detrás de él, para un Sol de radio
+/// `sun_radius` a `distance` de centro a centro: L = d · r / (R - r). Si el cuerpo no es menor
+/// que el Sol la umbra no se cierra: infinito.
+pub fn umbra_apex_distance(sun_radius: f32, body_radius: f32, distance: f32) -> f32 {
+    if body_radius >= sun_radius {
+        return f32::INFINITY;
+    }
+    distance * body_radius / (sun_radius - body_radius)
+}
+
+/// Distancia del centro del cuerpo al vértice de su penumbra, hacia el Sol: d · r / (R + r).
+pub fn penumbra_apex_distance(sun_radius: f32, body_radius: f32, distance: f32) -> f32 {
+    distance * body_radius / (sun_radius + body_radius)
+}
+
+pub struct ShadowCone {
+    pub apex: Vector3,
+    // Unitario, desde el vértice hacia donde el cono se ensancha
+    pub axis: Vector3,
+    pub half_angle: f32,
+    // Tramo del eje (distancias desde el vértice) que está en sombra
+    pub start: f32,
+    pub end: f32,
+}
+
+impl ShadowCone {
+    /// Umbra de un cuerpo (`body_center`, `body_radius`, menor que el Sol) iluminado por el Sol:
+    /// va desde el cuerpo hasta el vértice y se estrecha con semiángulo asin((R - r) / d).
+    pub fn umbra(sun_center: Vector3, sun_radius: f32, body_center: Vector3, body_radius: f32) -> ShadowCone {
+        let to_body = body_center - sun_center;
+        let distance = to_body.length();
+        let away = to_body / distance;
+        debug_assert!(body_radius < sun_radius, "la umbra de un cuerpo mayor que el Sol no se cierra");
+        let length = umbra_apex_distance(sun_radius, body_radius, distance);
+        let half_angle = ((sun_radius - body_radius) / distance).clamp(0.0, 1.0).asin();
+        // Las generatrices tocan el cuerpo un poco antes de su centro; basta con el centro
+        ShadowCone { apex: body_center + away * length, axis: -away, half_angle, start: 0.0, end: length }
+    }
+
+    /// Penumbra del mismo cuerpo: se abre con semiángulo asin((R + r) / d) desde el vértice y
+    /// empieza en el cuerpo; no tiene final.
+    pub fn penumbra(sun_center: Vector3, sun_radius: f32, body_center: Vector3, body_radius: f32) -> ShadowCone {
+        let to_body = body_center - sun_center;
+        let distance = to_body.length();
+        let away = to_body / distance;
+        let apex_distance = penumbra_apex_distance(sun_radius, body_radius, distance);
+        let half_angle = ((sun_radius + body_radius) / distance).clamp(0.0, 1.0).asin();
+        ShadowCone { apex: body_center - away * apex_distance, axis: away, half_angle, start: apex_distance, end: f32::INFINITY }
+    }
+
+    /// Radio del cono a `t` del vértice a lo largo del eje
+    pub fn radius_at(&self, t: f32) -> f32 {
+        t * self.half_angle.tan()
+    }
+
+    /// ¿Toca la esfera (`center`, `radius`) el tramo en sombra del cono?
+    pub fn touches_sphere(&self, center: Vector3, radius: f32) -> bool {
+        let offset = center - self.apex;
+        let t = offset.dot(self.axis);
+        if t + radius < self.start || t - radius > self.end {
+            return false;
+        }
+        let along = t.clamp(self.start, self.end);
+        let perpendicular = (offset - self.axis * t).length();
+        perpendicular <= self.radius_at(along) + radius / self.half_angle.cos()
+    }
+
+    /// Distancia de `point` al tramo del eje entre `start` y `end` (recortado a `max_length`)
+    pub fn distance_to_axis(&self, point: Vector3, max_length: f32) -> f32 {
+        let offset = point - self.apex;
+        let t = offset.dot(self.axis).clamp(self.start, self.end.min(max_length));
+        (offset - self.axis * t).length()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Sol, Luna y Tierra reales, en miles de km
+    const SUN_RADIUS: f32 = 696.0;
+    const MOON_RADIUS: f32 = 1.737;
+    const EARTH_RADIUS: f32 = 6.371;
+    const SUN_MOON_DISTANCE: f32 = 149_600.0;
+
+    #[test]
+    fn moon_umbra_ends_near_the_earth() {
+        // La umbra de la Luna mide ~374 000 km: por poco no llega a la Tierra en distancia media
+        let length = umbra_apex_distance(SUN_RADIUS, MOON_RADIUS, SUN_MOON_DISTANCE);
+        assert!((length - 374.3).abs() < 0.5, "umbra de {} mil km", length);
+        // Semejanza de triángulos: el vértice ve al Sol y a la Luna con el mismo ángulo
+        assert!((SUN_RADIUS / (SUN_MOON_DISTANCE + length) - MOON_RADIUS / length).abs() < 1e-6);
+        let penumbra = penumbra_apex_distance(SUN_RADIUS, MOON_RADIUS, SUN_MOON_DISTANCE);
+        assert!((penumbra - 372.4).abs() < 0.5, "penumbra desde {} mil km", penumbra);
+
+        let (sun, moon) = (Vector3::zero(), Vector3::new(SUN_MOON_DISTANCE, 0.0, 0.0));
+        let umbra = ShadowCone::umbra(sun, SUN_RADIUS, moon, MOON_RADIUS);
+        assert!((umbra.apex - Vector3::new(SUN_MOON_DISTANCE + length, 0.0, 0.0)).length() < 0.05);
+        assert_eq!(umbra.axis, Vector3::new(-1.0, 0.0, 0.0));
+        // Eclipse total con la Luna en el perigeo (363 300 km), anular en distancia media
+        assert!(umbra.touches_sphere(moon + Vector3::new(363.3, 0.0, 0.0), EARTH_RADIUS));
+        assert!(!umbra.touches_sphere(moon + Vector3::new(384.4, 0.0, 0.0), EARTH_RADIUS));
+        assert!(ShadowCone::penumbra(sun, SUN_RADIUS, moon, MOON_RADIUS).touches_sphere(moon + Vector3::new(384.4, 0.0, 0.0), EARTH_RADIUS));
+    }
+}
@@ -0,0 +1,58 @@
+// exploration.rs
+// 🗺️ Niebla de guerra: rejilla de celdas del plano XZ que la cámara ya visitó
+use raylib::math::Vector3;
+
+pub const EXPLORATION_GRID_SIZE: usize = 64;
+// La rejilla cubre [-EXTENT, EXTENT] en X y en Z (celdas de 6.25 unidades)
+pub const EXPLORATION_EXTENT: f32 = 200.0;
+// A esta distancia de la cámara lo inexplorado ya se ve como descubierto
+pub const EXPLORATION_REVEAL_DISTANCE: f32 = 30.0;
+
+// Indexada como `grid[fila z][columna x]`
+pub type ExploredGrid = [[bool; EXPLORATION_GRID_SIZE]; EXPLORATION_GRID_SIZE];
+
+/// Celda (fila z, columna x) que contiene `position`; `None` fuera de la rejilla. La altura no
+/// cuenta: una celda es toda la columna vertical sobre ella.
+pub fn grid_cell(position: Vector3) -> Option<(usize, usize)> {
+    let cell_size = 2.0 * EXPLORATION_EXTENT / EXPLORATION_GRID_SIZE as f32;
+    let column = ((position.x + EXPLORATION_EXTENT) / cell_size).floor();
+    let row = ((position.z + EXPLORATION_EXTENT) / cell_size).floor();
+    let range = 0.0..EXPLORATION_GRID_SIZE as f32;
+    if range.contains(&column) && range.contains(&row) {
+        Some((row as usize, column as usize))
+    } else {
+        None
+    }
+}
+
+pub fn mark_explored(grid: &mut ExploredGrid, position: Vector3) {
+    if let Some((row, column)) = grid_cell(position) {
+        grid[row][column] = true;
+    }
+}
+
+/// Fuera de la rejilla no hay nada que descubrir, así que cuenta como explorado
+pub fn is_explored(grid: &ExploredGrid, position: Vector3) -> bool {
+    grid_cell(position).is_none_or(|(row, column)| grid[row][column])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn world_position_maps_to_a_fixed_cell() {
+        // (50 + 200) / 6.25 = 40 en los dos ejes; la altura no cambia la celda
+        assert_eq!(grid_cell(Vector3::new(50.0, 0.0, 50.0)), Some((40, 40)));
+        assert_eq!(grid_cell(Vector3::new(50.0, 80.0, 50.0)), Some((40, 40)));
+        assert_eq!(grid_cell(Vector3::new(-200.0, 0.0, 199.9)), Some((63, 0)));
+        assert_eq!(grid_cell(Vector3::new(200.0, 0.0, 0.0)), None);
+
+        let mut grid = [[false; EXPLORATION_GRID_SIZE]; EXPLORATION_GRID_SIZE];
+        assert!(!is_explored(&grid, Vector3::new(50.0, 0.0, 50.0)));
+        mark_explored(&mut grid, Vector3::new(50.0, 0.0, 50.0));
+        assert!(grid[40][40]);
+        assert!(is_explored(&grid, Vector3::new(52.0, 10.0, 53.0)));
+        assert!(!is_explored(&grid, Vector3::new(57.0, 0.0, 50.0)));
+    }
+}
@@ -10,15 +10,22 @@ pub struct Fragment {
     pub color: Vector3,
     pub depth: f32,
     pub world_position: Vector3,
+    pub normal: Vector3,
+    pub vertex_color: Vector3,
+    // Bit i = muestra MSAA i cubierta por el triángulo (todas si no hay MSAA)
+    pub coverage: u16,
 }
 
 impl Fragment {
-    pub fn new(x: f32, y: f32, color: Vector3, depth: f32, world_position: Vector3) -> Self {
+    pub fn new(x: f32, y: f32, color: Vector3, depth: f32, world_position: Vector3, normal: Vector3, vertex_color: Vector3) -> Self {
         Fragment {
             position: Vector2::new(x, y),
             color,
             depth,
             world_position,
+            normal,
+            vertex_color,
+            coverage: u16::MAX,
         }
     }
 }
\ No newline at end of file
@@ -1,4 +1,199 @@
 use raylib::prelude::*;
+use crate::sdf_font::SdfFont;
+use crate::sprite::Sprite;
+use crate::line::wu_line;
+use crate::profiler::{FrameProfiler, elapsed_us};
+use crate::hdr::{tone_map, HdrFramebuffer, ToneMapping};
+use std::fmt;
+use std::time::Instant;
+
+// Índice de (x, y) en una curva de Hilbert que cubre un cuadrado de 2^order x 2^order
+pub fn hilbert_index(x: i32, y: i32, order: u32) -> usize {
+    let n = 1_i64 << order;
+    let (mut x, mut y) = (x as i64, y as i64);
+    let mut d = 0_i64;
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = i64::from(x & s > 0);
+        let ry = i64::from(y & s > 0);
+        d += s * s * ((3 * rx) ^ ry);
+        // Rotar el cuadrante para que la curva sea continua
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - x;
+                y = s - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x &= s - 1;
+        y &= s - 1;
+        s /= 2;
+    }
+    d as usize
+}
+
+// Inversa de `hilbert_index`: posición (x, y) del índice `index` en la curva
+#[allow(dead_code)]
+pub fn hilbert_coords(index: usize, order: u32) -> (i32, i32) {
+    let n = 1_i64 << order;
+    let mut t = index as i64;
+    let (mut x, mut y) = (0_i64, 0_i64);
+    let mut s = 1_i64;
+    while s < n {
+        let rx = 1 & (t / 2);
+        let ry = 1 & (t ^ rx);
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - x;
+                y = s - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+    (x as i32, y as i32)
+}
+
+// Matriz de Bayer 4x4 (umbrales ordenados 0..15)
+const BAYER_4X4: [[u8; 4]; 4] = [
+    [0, 8, 2, 10],
+    [12, 4, 14, 6],
+    [3, 11, 1, 9],
+    [15, 7, 13, 5],
+];
+const GOLDEN_RATIO_FRACT: f32 = 0.618_034;
+
+// Umbral de Bayer en [-0.5, 0.5) para el píxel (x, y); `rotation` lo desplaza (dithering temporal)
+fn bayer_offset(x: i32, y: i32, rotation: f32) -> f32 {
+    let threshold = (BAYER_4X4[y.rem_euclid(4) as usize][x.rem_euclid(4) as usize] as f32 + 0.5) / 16.0;
+    (threshold + rotation).fract() - 0.5
+}
+
+// Suma a cada canal un desplazamiento de Bayer de hasta medio escalón de 8 bits antes de cuantizar:
+// el banding de los degradados se convierte en un grano casi invisible
+pub fn dither_color(color: Vector3, x: i32, y: i32, bayer_scale: f32) -> Vector3 {
+    let offset = bayer_offset(x, y, 0.0) * bayer_scale / 255.0;
+    Vector3::new(color.x + offset, color.y + offset, color.z + offset)
+}
+
+// Variante temporal: el patrón rota por la razón áurea cada frame, repartiendo el grano en el tiempo
+pub fn dither_color_temporal(color: Vector3, x: i32, y: i32, bayer_scale: f32, frame: u32) -> Vector3 {
+    let rotation = (frame as f32 * GOLDEN_RATIO_FRACT).fract();
+    let offset = bayer_offset(x, y, rotation) * bayer_scale / 255.0;
+    Vector3::new(color.x + offset, color.y + offset, color.z + offset)
+}
+
+// Posiciones de las muestras MSAA respecto al centro del píxel: en diagonal para 2x y en rejilla
+// rotada (RGSS) para 4x, así ningún par de muestras comparte fila ni columna
+const MSAA_2X_OFFSETS: [(f32, f32); 2] = [(-0.25, -0.25), (0.25, 0.25)];
+const MSAA_4X_OFFSETS: [(f32, f32); 4] = [(0.125, -0.375), (0.375, 0.125), (-0.125, 0.375), (-0.375, -0.125)];
+// Rejillas ordenadas de `MsaaMode::Grid`: `side` x `side` muestras repartidas por igual en el píxel,
+// por filas, que al promediarlas son un filtro de caja sobre la imagen supermuestreada
+const MSAA_GRID_2X2_OFFSETS: [(f32, f32); 4] = ordered_grid_offsets(2);
+const MSAA_GRID_3X3_OFFSETS: [(f32, f32); 9] = ordered_grid_offsets(3);
+const MSAA_GRID_4X4_OFFSETS: [(f32, f32); 16] = ordered_grid_offsets(4);
+// Lado máximo de la rejilla: 4x4 son 16 muestras por píxel, lo que cabe en la máscara de
+// cobertura (u16) de cada fragmento
+pub const MSAA_MAX_GRID: u8 = 4;
+
+const fn ordered_grid_offsets<const N: usize>(side: usize) -> [(f32, f32); N] {
+    let mut offsets = [(0.0, 0.0); N];
+    let mut i = 0;
+    while i < N {
+        let column = (i % side) as f32;
+        let row = (i / side) as f32;
+        offsets[i] = ((column + 0.5) / side as f32 - 0.5, (row + 0.5) / side as f32 - 0.5);
+        i += 1;
+    }
+    offsets
+}
+
+// Cómo reparte el MSAA las muestras de cada píxel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MsaaMode {
+    Off,
+    // 2 o 4 muestras en diagonal / rejilla rotada, solo para suavizar los bordes (`--msaa`)
+    Rotated(u8),
+    // `side` x `side` muestras en rejilla ordenada, de 1 a MSAA_MAX_GRID (`--msaa-grid`)
+    Grid(u8),
+}
+
+#[derive(Debug)]
+pub enum FramebufferError {
+    // `MsaaMode::Rotated` con un número de muestras distinto de 2 o 4
+    InvalidSampleCount(u8),
+    // `MsaaMode::Grid` con una rejilla de 0 o de más de MSAA_MAX_GRID muestras de lado
+    InvalidGridSize(u8),
+}
+
+impl fmt::Display for FramebufferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FramebufferError::InvalidSampleCount(samples) => write!(f, "MSAA must use 2 or 4 samples, got {}", samples),
+            FramebufferError::InvalidGridSize(side) => write!(f, "MSAA grid must be between 1x1 and {0}x{0} samples, got {1}x{1}", MSAA_MAX_GRID, side),
+        }
+    }
+}
+
+impl std::error::Error for FramebufferError {}
+
+// 🔮 A-buffer: hasta TRANSPARENCY_LAYERS capas translúcidas por píxel, sin test de profundidad
+// entre ellas. Al resolver se ordenan de atrás hacia delante y se componen con "over", así dos
+// superficies translúcidas que se cruzan (los anillos delante y detrás del planeta) quedan bien
+// ordenadas píxel a píxel aunque sus triángulos no se puedan ordenar.
+pub const TRANSPARENCY_LAYERS: usize = 4;
+
+#[derive(Clone, Copy, Debug)]
+pub struct TransparentLayer {
+    pub depth: f32,
+    pub color: Vector3,
+    pub alpha: f32,
+}
+
+pub struct TransparencyBuffer {
+    // Capas de cada píxel (por filas) y cuántas hay en uso
+    layers: Vec<[TransparentLayer; TRANSPARENCY_LAYERS]>,
+    counts: Vec<u8>,
+    // Píxeles con alguna capa, para no recorrer toda la pantalla al resolver
+    touched: Vec<usize>,
+}
+
+impl TransparencyBuffer {
+    pub fn new(width: i32, height: i32) -> Self {
+        let empty = TransparentLayer { depth: f32::INFINITY, color: Vector3::zero(), alpha: 0.0 };
+        let pixels = (width * height) as usize;
+        TransparencyBuffer { layers: vec![[empty; TRANSPARENCY_LAYERS]; pixels], counts: vec![0; pixels], touched: Vec::new() }
+    }
+
+    /// Añade una capa al píxel `index`. Si ya tiene todas, la nueva sustituye a la más lejana
+    /// cuando está más cerca (lo del fondo es lo que menos se ve).
+    pub fn push(&mut self, index: usize, layer: TransparentLayer) {
+        let count = self.counts[index] as usize;
+        let layers = &mut self.layers[index];
+        if count < TRANSPARENCY_LAYERS {
+            if count == 0 {
+                self.touched.push(index);
+            }
+            layers[count] = layer;
+            self.counts[index] += 1;
+            return;
+        }
+        let farthest = (0..TRANSPARENCY_LAYERS).max_by(|&a, &b| layers[a].depth.total_cmp(&layers[b].depth)).unwrap_or(0);
+        if layer.depth < layers[farthest].depth {
+            layers[farthest] = layer;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for &index in &self.touched {
+            self.counts[index] = 0;
+        }
+        self.touched.clear();
+    }
+}
 
 pub struct Framebuffer {
     pub width: i32,
@@ -7,6 +202,28 @@ pub struct Framebuffer {
     background_color: Color,
     current_color: Color,
     depth_buffer: Vec<f32>,
+    // Orden de la curva si el depth buffer está en orden Hilbert; `None` = por filas.
+    // El color sigue por filas: la `Image` de raylib se sube así a la textura.
+    hilbert_order: Option<u32>,
+    // Rectángulo (x, y, ancho, alto) fuera del cual no se dibuja; `None` = toda la pantalla
+    scissor: Option<(i32, i32, i32, i32)>,
+    dithering: bool,
+    temporal_dithering: bool,
+    frame_index: u32,
+    // Tiempos por etapa del frame en curso (los llena `render` y `swap_buffers`)
+    pub profiler: FrameProfiler,
+    // MSAA: 0 (sin) o las muestras por píxel (2 o 4, o 4, 9 o 16 con `MsaaMode::Grid`), guardadas por
+    // filas en buffers propios hasta que `resolve_msaa` las promedia en `color_buffer`
+    pub msaa_level: u32,
+    msaa_offsets: &'static [(f32, f32)],
+    depth_buffer_msaa: Vec<f32>,
+    color_buffer_msaa: Vec<[f32; 3]>,
+    msaa_pending: bool,
+    // Capas translúcidas pendientes hasta `resolve_transparency`
+    transparency: TransparencyBuffer,
+    // Color sin recortar de las mallas (`point_hdr`) hasta que `resolve_hdr` lo pasa a 8 bits;
+    // `None` = sin HDR
+    hdr: Option<HdrFramebuffer>,
 }
 
 impl Framebuffer {
@@ -21,70 +238,625 @@ impl Framebuffer {
             background_color,
             current_color: Color::WHITE,
             depth_buffer,
+            hilbert_order: None,
+            scissor: None,
+            dithering: false,
+            temporal_dithering: false,
+            frame_index: 0,
+            profiler: FrameProfiler::default(),
+            msaa_level: 0,
+            msaa_offsets: &[],
+            depth_buffer_msaa: Vec::new(),
+            color_buffer_msaa: Vec::new(),
+            msaa_pending: false,
+            transparency: TransparencyBuffer::new(width, height),
+            hdr: None,
         }
     }
 
+    // Igual que `new`, pero el depth buffer se recorre en orden de curva de Hilbert para que
+    // píxeles cercanos en 2D queden cerca en memoria (se activa con la feature `hilbert`)
+    #[cfg_attr(not(feature = "hilbert"), allow(dead_code))]
+    pub fn new_hilbert(width: i32, height: i32) -> Self {
+        let side = width.max(height).max(1) as u32;
+        let order = side.next_power_of_two().trailing_zeros();
+        let mut framebuffer = Framebuffer::new(width, height);
+        framebuffer.depth_buffer = vec![f32::INFINITY; 1_usize << (2 * order)];
+        framebuffer.hilbert_order = Some(order);
+        framebuffer
+    }
+
+    // Igual que `new`, con `samples` x `samples` muestras por píxel en rejilla ordenada
+    // (`MsaaMode::Grid`). Con 1 no hay MSAA; 0 o más de MSAA_MAX_GRID es un error
+    #[allow(dead_code)]
+    pub fn new_msaa(width: i32, height: i32, samples: u8) -> Result<Self, FramebufferError> {
+        let mut framebuffer = Framebuffer::new(width, height);
+        framebuffer.set_msaa(MsaaMode::Grid(samples))?;
+        Ok(framebuffer)
+    }
+
+    // Limitar el dibujo a un rectángulo (para vistas divididas)
+    pub fn set_scissor(&mut self, scissor: Option<(i32, i32, i32, i32)>) {
+        self.scissor = scissor;
+    }
+
+    fn is_drawable(&self, x: i32, y: i32) -> bool {
+        let inside_screen = x >= 0 && x < self.width && y >= 0 && y < self.height;
+        match self.scissor {
+            Some((sx, sy, sw, sh)) => inside_screen && x >= sx && x < sx + sw && y >= sy && y < sy + sh,
+            None => inside_screen,
+        }
+    }
+
+    fn buffer_index(&self, x: i32, y: i32) -> usize {
+        match self.hilbert_order {
+            Some(order) => hilbert_index(x, y, order),
+            None => (y * self.width + x) as usize,
+        }
+    }
+
+    pub fn set_dithering(&mut self, enabled: bool) {
+        self.dithering = enabled;
+    }
+
+    pub fn set_temporal_dithering(&mut self, enabled: bool) {
+        self.temporal_dithering = enabled;
+    }
+
+    /// Cambia el modo de MSAA: color y profundidad se guardan por muestra (todo lo que se
+    /// rasteriza, y `point` cubre las del píxel entero) y al presentar se promedian con un filtro
+    /// de caja. `Grid(1)` equivale a `Off`. Un número de muestras que el modo no admite es un
+    /// error en lugar de redondearse, y el framebuffer queda como estaba.
+    pub fn set_msaa(&mut self, mode: MsaaMode) -> Result<(), FramebufferError> {
+        let offsets: &'static [(f32, f32)] = match mode {
+            MsaaMode::Off | MsaaMode::Grid(1) => &[],
+            MsaaMode::Rotated(2) => &MSAA_2X_OFFSETS,
+            MsaaMode::Rotated(4) => &MSAA_4X_OFFSETS,
+            MsaaMode::Grid(2) => &MSAA_GRID_2X2_OFFSETS,
+            MsaaMode::Grid(3) => &MSAA_GRID_3X3_OFFSETS,
+            MsaaMode::Grid(4) => &MSAA_GRID_4X4_OFFSETS,
+            MsaaMode::Rotated(samples) => return Err(FramebufferError::InvalidSampleCount(samples)),
+            MsaaMode::Grid(side) => return Err(FramebufferError::InvalidGridSize(side)),
+        };
+        self.allocate_msaa(offsets);
+        Ok(())
+    }
+
+    /// Activa el color HDR con `tone_mapping` (o lo quita con `None`): `point_hdr` guarda el color
+    /// sin recortar y `resolve_hdr` lo lleva a [0, 1] con el operador antes de cuantizar.
+    pub fn set_hdr(&mut self, tone_mapping: Option<ToneMapping>) {
+        self.hdr = tone_mapping.map(|tone_mapping| HdrFramebuffer::new(self.width, self.height, tone_mapping));
+    }
+
+    pub fn hdr_enabled(&self) -> bool {
+        self.hdr.is_some()
+    }
+
+    // Buffer HDR para los post-procesos que leen o modifican la imagen ya dibujada
+    pub fn hdr(&self) -> Option<&HdrFramebuffer> {
+        self.hdr.as_ref()
+    }
+
+    pub fn hdr_mut(&mut self) -> Option<&mut HdrFramebuffer> {
+        self.hdr.as_mut()
+    }
+
+    fn allocate_msaa(&mut self, offsets: &'static [(f32, f32)]) {
+        self.msaa_offsets = offsets;
+        self.msaa_level = offsets.len() as u32;
+        let samples = (self.width * self.height) as usize * self.msaa_level as usize;
+        self.depth_buffer_msaa = vec![f32::INFINITY; samples];
+        self.color_buffer_msaa = vec![[0.0; 3]; samples];
+        self.msaa_pending = false;
+    }
+
+    // Desplazamientos de las muestras para el nivel de MSAA actual (vacío sin MSAA)
+    pub fn msaa_sample_offsets(&self) -> &'static [(f32, f32)] {
+        self.msaa_offsets
+    }
+
     pub fn clear(&mut self) {
+        self.frame_index = self.frame_index.wrapping_add(1);
         self.color_buffer.clear_background(self.background_color);
         self.depth_buffer.fill(f32::INFINITY);
+        self.depth_buffer_msaa.fill(f32::INFINITY);
+        self.msaa_pending = false;
+        self.transparency.clear();
+        if let Some(hdr) = &mut self.hdr {
+            hdr.clear();
+        }
     }
     
     pub fn point(&mut self, x: i32, y: i32, color: Vector3, depth: f32) {
-        if x >= 0 && x < self.width && y >= 0 && y < self.height {
-            let index = (y * self.width + x) as usize;
+        if self.msaa_level > 0 {
+            self.point_msaa(x, y, color, depth, u16::MAX);
+            return;
+        }
+        if self.is_drawable(x, y) {
+            let index = self.buffer_index(x, y);
 
             if depth < self.depth_buffer[index] {
                 self.depth_buffer[index] = depth;
-                let pixel_color = Color::new(
-                    (color.x.clamp(0.0, 1.0) * 255.0) as u8,
-                    (color.y.clamp(0.0, 1.0) * 255.0) as u8,
-                    (color.z.clamp(0.0, 1.0) * 255.0) as u8,
-                    255,
-                );
+                let pixel_color = self.quantize(color, x, y);
                 self.color_buffer.draw_pixel(x, y, pixel_color);
             }
         }
     }
+
+    // Color en [0, 1] a 8 bits por canal, con dithering si está activo. Se redondea al nivel más
+    // cercano: truncando, el desplazamiento de Bayer (centrado en 0) oscurecería la media
+    pub fn quantize(&self, color: Vector3, x: i32, y: i32) -> Color {
+        let color = match (self.dithering, self.temporal_dithering) {
+            (false, _) => color,
+            (true, false) => dither_color(color, x, y, 1.0),
+            (true, true) => dither_color_temporal(color, x, y, 1.0, self.frame_index),
+        };
+        let to_byte = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        Color::new(to_byte(color.x), to_byte(color.y), to_byte(color.z), 255)
+    }
+
+    // Igual que `point` con el color ya cuantizado (el render cuantiza aparte para medir cada etapa)
+    pub fn point_quantized(&mut self, x: i32, y: i32, color: Color, depth: f32) {
+        if self.is_drawable(x, y) {
+            let index = self.buffer_index(x, y);
+            if depth < self.depth_buffer[index] {
+                self.depth_buffer[index] = depth;
+                self.color_buffer.draw_pixel(x, y, color);
+            }
+        }
+    }
     
-    // Método para dibujar una línea con profundidad específica
-    pub fn draw_line_with_depth(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: Color, depth: f32) {
-        let mut x0 = x0;
-        let mut y0 = y0;
-        let x1 = x1;
-        let y1 = y1;
-        
-        let dx = (x1 - x0).abs();
-        let dy = (y1 - y0).abs();
-        let sx = if x0 < x1 { 1 } else { -1 };
-        let sy = if y0 < y1 { 1 } else { -1 };
-        let mut err = dx - dy;
-        
-        loop {
-            // Convertir el color de raylib a Vector3 para usar en point
-            let color_vec3 = Vector3::new(
-                color.r as f32 / 255.0,
-                color.g as f32 / 255.0,
-                color.b as f32 / 255.0
-            );
-            
-            // Usar point con la profundidad especificada
-            self.point(x0, y0, color_vec3, depth);
-            
-            if x0 == x1 && y0 == y1 {
-                break;
-            }
-            
-            let e2 = 2 * err;
-            if e2 > -dy {
-                err -= dy;
-                x0 += sx;
-            }
-            if e2 < dx {
-                err += dx;
-                y0 += sy;
+    // Como `point_quantized`, pero el color va sin recortar al buffer HDR. Pasa también con la
+    // misma profundidad, así sirve para el modo diferido tras su pasada de profundidad.
+    pub fn point_hdr(&mut self, x: i32, y: i32, color: Vector3, depth: f32) {
+        if !self.is_drawable(x, y) {
+            return;
+        }
+        let index = self.buffer_index(x, y);
+        if let Some(hdr) = &mut self.hdr {
+            if depth <= self.depth_buffer[index] {
+                self.depth_buffer[index] = depth;
+                hdr.point(x, y, color);
+            }
+        }
+    }
+
+    // Pasa a `color_buffer` los píxeles HDR escritos desde la última vez, con tone mapping
+    pub fn resolve_hdr(&mut self) {
+        let Some(mut pending) = self.hdr.as_mut().map(HdrFramebuffer::take_pending) else {
+            return;
+        };
+        let start = Instant::now();
+        pending.sort_unstable();
+        pending.dedup();
+        if let Some(hdr) = &self.hdr {
+            for &(x, y) in &pending {
+                let pixel_color = self.quantize(hdr.tone_mapped_at(x, y), x, y);
+                self.color_buffer.draw_pixel(x, y, pixel_color);
+            }
+        }
+        self.profiler.tonemap_us += elapsed_us(start);
+    }
+
+    // Escribe las muestras de (x, y) marcadas en `coverage` que pasan su propio test de
+    // profundidad. El depth buffer normal guarda la más cercana para líneas y partículas.
+    pub fn point_msaa(&mut self, x: i32, y: i32, color: Vector3, depth: f32, coverage: u16) {
+        if !self.is_drawable(x, y) {
+            return;
+        }
+        let level = self.msaa_level as usize;
+        let base = (y * self.width + x) as usize * level;
+        let mut written = false;
+        for sample in 0..level {
+            if coverage & (1 << sample) != 0 && depth < self.depth_buffer_msaa[base + sample] {
+                self.depth_buffer_msaa[base + sample] = depth;
+                self.color_buffer_msaa[base + sample] = [color.x, color.y, color.z];
+                written = true;
+            }
+        }
+        if written {
+            let index = self.buffer_index(x, y);
+            self.depth_buffer[index] = self.depth_buffer[index].min(depth);
+            self.msaa_pending = true;
+        }
+    }
+
+    // Promedia las muestras MSAA de cada píxel en `color_buffer`. Las muestras que no cubrió
+    // ningún triángulo toman lo que ya había en el píxel (fondo o estrellas), que es lo que
+    // suaviza los bordes. Después las muestras quedan libres para lo siguiente que se dibuje.
+    pub fn resolve_msaa(&mut self) {
+        if !self.msaa_pending {
+            return;
+        }
+        let start = Instant::now();
+        let level = self.msaa_level as usize;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let Some(pixel_color) = self.msaa_resolved_color(x, y) else {
+                    continue;
+                };
+                self.color_buffer.draw_pixel(x, y, pixel_color);
+                let base = (y * self.width + x) as usize * level;
+                self.depth_buffer_msaa[base..base + level].fill(f32::INFINITY);
+            }
+        }
+        self.msaa_pending = false;
+        self.profiler.tonemap_us += elapsed_us(start);
+    }
+
+    // Promedio cuantizado de las muestras MSAA de (x, y); `None` si ninguna está cubierta
+    fn msaa_resolved_color(&self, x: i32, y: i32) -> Option<Color> {
+        let level = self.msaa_level as usize;
+        let base = (y * self.width + x) as usize * level;
+        let samples = base..base + level;
+        if self.depth_buffer_msaa[samples.clone()].iter().all(|d| d.is_infinite()) {
+            return None;
+        }
+        let under = self.color_buffer.get_color(x, y);
+        let under = [under.r as f32 / 255.0, under.g as f32 / 255.0, under.b as f32 / 255.0];
+        // Con HDR cada muestra se lleva a [0, 1] antes de promediar (lo de debajo ya lo está),
+        // así un borde del Sol no deja un escalón saturado
+        let display = |[r, g, b]: [f32; 3]| match &self.hdr {
+            Some(hdr) => {
+                let mapped = tone_map(Vector3::new(r, g, b), hdr.tone_mapping, hdr.exposure);
+                [mapped.x, mapped.y, mapped.z]
+            }
+            None => [r, g, b],
+        };
+        let mut sum = [0.0_f32; 3];
+        for i in samples {
+            let sample = if self.depth_buffer_msaa[i].is_infinite() { under } else { display(self.color_buffer_msaa[i]) };
+            for (total, channel) in sum.iter_mut().zip(sample) {
+                *total += channel;
+            }
+        }
+        let average = Vector3::new(sum[0], sum[1], sum[2]) / level as f32;
+        Some(self.quantize(average, x, y))
+    }
+
+    /// Guarda la imagen actual como PNG en `path`. Se puede llamar en cualquier punto del frame:
+    /// las muestras MSAA aún sin resolver se promedian en la copia, sin tocar el framebuffer.
+    pub fn save_screenshot(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut image = Image::gen_image_color(self.width, self.height, self.background_color);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let resolved = if self.msaa_pending { self.msaa_resolved_color(x, y) } else { None };
+                image.draw_pixel(x, y, resolved.unwrap_or_else(|| self.color_buffer.get_color(x, y)));
+            }
+        }
+        if !image.export_image(path) {
+            return Err(format!("could not write screenshot {}", path).into());
+        }
+        Ok(())
+    }
+
+    // Pasada de profundidad: solo actualiza el depth buffer
+    pub fn depth_point(&mut self, x: i32, y: i32, depth: f32) {
+        if self.is_drawable(x, y) {
+            let index = self.buffer_index(x, y);
+            if depth < self.depth_buffer[index] {
+                self.depth_buffer[index] = depth;
+            }
+        }
+    }
+
+    // Escritura tras la pasada de profundidad: el color solo entra si la profundidad es
+    // exactamente la que quedó guardada (el fragmento visible de ese píxel)
+    pub fn point_depth_equal(&mut self, x: i32, y: i32, color: Color, depth: f32) {
+        if self.is_drawable(x, y) {
+            let index = self.buffer_index(x, y);
+            if depth == self.depth_buffer[index] {
+                self.color_buffer.draw_pixel(x, y, color);
+            }
+        }
+    }
+
+    // Limpia solo el rectángulo (x, y, ancho, alto): color de fondo y profundidad infinita
+    #[allow(dead_code)]
+    pub fn clear_rect(&mut self, x: i32, y: i32, w: i32, h: i32) {
+        let (x0, y0) = (x.max(0), y.max(0));
+        let (x1, y1) = ((x + w).min(self.width), (y + h).min(self.height));
+        for py in y0..y1 {
+            for px in x0..x1 {
+                let index = self.buffer_index(px, py);
+                self.depth_buffer[index] = f32::INFINITY;
+                let base = (py * self.width + px) as usize * self.msaa_level as usize;
+                self.depth_buffer_msaa[base..base + self.msaa_level as usize].fill(f32::INFINITY);
+                self.color_buffer.draw_pixel(px, py, self.background_color);
+            }
+        }
+    }
+
+    // Copia el rectángulo `src_rect` de `src` en `dst_rect` (x, y, ancho, alto) de este buffer.
+    // Si los tamaños no coinciden se escala con filtrado bilineal. Solo copia color: las
+    // profundidades de dos buffers con otra proyección no son comparables.
+    pub fn blit(&mut self, src: &Framebuffer, src_rect: (i32, i32, i32, i32), dst_rect: (i32, i32, i32, i32)) {
+        let (sx, sy, sw, sh) = src_rect;
+        let (dx, dy, dw, dh) = dst_rect;
+        if sw <= 0 || sh <= 0 || dw <= 0 || dh <= 0 {
+            return;
+        }
+        // Vecino dentro de `src_rect` y de `src`
+        let fetch = |x: i32, y: i32| {
+            let x = x.clamp(sx.max(0), (sx + sw - 1).min(src.width - 1));
+            let y = y.clamp(sy.max(0), (sy + sh - 1).min(src.height - 1));
+            src.color_buffer.get_color(x, y)
+        };
+        let same_size = sw == dw && sh == dh;
+        for py in 0..dh {
+            for px in 0..dw {
+                let (x, y) = (dx + px, dy + py);
+                if !self.is_drawable(x, y) {
+                    continue;
+                }
+                let color = if same_size {
+                    fetch(sx + px, sy + py)
+                } else {
+                    // Centro del píxel destino llevado al espacio del origen
+                    let u = (px as f32 + 0.5) * sw as f32 / dw as f32 - 0.5 + sx as f32;
+                    let v = (py as f32 + 0.5) * sh as f32 / dh as f32 - 0.5 + sy as f32;
+                    let (x0, y0) = (u.floor() as i32, v.floor() as i32);
+                    let (fx, fy) = (u - x0 as f32, v - y0 as f32);
+                    let (c00, c10, c01, c11) = (fetch(x0, y0), fetch(x0 + 1, y0), fetch(x0, y0 + 1), fetch(x0 + 1, y0 + 1));
+                    let bilinear = |a: u8, b: u8, c: u8, d: u8| {
+                        let top = a as f32 * (1.0 - fx) + b as f32 * fx;
+                        let bottom = c as f32 * (1.0 - fx) + d as f32 * fx;
+                        (top * (1.0 - fy) + bottom * fy).round() as u8
+                    };
+                    Color::new(
+                        bilinear(c00.r, c10.r, c01.r, c11.r),
+                        bilinear(c00.g, c10.g, c01.g, c11.g),
+                        bilinear(c00.b, c10.b, c01.b, c11.b),
+                        255,
+                    )
+                };
+                self.color_buffer.draw_pixel(x, y, color);
+            }
+        }
+    }
+
+    // Profundidad guardada en (x, y); infinito fuera de la pantalla o donde no hay nada dibujado
+    pub fn get_depth_at(&self, x: i32, y: i32) -> f32 {
+        if x >= 0 && x < self.width && y >= 0 && y < self.height {
+            self.depth_buffer[self.buffer_index(x, y)]
+        } else {
+            f32::INFINITY
+        }
+    }
+
+    // Mezcla `color` sobre el píxel actual con opacidad `alpha` (sin escribir profundidad,
+    // para que las partículas transparentes no se tapen entre sí)
+    pub fn blend_point(&mut self, x: i32, y: i32, color: Vector3, alpha: f32, depth: f32) {
+        if self.is_drawable(x, y) {
+            let index = self.buffer_index(x, y);
+            if depth < self.depth_buffer[index] {
+                self.blend_pixel(x, y, color, alpha);
+            }
+        }
+    }
+
+    // Suma `color` al píxel actual (mezcla aditiva), sin escribir profundidad
+    pub fn add_point(&mut self, x: i32, y: i32, color: Vector3, depth: f32) {
+        if self.is_drawable(x, y) {
+            let index = self.buffer_index(x, y);
+            if depth < self.depth_buffer[index] {
+                let dst = self.color_buffer.get_color(x, y);
+                let add = |src: f32, dst: u8| (dst as f32 + src.max(0.0) * 255.0).round().min(255.0) as u8;
+                let pixel_color = Color::new(add(color.x, dst.r), add(color.y, dst.g), add(color.z, dst.b), 255);
+                self.color_buffer.draw_pixel(x, y, pixel_color);
+            }
+        }
+    }
+
+    fn blend_pixel(&mut self, x: i32, y: i32, color: Vector3, alpha: f32) {
+        let alpha = alpha.clamp(0.0, 1.0);
+        let dst = self.color_buffer.get_color(x, y);
+        let mix = |src: f32, dst: u8| ((src.clamp(0.0, 1.0) * 255.0) * alpha + dst as f32 * (1.0 - alpha)).round() as u8;
+        let pixel_color = Color::new(mix(color.x, dst.r), mix(color.y, dst.g), mix(color.z, dst.b), 255);
+        self.color_buffer.draw_pixel(x, y, pixel_color);
+    }
+
+    // Guarda un fragmento translúcido en las capas del píxel, sin test de profundidad; se compone
+    // en `resolve_transparency`
+    pub fn point_transparent(&mut self, x: i32, y: i32, color: Vector3, alpha: f32, depth: f32) {
+        if self.is_drawable(x, y) && alpha > 0.0 {
+            self.transparency.push((y * self.width + x) as usize, TransparentLayer { depth, color, alpha });
+        }
+    }
+
+    // Compone las capas de cada píxel de atrás hacia delante con "over"
+    // (color * alfa + destino * (1 - alfa)). Las que quedan detrás de lo opaco se descartan.
+    pub fn resolve_transparency(&mut self) {
+        let touched = std::mem::take(&mut self.transparency.touched);
+        for &index in &touched {
+            let (x, y) = (index as i32 % self.width, index as i32 / self.width);
+            let opaque_depth = self.depth_buffer[self.buffer_index(x, y)];
+            let count = self.transparency.counts[index] as usize;
+            let mut layers = self.transparency.layers[index];
+            let layers = &mut layers[..count];
+            layers.sort_unstable_by(|a, b| b.depth.total_cmp(&a.depth));
+            for layer in layers.iter().filter(|layer| layer.depth < opaque_depth) {
+                self.blend_pixel(x, y, layer.color, layer.alpha);
+            }
+            self.transparency.counts[index] = 0;
+        }
+    }
+
+    // Contorno de círculo de 2 px de grosor mezclado con opacidad `alpha`, por encima de la escena
+    pub fn draw_circle_outline(&mut self, cx: i32, cy: i32, radius: f32, color: Vector3, alpha: f32) {
+        if radius < 0.5 {
+            return;
+        }
+        // Suficientes pasos para que no queden huecos entre píxeles
+        let steps = (radius * 2.0 * std::f32::consts::PI).ceil().max(8.0) as i32;
+        for i in 0..steps {
+            let angle = i as f32 / steps as f32 * 2.0 * std::f32::consts::PI;
+            let (sin_a, cos_a) = angle.sin_cos();
+            for thickness in 0..2 {
+                let r = radius - thickness as f32;
+                let x = cx + (cos_a * r).round() as i32;
+                let y = cy + (sin_a * r).round() as i32;
+                self.blend_point(x, y, color, alpha, f32::NEG_INFINITY);
             }
         }
     }
+
+    // Disco relleno de radio `radius` px a profundidad `depth` (con test de profundidad)
+    pub fn draw_filled_circle(&mut self, cx: i32, cy: i32, radius: f32, color: Color, depth: f32) {
+        let color_vec3 = Vector3::new(color.r as f32 / 255.0, color.g as f32 / 255.0, color.b as f32 / 255.0);
+        let extent = radius.ceil() as i32;
+        for dy in -extent..=extent {
+            for dx in -extent..=extent {
+                if (dx * dx + dy * dy) as f32 <= radius * radius {
+                    self.point(cx + dx, cy + dy, color_vec3, depth);
+                }
+            }
+        }
+    }
+
+    // Copia `sprite` con la esquina superior izquierda en (x, y), escalado `scale` (texel más
+    // cercano) y multiplicado por `tint`, por encima de la escena. Cada texel se mezcla con su
+    // alfa: los de alfa 0 no tocan el píxel.
+    pub fn draw_sprite(&mut self, x: i32, y: i32, sprite: &Sprite, scale: f32, tint: Vector3) {
+        if scale <= 0.0 || sprite.width == 0 || sprite.height == 0 {
+            return;
+        }
+        let width = (sprite.width as f32 * scale).round() as i32;
+        let height = (sprite.height as f32 * scale).round() as i32;
+        for py in 0..height {
+            let sy = (((py as f32 + 0.5) / scale) as u32).min(sprite.height - 1);
+            for px in 0..width {
+                let sx = (((px as f32 + 0.5) / scale) as u32).min(sprite.width - 1);
+                let [r, g, b, a] = sprite.image_data[(sy * sprite.width + sx) as usize];
+                if a == 0 {
+                    continue;
+                }
+                let color = Vector3::new(r as f32 / 255.0 * tint.x, g as f32 / 255.0 * tint.y, b as f32 / 255.0 * tint.z);
+                self.blend_point(x + px, y + py, color, a as f32 / 255.0, f32::NEG_INFINITY);
+            }
+        }
+    }
+
+    /// Resplandor: lo que pasa de `threshold` (en luma) se copia a un buffer aparte, se desenfoca
+    /// con una gaussiana separable de radio `radius` px (una pasada horizontal y otra vertical) y
+    /// se suma a la imagen multiplicado por `strength`. Va entre el render y `swap_buffers`.
+    /// Sin HDR se lee la imagen de 8 bits, así que nada pasa de 1.0; con HDR (`set_hdr`) lo que
+    /// pintaron las mallas se lee sin recortar y el Sol por encima de 1.0 brilla más. Con
+    /// `radius` 0 el núcleo es una sola muestra: el exceso se suma sin desenfocar.
+    pub fn apply_bloom(&mut self, threshold: f32, radius: u32, strength: f32) {
+        self.resolve_msaa();
+        self.resolve_hdr();
+        let (width, height) = (self.width, self.height);
+        let mut bright = vec![Vector3::zero(); (width * height) as usize];
+        let mut any_bright = false;
+        for y in 0..height {
+            for x in 0..width {
+                let c = self.color_buffer.get_color(x, y);
+                let mut color = Vector3::new(c.r as f32, c.g as f32, c.b as f32) / 255.0;
+                if let Some(hdr) = &self.hdr {
+                    let radiance = hdr.get_color_at(x, y);
+                    color = Vector3::new(color.x.max(radiance.x), color.y.max(radiance.y), color.z.max(radiance.z));
+                }
+                let luma = color.x * 0.299 + color.y * 0.587 + color.z * 0.114;
+                // Solo lo que sobra por encima del umbral, así el borde de la zona brillante no corta
+                if luma > threshold {
+                    bright[(y * width + x) as usize] = color * ((luma - threshold) / luma);
+                    any_bright = true;
+                }
+            }
+        }
+        if !any_bright {
+            return;
+        }
+
+        // Núcleo gaussiano con σ = radio / 2 (±radio cubre dos sigmas), normalizado
+        let radius = radius as i32;
+        let sigma = (radius as f32 * 0.5).max(0.5);
+        let weights: Vec<f32> = (-radius..=radius).map(|k| (-((k * k) as f32) / (2.0 * sigma * sigma)).exp()).collect();
+        let total: f32 = weights.iter().sum();
+        let weights: Vec<f32> = weights.iter().map(|w| w / total).collect();
+        let blur_axis = |src: &[Vector3], horizontal: bool| {
+            let mut dst = vec![Vector3::zero(); src.len()];
+            for y in 0..height {
+                for x in 0..width {
+                    let mut sum = Vector3::zero();
+                    for (k, weight) in (-radius..=radius).zip(&weights) {
+                        // Fuera de la imagen se repite el borde
+                        let (sx, sy) = if horizontal { ((x + k).clamp(0, width - 1), y) } else { (x, (y + k).clamp(0, height - 1)) };
+                        sum += src[(sy * width + sx) as usize] * *weight;
+                    }
+                    dst[(y * width + x) as usize] = sum;
+                }
+            }
+            dst
+        };
+        let glow = blur_axis(&blur_axis(&bright, true), false);
+
+        for y in 0..height {
+            for x in 0..width {
+                let g = glow[(y * width + x) as usize] * strength;
+                if g.x <= 0.0 && g.y <= 0.0 && g.z <= 0.0 {
+                    continue;
+                }
+                let dst = self.color_buffer.get_color(x, y);
+                let add = |src: f32, dst: u8| (dst as f32 + src * 255.0).round().min(255.0) as u8;
+                self.color_buffer.draw_pixel(x, y, Color::new(add(g.x, dst.r), add(g.y, dst.g), add(g.z, dst.b), 255));
+            }
+        }
+    }
+
+    // Mezcla `color` sobre toda la imagen (destellos a pantalla completa), sin test de profundidad
+    pub fn fill_overlay(&mut self, color: Vector3, alpha: f32) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                self.blend_pixel(x, y, color, alpha);
+            }
+        }
+    }
+
+    // Texto SDF: `size` es el alto de la celda en píxeles. Cada píxel muestrea el campo de distancia
+    // en su centro y se suaviza el borde en un píxel de pantalla, así se ve nítido a cualquier tamaño.
+    pub fn draw_sdf_text(&mut self, x: i32, y: i32, text: &str, size: f32, color: Vector3, font: &SdfFont) {
+        let cell = size.max(1.0);
+        // Ancho de medio píxel de pantalla en unidades del bitmap 8x8
+        let edge = 0.5 * 8.0 / cell;
+        // Los glifos ocupan 5 de las 8 columnas: avanzar 6 para dejar una de separación
+        let advance = cell * 6.0 / 8.0;
+        let cell_px = cell.ceil() as i32;
+        for (i, c) in text.chars().enumerate() {
+            let origin_x = x as f32 + i as f32 * advance;
+            for py in 0..cell_px {
+                for px in 0..cell_px {
+                    let u = (px as f32 + 0.5) / cell;
+                    let v = (py as f32 + 0.5) / cell;
+                    if u > 1.0 || v > 1.0 {
+                        continue;
+                    }
+                    let d = font.sample(c, u, v);
+                    let alpha = ((d + edge) / (2.0 * edge)).clamp(0.0, 1.0);
+                    if alpha > 0.0 {
+                        self.blend_point(origin_x as i32 + px, y + py, color, alpha, f32::NEG_INFINITY);
+                    }
+                }
+            }
+        }
+    }
+
+    // Método para dibujar una línea con profundidad específica (antialiasada, ver `draw_line_wu`)
+    pub fn draw_line_with_depth(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: Color, depth: f32) {
+        let color_vec3 = Vector3::new(
+            color.r as f32 / 255.0,
+            color.g as f32 / 255.0,
+            color.b as f32 / 255.0
+        );
+        self.draw_line_wu(x0 as f32, y0 as f32, x1 as f32, y1 as f32, color_vec3, depth);
+    }
+
+    // Línea de Xiaolin Wu: cada píxel se mezcla con el fondo según lo que la línea lo cubre
+    // (sin escribir profundidad, como `blend_point`)
+    pub fn draw_line_wu(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, color: Vector3, depth: f32) {
+        wu_line(x0, y0, x1, y1, |x, y, coverage, _| self.blend_point(x, y, color, coverage, depth));
+    }
     
     pub fn set_background_color(&mut self, color: Color) {
         self.background_color = color;
@@ -94,11 +866,298 @@ impl Framebuffer {
         self.current_color = color;
     }
 
-    pub fn swap_buffers(&self, d: &mut RaylibHandle, thread: &RaylibThread) {
+    pub fn swap_buffers(&mut self, d: &mut RaylibHandle, thread: &RaylibThread) {
+        // Por si quedaron muestras sin resolver (la escena normalmente las resuelve antes
+        // de dibujar la interfaz encima)
+        self.resolve_msaa();
+        self.resolve_hdr();
+        let start = Instant::now();
         if let Ok(texture) = d.load_texture_from_image(thread, &self.color_buffer) {
             let mut d = d.begin_drawing(thread);
             d.clear_background(self.background_color);
             d.draw_texture(&texture, 0, 0, Color::WHITE);
         }
-    } 
-}
\ No newline at end of file
+        self.profiler.present_us += elapsed_us(start);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ordered_grid_offsets_cover_the_pixel_evenly() {
+        let offsets: [(f32, f32); 4] = ordered_grid_offsets(2);
+        assert_eq!(offsets, [(-0.25, -0.25), (0.25, -0.25), (-0.25, 0.25), (0.25, 0.25)]);
+        let offsets: [(f32, f32); 9] = ordered_grid_offsets(3);
+        assert_eq!(offsets[4], (0.0, 0.0));
+        assert!(offsets.iter().all(|&(x, y)| x.abs() < 0.5 && y.abs() < 0.5));
+    }
+
+    #[test]
+    fn msaa_rejects_unsupported_sample_counts() {
+        let mut framebuffer = Framebuffer::new(4, 4);
+        assert!(matches!(framebuffer.set_msaa(MsaaMode::Grid(5)), Err(FramebufferError::InvalidGridSize(5))));
+        assert!(matches!(framebuffer.set_msaa(MsaaMode::Grid(0)), Err(FramebufferError::InvalidGridSize(0))));
+        // Tampoco se redondea al patrón más cercano
+        assert!(matches!(framebuffer.set_msaa(MsaaMode::Rotated(3)), Err(FramebufferError::InvalidSampleCount(3))));
+        assert_eq!(framebuffer.msaa_level, 0);
+        framebuffer.set_msaa(MsaaMode::Grid(3)).unwrap();
+        assert_eq!(framebuffer.msaa_level, 9);
+        framebuffer.set_msaa(MsaaMode::Rotated(4)).unwrap();
+        assert_eq!(framebuffer.msaa_level, 4);
+    }
+
+    #[test]
+    fn new_msaa_allocates_a_grid_or_fails() {
+        assert_eq!(Framebuffer::new_msaa(4, 4, 1).unwrap().msaa_level, 0);
+        let framebuffer = Framebuffer::new_msaa(4, 4, 2).unwrap();
+        assert_eq!(framebuffer.msaa_level, 4);
+        assert_eq!(framebuffer.depth_buffer_msaa.len(), 4 * 4 * 4);
+        assert!(matches!(Framebuffer::new_msaa(4, 4, 5), Err(FramebufferError::InvalidGridSize(5))));
+    }
+
+    #[test]
+    fn msaa_grid_keeps_the_hilbert_layout() {
+        let mut framebuffer = Framebuffer::new_hilbert(8, 8);
+        framebuffer.set_msaa(MsaaMode::Grid(2)).unwrap();
+        assert!(framebuffer.hilbert_order.is_some());
+        assert_eq!(framebuffer.msaa_level, 4);
+    }
+
+    #[test]
+    fn hdr_resolve_tone_maps_instead_of_clipping() {
+        let mut framebuffer = Framebuffer::new(2, 1);
+        framebuffer.set_hdr(Some(ToneMapping::Reinhard));
+        framebuffer.point_hdr(0, 0, Vector3::new(1.0, 3.0, 0.0), 0.5);
+        framebuffer.point_hdr(0, 0, Vector3::one(), 0.9);
+        framebuffer.resolve_hdr();
+        let color = framebuffer.color_buffer.get_color(0, 0);
+        assert_eq!((color.r, color.g, color.b), (128, 191, 0));
+        assert_eq!(framebuffer.get_depth_at(0, 0), 0.5);
+    }
+
+    #[test]
+    fn bloom_leaves_a_black_image_black() {
+        let mut framebuffer = Framebuffer::new(8, 8);
+        framebuffer.apply_bloom(0.5, 3, 1.0);
+        assert!(framebuffer.color_buffer.get_image_data().iter().all(|c| (c.r, c.g, c.b) == (0, 0, 0)));
+    }
+
+    #[test]
+    fn bloom_spreads_bright_pixels_and_reads_hdr() {
+        let bloom_next_to = |framebuffer: &mut Framebuffer| {
+            framebuffer.apply_bloom(0.5, 2, 1.0);
+            framebuffer.color_buffer.get_color(5, 4).r
+        };
+        let mut ldr = Framebuffer::new(9, 9);
+        ldr.point(4, 4, Vector3::one(), 0.5);
+        let mut hdr = Framebuffer::new(9, 9);
+        hdr.set_hdr(Some(ToneMapping::Reinhard));
+        hdr.point_hdr(4, 4, Vector3::new(4.0, 4.0, 4.0), 0.5);
+        let (ldr_glow, hdr_glow) = (bloom_next_to(&mut ldr), bloom_next_to(&mut hdr));
+        assert!(ldr_glow > 0);
+        assert!(hdr_glow > ldr_glow);
+    }
+
+    #[test]
+    fn bloom_with_radius_zero_only_brightens_the_bright_pixels() {
+        let mut framebuffer = Framebuffer::new(3, 3);
+        framebuffer.point(1, 1, Vector3::new(0.6, 0.6, 0.6), 0.5);
+        framebuffer.apply_bloom(0.4, 0, 1.0);
+        // Núcleo de una sola muestra: se suma el exceso (0.2) sin repartirlo a los vecinos
+        assert_eq!(framebuffer.color_buffer.get_color(1, 1).r, 204);
+        assert_eq!(framebuffer.color_buffer.get_color(0, 1).r, 0);
+    }
+
+    // Degradado suave de un escalón de 8 bits (de 51.2 a 52.2) a lo largo de 64 píxeles, en las
+    // cuatro filas del patrón de Bayer
+    fn gradient_block(dithering: bool) -> Vec<u8> {
+        let mut framebuffer = Framebuffer::new(64, 4);
+        framebuffer.set_dithering(dithering);
+        let mut values = Vec::new();
+        for y in 0..4 {
+            for x in 0..64 {
+                let grey = (51.2 + x as f32 / 63.0) / 255.0;
+                values.push(framebuffer.quantize(Vector3::new(grey, grey, grey), x, y).r);
+            }
+        }
+        values
+    }
+
+    #[test]
+    fn dithering_adds_grey_levels_to_a_gradient() {
+        let unique = |values: Vec<u8>| values.into_iter().collect::<std::collections::HashSet<u8>>().len();
+        assert_eq!(unique(gradient_block(false)), 2);
+        assert!(unique(gradient_block(true)) > 2);
+    }
+
+    #[test]
+    fn dithering_keeps_the_average_brightness() {
+        let mean = |values: Vec<u8>| values.iter().map(|&v| v as f32).sum::<f32>() / values.len() as f32;
+        assert!((mean(gradient_block(true)) - 51.7).abs() < 0.1);
+    }
+
+    #[test]
+    fn blending_rounds_like_quantize() {
+        let mut framebuffer = Framebuffer::new(2, 1);
+        let grey = Vector3::new(0.5, 0.5, 0.5);
+        framebuffer.blend_point(0, 0, grey, 1.0, 0.5);
+        framebuffer.add_point(1, 0, grey, 0.5);
+        assert_eq!(framebuffer.color_buffer.get_color(0, 0), framebuffer.quantize(grey, 0, 0));
+        assert_eq!(framebuffer.color_buffer.get_color(1, 0), framebuffer.quantize(grey, 1, 0));
+    }
+
+    #[test]
+    fn add_point_saturates_and_respects_depth() {
+        let mut framebuffer = Framebuffer::new(2, 1);
+        let orange = Vector3::new(0.8, 0.2, 0.0);
+        framebuffer.add_point(0, 0, orange, 0.4);
+        framebuffer.add_point(0, 0, orange, 0.4);
+        let c = framebuffer.color_buffer.get_color(0, 0);
+        assert_eq!((c.r, c.g, c.b), (255, 102, 0));
+        // Detrás de algo opaco no suma nada; delante, sí
+        framebuffer.point(1, 0, orange, 0.5);
+        let opaque = framebuffer.color_buffer.get_color(1, 0);
+        framebuffer.add_point(1, 0, Vector3::one(), 0.6);
+        assert_eq!(framebuffer.color_buffer.get_color(1, 0), opaque);
+        framebuffer.add_point(1, 0, Vector3::one(), 0.4);
+        assert_eq!(framebuffer.color_buffer.get_color(1, 0), Color::new(255, 255, 255, 255));
+    }
+
+    #[test]
+    fn hilbert_index_and_coords_round_trip() {
+        let mut seen = vec![false; 16 * 16];
+        for y in 0..16 {
+            for x in 0..16 {
+                let index = hilbert_index(x, y, 4);
+                assert_eq!(hilbert_coords(index, 4), (x, y));
+                assert!(!seen[index]);
+                seen[index] = true;
+            }
+        }
+        // Índices consecutivos son celdas vecinas
+        for index in 1..256 {
+            let ((x0, y0), (x1, y1)) = (hilbert_coords(index - 1, 4), hilbert_coords(index, 4));
+            assert_eq!((x1 - x0).abs() + (y1 - y0).abs(), 1);
+        }
+    }
+
+    #[test]
+    fn scissored_viewport_never_draws_outside_its_bounds() {
+        let mut fb = Framebuffer::new(32, 16);
+        let background = fb.color_buffer.get_color(0, 0);
+        // Vista de la derecha, como en la pantalla dividida
+        fb.set_scissor(Some((16, 0, 16, 16)));
+        for y in -2..18 {
+            for x in -2..34 {
+                fb.point(x, y, Vector3::one(), 0.5);
+            }
+        }
+        fb.draw_line_with_depth(0, 8, 31, 8, Color::RED, 0.1);
+        fb.draw_filled_circle(16, 8, 10.0, Color::GREEN, 0.1);
+        fb.set_scissor(None);
+        for y in 0..16 {
+            for x in 0..32 {
+                if x < 16 {
+                    assert_eq!(fb.color_buffer.get_color(x, y), background, "({}, {})", x, y);
+                    assert_eq!(fb.get_depth_at(x, y), f32::INFINITY);
+                } else {
+                    assert!(fb.get_depth_at(x, y) < 1.0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn blit_with_equal_rects_copies_pixels_exactly() {
+        let mut src = Framebuffer::new(16, 16);
+        for y in 0..16 {
+            for x in 0..16 {
+                src.color_buffer.draw_pixel(x, y, Color::new((x * 16) as u8, (y * 16) as u8, ((x * 7 + y * 3) % 256) as u8, 255));
+            }
+        }
+        let mut dst = Framebuffer::new(20, 20);
+        let background = dst.color_buffer.get_color(0, 0);
+        dst.blit(&src, (3, 5, 8, 6), (10, 2, 8, 6));
+        for y in 0..20 {
+            for x in 0..20 {
+                let color = dst.color_buffer.get_color(x, y);
+                if (10..18).contains(&x) && (2..8).contains(&y) {
+                    assert_eq!(color, src.color_buffer.get_color(x - 10 + 3, y - 2 + 5), "({}, {})", x, y);
+                } else {
+                    assert_eq!(color, background, "({}, {})", x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn transparent_sprite_texels_leave_the_framebuffer_alone() {
+        let background = Color::new(10, 20, 30, 255);
+        let mut framebuffer = Framebuffer::new(16, 16);
+        for y in 0..16 {
+            for x in 0..16 {
+                framebuffer.color_buffer.draw_pixel(x, y, background);
+            }
+        }
+        // Tablero de 4x4: rojo opaco donde x + y es par, alfa 0 (pero con color) donde es impar
+        let mut sprite = Sprite::new(4, 4);
+        for (i, texel) in sprite.image_data.iter_mut().enumerate() {
+            *texel = if (i % 4 + i / 4) % 2 == 0 { [255, 0, 0, 255] } else { [0, 255, 255, 0] };
+        }
+        framebuffer.draw_sprite(3, 5, &sprite, 2.0, Vector3::one());
+        for y in 0..16 {
+            for x in 0..16 {
+                let (sx, sy) = (x - 3, y - 5);
+                let inside = (0..8).contains(&sx) && (0..8).contains(&sy);
+                let expected = if inside && (sx / 2 + sy / 2) % 2 == 0 { Color::new(255, 0, 0, 255) } else { background };
+                assert_eq!(framebuffer.color_buffer.get_color(x, y), expected, "píxel ({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn two_transparent_layers_composite_back_to_front() {
+        let background = Vector3::new(0.2, 0.2, 0.2);
+        let (near, far) = ((Vector3::new(1.0, 0.0, 0.0), 0.5), (Vector3::new(0.0, 0.0, 1.0), 0.6));
+        let over = |src: Vector3, alpha: f32, dst: Vector3| src * alpha + dst * (1.0 - alpha);
+        let expected = over(near.0, near.1, over(far.0, far.1, background));
+
+        let mut framebuffer = Framebuffer::new(3, 1);
+        for x in 0..3 {
+            framebuffer.point(x, 0, background, 10.0);
+        }
+        // Píxel 0: la capa cercana llega primero; píxel 1: al revés. Píxel 2: hay algo opaco
+        // a 0.6, que tapa la capa de 0.8
+        framebuffer.point_transparent(0, 0, near.0, near.1, 0.5);
+        framebuffer.point_transparent(0, 0, far.0, far.1, 0.8);
+        framebuffer.point_transparent(1, 0, far.0, far.1, 0.8);
+        framebuffer.point_transparent(1, 0, near.0, near.1, 0.5);
+        framebuffer.depth_point(2, 0, 0.6);
+        framebuffer.point_transparent(2, 0, near.0, near.1, 0.5);
+        framebuffer.point_transparent(2, 0, far.0, far.1, 0.8);
+        framebuffer.resolve_transparency();
+
+        let read = |x: i32| {
+            let c = framebuffer.color_buffer.get_color(x, 0);
+            Vector3::new(c.r as f32, c.g as f32, c.b as f32) / 255.0
+        };
+        for x in 0..2 {
+            assert!((read(x) - expected).length() < 3.0 / 255.0, "píxel {}: {:?} frente a {:?}", x, read(x), expected);
+        }
+        assert!((read(2) - over(near.0, near.1, background)).length() < 3.0 / 255.0);
+    }
+
+    #[test]
+    fn save_screenshot_writes_a_png() {
+        let mut framebuffer = Framebuffer::new(8, 8);
+        framebuffer.point(3, 3, Vector3::one(), 0.5);
+        let path = std::env::temp_dir().join("proyecto3_screenshot.png");
+        let path = path.to_string_lossy().into_owned();
+        framebuffer.save_screenshot(&path).unwrap();
+        let size = std::fs::metadata(&path).unwrap().len();
+        std::fs::remove_file(&path).unwrap();
+        assert!(size > 0);
+    }
+}
@@ -0,0 +1,140 @@
+// hdr.rs
+// 🔆 Color HDR de la escena: los shaders de las mallas guardan aquí su color en f32 sin recortar
+// (el Sol devuelve hasta 2.0) y al resolver se lleva a [0, 1] con un operador de tone mapping
+// antes de cuantizar a 8 bits. Lo activa `Framebuffer::set_hdr` (`--hdr`); sin él el render
+// recorta a [0, 1] como siempre.
+
+use raylib::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ToneMapping {
+    // c / (1 + c): nunca satura, pero aplana los brillos
+    Reinhard,
+    // Curva fílmica ACES (ajuste de Narkowicz): más contraste y un hombro suave
+    #[default]
+    Aces,
+}
+
+impl ToneMapping {
+    // Nombre de la línea de comandos (`--hdr reinhard` o `--hdr aces`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "reinhard" => Some(ToneMapping::Reinhard),
+            "aces" => Some(ToneMapping::Aces),
+            _ => None,
+        }
+    }
+}
+
+/// Lleva un canal HDR (>= 0, sin límite superior) a [0, 1] con `operator` tras multiplicarlo
+/// por `exposure`.
+pub fn tone_map_channel(value: f32, operator: ToneMapping, exposure: f32) -> f32 {
+    let v = (value * exposure).max(0.0);
+    let mapped = match operator {
+        ToneMapping::Reinhard => v / (1.0 + v),
+        ToneMapping::Aces => (v * (2.51 * v + 0.03)) / (v * (2.43 * v + 0.59) + 0.14),
+    };
+    mapped.clamp(0.0, 1.0)
+}
+
+pub fn tone_map(color: Vector3, operator: ToneMapping, exposure: f32) -> Vector3 {
+    Vector3::new(
+        tone_map_channel(color.x, operator, exposure),
+        tone_map_channel(color.y, operator, exposure),
+        tone_map_channel(color.z, operator, exposure),
+    )
+}
+
+/// Buffer de color f32 del mismo tamaño que el `Framebuffer` al que acompaña. El test de
+/// profundidad lo hace el framebuffer; aquí solo se guarda el color ganador de cada píxel y qué
+/// píxeles cambiaron desde el último `take_pending`.
+pub struct HdrFramebuffer {
+    pub width: i32,
+    pub height: i32,
+    // Color lineal por filas, sin recortar
+    pub color_buffer: Vec<[f32; 3]>,
+    pending: Vec<(i32, i32)>,
+    pub tone_mapping: ToneMapping,
+    pub exposure: f32,
+}
+
+impl HdrFramebuffer {
+    pub fn new(width: i32, height: i32, tone_mapping: ToneMapping) -> Self {
+        HdrFramebuffer {
+            width,
+            height,
+            color_buffer: vec![[0.0; 3]; (width * height) as usize],
+            pending: Vec::new(),
+            tone_mapping,
+            exposure: 1.0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.color_buffer.fill([0.0; 3]);
+        self.pending.clear();
+    }
+
+    // Como `Framebuffer::point`, pero el color se guarda tal cual, sin recortar a [0, 1]
+    pub fn point(&mut self, x: i32, y: i32, color: Vector3) {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return;
+        }
+        self.color_buffer[(y * self.width + x) as usize] = [color.x, color.y, color.z];
+        self.pending.push((x, y));
+    }
+
+    // Cambia el color de (x, y) sin marcarlo pendiente: para pases que ya pintaron su resultado
+    // en la imagen de 8 bits (SSAO, lente gravitatoria)
+    pub fn set_color(&mut self, x: i32, y: i32, color: Vector3) {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return;
+        }
+        self.color_buffer[(y * self.width + x) as usize] = [color.x, color.y, color.z];
+    }
+
+    pub fn get_color_at(&self, x: i32, y: i32) -> Vector3 {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return Vector3::zero();
+        }
+        let [r, g, b] = self.color_buffer[(y * self.width + x) as usize];
+        Vector3::new(r, g, b)
+    }
+
+    // Color de (x, y) tras `tone_mapping` y `exposure`, ya en [0, 1]
+    pub fn tone_mapped_at(&self, x: i32, y: i32) -> Vector3 {
+        tone_map(self.get_color_at(x, y), self.tone_mapping, self.exposure)
+    }
+
+    /// Píxeles escritos desde la última llamada (puede haber repetidos), para pasarlos a 8 bits.
+    pub fn take_pending(&mut self) -> Vec<(i32, i32)> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tone_map_channel_stays_in_range_and_keeps_order() {
+        for operator in [ToneMapping::Reinhard, ToneMapping::Aces] {
+            assert_eq!(tone_map_channel(0.0, operator, 1.0), 0.0);
+            assert!(tone_map_channel(-1.0, operator, 1.0) >= 0.0);
+            let (one, two, huge) = (tone_map_channel(1.0, operator, 1.0), tone_map_channel(2.0, operator, 1.0), tone_map_channel(1e6, operator, 1.0));
+            assert!(one < two && two <= huge && huge <= 1.0);
+        }
+        assert!((tone_map_channel(1.0, ToneMapping::Reinhard, 1.0) - 0.5).abs() < 1e-6);
+        assert!((tone_map_channel(1.0, ToneMapping::Reinhard, 2.0) - tone_map_channel(2.0, ToneMapping::Reinhard, 1.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn hdr_point_keeps_values_above_one() {
+        let mut hdr = HdrFramebuffer::new(2, 2, ToneMapping::Aces);
+        hdr.point(1, 0, Vector3::new(2.0, 1.5, 0.5));
+        hdr.point(5, 5, Vector3::one());
+        assert_eq!(hdr.color_buffer[1], [2.0, 1.5, 0.5]);
+        assert_eq!(hdr.take_pending(), vec![(1, 0)]);
+        assert!(hdr.take_pending().is_empty());
+    }
+}
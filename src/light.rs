@@ -1,11 +1,112 @@
 use raylib::prelude::*;
 
+#[derive(Clone, Copy, Debug)]
+pub enum LightKind {
+    Point,
+    Spotlight {
+        direction: Vector3,
+        inner_angle: f32, // radianes, intensidad completa dentro de este cono
+        outer_angle: f32, // radianes, sin luz fuera de este cono
+    },
+}
+
+/// Atenuación con la distancia d: 1 / (constant + linear·d + quadratic·d²)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LightAttenuation {
+    pub constant: f32,
+    pub linear: f32,
+    pub quadratic: f32,
+}
+
+impl LightAttenuation {
+    // Sin atenuación: la misma intensidad a cualquier distancia
+    pub const NONE: LightAttenuation = LightAttenuation { constant: 1.0, linear: 0.0, quadratic: 0.0 };
+    // Inversa del cuadrado, la de una fuente puntual real
+    pub const INVERSE_SQUARE: LightAttenuation = LightAttenuation { constant: 0.0, linear: 0.0, quadratic: 1.0 };
+}
+
+#[derive(Clone, Copy, Debug)]
 pub struct Light {
     pub position: Vector3,
+    pub kind: LightKind,
+    pub color: Vector3,
+    pub intensity: f32,
+    pub attenuation: LightAttenuation,
 }
 
 impl Light {
     pub fn new(position: Vector3) -> Self {
-        Light { position }
+        Light::point(position, Vector3::one(), 1.0, LightAttenuation::NONE)
     }
-}
\ No newline at end of file
+
+    pub fn point(position: Vector3, color: Vector3, intensity: f32, attenuation: LightAttenuation) -> Self {
+        Light { position, kind: LightKind::Point, color, intensity, attenuation }
+    }
+
+    // Como `point`, pero solo ilumina dentro del cono que abre hacia `direction`
+    pub fn spotlight(position: Vector3, direction: Vector3, inner_angle: f32, outer_angle: f32, color: Vector3, intensity: f32, attenuation: LightAttenuation) -> Self {
+        Light {
+            position,
+            kind: LightKind::Spotlight {
+                direction: direction.normalized(),
+                inner_angle,
+                outer_angle,
+            },
+            color,
+            intensity,
+            attenuation,
+        }
+    }
+
+    /// Fracción de la intensidad que llega a `distance` de la luz
+    pub fn attenuation_factor(&self, distance: f32) -> f32 {
+        let a = self.attenuation;
+        // Con atenuación pura por distancia, d = 0 dividiría entre cero
+        1.0 / (a.constant + a.linear * distance + a.quadratic * distance * distance).max(1e-6)
+    }
+
+    /// Color que llega a `point`: color · intensidad · atenuación
+    pub fn radiance_at(&self, point: Vector3) -> Vector3 {
+        self.color * (self.intensity * self.attenuation_factor((point - self.position).length()))
+    }
+
+    /// Factor del cono para un punto en mundo: 1 dentro del cono interior, 0 fuera del exterior,
+    /// transición suave entre ambos. Las luces puntuales siempre devuelven 1.
+    pub fn spot_factor(&self, point: Vector3) -> f32 {
+        match self.kind {
+            LightKind::Point => 1.0,
+            LightKind::Spotlight { direction, inner_angle, outer_angle } => {
+                let light_to_point = (point - self.position).normalized();
+                smoothstep(outer_angle.cos(), inner_angle.cos(), light_to_point.dot(direction))
+            }
+        }
+    }
+}
+
+pub fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spotlight_is_full_at_the_center_and_zero_outside_the_cone() {
+        let light = Light::spotlight(Vector3::zero(), Vector3::new(0.0, 0.0, -1.0), 10f32.to_radians(), 20f32.to_radians(), Vector3::one(), 2.0, LightAttenuation::NONE);
+        assert_eq!(light.spot_factor(Vector3::new(0.0, 0.0, -5.0)), 1.0);
+        assert_eq!(light.radiance_at(Vector3::new(0.0, 0.0, -5.0)), Vector3::new(2.0, 2.0, 2.0));
+        let outside = Vector3::new(30f32.to_radians().tan() * 5.0, 0.0, -5.0);
+        assert_eq!(light.spot_factor(outside), 0.0);
+        assert_eq!(light.spot_factor(Vector3::new(0.0, 0.0, 5.0)), 0.0);
+    }
+
+    #[test]
+    fn inverse_square_quarters_the_light_at_twice_the_distance() {
+        let light = Light::point(Vector3::zero(), Vector3::one(), 3.0, LightAttenuation::INVERSE_SQUARE);
+        assert!((light.attenuation_factor(2.0) - light.attenuation_factor(1.0) / 4.0).abs() < 1e-6);
+        let (near, far) = (light.radiance_at(Vector3::new(1.0, 0.0, 0.0)), light.radiance_at(Vector3::new(0.0, 2.0, 0.0)));
+        assert!((far.x - near.x / 4.0).abs() < 1e-6);
+    }
+}
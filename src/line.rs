@@ -35,6 +35,8 @@ pub fn line(a: &Vertex, b: &Vertex) -> Vec<Fragment> {
             Vector3::new(1.0, 1.0, 1.0), 
             z,
             Vector3::new(0.0, 0.0, 0.0), // dummy world pos
+            Vector3::new(0.0, 0.0, 0.0), // dummy normal
+            Vector3::new(1.0, 1.0, 1.0), // vertex color
         ));
         
         if x0 == x1 && y0 == y1 { break; }
@@ -51,4 +53,72 @@ pub fn line(a: &Vertex, b: &Vertex) -> Vec<Fragment> {
     }
     
     fragments
-}
\ No newline at end of file
+}
+
+/// Línea antialiasada de Xiaolin Wu. Las coordenadas son de píxel con el centro del píxel
+/// (x, y) en (x, y). Por cada columna (o fila, si la línea es más vertical que horizontal) llama
+/// a `plot(x, y, cobertura, t)` en los dos píxeles que toca, con la cobertura repartida según la
+/// parte fraccionaria y `t` en [0, 1] de un extremo al otro. Una línea horizontal sobre centros
+/// de píxel tiene cobertura 1 en todos; una a 45° entre dos filas, 0.5 en cada una.
+pub fn wu_line(x0: f32, y0: f32, x1: f32, y1: f32, mut plot: impl FnMut(i32, i32, f32, f32)) {
+    let steep = (y1 - y0).abs() > (x1 - x0).abs();
+    // Se recorre siempre el eje largo de menor a mayor
+    let (x0, y0, x1, y1) = if steep { (y0, x0, y1, x1) } else { (x0, y0, x1, y1) };
+    let reversed = x0 > x1;
+    let (x0, y0, x1, y1) = if reversed { (x1, y1, x0, y0) } else { (x0, y0, x1, y1) };
+
+    let dx = x1 - x0;
+    let gradient = if dx > 0.0 { (y1 - y0) / dx } else { 0.0 };
+    for x in x0.round() as i32..=x1.round() as i32 {
+        let y = y0 + gradient * (x as f32 - x0);
+        let row = y.floor();
+        let fraction = y - row;
+        let t = if dx > 0.0 { ((x as f32 - x0) / dx).clamp(0.0, 1.0) } else { 0.0 };
+        let t = if reversed { 1.0 - t } else { t };
+        for (row, coverage) in [(row as i32, 1.0 - fraction), (row as i32 + 1, fraction)] {
+            if coverage > 0.0 {
+                if steep {
+                    plot(row, x, coverage, t);
+                } else {
+                    plot(x, row, coverage, t);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Todas las llamadas de `wu_line` como (x, y, cobertura)
+    fn wu_plots(x0: f32, y0: f32, x1: f32, y1: f32) -> Vec<(i32, i32, f32)> {
+        let mut plots = Vec::new();
+        wu_line(x0, y0, x1, y1, |x, y, coverage, _| plots.push((x, y, coverage)));
+        plots
+    }
+
+    #[test]
+    fn wu_horizontal_line_is_full_and_diagonal_is_half_on_two_rows() {
+        let horizontal = wu_plots(2.0, 5.0, 12.0, 5.0);
+        assert_eq!(horizontal.len(), 11);
+        assert!(horizontal.iter().all(|&(_, y, coverage)| y == 5 && coverage == 1.0));
+
+        // A 45° y a medio camino entre dos filas: cada columna reparte 0.5 y 0.5
+        let diagonal = wu_plots(0.0, 0.5, 10.0, 10.5);
+        assert_eq!(diagonal.len(), 22);
+        for column in diagonal.chunks(2) {
+            let [(xa, ya, ca), (xb, yb, cb)] = [column[0], column[1]];
+            assert_eq!(xa, xb);
+            assert_eq!((ya, yb), (xa, xa + 1));
+            assert_eq!((ca, cb), (0.5, 0.5));
+        }
+
+        // En el framebuffer: blanco entero en la horizontal, gris medio en la diagonal
+        let mut framebuffer = crate::framebuffer::Framebuffer::new(16, 16);
+        framebuffer.draw_line_wu(2.0, 5.0, 12.0, 5.0, Vector3::one(), 0.0);
+        assert!((2..=12).all(|x| framebuffer.color_buffer.get_color(x, 5).r == 255));
+        framebuffer.draw_line_wu(0.0, 7.5, 3.0, 10.5, Vector3::one(), 0.0);
+        assert!((0..=3).all(|x| [7 + x, 8 + x].iter().all(|&y| (framebuffer.color_buffer.get_color(x, y).r as i32 - 128).abs() <= 1)));
+    }
+}
@@ -10,19 +10,67 @@ mod camera;
 mod shaders;
 mod light;
 mod line;
+mod mesh;
+mod font;
+mod particles;
+mod sdf_font;
+mod scripted_shader;
+mod sandbox;
+mod model_node;
+mod exploration;
+mod profiler;
+mod atmosphere;
+mod orbit;
+mod transparency;
+mod catalog;
+mod raymarcher;
+mod postprocess;
+mod render_queue;
+mod texture;
+mod eclipse;
+mod sprite;
+mod docking;
+mod save;
+mod hdr;
+mod config;
 
-use framebuffer::Framebuffer;
+use framebuffer::{Framebuffer, MsaaMode};
 use triangle::triangle;
 use obj::Obj;
 use raylib::prelude::*;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::f32::consts::PI;
-use matrix::{create_model_matrix, create_projection_matrix, create_viewport_matrix, create_view_matrix, multiply_matrix_vector4};
+use std::collections::VecDeque;
+use matrix::{create_model_matrix, create_model_matrix_with_rotation, create_rotation_matrix_x, create_rotation_matrix_y, create_rotation_matrix_z, create_projection_matrix, create_ortho_projection_matrix, create_viewport_matrix, create_view_matrix, multiply_matrix_vector4, new_matrix3};
 use vertex::Vertex;
-use camera::Camera;
-use shaders::{vertex_shader, fragment_shader, mercury_fragment_shader, sun_fragment_shader, earth_fragment_shader, mars_fragment_shader, uranus_fragment_shader, nave_fragment_shader, skybox_fragment_shader};
-use light::Light;
+use camera::{Camera, CameraMode, ClipPlanes};
+use shaders::{vertex_shader, fragment_shader, mercury_fragment_shader, sun_fragment_shader, earth_fragment_shader, mars_fragment_shader, uranus_fragment_shader, nave_fragment_shader, nav_light_fragment_shader, skybox_fragment_shader, saturn_fragment_shader, saturn_ring_fragment_shader, venus_fragment_shader, ceres_fragment_shader, pluto_fragment_shader, jupiter_fragment_shader, io_fragment_shader, europa_fragment_shader, ganymede_fragment_shader, callisto_fragment_shader, warp_tunnel_fragment_shader, comet_nucleus_fragment_shader, comet_surface_temperature, COMET_SUBLIMATION_TEMP, WARP_TUNNEL_RADIUS, WARP_TUNNEL_LENGTH, WARP_TUNNEL_RINGS, WARP_STREAK_PERIOD, alien_planet_fragment_shader, nova_shockwave_fragment_shader, nova_shockwave_alpha, nebula_volume_density, asteroid_fragment_shader, ocean_world_fragment_shader, bioluminescent_ocean_fragment_shader, uranus_ring_fragment_shader, saturn_ring_alpha, uranus_ring_alpha, atmosphere_shell_fragment_shader, atmosphere_shell_alpha, apply_fog_of_war, apply_texture_atlas, displacement_for_shader, DisplacementType};
+use shaders::{saturn_ring_normal, SATURN_AXIAL_TILT, SATURN_C_RING_INNER, SATURN_CASSINI_INNER, SATURN_CASSINI_OUTER, SATURN_A_RING_OUTER, URANUS_AXIAL_TILT, URANUS_RADIUS_KM, URANUS_RING_MIN_WIDTH, URANUS_RING_OUTER, URANUS_RINGS};
+use mesh::{generate_ring_mesh, generate_ring_system, generate_asteroid_mesh, generate_asteroid_detail_mesh, asteroid_surface_radius, adaptive_subdivide, generate_cube_sphere, generate_box, generate_cone_mesh, generate_cylinder_mesh, generate_warp_tunnel, bake_ao_to_vertex_colors, save_vertex_colors, load_vertex_colors};
+use light::{Light, LightAttenuation};
+use font::{draw_text, text_width, GLYPH_HEIGHT};
+use particles::{CometJetSystem, KuiperBeltSimulation, Particle, ParticleSystem, RingDustSystem, SolarFlare, SolarWindSystem, soft_particle_factor, linearize_depth};
+use sdf_font::SdfFont;
+use scripted_shader::{ScriptShaderCache, SCRIPT_SHADER_PREFIX};
+use sandbox::{Sandbox, circular_speed};
+use model_node::{ModelNode, build_model_hierarchy};
+use profiler::{FrameProfiler, ProfileTrace, elapsed_us};
+use atmosphere::{precompute_transmittance_table, TRANSMITTANCE_RESOLUTION};
+use orbit::{compute_lagrange_points, hill_sphere_radius, j2_precession_rate, kepler_position, orbital_position, SUN_J2};
+use transparency::{TransparentItem, push_line};
+use catalog::{export_orbital_data, load_body_catalog, fallback_body_catalog};
+use raymarcher::Volume;
+use eclipse::{ShadowCone, SHADOW_CONE_VISIBLE_DISTANCE};
+use texture::{TextureAtlas, ATLAS_PATH};
+use sprite::{planet_icon, HudSprites, Sprite, SpriteBatch, SPRITE_DIR};
+use docking::{generate_station_mesh, generate_strut_segment, station_model_matrix, station_position, strut_segment_matrix, DockingAnimation, DockingStruts, DOCKING_CONFIG_PATH, DOCKING_RANGE};
+use render_queue::{OrbitLine, RenderItem, RenderQueue, RenderTarget, SurfaceDraw};
+use postprocess::{ssao_pass, apply_ssao, gravity_lensing_pass, PostProcessPipeline};
+use hdr::ToneMapping;
+use save::{GameSave, SaveTracker, SAVE_PATH};
+use exploration::{ExploredGrid, EXPLORATION_GRID_SIZE, mark_explored, is_explored};
+use std::collections::{HashMap, HashSet};
 
 /// Helpers para operar con `raylib::prelude::Vector3` de forma segura
 fn add_vec3(a: Vector3, b: Vector3) -> Vector3 {
@@ -50,29 +98,113 @@ fn clamp_f32(x: f32, lo: f32, hi: f32) -> f32 {
     if x < lo { lo } else if x > hi { hi } else { x }
 }
 
-pub struct Uniforms {
+// Intensidad del Sol: con atenuación d², a la distancia de la Tierra (25) llega 1
+const SUN_LIGHT_INTENSITY: f32 = 625.0;
+
+// Luz ambiental: luz solar dispersada en el medio interplanetario
+const AMBIENT_INTENSITY: f32 = 2.0;
+const SUN_AMBIENT_INTENSITY: f32 = 1.0;
+const AMBIENT_COLOR_NEAR: Vector3 = Vector3::new(1.0, 0.8, 0.6); // anaranjada cerca del Sol
+const AMBIENT_COLOR_FAR: Vector3 = Vector3::new(0.6, 0.75, 1.0); // azulada lejos del Sol
+const AMBIENT_FAR_DISTANCE: f32 = 45.0; // distancia a la que el ambiente es completamente frío
+
+// Color ambiental según la distancia al Sol: cálido para Mercurio, frío para Urano
+fn ambient_color_at(distance: f32) -> Vector3 {
+    let t = clamp_f32(distance / AMBIENT_FAR_DISTANCE, 0.0_f32, 1.0_f32);
+    lerp_vec3(AMBIENT_COLOR_NEAR, AMBIENT_COLOR_FAR, t)
+}
+
+pub struct Uniforms<'a> {
     pub model_matrix: Matrix,
     pub view_matrix: Matrix,
     pub projection_matrix: Matrix,
     pub viewport_matrix: Matrix,
     pub time: f32,
     pub dt: f32,
+    pub camera_pos: Vector3,
+    pub lights: Vec<Light>,
+    pub ambient_intensity: f32,
+    pub ambient_color: Vector3,
+    pub atmosphere_height: f32,  // en radios del planeta
+    pub atmosphere_density: f32, // extinción por radio de planeta recorrido
+    pub explored_grid: &'a ExploredGrid, // niebla de guerra: celdas ya visitadas
+    pub earth_transmittance: &'a [f32],  // tabla de transmitancia de la atmósfera terrestre
+    pub explosion_phase: f32,            // segundos desde el inicio de la nova (0 sin nova)
+    pub sun_radius: f32,                 // radio del Sol en mundo, para la penumbra de las sombras
+    pub shadow_quality: u32,             // rayos de sombra por fragmento (1 rápido, 8 calidad)
+    pub displacement: DisplacementType,  // relieve procedural que aplica el vertex shader
+    pub displacement_scale: f32,         // amplitud del relieve (0 = esfera lisa)
+    pub atlas: Option<(&'a TextureAtlas, u32)>, // casilla del atlas que se mezcla con el shader
+    pub effect_alpha: f32,               // opacidad de los efectos translúcidos (fundido del túnel de warp)
+}
+
+#[cfg(test)]
+impl<'a> Uniforms<'a> {
+    // Uniformes mínimos para las pruebas: matrices identidad, sin luces, atmósfera ni relieve
+    pub fn for_tests(explored_grid: &'a ExploredGrid) -> Self {
+        Uniforms {
+            model_matrix: Matrix::identity(),
+            view_matrix: Matrix::identity(),
+            projection_matrix: Matrix::identity(),
+            viewport_matrix: Matrix::identity(),
+            time: 0.0,
+            dt: 0.0,
+            camera_pos: Vector3::zero(),
+            lights: Vec::new(),
+            ambient_intensity: 0.0,
+            ambient_color: Vector3::zero(),
+            atmosphere_height: 0.0,
+            atmosphere_density: 0.0,
+            explored_grid,
+            earth_transmittance: &[],
+            explosion_phase: 0.0,
+            sun_radius: 1.0,
+            shadow_quality: 1,
+            displacement: DisplacementType::None,
+            displacement_scale: 0.0,
+            atlas: None,
+            effect_alpha: 1.0,
+        }
+    }
+}
+
+// Coordenada de pantalla a partir de la cual un vértice se considera degenerado (p. ej. el
+// centinela de `safe_perspective_divide`) o no finito: rasterizar su caja recorrería miles de millones de píxeles
+const DEGENERATE_SCREEN_EXTENT: f32 = 1e6;
+
+fn is_degenerate_triangle(tri: &[Vertex]) -> bool {
+    tri.iter().any(|v| {
+        let p = v.transformed_position;
+        !(p.x.abs() <= DEGENERATE_SCREEN_EXTENT && p.y.abs() <= DEGENERATE_SCREEN_EXTENT)
+    })
 }
 
 fn render(
     framebuffer: &mut Framebuffer,
     uniforms: &Uniforms,
     vertex_array: &[Vertex],
-    light: &Light,
     planet_type: &str,
+    script_shaders: Option<&ScriptShaderCache>,
+    mode: RenderMode,
+    transparent: &mut Vec<TransparentItem>,
 ) {
+    // La iluminación base del rasterizador usa la luz principal (el Sol)
+    let default_light = Light::new(Vector3::new(0.0_f32, 0.0_f32, 0.0_f32));
+    let light = uniforms.lights.first().unwrap_or(&default_light);
+
+    // ⏱️ Cada etapa se mide por separado y se acumula en `framebuffer.profiler`
+    let vertex_start = Instant::now();
     let mut transformed_vertices = Vec::with_capacity(vertex_array.len());
     for vertex in vertex_array {
         transformed_vertices.push(vertex_shader(vertex, uniforms));
     }
+    framebuffer.profiler.vertex_transform_us += elapsed_us(vertex_start);
+
+    let raster_start = Instant::now();
+    let sample_offsets = framebuffer.msaa_sample_offsets();
     let mut triangles = Vec::new();
     for i in (0..transformed_vertices.len()).step_by(3) {
-        if i + 2 < transformed_vertices.len() {
+        if i + 2 < transformed_vertices.len() && !is_degenerate_triangle(&transformed_vertices[i..i + 3]) {
             triangles.push([
                 transformed_vertices[i].clone(),
                 transformed_vertices[i + 1].clone(),
@@ -82,9 +214,15 @@ fn render(
     }
     let mut fragments = Vec::new();
     for tri in &triangles {
-        fragments.extend(triangle(&tri[0], &tri[1], &tri[2], light));
+        fragments.extend(triangle(&tri[0], &tri[1], &tri[2], light, sample_offsets, (framebuffer.width, framebuffer.height)));
     }
-    for fragment in fragments {
+    framebuffer.profiler.triangle_raster_us += elapsed_us(raster_start);
+
+    // Primero se sombrean todos los fragmentos; cuantizar y escribir (test de profundidad) va aparte.
+    // Los translúcidos no pasan por aquí: van a `transparent` y se componen al final de la vista.
+    let shade_start = Instant::now();
+    let mut shaded: Vec<(i32, i32, Vector3, f32, u16)> = Vec::with_capacity(fragments.len());
+    for fragment in &fragments {
         // Protección: evitar NaN/Inf y fragmentos fuera de pantalla para prevenir panics/overflows
         if !fragment.position.x.is_finite() || !fragment.position.y.is_finite() || !fragment.depth.is_finite() {
             continue;
@@ -94,38 +232,228 @@ fn render(
         if sx < 0 || sx >= framebuffer.width || sy < 0 || sy >= framebuffer.height {
             continue;
         }
+        // Diferido: solo se sombrea el fragmento que ganó la pasada de profundidad en este píxel
+        if mode == RenderMode::Deferred && fragment.depth != framebuffer.get_depth_at(sx, sy) {
+            continue;
+        }
 
         let final_color = match planet_type {
-            "Sun" => sun_fragment_shader(&fragment, uniforms),
-            "Mercury" => mercury_fragment_shader(&fragment, uniforms),
-            "Earth" => earth_fragment_shader(&fragment, uniforms),
-            "Mars" => mars_fragment_shader(&fragment, uniforms),
-            "Uranus" => uranus_fragment_shader(&fragment, uniforms),
-            "Nave" => nave_fragment_shader(&fragment, uniforms),
-            "Skybox" => skybox_fragment_shader(&fragment, uniforms),
-            _ => fragment_shader(&fragment, uniforms),
-        };
-        framebuffer.point(
-            sx,
-            sy,
-            final_color,
-            fragment.depth,
-        );
+            "Sun" => sun_fragment_shader(fragment, uniforms),
+            "Mercury" => mercury_fragment_shader(fragment, uniforms),
+            "Earth" => earth_fragment_shader(fragment, uniforms),
+            "Mars" => mars_fragment_shader(fragment, uniforms),
+            "Uranus" => uranus_fragment_shader(fragment, uniforms),
+            "Venus" => venus_fragment_shader(fragment, uniforms),
+            "Ceres" => ceres_fragment_shader(fragment, uniforms),
+            "Asteroid" => asteroid_fragment_shader(fragment, uniforms),
+            "Pluto" => pluto_fragment_shader(fragment, uniforms),
+            "Jupiter" => jupiter_fragment_shader(fragment, uniforms),
+            "Kepler-186f" => alien_planet_fragment_shader(fragment, uniforms),
+            "Kepler-22b" => ocean_world_fragment_shader(fragment, uniforms),
+            "BioluminescentOcean" => bioluminescent_ocean_fragment_shader(fragment, uniforms),
+            "Saturn" => saturn_fragment_shader(fragment, uniforms),
+            "Io" => io_fragment_shader(fragment, uniforms),
+            "Europa" => europa_fragment_shader(fragment, uniforms),
+            "Ganymede" => ganymede_fragment_shader(fragment, uniforms),
+            "Callisto" => callisto_fragment_shader(fragment, uniforms),
+            "WarpTunnel" => warp_tunnel_fragment_shader(fragment, uniforms),
+            "CometNucleus" => comet_nucleus_fragment_shader(fragment, uniforms),
+            "SaturnRings" => saturn_ring_fragment_shader(fragment, uniforms),
+            "UranusRings" => uranus_ring_fragment_shader(fragment, uniforms),
+            "AtmosphereShell" => atmosphere_shell_fragment_shader(fragment, uniforms),
+            "Nave" => nave_fragment_shader(fragment, uniforms),
+            "NovaShockwave" => nova_shockwave_fragment_shader(fragment, uniforms),
+            "LuzNavegacion" => nav_light_fragment_shader(fragment, uniforms),
+            "Skybox" => skybox_fragment_shader(fragment, uniforms),
+            // Horizonte de sucesos: no emite ni refleja nada
+            "BlackHole" => Vector3::zero(),
+            // Shader en Rhai; si el script no está cargado o falla, el shader por defecto
+            script if script.starts_with(SCRIPT_SHADER_PREFIX) => script_shaders
+                .and_then(|cache| cache.shade(&script[SCRIPT_SHADER_PREFIX.len()..], fragment, uniforms))
+                .unwrap_or_else(|| fragment_shader(fragment, uniforms)),
+            _ => fragment_shader(fragment, uniforms),
+        };
+        // Retoque del artista desde el atlas, si el cuerpo tiene casilla
+        let final_color = apply_texture_atlas(fragment, uniforms, final_color);
+        // La onda de choque de la nova es translúcida y el túnel del warp se suma a lo que hay detrás
+        let alpha = match planet_type {
+            "NovaShockwave" => Some(nova_shockwave_alpha(fragment, uniforms)),
+            "WarpTunnel" => Some(uniforms.effect_alpha),
+            _ => None,
+        };
+        if let Some(alpha) = alpha {
+            transparent.push(TransparentItem::from_fragment(fragment, planet_type).with_color(final_color, alpha));
+            continue;
+        }
+        // 🗺️ Niebla de guerra sobre los cuerpos; el Sol se ve desde todo el sistema y la nave
+        // y el cielo no están en ninguna celda
+        let final_color = match planet_type {
+            "Sun" | "Nave" | "LuzNavegacion" | "Skybox" => final_color,
+            _ => apply_fog_of_war(fragment, uniforms, final_color),
+        };
+        // Anillos y capas de atmósfera van a las capas translúcidas del framebuffer, que las
+        // ordena píxel a píxel (se cruzan con el planeta y entre ellas)
+        let layer_alpha = match planet_type {
+            "SaturnRings" => Some(saturn_ring_alpha(fragment)),
+            "UranusRings" => Some(uranus_ring_alpha(fragment)),
+            "AtmosphereShell" => Some(atmosphere_shell_alpha(fragment, uniforms)),
+            _ => None,
+        };
+        if let Some(alpha) = layer_alpha {
+            framebuffer.point_transparent(sx, sy, final_color, alpha, fragment.depth);
+            continue;
+        }
+        shaded.push((sx, sy, final_color, fragment.depth, fragment.coverage));
+    }
+    framebuffer.profiler.fragment_shade_us += elapsed_us(shade_start);
+
+    // Con MSAA se guardan las muestras en color lineal y se cuantizan al resolver; con HDR el
+    // color va sin recortar y se cuantiza con tone mapping al final de esta malla
+    let msaa = framebuffer.msaa_level > 0;
+    let hdr = framebuffer.hdr_enabled();
+    let tonemap_start = Instant::now();
+    let quantized: Vec<Color> = if msaa || hdr {
+        Vec::new()
+    } else {
+        shaded.iter().map(|&(sx, sy, color, _, _)| framebuffer.quantize(color, sx, sy)).collect()
+    };
+    framebuffer.profiler.tonemap_us += elapsed_us(tonemap_start);
+
+    let depth_start = Instant::now();
+    for (i, &(sx, sy, color, depth, coverage)) in shaded.iter().enumerate() {
+        if msaa {
+            framebuffer.point_msaa(sx, sy, color, depth, coverage);
+        } else if hdr {
+            framebuffer.point_hdr(sx, sy, color, depth);
+        } else if mode == RenderMode::Deferred {
+            framebuffer.point_depth_equal(sx, sy, quantized[i], depth);
+        } else {
+            framebuffer.point_quantized(sx, sy, quantized[i], depth);
+        }
+    }
+    framebuffer.profiler.depth_test_us += elapsed_us(depth_start);
+    framebuffer.resolve_hdr();
+}
+
+// 🧱 Pasada de profundidad del modo diferido: transforma y rasteriza igual que `render` (así la
+// profundidad coincide exactamente) pero solo escribe el depth buffer, sin sombrear
+fn depth_prepass_render(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Vertex]) {
+    let default_light = Light::new(Vector3::new(0.0_f32, 0.0_f32, 0.0_f32));
+    let light = uniforms.lights.first().unwrap_or(&default_light);
+
+    let vertex_start = Instant::now();
+    let transformed_vertices: Vec<Vertex> = vertex_array.iter().map(|vertex| vertex_shader(vertex, uniforms)).collect();
+    framebuffer.profiler.vertex_transform_us += elapsed_us(vertex_start);
+
+    let raster_start = Instant::now();
+    let sample_offsets = framebuffer.msaa_sample_offsets();
+    let mut fragments = Vec::new();
+    for tri in transformed_vertices.chunks_exact(3).filter(|tri| !is_degenerate_triangle(tri)) {
+        fragments.extend(triangle(&tri[0], &tri[1], &tri[2], light, sample_offsets, (framebuffer.width, framebuffer.height)));
+    }
+    framebuffer.profiler.triangle_raster_us += elapsed_us(raster_start);
+
+    let depth_start = Instant::now();
+    for fragment in &fragments {
+        if !fragment.position.x.is_finite() || !fragment.position.y.is_finite() || !fragment.depth.is_finite() {
+            continue;
+        }
+        framebuffer.depth_point(fragment.position.x.round() as i32, fragment.position.y.round() as i32, fragment.depth);
+    }
+    framebuffer.profiler.depth_test_us += elapsed_us(depth_start);
+}
+
+// ✨ Centelleo: las estrellas cerca del "horizonte" (el borde de la pantalla) atraviesan más
+// aire y titilan más; la dispersión separa R, G y B hasta 1 px hacia el cenit (el centro)
+const STAR_DISPERSION_PIXELS: f32 = 1.0_f32;
+
+/// Variación de brillo (se suma a 1) de la estrella `star_index` en el píxel (`x`, `y`).
+/// `altitude_deg` es su altura sobre el horizonte de la pantalla: 90° en el centro (mirando
+/// "hacia arriba", sin centelleo) y 0° en el borde (centelleo máximo, proporcional a cos(altura)).
+/// El píxel desfasa un poco el centelleo: cada estrella mira a través de otra columna de aire.
+fn star_scintillation(x: i32, y: i32, time: f32, star_index: u32, altitude_deg: f32) -> f32 {
+    let air_phase = x as f32 * 0.031_f32 + y as f32 * 0.017_f32;
+    let t = time + air_phase;
+    let index = star_index as f32;
+    let scint = (t * 3.0_f32 + index * 2.7_f32).sin() * 0.15_f32 + (t * 7.0_f32 + index * 1.3_f32).sin() * 0.08_f32;
+    let horizon_factor = clamp_f32(altitude_deg, 0.0_f32, 90.0_f32).to_radians().cos();
+    scint * horizon_factor
+}
+
+// Suma un canal de la estrella en una posición con fracción de píxel, repartido entre los
+// cuatro píxeles vecinos
+fn splat_star_channel(framebuffer: &mut Framebuffer, x: f32, y: f32, color: Vector3, depth: f32) {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    for (dx, dy, weight) in [(0, 0, (1.0_f32 - fx) * (1.0_f32 - fy)), (1, 0, fx * (1.0_f32 - fy)), (0, 1, (1.0_f32 - fx) * fy), (1, 1, fx * fy)] {
+        if weight > 0.0_f32 {
+            framebuffer.add_point(x0 as i32 + dx, y0 as i32 + dy, mul_vec3_scalar(color, weight), depth);
+        }
     }
 }
 
-// 🌟 Renderiza estrellas en el fondo (skybox simple)
+// 🌌 Vía Láctea: disco exponencial en latitud galáctica y bulbo alrededor del centro galáctico
+// (Sagitario, AR 17h45m40s, Dec -29°). El polo norte galáctico está en AR 12h51m26s, Dec +27.1°
+const GALACTIC_CENTER_RA_DEG: f32 = 266.4;
+const GALACTIC_CENTER_DEC_DEG: f32 = -29.0;
+const GALACTIC_POLE_RA_DEG: f32 = 192.86;
+const GALACTIC_POLE_DEC_DEG: f32 = 27.13;
+const ECLIPTIC_OBLIQUITY_DEG: f32 = 23.44;
+const GALAXY_DISK_SCALE_DEG: f32 = 3.0;   // altura de escala del disco en latitud galáctica
+const GALAXY_BULGE_SCALE_DEG: f32 = 10.0; // caída del bulbo con la separación al centro
+const GALAXY_BULGE_WEIGHT: f32 = 3.0;
+// Candidatas a estrella débil de la galaxia por frame (se aceptan según la densidad)
+const GALAXY_STAR_CANDIDATES: u32 = 2000;
+const SKYBOX_STAR_COUNT: u32 = 300;
+// Estrellas viejas (población II) del bulbo
+const GALAXY_BULGE_TINT: Vector3 = Vector3::new(1.0, 0.72, 0.42);
+
+// Dirección de mundo de unas coordenadas ecuatoriales. El plano de las órbitas (XZ) es la
+// eclíptica con +Y al norte: ecuatorial -> eclíptica girando la oblicuidad sobre X, y la eclíptica
+// (x, y, z) va a (x, z, -y) para seguir siendo dextrógira con Y hacia arriba
+fn equatorial_to_world(ra_deg: f32, dec_deg: f32) -> Vector3 {
+    let (ra, dec, obliquity) = (ra_deg.to_radians(), dec_deg.to_radians(), ECLIPTIC_OBLIQUITY_DEG.to_radians());
+    let (x, y, z) = (dec.cos() * ra.cos(), dec.cos() * ra.sin(), dec.sin());
+    let (y_ecl, z_ecl) = (y * obliquity.cos() + z * obliquity.sin(), -y * obliquity.sin() + z * obliquity.cos());
+    Vector3::new(x, z_ecl, -y_ecl)
+}
+
+// Cuánto del bulbo hay en `direction` (unitaria): 1 hacia el centro galáctico, cae
+// exponencialmente con la separación angular
+fn galaxy_bulge_density(direction: Vector3) -> f32 {
+    let center = equatorial_to_world(GALACTIC_CENTER_RA_DEG, GALACTIC_CENTER_DEC_DEG);
+    let cos_separation = clamp_f32(direction.x * center.x + direction.y * center.y + direction.z * center.z, -1.0_f32, 1.0_f32);
+    (-cos_separation.acos().to_degrees() / GALAXY_BULGE_SCALE_DEG).exp()
+}
+
+/// Densidad estelar de la Vía Láctea en `direction` (unitaria): el disco, exp(-|b| / 3°) con b
+/// la latitud galáctica, más el bulbo central. Entre 0 y 1 + GALAXY_BULGE_WEIGHT; hacia el
+/// centro galáctico vale unas 4, hacia el polo galáctico prácticamente 0.
+fn galaxy_core_density(direction: Vector3) -> f32 {
+    let pole = equatorial_to_world(GALACTIC_POLE_RA_DEG, GALACTIC_POLE_DEC_DEG);
+    let sin_latitude = clamp_f32(direction.x * pole.x + direction.y * pole.y + direction.z * pole.z, -1.0_f32, 1.0_f32);
+    let disk_density = (-sin_latitude.asin().to_degrees().abs() / GALAXY_DISK_SCALE_DEG).exp();
+    disk_density + GALAXY_BULGE_WEIGHT * galaxy_bulge_density(direction)
+}
+
+// 🌟 Renderiza estrellas en el fondo (skybox simple): las SKYBOX_STAR_COUNT repartidas por igual
+// y, detrás, estrellas débiles de la galaxia aceptadas según `galaxy_core_density`
 fn render_skybox(framebuffer: &mut Framebuffer, view_matrix: &Matrix, projection_matrix: &Matrix, viewport_matrix: &Matrix, time: f32) {
     let mut rng = fastrand::Rng::with_seed(time as u64);
-    for i in 0..300 { // ↑ Subimos a 300 para más riqueza (sigue siendo liviano)
+    for i in 0..SKYBOX_STAR_COUNT + GALAXY_STAR_CANDIDATES {
         let radius = 300.0_f32;
         let u = rng.f32();
         let v = rng.f32();
         let theta = 2.0_f32 * PI * u;
         let phi = (2.0_f32 * v - 1.0_f32).acos();
-        let x = radius * phi.sin() * theta.cos();
-        let y = radius * phi.cos();
-        let z = radius * phi.sin() * theta.sin();
+        let direction = Vector3::new(phi.sin() * theta.cos(), phi.cos(), phi.sin() * theta.sin());
+        let galaxy_star = i >= SKYBOX_STAR_COUNT;
+        if galaxy_star && rng.f32() * (1.0_f32 + GALAXY_BULGE_WEIGHT) > galaxy_core_density(direction) {
+            continue;
+        }
+        let x = radius * direction.x;
+        let y = radius * direction.y;
+        let z = radius * direction.z;
 
         let pos4 = Vector4::new(x, y, z, 1.0_f32);
         let view_pos = multiply_matrix_vector4(view_matrix, &pos4);
@@ -144,59 +472,123 @@ fn render_skybox(framebuffer: &mut Framebuffer, view_matrix: &Matrix, projection
         if sx >= 0 && sx < framebuffer.width && sy >= 0 && sy < framebuffer.height {
             // 🌟 Variedad de colores: 70% blanco, 20% azul, 10% amarillo
             let star_type = i % 10; // simple, determinista por índice (evita RNG extra)
-            let (r, g, b) = if star_type < 7 {
+            let (r, g, b) = if galaxy_star {
+                (GALAXY_BULGE_TINT.x, GALAXY_BULGE_TINT.y, GALAXY_BULGE_TINT.z)
+            } else if star_type < 7 {
                 (1.0, 1.0, 1.0) // blanca
             } else if star_type < 9 {
                 (0.6, 0.8, 1.0) // azulada (estrellas calientes)
             } else {
                 (1.0, 0.9, 0.7) // amarillenta (como el Sol, pero pequeñas)
             };
-            let brightness = 0.8_f32 + rng.f32() * 0.4_f32;
-            let star_color = Vector3::new(r * brightness, g * brightness, b * brightness);
-            framebuffer.point(sx, sy, star_color, clip_pos.z / clip_pos.w);
+            // Altura sobre el "horizonte": 90° en el centro de la pantalla, 0° en el borde
+            let edge_distance = clamp_f32((ndc.x * ndc.x + ndc.y * ndc.y).sqrt(), 0.0_f32, 1.0_f32);
+            let altitude_deg = 90.0_f32 * (1.0_f32 - edge_distance);
+            let scintillation = star_scintillation(sx, sy, time, i, altitude_deg);
+            let base_brightness = if galaxy_star { 0.25_f32 + rng.f32() * 0.2_f32 } else { 0.8_f32 + rng.f32() * 0.4_f32 };
+            let brightness = base_brightness * (1.0_f32 + scintillation);
+            // Hacia el bulbo todas las estrellas tiran a naranja
+            let warm = lerp_vec3(Vector3::new(r, g, b), GALAXY_BULGE_TINT, 0.5_f32 * galaxy_bulge_density(direction));
+            let star_color = mul_vec3_scalar(warm, brightness);
+            let depth = clip_pos.z / clip_pos.w;
+
+            // Dispersión: G y B se desplazan 0.5 y 1 px hacia el cenit, más cuanto más baja está
+            let dispersion = STAR_DISPERSION_PIXELS * altitude_deg.to_radians().cos();
+            if dispersion < 0.05_f32 {
+                framebuffer.point(sx, sy, star_color, depth);
+                continue;
+            }
+            let to_zenith = normalize_vec3(Vector3::new(
+                framebuffer.width as f32 * 0.5_f32 - sx as f32,
+                framebuffer.height as f32 * 0.5_f32 - sy as f32,
+                0.0_f32,
+            ));
+            for (channel, shift) in [(Vector3::new(star_color.x, 0.0_f32, 0.0_f32), 0.0_f32), (Vector3::new(0.0_f32, star_color.y, 0.0_f32), 0.5_f32), (Vector3::new(0.0_f32, 0.0_f32, star_color.z), 1.0_f32)] {
+                let offset = mul_vec3_scalar(to_zenith, shift * dispersion);
+                splat_star_channel(framebuffer, sx as f32 + offset.x, sy as f32 + offset.y, channel, depth);
+            }
         }
     }
 }
 
-fn draw_orbit_3d(framebuffer: &mut Framebuffer, orbit_radius: f32, orbit_color: Color, view_matrix: &Matrix, projection_matrix: &Matrix, viewport_matrix: &Matrix) {
-    let segments = 128;
+// Error máximo permitido entre la órbita real y cada segmento, en píxeles
+const ORBIT_MAX_CHORD_ERROR_PIXELS: f32 = 1.0;
+const ORBIT_MIN_SEGMENTS: u32 = 12;
+const ORBIT_MAX_SEGMENTS: u32 = 4096;
+
+/// Segmentos mínimos para que la órbita se vea suave. `screen_size` son los píxeles que ocupa
+/// una unidad de mundo a distancia 1 de la cámara (alto de pantalla / 2 / tan(fov / 2)).
+fn orbit_line_segment_count(orbit_radius: f32, camera_distance: f32, screen_size: f32) -> u32 {
+    let radius_pixels = orbit_radius / camera_distance.max(0.001_f32) * screen_size;
+    let segments = (2.0_f32 * PI * radius_pixels / ORBIT_MAX_CHORD_ERROR_PIXELS).ceil() as u32;
+    segments.clamp(ORBIT_MIN_SEGMENTS, ORBIT_MAX_SEGMENTS)
+}
+
+// Órbita alrededor de `center` como línea translúcida (alfa del color), con la profundidad real de
+// cada vértice para que solo la tapen los cuerpos que están delante. Con `dashed` solo se dibuja
+// un segmento de cada dos (órbitas de planetas sin descubrir).
+#[allow(clippy::too_many_arguments)]
+fn draw_orbit_3d(transparent: &mut Vec<TransparentItem>, center: Vector3, orbit_radius: f32, inclination: f32, ascending_node: f32, segments: u32, orbit_color: Color, dashed: bool, view_matrix: &Matrix, projection_matrix: &Matrix, viewport_matrix: &Matrix) {
     let angle_increment = 2.0_f32 * PI / segments as f32;
-    let mut prev_x = 0;
-    let mut prev_y = 0;
-    let mut first_point = true;
-    let mut first_x = 0;
-    let mut first_y = 0;
-    for i in 0..segments {
-        let angle = i as f32 * angle_increment;
-        let x = angle.cos() * orbit_radius;
-        let y = 0.0_f32;
-        let z = angle.sin() * orbit_radius;
-        let position_vec4 = Vector4::new(x, y, z, 1.0_f32);
-        let view_position = multiply_matrix_vector4(view_matrix, &position_vec4);
-        let clip_position = multiply_matrix_vector4(projection_matrix, &view_position);
-        let ndc = if clip_position.w != 0.0 {
-            Vector3::new(clip_position.x / clip_position.w, clip_position.y / clip_position.w, clip_position.z / clip_position.w)
-        } else {
-            Vector3::new(clip_position.x, clip_position.y, clip_position.z)
-        };
-        let ndc_vec4 = Vector4::new(ndc.x, ndc.y, ndc.z, 1.0_f32);
-        let screen_position = multiply_matrix_vector4(viewport_matrix, &ndc_vec4);
-        let screen_x = screen_position.x as i32;
-        let screen_y = screen_position.y as i32;
-        if i == 0 {
-            first_x = screen_x;
-            first_y = screen_y;
-        }
-        if !first_point {
-            framebuffer.draw_line_with_depth(prev_x, prev_y, screen_x, screen_y, orbit_color, 1000.0_f32);
-        } else {
-            first_point = false;
+    let color = Vector3::new(orbit_color.r as f32 / 255.0_f32, orbit_color.g as f32 / 255.0_f32, orbit_color.b as f32 / 255.0_f32);
+    let alpha = orbit_color.a as f32 / 255.0_f32;
+    let project = |i: u32| {
+        let point = add_vec3(center, orbital_position(orbit_radius, i as f32 * angle_increment, inclination, ascending_node));
+        project_to_screen_subpixel(point, view_matrix, projection_matrix, viewport_matrix)
+    };
+    // El último segmento cierra la órbita volviendo al vértice 0
+    for i in 1..=segments {
+        if dashed && i % 2 == 0 {
+            continue;
+        }
+        // Los segmentos con un extremo detrás de la cámara no se dibujan
+        if let (Some(from), Some(to)) = (project(i - 1), project(i % segments)) {
+            push_line(transparent, from, to, color, alpha);
+        }
+    }
+}
+
+// 🌠 Estela de la órbita: posiciones recientes del cuerpo en coordenadas de mundo
+#[derive(Clone)]
+struct OrbitTrail {
+    positions: VecDeque<Vector3>,
+    max_length: usize,
+    color: Color,
+    last_sample_time: f32,
+    newest_at_front: bool,
+}
+
+impl OrbitTrail {
+    const SAMPLE_INTERVAL: f32 = 0.1; // unidades de tiempo simulado entre muestras
+
+    fn new(max_length: usize, color: Color) -> Self {
+        OrbitTrail {
+            positions: VecDeque::with_capacity(max_length),
+            max_length,
+            color,
+            last_sample_time: f32::NEG_INFINITY,
+            newest_at_front: false,
         }
-        prev_x = screen_x;
-        prev_y = screen_y;
     }
-    if segments > 0 {
-        framebuffer.draw_line_with_depth(prev_x, prev_y, first_x, first_y, orbit_color, 1000.0_f32);
+
+    // Con el tiempo hacia atrás se empuja por delante: la estela muestra el futuro, no la historia
+    fn sample(&mut self, position: Vector3, time: f32, reversed: bool) {
+        if self.last_sample_time.is_finite() && (time - self.last_sample_time).abs() < Self::SAMPLE_INTERVAL {
+            return;
+        }
+        self.last_sample_time = time;
+        self.newest_at_front = reversed;
+        if reversed {
+            self.positions.push_front(position);
+            if self.positions.len() > self.max_length {
+                self.positions.pop_back();
+            }
+        } else {
+            self.positions.push_back(position);
+            if self.positions.len() > self.max_length {
+                self.positions.pop_front();
+            }
+        }
     }
 }
 
@@ -210,317 +602,2659 @@ struct CelestialBody {
     orbit_speed: f32,
     rotation_speed: f32,
     color: Color,
+    trail: Option<OrbitTrail>,
+    // Atmósfera: espesor (en radios del planeta) y densidad; más masa retiene más atmósfera
+    atmosphere_height: f32,
+    atmosphere_density: f32,
+    // Órbita inclinada (rad) con su nodo ascendente inicial (rad) y la precesión de éste por el
+    // J2 del Sol (rad por segundo simulado, se calcula al crear la escena)
+    inclination: f32,
+    ascending_node: f32,
+    node_precession_rate: f32,
+    // Masa de lente gravitatoria (G = c = 1, radio de Schwarzschild 2M); 0 si no curva la luz
+    lensing_mass: f32,
+    // Masa en masas terrestres (el Sol ~333000); 0 si no cuenta para el baricentro
+    mass: f32,
+    // Casilla del atlas de texturas pintada para este cuerpo (ver `texture.rs`)
+    atlas_tile: Option<u32>,
+    // Shader con el que se pinta (ver `render`), normalmente el mismo nombre del cuerpo
+    shader_name: String,
+    // Satélites: índice del cuerpo alrededor del que orbitan y la posición actual de éste
+    // (la actualiza `update_orbit_centers` cada frame; en el origen para los planetas)
+    parent: Option<usize>,
+    orbit_center: Vector3,
 }
 
-fn check_collision(pos1: Vector3, radius1: f32, pos2: Vector3, radius2: f32) -> bool {
-    let dx = pos1.x - pos2.x;
-    let dy = pos1.y - pos2.y;
-    let dz = pos1.z - pos2.z;
-    let dist_sq = dx*dx + dy*dy + dz*dz;
-    dist_sq < (radius1 + radius2)*(radius1 + radius2)
+// Catálogo de cuerpos celestes (ver `catalog.rs`)
+const BODY_CATALOG_PATH: &str = "./assets/bodies.json";
+
+// Radio de la malla de los planetas antes de escalar: la cube-sphere se genera con él y
+// `sphere.obj` se normaliza a él al cargarla (es también su esfera envolvente)
+const PLANET_MESH_RADIUS: f32 = 0.52;
+
+// Ángulo exacto `time * speed` reducido a [0, 2π). Se calcula en f64 y con módulo en vez de
+// acumular por frame, así no hay deriva aunque `time_scale` sea alto o la simulación muy larga.
+fn angle_at(time: f32, speed: f32) -> f32 {
+    (time as f64 * speed as f64).rem_euclid(std::f64::consts::TAU) as f32
 }
 
-fn avoid_collision(camera_pos: Vector3, target_pos: Vector3, celestial_bodies: &[CelestialBody], time: f32) -> (Vector3, Vector3) {
-    let mut new_camera_pos = camera_pos;
-    let mut new_target_pos = target_pos;
-    for body in celestial_bodies {
-        let body_pos = if body.name != "Sun" {
-            let x = (time * body.orbit_speed).cos() * body.orbit_radius;
-            let z = (time * body.orbit_speed).sin() * body.orbit_radius;
-            Vector3::new(x, 0.0_f32, z)
-        } else {
-            body.translation
-        };
-        let camera_radius = 2.0_f32;
-        let body_radius = body.scale * 0.8_f32;
-        if check_collision(new_camera_pos, camera_radius, body_pos, body_radius) {
-            let dx = new_camera_pos.x - body_pos.x;
-            let dy = new_camera_pos.y - body_pos.y;
-            let dz = new_camera_pos.z - body_pos.z;
-            let dist = (dx*dx + dy*dy + dz*dz).sqrt();
-            if dist > 0.0 {
-                let min_dist = body_radius + camera_radius;
-                let scale = min_dist / dist;
-                new_camera_pos.x = body_pos.x + dx * scale;
-                new_camera_pos.y = body_pos.y + dy * scale;
-                new_camera_pos.z = body_pos.z + dz * scale;
-            }
-        }
-        if check_collision(new_target_pos, camera_radius, body_pos, body_radius) {
-            let dx = new_target_pos.x - body_pos.x;
-            let dy = new_target_pos.y - body_pos.y;
-            let dz = new_target_pos.z - body_pos.z;
-            let dist = (dx*dx + dy*dy + dz*dz).sqrt();
-            if dist > 0.0 {
-                let min_dist = body_radius + camera_radius;
-                let scale = min_dist / dist;
-                new_target_pos.x = body_pos.x + dx * scale;
-                new_target_pos.y = body_pos.y + dy * scale;
-                new_target_pos.z = body_pos.z + dz * scale;
-            }
+// El Sol se reconoce por su shader, no por su nombre ni por su sitio en el catálogo
+fn is_sun(body: &CelestialBody) -> bool {
+    body.shader_name == "Sun"
+}
+
+// Posición actual del cuerpo en su órbita (el Sol queda fijo en su traslación)
+fn body_position(body: &CelestialBody, time: f32) -> Vector3 {
+    if !is_sun(body) {
+        let angle = angle_at(time, body.orbit_speed);
+        add_vec3(body.orbit_center, orbital_position(body.orbit_radius, angle, body.inclination, ascending_node_at(body, time)))
+    } else {
+        body.translation
+    }
+}
+
+// Lleva el centro de la órbita de cada satélite a la posición de su padre en `time`. El catálogo
+// pone los padres antes que sus satélites, así un recorrido en orden basta para lunas de lunas.
+fn update_orbit_centers(celestial_bodies: &mut [CelestialBody], time: f32) {
+    for i in 0..celestial_bodies.len() {
+        if let Some(parent) = celestial_bodies[i].parent {
+            celestial_bodies[i].orbit_center = body_position(&celestial_bodies[parent], time);
         }
     }
-    (new_camera_pos, new_target_pos)
 }
 
-// Estado para warping animado
-#[derive(Clone)]
-struct WarpTarget {
-    eye: Vector3,
-    target: Vector3,
-    up: Vector3,
+// Longitud del nodo ascendente en `time`, ya con la precesión acumulada, en [0, 2π)
+fn ascending_node_at(body: &CelestialBody, time: f32) -> f32 {
+    (body.ascending_node + angle_at(time, body.node_precession_rate)).rem_euclid(2.0_f32 * PI)
 }
 
-impl WarpTarget {
-    fn to_camera_state(&self) -> Camera {
-        Camera::new(self.eye, self.target, self.up)
-    }
+// Radio visible del cuerpo en unidades de mundo (el de la esfera envolvente de su malla)
+fn body_radius(body: &CelestialBody) -> f32 {
+    body.scale * PLANET_MESH_RADIUS
 }
 
-// 🌟 Nueva función: interpolar entre dos cámaras (Vector3)
-fn lerp_vec3(a: Vector3, b: Vector3, t: f32) -> Vector3 {
-    Vector3::new(
-        a.x + (b.x - a.x) * t,
-        a.y + (b.y - a.y) * t,
-        a.z + (b.z - a.z) * t,
-    )
+// Cuerpo alrededor del que orbita: su planeta si es un satélite, si no el Sol
+fn primary_body<'a>(body: &CelestialBody, celestial_bodies: &'a [CelestialBody]) -> Option<&'a CelestialBody> {
+    match body.parent {
+        Some(parent) => celestial_bodies.get(parent),
+        None if !is_sun(body) => celestial_bodies.iter().find(|candidate| is_sun(candidate)),
+        None => None,
+    }
 }
 
-// 🌟 Nueva función: ease-in-out cúbico
-fn ease_in_out(t: f32) -> f32 {
-    if t < 0.5 {
-        4.0 * t * t * t
-    } else {
-        1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+// 🛰️ Precesión de los nodos por el achatamiento del Sol (órbitas circulares: e = 0). Los
+// satélites no la tienen: su cuerpo central no es el Sol. Devuelve false si no hay Sol
+fn apply_sun_node_precession(celestial_bodies: &mut [CelestialBody]) -> bool {
+    let Some(sun_radius) = celestial_bodies.iter().find(|body| is_sun(body)).map(body_radius) else {
+        return false;
+    };
+    for body in celestial_bodies.iter_mut().filter(|body| body.parent.is_none()) {
+        body.node_precession_rate = j2_precession_rate(body.orbit_radius, 0.0_f32, body.inclination, SUN_J2, sun_radius, body.orbit_speed);
     }
+    true
 }
 
-fn main() {
-    let window_width = 1300;
-    let window_height = 900;
-    let (mut window, raylib_thread) = raylib::init()
-        .size(window_width, window_height)
-        .title("Proyecto 3 - Sistema Solar")
-        .log_level(TraceLogLevel::LOG_WARNING)
-        .build();
+// Radio de la esfera de Hill respecto a su primario; None si alguno de los dos no tiene masa
+fn hill_radius_of(body: &CelestialBody, celestial_bodies: &[CelestialBody]) -> Option<f32> {
+    let primary = primary_body(body, celestial_bodies)?;
+    (body.mass > 0.0_f32 && primary.mass > 0.0_f32).then(|| hill_sphere_radius(body.orbit_radius, body.mass, primary.mass))
+}
 
-    let mut framebuffer = Framebuffer::new(window_width, window_height);
+// 🔍 Con la cámara a menos de NEAR_SURFACE_RADII radios del centro, la esfera del cuerpo se
+// subdivide alrededor de la cámara. La malla se rehace cuando la cámara (en el espacio de la malla)
+// se ha movido más de NEAR_SURFACE_REFRESH_FRACTION de su altura sobre la superficie.
+const NEAR_SURFACE_RADII: f32 = 2.0;
+const NEAR_SURFACE_MAX_SUBDIVISION: u32 = 3;
+const NEAR_SURFACE_REFRESH_FRACTION: f32 = 0.1;
 
-    // Alejar la cámara para ver mejor todo el sistema
-    let initial_camera_pos = Vector3::new(0.0_f32, 40.0_f32, 140.0_f32);
-    let initial_camera_target = Vector3::new(0.0_f32, 0.0_f32, 0.0_f32);
-    let initial_camera_up = Vector3::new(0.0_f32, 1.0_f32, 0.0_f32);
-    let mut camera = Camera::new(initial_camera_pos, initial_camera_target, initial_camera_up);
+struct NearSurfaceMesh {
+    camera_local: Vector3,
+    vertices: Vec<Vertex>,
+}
 
-    let light = Light::new(Vector3::new(0.0_f32, 0.0_f32, 0.0_f32));
+// Punto de mundo llevado al espacio de la malla del cuerpo en `time` (deshace traslación, escala y giro)
+fn to_body_space(body: &CelestialBody, time: f32, point: Vector3) -> Vector3 {
+    let offset = mul_vec3_scalar(sub_vec3(point, body_position(body, time)), 1.0_f32 / body.scale);
+    let inverse = create_rotation_matrix_y(-angle_at(time, body.rotation_speed));
+    let local = multiply_matrix_vector4(&inverse, &Vector4::new(offset.x, offset.y, offset.z, 0.0_f32));
+    Vector3::new(local.x, local.y, local.z)
+}
 
-    // Cargar nave y esfera (sphere como malla de planetas). Añadir logging y comprobación.
-    let ship_obj = match Obj::load("./assets/nave.obj") {
-        Ok(o) => {
-            eprintln!("Loaded ./assets/nave.obj successfully");
-            o
-        },
-        Err(e) => panic!("Failed to load ./assets/nave.obj: {}", e),
-    };
-    let nave_vertex_array = ship_obj.get_vertex_array();
-    if nave_vertex_array.is_empty() {
-        panic!("nave.obj vertex array empty — check model export");
-    } else {
-        eprintln!("nave.obj vertex count = {}", nave_vertex_array.len());
+// Rehace la malla de cerca de los cuerpos a los que se ha acercado la cámara y olvida las demás
+fn update_near_surface_meshes(cache: &mut HashMap<String, NearSurfaceMesh>, celestial_bodies: &[CelestialBody], planet_vertex_array: &[Vertex], camera_pos: Vector3, time: f32) {
+    for body in celestial_bodies {
+        let near = length_vec3(sub_vec3(camera_pos, body_position(body, time))) < body_radius(body) * NEAR_SURFACE_RADII;
+        if !near || body.lensing_mass > 0.0 {
+            cache.remove(&body.name);
+            continue;
+        }
+        let camera_local = to_body_space(body, time, camera_pos);
+        let altitude = (length_vec3(camera_local) - PLANET_MESH_RADIUS).max(0.001_f32);
+        let stale = cache
+            .get(&body.name)
+            .is_none_or(|mesh| length_vec3(sub_vec3(mesh.camera_local, camera_local)) > altitude * NEAR_SURFACE_REFRESH_FRACTION);
+        if stale {
+            let vertices = adaptive_subdivide(planet_vertex_array, camera_local, Vector3::zero(), NEAR_SURFACE_MAX_SUBDIVISION);
+            cache.insert(body.name.clone(), NearSurfaceMesh { camera_local, vertices });
+        }
     }
+}
 
-    let planet_vertex_array = match Obj::load("./assets/sphere.obj") {
-        Ok(sphere) => {
-            eprintln!("Loaded ./assets/sphere.obj successfully");
-            sphere.get_vertex_array()
-        },
-        Err(_) => {
-            eprintln!("Warning: ./assets/sphere.obj not found — using nave mesh as fallback for planets");
-            nave_vertex_array.clone()
+// Cuerpo más cercano al centro de la vista (el "seleccionado"), si hay alguno delante de la cámara
+fn body_in_view(camera: &Camera, celestial_bodies: &[CelestialBody], time: f32) -> Option<usize> {
+    let forward = normalize_vec3(sub_vec3(camera.target, camera.eye));
+    let mut best: Option<(usize, f32)> = None;
+    for (i, body) in celestial_bodies.iter().enumerate() {
+        let to_body = normalize_vec3(sub_vec3(body_position(body, time), camera.eye));
+        let alignment = forward.x * to_body.x + forward.y * to_body.y + forward.z * to_body.z;
+        if alignment > 0.0 && best.is_none_or(|(_, a)| alignment > a) {
+            best = Some((i, alignment));
         }
-    };
-
-
-    framebuffer.set_background_color(Color::new(0, 0, 0, 255)); 
+    }
+    best.map(|(i, _)| i)
+}
 
-    let sun = CelestialBody {
-        name: "Sun".to_string(),
-        translation: Vector3::new(0.0_f32, 0.0_f32, 0.0_f32),
-        scale: 15.0_f32,
-        rotation: Vector3::new(0.0_f32, 0.0_f32, 0.0_f32),
-        orbit_radius: 0.0_f32,
-        orbit_speed: 0.0_f32,
-        rotation_speed: 0.5_f32,
-        color: Color::new(255, 255, 0, 255),
-    };
-    let mercury = CelestialBody {
-        name: "Mercury".to_string(),
-        translation: Vector3::new(0.0_f32, 0.0_f32, 0.0_f32),
-        scale: 2.0_f32,
-        rotation: Vector3::new(0.0_f32, 0.0_f32, 0.0_f32),
-        orbit_radius: 15.0_f32,
-        orbit_speed: 0.8_f32,
-        rotation_speed: 2.0_f32,
-        color: Color::new(169, 169, 169, 255),
-    };
-    let earth = CelestialBody {
-        name: "Earth".to_string(),
-        translation: Vector3::new(0.0_f32, 0.0_f32, 0.0_f32),
-        scale: 3.0_f32,
-        rotation: Vector3::new(0.0_f32, 0.0_f32, 0.0_f32),
-        orbit_radius: 25.0_f32,
-        orbit_speed: 0.5_f32,
-        rotation_speed: 1.5_f32,
-        color: Color::new(0, 100, 200, 255),
-    };
-    let mars = CelestialBody {
-        name: "Mars".to_string(),
-        translation: Vector3::new(0.0_f32, 0.0_f32, 0.0_f32),
-        scale: 2.5_f32,
-        rotation: Vector3::new(0.0_f32, 0.0_f32, 0.0_f32),
-        orbit_radius: 35.0_f32,
-        orbit_speed: 0.3_f32,
-        rotation_speed: 1.2_f32,
-        color: Color::new(205, 92, 92, 255),
-    };
-    let uranus = CelestialBody {
-        name: "Uranus".to_string(),
-        translation: Vector3::new(0.0_f32, 0.0_f32, 0.0_f32),
-        scale: 5.0_f32,
-        rotation: Vector3::new(0.0_f32, 0.0_f32, 0.0_f32),
-        orbit_radius: 45.0_f32,
-        orbit_speed: 0.1_f32,
-        rotation_speed: 0.8_f32,
-        color: Color::new(173, 216, 230, 255),
-    };
+// 🔭 FOV vertical necesario para que una esfera de `radius` a `distance` ocupe
+// `screen_fraction` de la altura de la pantalla
+fn fov_to_frame(radius: f32, distance: f32, screen_fraction: f32) -> f32 {
+    let half_angle = (radius / distance.max(radius)).asin();
+    2.0_f32 * (half_angle.tan() / screen_fraction).atan()
+}
 
-    let celestial_bodies = vec![sun, mercury.clone(), earth.clone(), mars.clone(), uranus.clone()];
+// Proyecta un punto de mundo a pantalla; `None` si queda detrás de la cámara
+fn project_to_screen(position: Vector3, view_matrix: &Matrix, projection_matrix: &Matrix, viewport_matrix: &Matrix) -> Option<(i32, i32, f32)> {
+    project_to_screen_subpixel(position, view_matrix, projection_matrix, viewport_matrix).map(|(x, y, z)| ((x + 0.5_f32) as i32, (y + 0.5_f32) as i32, z))
+}
 
-    // 🌟 Definir posiciones de warp (animado)
-    let warp_targets = [
-        WarpTarget { eye: initial_camera_pos, target: initial_camera_target, up: initial_camera_up },
-        WarpTarget {
-            eye: Vector3::new(0.0_f32, 100.0_f32, 0.0_f32),
-            target: Vector3::new(0.0_f32, 0.0_f32, 0.0_f32),
-            up: Vector3::new(0.0_f32, 0.0_f32, -1.0_f32),
-        },
-        WarpTarget {
-            eye: Vector3::new(0.0_f32, 20.0_f32, earth.orbit_radius + 20.0_f32),
-            target: Vector3::new(0.0_f32, -15.0_f32, 0.0_f32),
-            up: Vector3::new(0.0_f32, 1.0_f32, 0.0_f32),
-        },
-        WarpTarget {
-            eye: Vector3::new(0.0_f32, 15.0_f32, mars.orbit_radius + 20.0_f32),
-            target: Vector3::new(0.0_f32, -10.0_f32, 0.0_f32),
-            up: Vector3::new(0.0_f32, 1.0_f32, 0.0_f32),
-        },
-        WarpTarget {
-            eye: Vector3::new(0.0_f32, 10.0_f32, uranus.orbit_radius + 20.0_f32),
-            target: Vector3::new(0.0_f32, -5.0_f32, 0.0_f32),
-            up: Vector3::new(0.0_f32, 1.0_f32, 0.0_f32),
-        },
-    ];
+// Como `project_to_screen` pero sin redondear, con el centro de cada píxel en coordenadas enteras
+// (lo que espera `wu_line`)
+fn project_to_screen_subpixel(position: Vector3, view_matrix: &Matrix, projection_matrix: &Matrix, viewport_matrix: &Matrix) -> Option<(f32, f32, f32)> {
+    let view_pos = multiply_matrix_vector4(view_matrix, &Vector4::new(position.x, position.y, position.z, 1.0_f32));
+    let clip_pos = multiply_matrix_vector4(projection_matrix, &view_pos);
+    if clip_pos.w <= 0.0 {
+        return None;
+    }
+    let ndc = Vector4::new(clip_pos.x / clip_pos.w, clip_pos.y / clip_pos.w, clip_pos.z / clip_pos.w, 1.0_f32);
+    let screen = multiply_matrix_vector4(viewport_matrix, &ndc);
+    Some((screen.x - 0.5_f32, screen.y - 0.5_f32, screen.z))
+}
 
-    let mut time = 0.0_f32;
-    let mut is_warping = false;
-    let mut warp_start_time = 0.0_f32;
-    let mut warp_duration = 1.0_f32; // segundos
-    let mut current_warp_index = 0_usize;
+// 🌠 Dibuja la estela como una polilínea translúcida que se desvanece desde la posición actual del cuerpo
+fn draw_orbit_trail(transparent: &mut Vec<TransparentItem>, trail: &OrbitTrail, view_matrix: &Matrix, projection_matrix: &Matrix, viewport_matrix: &Matrix) {
+    let count = trail.positions.len();
+    if count < 2 {
+        return;
+    }
+    let color = Vector3::new(trail.color.r as f32 / 255.0_f32, trail.color.g as f32 / 255.0_f32, trail.color.b as f32 / 255.0_f32);
+    let mut prev: Option<(f32, f32, f32)> = None;
+    for (i, position) in trail.positions.iter().enumerate() {
+        let current = project_to_screen_subpixel(*position, view_matrix, projection_matrix, viewport_matrix);
+        if let (Some(from), Some(to)) = (prev, current) {
+            // Opacidad 1 en la muestra más nueva, 0 en la más vieja
+            let age = if trail.newest_at_front { i as f32 } else { (count - 1 - i) as f32 };
+            let opacity = 1.0_f32 - age / (count - 1) as f32;
+            push_line(transparent, from, to, color, opacity);
+        }
+        prev = current;
+    }
+}
 
-    // Posición segura inicial de cámara (para restaurar si algo sale mal)
-    let mut safe_camera_eye = camera.eye;
-    let mut safe_camera_target = camera.target;
+// Planos de recorte por defecto (`projection.toml` los cambia, ver `ClipPlanes`). Con 0.5 / 600
+// la razón lejano/cercano es 1200:1 en vez de 10000:1 y el depth buffer en f32 aún separa
+// superficies a unas décimas de unidad en el fondo de la escena
+const NEAR_PLANE: f32 = 0.5;
+const FAR_PLANE: f32 = 600.0;
+const PROJECTION_CONFIG_PATH: &str = "projection.toml";
+// Distancia (en unidades de mundo) en la que una partícula se desvanece al tocar una superficie
+const PARTICLE_FADE_DISTANCE: f32 = 0.6;
 
-    // Parámetros para posicionar la nave relativa a la cámara (nave sigue la cámara)
-    let nave_offset_back = 6.0_f32;        // cuánto queda detrás del ojo (positivo = atrás)
-    let nave_offset_down = 2.5_f32;        // cuánto hacia abajo respecto al eye
-    let default_nave_scale = 1.0_f32;      // ajustar según tu modelo
-    let nave_model_offset_forward = 0.4_f32; // compensación por pivote del modelo (hacia el frente)
+// ✨ Partículas como discos suaves; cada píxel se atenúa según la superficie que tiene detrás
+#[allow(clippy::too_many_arguments)]
+fn draw_particles(framebuffer: &mut Framebuffer, particles: &ParticleSystem, camera: &Camera, clip_planes: ClipPlanes, pixels_per_unit: f32, view_matrix: &Matrix, projection_matrix: &Matrix, viewport_matrix: &Matrix) {
+    for particle in &particles.particles {
+        let Some((sx, sy, depth)) = project_to_screen(particle.position, view_matrix, projection_matrix, viewport_matrix) else {
+            continue;
+        };
+        let particle_distance = linearize_depth(depth, clip_planes.near, clip_planes.far);
+        let distance = length_vec3(sub_vec3(particle.position, camera.eye)).max(NEAR_PLANE);
+        let radius = (particle.size / distance * pixels_per_unit).max(1.0_f32);
+        let r = radius.ceil() as i32;
+        let base_alpha = particle.life_ratio();
+        for dy in -r..=r {
+            for dx in -r..=r {
+                let falloff = 1.0_f32 - ((dx * dx + dy * dy) as f32).sqrt() / radius;
+                if falloff <= 0.0 {
+                    continue;
+                }
+                let (x, y) = (sx + dx, sy + dy);
+                // Sin nada detrás (cielo, profundidad infinita) no hay con qué fundirse
+                let background_depth = framebuffer.get_depth_at(x, y);
+                let soft = if background_depth.is_finite() {
+                    soft_particle_factor(particle_distance, linearize_depth(background_depth, clip_planes.near, clip_planes.far), PARTICLE_FADE_DISTANCE)
+                } else {
+                    1.0_f32
+                };
+                framebuffer.blend_point(x, y, particle.color, base_alpha * falloff * soft, depth);
+            }
+        }
+    }
+}
 
-    // Parámetros de navegación libre (control 3D)
-    let base_speed = 40.0_f32;      // unidades / s
-    let sprint_mult = 2.2_f32;
-    let yaw_speed = 1.8_f32;        // rad/s (flechas izquierda/derecha)
-    let pitch_speed = 1.2_f32;      // rad/s (flechas arriba/abajo)
+// 💍 Polvo de los anillos: puntos de menos de un píxel sumados (mezcla aditiva) con un brillo
+// proporcional al área que cubrirían y a la vida que les queda
+#[allow(clippy::too_many_arguments)]
+fn draw_ring_dust(framebuffer: &mut Framebuffer, ring_dust: &RingDustSystem, saturn_position: Vector3, saturn_radius: f32, camera: &Camera, pixels_per_unit: f32, view_matrix: &Matrix, projection_matrix: &Matrix, viewport_matrix: &Matrix) {
+    for particle in &ring_dust.particles {
+        let position = add_vec3(saturn_position, mul_vec3_scalar(particle.position, saturn_radius));
+        let Some((sx, sy, depth)) = project_to_screen(position, view_matrix, projection_matrix, viewport_matrix) else {
+            continue;
+        };
+        let distance = length_vec3(sub_vec3(position, camera.eye)).max(NEAR_PLANE);
+        let size_px = particle.size * saturn_radius / distance * pixels_per_unit;
+        let coverage = clamp_f32(size_px * size_px, 0.05_f32, 1.0_f32);
+        framebuffer.add_point(sx, sy, mul_vec3_scalar(particle.color, coverage * particle.life_ratio()), depth);
+    }
+}
 
-    while !window.window_should_close() {
-        let dt = window.get_frame_time();
-        time += dt;
+// ☀️ Plasma de las fulguraciones y del viento solar (relativo al Sol, en radios solares): puntos
+// brillantes sumados como el polvo de los anillos
+#[allow(clippy::too_many_arguments)]
+fn draw_solar_plasma(framebuffer: &mut Framebuffer, particles: &[Particle], sun_position: Vector3, sun_radius: f32, camera: &Camera, pixels_per_unit: f32, view_matrix: &Matrix, projection_matrix: &Matrix, viewport_matrix: &Matrix) {
+    for particle in particles {
+        let position = add_vec3(sun_position, mul_vec3_scalar(particle.position, sun_radius));
+        let Some((sx, sy, depth)) = project_to_screen(position, view_matrix, projection_matrix, viewport_matrix) else {
+            continue;
+        };
+        let distance = length_vec3(sub_vec3(position, camera.eye)).max(NEAR_PLANE);
+        let size_px = particle.size * sun_radius / distance * pixels_per_unit;
+        let coverage = clamp_f32(size_px * size_px, 0.2_f32, 1.0_f32);
+        let brightness = coverage * (0.3_f32 + 0.7_f32 * particle.life_ratio());
+        framebuffer.add_point(sx, sy, mul_vec3_scalar(particle.color, brightness), depth);
+    }
+}
 
-        // Guardar posición segura previa
-        let prev_eye = camera.eye;
-        let prev_target = camera.target;
+// 🧊 Neptuno no está en el catálogo: semieje con el que sus resonancias 2:3 y 3:5 caen dentro
+// del cinturón de Kuiper (a 81 y 87 unidades)
+const NEPTUNE_ORBIT_RADIUS: f32 = 62.0;
 
-        // 🌟 Warping animado
-        if !is_warping {
-            for (i, key) in [
-                KeyboardKey::KEY_ONE,
-                KeyboardKey::KEY_TWO,
-                KeyboardKey::KEY_THREE,
-                KeyboardKey::KEY_FOUR,
-                KeyboardKey::KEY_FIVE,
-            ]
-            .iter()
-            .enumerate()
-            {
-                if window.is_key_pressed(*key) && i < warp_targets.len() {
-                    is_warping = true;
-                    warp_start_time = time;
-                    current_warp_index = i;
-                }
-            }
+// Objetos del cinturón de Kuiper: un punto aditivo por objeto (más brillante cuanto más grande),
+// también en la vista cenital, donde se ven los huecos de las resonancias
+fn draw_kuiper_belt(framebuffer: &mut Framebuffer, belt: &KuiperBeltSimulation, view_matrix: &Matrix, projection_matrix: &Matrix, viewport_matrix: &Matrix) {
+    for object in &belt.objects {
+        if let Some((sx, sy, depth)) = project_to_screen(object.position, view_matrix, projection_matrix, viewport_matrix) {
+            framebuffer.add_point(sx, sy, mul_vec3_scalar(object.color, object.radius * 6.0_f32), depth);
         }
+    }
+}
 
-        if is_warping {
-            let t = ((time - warp_start_time) / warp_duration).min(1.0_f32);
-            let eased_t = ease_in_out(t);
+// 🌀 Túnel del warp: las estelas pasan junto a la cámara a esta velocidad (unidades por segundo real)
+const WARP_TUNNEL_SPEED: f32 = 400.0;
+const WARP_TUNNEL_SEGMENTS: u32 = 24;
 
-            // en lugar de `camera.clone()` tomamos los campos directamente
-            let start_eye = camera.eye;
-            let start_target = camera.target;
-            let start_up = camera.up;
-            // Si `Camera` tiene yaw/pitch/distance expuestos los leemos directamente
-            // (tu código original los usa, así que los copiamos aquí)
-            let start_yaw = camera.yaw;
-            let start_pitch = camera.pitch;
-            let start_distance = camera.distance;
+// Matriz de mundo del túnel: su eje +Y apunta hacia donde mira la cámara y la boca queda detrás
+// del ojo, retrasada `scroll` módulo el periodo de las estelas para que parezcan avanzar sin fin
+fn warp_tunnel_model_matrix(eye: Vector3, target: Vector3, scroll: f32) -> Matrix {
+    let forward = normalize_vec3(sub_vec3(target, eye));
+    let side = if forward.y.abs() > 0.99_f32 { Vector3::new(1.0_f32, 0.0_f32, 0.0_f32) } else { Vector3::new(0.0_f32, 1.0_f32, 0.0_f32) };
+    let right = normalize_vec3(forward.cross(side));
+    let third = right.cross(forward);
+    // Columnas: X -> right, Y -> forward, Z -> third
+    let rotation = new_matrix3(
+        right.x, forward.x, third.x,
+        right.y, forward.y, third.y,
+        right.z, forward.z, third.z,
+    );
+    let mouth = sub_vec3(eye, mul_vec3_scalar(forward, scroll % WARP_STREAK_PERIOD));
+    create_model_matrix_with_rotation(mouth, 1.0_f32, rotation)
+}
 
-            let target_cam = warp_targets[current_warp_index].to_camera_state();
+// ☄️ Cometa en una órbita muy excéntrica: perihelio a 9 unidades del Sol (donde sublima) y
+// afelio más allá de Plutón
+const COMET_SEMI_MAJOR_AXIS: f32 = 60.0;
+const COMET_ECCENTRICITY: f32 = 0.85;
+const COMET_INCLINATION: f32 = 0.35;
+const COMET_ASCENDING_NODE: f32 = 1.2;
+const COMET_MEAN_MOTION: f32 = 0.008; // rad por segundo simulado
+const COMET_NUCLEUS_RADIUS: f32 = 0.35;
+const COMET_NUCLEUS_SEED: u64 = 1986;
+// Radio de la coma con el núcleo a plena actividad y su color (luz solar dispersada por el polvo
+// con algo de la emisión azulada del gas)
+const COMET_COMA_RADIUS: f32 = 2.5;
+const COMET_COMA_COLOR: Vector3 = Vector3::new(0.35, 0.45, 0.5);
+// Radio máximo de la coma en pantalla, para acotar el coste de verla de muy cerca
+const COMET_COMA_MAX_PIXELS: f32 = 300.0;
 
-            // interpolamos campos
-            camera.eye = lerp_vec3(start_eye, target_cam.eye, eased_t);
-            camera.target = lerp_vec3(start_target, target_cam.target, eased_t);
-            camera.up = lerp_vec3(start_up, target_cam.up, eased_t);
+fn comet_position(time: f32) -> Vector3 {
+    kepler_position(COMET_SEMI_MAJOR_AXIS, COMET_ECCENTRICITY, angle_at(time, COMET_MEAN_MOTION), COMET_INCLINATION, COMET_ASCENDING_NODE)
+}
 
-            camera.yaw = start_yaw + (target_cam.yaw - start_yaw) * eased_t;
-            camera.pitch = start_pitch + (target_cam.pitch - start_pitch) * eased_t;
-            camera.distance = start_distance + (target_cam.distance - start_distance) * eased_t;
+// Actividad [0, 1] del núcleo en `position`: 0 mientras la superficie no llega a sublimar y 1 con
+// el doble de la temperatura de sublimación (el Sol está en el origen)
+fn comet_activity(position: Vector3) -> f32 {
+    let temperature = comet_surface_temperature(length_vec3(position));
+    clamp_f32((temperature - COMET_SUBLIMATION_TEMP) / COMET_SUBLIMATION_TEMP, 0.0_f32, 1.0_f32)
+}
 
-            if t >= 1.0 {
-                is_warping = false;
-                // Asegurar valores exactos al final
-                camera = warp_targets[current_warp_index].to_camera_state();
-            }
-        } else {
+// Coma: un halo aditivo y suave alrededor del núcleo, más denso en el centro
+#[allow(clippy::too_many_arguments)]
+fn render_comet_coma(framebuffer: &mut Framebuffer, nucleus_pos: Vector3, coma_radius: f32, camera: &Camera, pixels_per_unit: f32, view_matrix: &Matrix, projection_matrix: &Matrix, viewport_matrix: &Matrix) {
+    let Some((cx, cy, depth)) = project_to_screen(nucleus_pos, view_matrix, projection_matrix, viewport_matrix) else {
+        return;
+    };
+    let distance = length_vec3(sub_vec3(nucleus_pos, camera.eye)).max(NEAR_PLANE);
+    let radius_px = (coma_radius / distance * pixels_per_unit).min(COMET_COMA_MAX_PIXELS);
+    if radius_px < 1.0_f32 {
+        return;
+    }
+    let extent = radius_px.ceil() as i32;
+    for dy in -extent..=extent {
+        for dx in -extent..=extent {
+            let r2 = (dx * dx + dy * dy) as f32 / (radius_px * radius_px);
+            if r2 >= 1.0_f32 {
+                continue;
+            }
+            let haze = (1.0_f32 - r2) * (1.0_f32 - r2) * 0.25_f32;
+            framebuffer.add_point(cx + dx, cy + dy, mul_vec3_scalar(COMET_COMA_COLOR, haze), depth);
+        }
+    }
+}
+
+// Chorros del cometa: puntos aditivos que se apagan con la vida de cada partícula
+fn draw_comet_jets(framebuffer: &mut Framebuffer, particles: &[Particle], camera: &Camera, pixels_per_unit: f32, view_matrix: &Matrix, projection_matrix: &Matrix, viewport_matrix: &Matrix) {
+    for particle in particles {
+        let Some((sx, sy, depth)) = project_to_screen(particle.position, view_matrix, projection_matrix, viewport_matrix) else {
+            continue;
+        };
+        let distance = length_vec3(sub_vec3(particle.position, camera.eye)).max(NEAR_PLANE);
+        let size_px = particle.size / distance * pixels_per_unit;
+        let coverage = clamp_f32(size_px * size_px, 0.2_f32, 1.0_f32);
+        framebuffer.add_point(sx, sy, mul_vec3_scalar(particle.color, coverage * particle.life_ratio()), depth);
+    }
+}
+
+// 🧱 Forward sombrea cada fragmento que pasa el test de profundidad en ese momento; Deferred hace
+// antes una pasada solo de profundidad con toda la geometría opaca y después sombrea únicamente
+// el fragmento visible de cada píxel (sin sobredibujado en el sombreado)
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum RenderMode {
+    Forward,
+    Deferred,
+}
+
+impl RenderMode {
+    // Con MSAA la profundidad va por muestra y la pasada previa solo llena el depth buffer del
+    // píxel: se dibuja en Forward aunque se haya pedido Deferred
+    fn effective(self, msaa_level: u32) -> Self {
+        if msaa_level > 0 {
+            RenderMode::Forward
+        } else {
+            self
+        }
+    }
+}
+
+// 🪟 Proyección de cada vista: perspectiva para el piloto, ortográfica para la vista cenital
+#[derive(Clone, Copy)]
+enum ViewProjection {
+    Perspective { fov: f32 },
+    Orthographic { half_height: f32 },
+}
+
+// Rayos de sombra por fragmento: uno (sombra dura) o varios para la penumbra (F4)
+const SHADOW_QUALITY_FAST: u32 = 1;
+const SHADOW_QUALITY_HIGH: u32 = 8;
+
+// 🌑 SSAO (F6): muestras por píxel, radio del hemisferio (unidades de mundo) y margen de profundidad
+const SSAO_KERNEL_SIZE: u32 = 16;
+const SSAO_RADIUS: f32 = 0.5;
+const SSAO_BIAS: f32 = 0.02;
+// 🎞️ Cadena de post-procesos: "/pp <pase>" enciende o apaga un pase, F7 guarda la configuración
+const POSTPROCESS_CONFIG_PATH: &str = "postprocess.toml";
+
+// Lo que se dibuja en cada vista de la escena (igual para todas las vistas del frame)
+struct SceneFrame<'a> {
+    celestial_bodies: &'a [CelestialBody],
+    planet_vertex_array: &'a [Vertex],
+    // Atlas de texturas de los planetas, si se pudo cargar
+    planet_atlas: Option<&'a TextureAtlas>,
+    saturn_ring_vertex_array: &'a [Vertex],
+    uranus_ring_vertex_array: &'a [Vertex],
+    // Piezas de la nave: malla, matriz de mundo y shader
+    nave_parts: &'a [(&'a [Vertex], Matrix, &'a str)],
+    nave_ambient_color: Vector3,
+    particles: &'a ParticleSystem,
+    ring_dust: &'a RingDustSystem,
+    // ☀️ Fulguración activa (si hay) y el viento solar que alimenta
+    solar_flare: Option<&'a SolarFlare>,
+    solar_wind: &'a SolarWindSystem,
+    // ☄️ Núcleo del cometa y sus chorros de sublimación
+    comet_nucleus_vertex_array: &'a [Vertex],
+    comet_jets: &'a CometJetSystem,
+    // 🧊 Cinturón de Kuiper simulado con gravedad
+    kuiper_belt: &'a KuiperBeltSimulation,
+    // 🌀 Túnel del warp: malla y, durante un salto, su matriz de mundo y opacidad
+    warp_tunnel_vertex_array: &'a [Vertex],
+    warp_tunnel: Option<(Matrix, f32)>,
+    lights: &'a [Light],
+    trajectory: &'a [Vector3],
+    explored_grid: &'a ExploredGrid,
+    earth_transmittance: &'a [f32],
+    // 💥 Nova del Sol: segundos desde que empezó, si hay una activa
+    nova_phase: Option<f32>,
+    nova_shell_vertex_array: &'a [Vertex],
+    dust_cloud: &'a DustCloud,
+    // ☄️ Cinturón de asteroides y sus mallas (una por forma)
+    asteroids: &'a [Asteroid],
+    asteroid_meshes: &'a [Vec<Vertex>],
+    // Mallas detalladas ya generadas, por semilla
+    asteroid_detail_meshes: &'a HashMap<u64, Vec<Vertex>>,
+    // 🔍 Esferas subdivididas de los cuerpos vistos de cerca, por nombre
+    near_surface_meshes: &'a HashMap<String, NearSurfaceMesh>,
+    // 🌫️ Capa de atmósfera de cada cuerpo que la tiene, por nombre (tinte en el color de vértice)
+    atmosphere_shells: &'a HashMap<String, Vec<Vertex>>,
+    // 🪐 Sandbox orbital: cuerpos colocados por el usuario y sus órbitas previstas
+    sandbox_bodies: &'a [Vector3],
+    sandbox_orbits: &'a [Vec<Vector3>],
+    script_shaders: &'a ScriptShaderCache,
+    shader_overrides: &'a HashMap<String, String>,
+    clip_planes: ClipPlanes,
+    time: f32,
+    dt: f32,
+    show_trails: bool,
+    render_mode: RenderMode,
+    shadow_quality: u32,
+}
+
+// Matrices con las que se dibujó una vista, para superponer después interfaz o selección
+struct ViewMatrices {
+    view_matrix: Matrix,
+    projection_matrix: Matrix,
+    viewport_matrix: Matrix,
+    pixels_per_unit: f32, // píxeles que ocupa una unidad de mundo a distancia 1
+}
+
+// 🔭 Culling por frustum: la esfera (centro en mundo, radio) toca el volumen visible de la vista.
+// Se prueba en espacio de cámara (que mira hacia -Z) contra los cuatro lados, el cercano y el lejano.
+fn sphere_in_view(center: Vector3, radius: f32, view_matrix: &Matrix, projection: ViewProjection, aspect: f32, clip_planes: ClipPlanes) -> bool {
+    let p = multiply_matrix_vector4(view_matrix, &Vector4::new(center.x, center.y, center.z, 1.0_f32));
+    let depth = -p.z;
+    if depth + radius < clip_planes.near || depth - radius > clip_planes.far {
+        return false;
+    }
+    match projection {
+        ViewProjection::Perspective { fov } => {
+            // Distancia con signo a cada plano lateral (positiva hacia dentro)
+            let half_v = fov * 0.5_f32;
+            let half_h = (half_v.tan() * aspect).atan();
+            let (sin_v, cos_v) = half_v.sin_cos();
+            let (sin_h, cos_h) = half_h.sin_cos();
+            depth * sin_v - p.y.abs() * cos_v >= -radius && depth * sin_h - p.x.abs() * cos_h >= -radius
+        }
+        ViewProjection::Orthographic { half_height } => {
+            p.y.abs() <= half_height + radius && p.x.abs() <= half_height * aspect + radius
+        }
+    }
+}
+
+// Dibuja la escena completa dentro del rectángulo `viewport` (x, y, ancho, alto). Nada se
+// dibuja fuera de él: el framebuffer recorta con el scissor mientras tanto.
+fn render_scene_to_viewport(framebuffer: &mut Framebuffer, viewport: (i32, i32, i32, i32), camera: &Camera, projection: ViewProjection, scene: &SceneFrame) -> ViewMatrices {
+    let (vx, vy, vw, vh) = viewport;
+    framebuffer.set_scissor(Some(viewport));
+
+    let aspect = vw as f32 / vh as f32;
+    let view_matrix = camera.get_view_matrix();
+    let viewport_matrix = create_viewport_matrix(vx as f32, vy as f32, vw as f32, vh as f32);
+    let (projection_matrix, pixels_per_unit) = match projection {
+        ViewProjection::Perspective { fov } => (
+            create_projection_matrix(fov, aspect, scene.clip_planes.near, scene.clip_planes.far),
+            vh as f32 * 0.5_f32 / (fov * 0.5_f32).tan(),
+        ),
+        ViewProjection::Orthographic { half_height } => (
+            create_ortho_projection_matrix(-half_height * aspect, half_height * aspect, -half_height, half_height, scene.clip_planes.near, scene.clip_planes.far),
+            vh as f32 * 0.5_f32 / half_height,
+        ),
+    };
+    let is_perspective = matches!(projection, ViewProjection::Perspective { .. });
+    let sun_radius = scene.celestial_bodies.iter().find(|body| is_sun(body)).map(body_radius).unwrap_or(0.0_f32);
+
+    // Todo lo de la vista se encola con su prioridad y se dibuja al final en ese orden (ver
+    // `RenderQueue::flush`): cielo, geometría opaca y lo translúcido
+    let mut queue = RenderQueue::new();
+
+    // 🌟 La nebulosa y el skybox van los primeros (más atrás); en ortográfica no tienen sentido.
+    // La nebulosa (un volumen por ray-marching) se suma antes que las estrellas, que quedan por delante.
+    if is_perspective {
+        queue.push(RenderItem::skybox(scene.time, Some(scene.dust_cloud.volume(scene.time))));
+    }
+
+    // Geometría opaca: planetas (con sus anillos y atmósferas translúcidos), cuerpos del sandbox y piezas de la nave
+
+    // Renderizar planetas (se mantiene), pero añadir culling por distancia (evita renderar cuerpos demasiado próximos con triangulación muy densa)
+    let max_render_distance = 5000.0_f32; // puedes ajustar
+    for source in scene.celestial_bodies {
+        // 🕳️ En perspectiva la sombra de un agujero negro la pinta `gravity_lensing_pass`, que
+        // necesita ver el fondo que hay detrás
+        if is_perspective && source.lensing_mass > 0.0 {
+            continue;
+        }
+        let mut body = source.clone();
+        body.translation = body_position(&body, scene.time);
+        // Giro sobre su eje según el tiempo simulado (escala con `time_scale` igual que la órbita)
+        body.rotation.y = angle_at(scene.time, body.rotation_speed);
+
+        // distancia cámara <-> body
+        let dx = camera.eye.x - body.translation.x;
+        let dy = camera.eye.y - body.translation.y;
+        let dz = camera.eye.z - body.translation.z;
+        let dist_sq = dx*dx + dy*dy + dz*dz;
+        if dist_sq > max_render_distance * max_render_distance {
+            // omitimos objetos muy lejanos (mejora rendimiento)
+            continue;
+        }
+        // Fuera de la vista: la esfera envolvente (con los anillos en Saturno) no toca el frustum
+        let bounding_radius = match body.name.as_str() {
+            "Saturn" => body_radius(&body) * SATURN_A_RING_OUTER,
+            "Uranus" => body_radius(&body) * URANUS_RING_OUTER,
+            _ => body_radius(&body),
+        };
+        if !sphere_in_view(body.translation, bounding_radius, &view_matrix, projection, aspect, scene.clip_planes) {
+            continue;
+        }
+
+        let (ambient_intensity, ambient_color) = if is_sun(body) {
+            (SUN_AMBIENT_INTENSITY, Vector3::new(1.0_f32, 1.0_f32, 1.0_f32))
+        } else {
+            (AMBIENT_INTENSITY * heat_pulse_boost(scene.nova_phase), ambient_color_at(length_vec3(body.translation)))
+        };
+
+        // `--script-shader Cuerpo=archivo.rhai` reemplaza el shader del cuerpo por un script
+        let planet_type = scene.shader_overrides.get(&source.name).map(String::as_str).unwrap_or(&source.shader_name);
+        let (displacement, displacement_scale) = displacement_for_shader(planet_type);
+
+        let model_matrix = create_model_matrix(body.translation, body.scale, body.rotation);
+        let uniforms = Uniforms {
+            model_matrix,
+            view_matrix,
+            projection_matrix,
+            viewport_matrix,
+            time: scene.time,
+            dt: scene.dt,
+            camera_pos: camera.eye,
+            lights: scene.lights.to_vec(),
+            ambient_intensity,
+            ambient_color,
+            atmosphere_height: body.atmosphere_height,
+            atmosphere_density: body.atmosphere_density,
+            explored_grid: scene.explored_grid,
+            earth_transmittance: scene.earth_transmittance,
+            explosion_phase: scene.nova_phase.unwrap_or(0.0_f32),
+            sun_radius,
+            shadow_quality: scene.shadow_quality,
+            displacement,
+            displacement_scale,
+            atlas: scene.planet_atlas.zip(source.atlas_tile),
+            effect_alpha: 1.0_f32,
+        };
+        let rings = match body.name.as_str() {
+            "Saturn" => Some((scene.saturn_ring_vertex_array, "SaturnRings")),
+            "Uranus" => Some((scene.uranus_ring_vertex_array, "UranusRings")),
+            _ => None,
+        }
+        .map(|(vertex_array, shader)| (ring_uniforms(&uniforms, &body), vertex_array, shader));
+        // 🌫️ Halo de la atmósfera en el limbo (en la vista cenital no se aprecia)
+        let atmosphere = scene.atmosphere_shells.get(&source.name).filter(|_| is_perspective).map(|shell| (atmosphere_shell_uniforms(&uniforms, &body), shell));
+        let vertices = scene.near_surface_meshes.get(&source.name).map_or(scene.planet_vertex_array, |mesh| mesh.vertices.as_slice());
+        queue.push(RenderItem::opaque(SurfaceDraw { uniforms, vertices, shader: planet_type, script_shaders: Some(scene.script_shaders) }));
+        // Anillos y atmósfera son translúcidos: no entran en la pasada de profundidad del diferido
+        if let Some((ring_uniforms, vertex_array, shader)) = rings {
+            queue.push(RenderItem::transparent(SurfaceDraw { uniforms: ring_uniforms, vertices: vertex_array, shader, script_shaders: None }));
+        }
+        if let Some((shell_uniforms, shell)) = atmosphere {
+            queue.push(RenderItem::transparent(SurfaceDraw { uniforms: shell_uniforms, vertices: shell, shader: "AtmosphereShell", script_shaders: None }));
+        }
+    }
+
+    render_asteroid_belt(&mut queue, scene, camera, view_matrix, projection_matrix, viewport_matrix, projection, aspect, sun_radius);
+
+    // 🪐 Cuerpos del sandbox (con el shader rocoso de Ceres)
+    for position in scene.sandbox_bodies {
+        if !sphere_in_view(*position, SANDBOX_BODY_SCALE * PLANET_MESH_RADIUS, &view_matrix, projection, aspect, scene.clip_planes) {
+            continue;
+        }
+        let uniforms = Uniforms {
+            model_matrix: create_model_matrix(*position, SANDBOX_BODY_SCALE, Vector3::new(0.0_f32, 0.0_f32, 0.0_f32)),
+            view_matrix,
+            projection_matrix,
+            viewport_matrix,
+            time: scene.time,
+            dt: scene.dt,
+            camera_pos: camera.eye,
+            lights: scene.lights.to_vec(),
+            ambient_intensity: AMBIENT_INTENSITY,
+            ambient_color: ambient_color_at(length_vec3(*position)),
+            atmosphere_height: 0.0_f32,
+            atmosphere_density: 0.0_f32,
+            explored_grid: scene.explored_grid,
+            earth_transmittance: scene.earth_transmittance,
+            explosion_phase: scene.nova_phase.unwrap_or(0.0_f32),
+            sun_radius,
+            shadow_quality: scene.shadow_quality,
+            displacement: DisplacementType::None,
+            displacement_scale: 0.0_f32,
+            atlas: None,
+            effect_alpha: 1.0_f32,
+        };
+        queue.push(RenderItem::opaque(SurfaceDraw { uniforms, vertices: scene.planet_vertex_array, shader: "Ceres", script_shaders: None }));
+    }
+
+    // ☄️ Núcleo del cometa (sin giro: ver `comet_nucleus_fragment_shader`)
+    let comet_pos = comet_position(scene.time);
+    if sphere_in_view(comet_pos, COMET_NUCLEUS_RADIUS * 1.5_f32, &view_matrix, projection, aspect, scene.clip_planes) {
+        let uniforms = Uniforms {
+            model_matrix: create_model_matrix(comet_pos, 1.0_f32, Vector3::new(0.0_f32, 0.0_f32, 0.0_f32)),
+            view_matrix,
+            projection_matrix,
+            viewport_matrix,
+            time: scene.time,
+            dt: scene.dt,
+            camera_pos: camera.eye,
+            lights: scene.lights.to_vec(),
+            ambient_intensity: AMBIENT_INTENSITY,
+            ambient_color: ambient_color_at(length_vec3(comet_pos)),
+            atmosphere_height: 0.0_f32,
+            atmosphere_density: 0.0_f32,
+            explored_grid: scene.explored_grid,
+            earth_transmittance: scene.earth_transmittance,
+            explosion_phase: scene.nova_phase.unwrap_or(0.0_f32),
+            sun_radius,
+            shadow_quality: scene.shadow_quality,
+            displacement: DisplacementType::None,
+            displacement_scale: 0.0_f32,
+            atlas: None,
+            effect_alpha: 1.0_f32,
+        };
+        queue.push(RenderItem::opaque(SurfaceDraw { uniforms, vertices: scene.comet_nucleus_vertex_array, shader: "CometNucleus", script_shaders: None }));
+    }
+
+    // La nave, pieza por pieza
+    for (mesh, model_matrix, shader) in scene.nave_parts {
+        let uniforms = Uniforms {
+            model_matrix: *model_matrix,
+            view_matrix,
+            projection_matrix,
+            viewport_matrix,
+            time: scene.time,
+            dt: scene.dt,
+            camera_pos: camera.eye,
+            lights: scene.lights.to_vec(),
+            ambient_intensity: AMBIENT_INTENSITY,
+            ambient_color: scene.nave_ambient_color,
+            atmosphere_height: 0.0_f32,
+            atmosphere_density: 0.0_f32,
+            explored_grid: scene.explored_grid,
+            earth_transmittance: scene.earth_transmittance,
+            explosion_phase: scene.nova_phase.unwrap_or(0.0_f32),
+            sun_radius,
+            shadow_quality: scene.shadow_quality,
+            displacement: DisplacementType::None,
+            displacement_scale: 0.0_f32,
+            atlas: None,
+            effect_alpha: 1.0_f32,
+        };
+        queue.push(RenderItem::opaque(SurfaceDraw { uniforms, vertices: mesh, shader, script_shaders: None }));
+    }
+
+    // Renderizar órbitas (centradas en el Sol, o en su planeta las de los satélites)
+    for body in scene.celestial_bodies {
+        if !is_sun(body) {
+            let orbit_color = Color::new(255, 255, 255, 50);
+            let camera_distance = if is_perspective { length_vec3(sub_vec3(camera.eye, body.orbit_center)) } else { 1.0_f32 };
+            let segments = orbit_line_segment_count(body.orbit_radius, camera_distance, pixels_per_unit);
+            let undiscovered = !is_explored(scene.explored_grid, body_position(body, scene.time));
+            queue.push(RenderItem::orbit_line(OrbitLine {
+                center: body.orbit_center,
+                radius: body.orbit_radius,
+                inclination: body.inclination,
+                ascending_node: ascending_node_at(body, scene.time),
+                segments,
+                color: orbit_color,
+                dashed: undiscovered,
+            }));
+        }
+        // 🫧 Esfera de Hill de cada planeta: un anillo tenue en su plano orbital
+        if body.parent.is_none() {
+            if let Some(hill_radius) = hill_radius_of(body, scene.celestial_bodies) {
+                let center = body_position(body, scene.time);
+                let camera_distance = if is_perspective { length_vec3(sub_vec3(camera.eye, center)) } else { 1.0_f32 };
+                queue.push(RenderItem::orbit_line(OrbitLine {
+                    center,
+                    radius: hill_radius,
+                    inclination: body.inclination,
+                    ascending_node: ascending_node_at(body, scene.time),
+                    segments: orbit_line_segment_count(hill_radius, camera_distance, pixels_per_unit),
+                    color: Color::new(120, 220, 255, 35),
+                    dashed: false,
+                }));
+            }
+        }
+        if scene.show_trails {
+            if let Some(trail) = &body.trail {
+                queue.push(RenderItem::trail(trail));
+            }
+        }
+    }
+
+    // 💥 Onda de choque de la nova: translúcida, se ordena con las órbitas y estelas
+    if let (Some(phase), Some(sun)) = (scene.nova_phase, scene.celestial_bodies.iter().find(|body| is_sun(body))) {
+        let uniforms = Uniforms {
+            model_matrix: create_model_matrix(body_position(sun, scene.time), NovaEffect::shell_radius(body_radius(sun), phase), Vector3::new(0.0_f32, 0.0_f32, 0.0_f32)),
+            view_matrix,
+            projection_matrix,
+            viewport_matrix,
+            time: scene.time,
+            dt: scene.dt,
+            camera_pos: camera.eye,
+            lights: scene.lights.to_vec(),
+            ambient_intensity: AMBIENT_INTENSITY,
+            ambient_color: Vector3::new(1.0_f32, 1.0_f32, 1.0_f32),
+            atmosphere_height: 0.0_f32,
+            atmosphere_density: 0.0_f32,
+            explored_grid: scene.explored_grid,
+            earth_transmittance: scene.earth_transmittance,
+            explosion_phase: phase,
+            sun_radius,
+            shadow_quality: scene.shadow_quality,
+            displacement: DisplacementType::None,
+            displacement_scale: 0.0_f32,
+            atlas: None,
+            effect_alpha: 1.0_f32,
+        };
+        queue.push(RenderItem::transparent(SurfaceDraw { uniforms, vertices: scene.nova_shell_vertex_array, shader: "NovaShockwave", script_shaders: None }));
+    }
+
+    // 🌀 Túnel del warp alrededor de la cámara del piloto: aditivo, se funde al entrar y al salir
+    if let Some((model_matrix, alpha)) = scene.warp_tunnel.filter(|_| is_perspective) {
+        let uniforms = Uniforms {
+            model_matrix,
+            view_matrix,
+            projection_matrix,
+            viewport_matrix,
+            time: scene.time,
+            dt: scene.dt,
+            camera_pos: camera.eye,
+            lights: Vec::new(),
+            ambient_intensity: AMBIENT_INTENSITY,
+            ambient_color: Vector3::new(1.0_f32, 1.0_f32, 1.0_f32),
+            atmosphere_height: 0.0_f32,
+            atmosphere_density: 0.0_f32,
+            explored_grid: scene.explored_grid,
+            earth_transmittance: scene.earth_transmittance,
+            explosion_phase: 0.0_f32,
+            sun_radius,
+            shadow_quality: scene.shadow_quality,
+            displacement: DisplacementType::None,
+            displacement_scale: 0.0_f32,
+            atlas: None,
+            effect_alpha: alpha,
+        };
+        queue.push(RenderItem::transparent(SurfaceDraw { uniforms, vertices: scene.warp_tunnel_vertex_array, shader: "WarpTunnel", script_shaders: None }));
+    }
+
+    let target = RenderTarget { camera, view_matrix, projection_matrix, viewport_matrix, viewport, projection, render_mode: scene.render_mode };
+    queue.flush(framebuffer, &target);
+
+    // Trayectoria y órbitas del sandbox: líneas de interfaz encima de la escena ya compuesta
+    draw_trajectory(framebuffer, scene.trajectory, &view_matrix, &projection_matrix, &viewport_matrix);
+    for orbit in scene.sandbox_orbits {
+        draw_path_3d(framebuffer, orbit, Color::new(90, 200, 230, 255), &view_matrix, &projection_matrix, &viewport_matrix);
+    }
+    draw_kuiper_belt(framebuffer, scene.kuiper_belt, &view_matrix, &projection_matrix, &viewport_matrix);
+    draw_barycenter_marker(framebuffer, compute_barycenter(scene.celestial_bodies, scene.time), &view_matrix, &projection_matrix, &viewport_matrix);
+
+    // 🌑 Conos de sombra de la Luna (eclipse solar) y de la Tierra (eclipse lunar) mientras haya eclipse
+    if let Some([sun, earth, moon]) = sun_earth_moon(scene.celestial_bodies, scene.time) {
+        for (caster, target) in [(moon, earth), (earth, moon)] {
+            let Some((umbra, penumbra, _)) = shadow_on(sun, caster, target) else {
+                continue;
+            };
+            let reach = length_vec3(sub_vec3(target.0, caster.0)) + target.1;
+            if umbra.distance_to_axis(camera.eye, umbra.end) < SHADOW_CONE_VISIBLE_DISTANCE {
+                draw_shadow_cone(framebuffer, &umbra, umbra.start, umbra.end, Color::new(60, 60, 60, 255), &view_matrix, &projection_matrix, &viewport_matrix);
+                draw_shadow_cone(framebuffer, &penumbra, penumbra.start, penumbra.start + reach, Color::new(140, 140, 140, 255), &view_matrix, &projection_matrix, &viewport_matrix);
+            }
+        }
+    }
+
+    // Las partículas van al final: necesitan la profundidad de todo lo opaco
+    if is_perspective {
+        draw_particles(framebuffer, scene.particles, camera, scene.clip_planes, pixels_per_unit, &view_matrix, &projection_matrix, &viewport_matrix);
+        if let Some(saturn) = scene.celestial_bodies.iter().find(|body| body.name == "Saturn") {
+            draw_ring_dust(framebuffer, scene.ring_dust, body_position(saturn, scene.time), body_radius(saturn), camera, pixels_per_unit, &view_matrix, &projection_matrix, &viewport_matrix);
+        }
+        if let Some(sun) = scene.celestial_bodies.iter().find(|body| is_sun(body)) {
+            let (sun_position, sun_radius) = (body_position(sun, scene.time), body_radius(sun));
+            if let Some(flare) = scene.solar_flare {
+                draw_solar_plasma(framebuffer, &flare.particles, sun_position, sun_radius, camera, pixels_per_unit, &view_matrix, &projection_matrix, &viewport_matrix);
+            }
+            draw_solar_plasma(framebuffer, &scene.solar_wind.particles, sun_position, sun_radius, camera, pixels_per_unit, &view_matrix, &projection_matrix, &viewport_matrix);
+        }
+        let activity = comet_activity(comet_pos);
+        if activity > 0.0 {
+            render_comet_coma(framebuffer, comet_pos, COMET_COMA_RADIUS * activity, camera, pixels_per_unit, &view_matrix, &projection_matrix, &viewport_matrix);
+        }
+        draw_comet_jets(framebuffer, &scene.comet_jets.particles, camera, pixels_per_unit, &view_matrix, &projection_matrix, &viewport_matrix);
+    }
+
+    framebuffer.set_scissor(None);
+    ViewMatrices { view_matrix, projection_matrix, viewport_matrix, pixels_per_unit }
+}
+
+// ⚖️ Baricentro del sistema en `time`: Σ m·r / Σ m. Se suma relativo al primer cuerpo con masa,
+// así con un solo cuerpo el resultado es exactamente su posición (y no se pierde precisión con
+// la masa del Sol). Sin masas, el origen.
+fn compute_barycenter(bodies: &[CelestialBody], time: f32) -> Vector3 {
+    let mut massive = bodies.iter().filter(|body| body.mass > 0.0);
+    let Some(reference) = massive.next() else {
+        return Vector3::zero();
+    };
+    let origin = body_position(reference, time);
+    let mut total_mass = reference.mass;
+    let mut weighted = Vector3::zero();
+    for body in massive {
+        weighted = add_vec3(weighted, mul_vec3_scalar(sub_vec3(body_position(body, time), origin), body.mass));
+        total_mass += body.mass;
+    }
+    add_vec3(origin, mul_vec3_scalar(weighted, 1.0_f32 / total_mass))
+}
+
+const BARYCENTER_MARKER_PIXELS: i32 = 5;
+
+// Cruz en pantalla sobre el baricentro, por encima de todo (suele quedar dentro del Sol)
+fn draw_barycenter_marker(framebuffer: &mut Framebuffer, barycenter: Vector3, view_matrix: &Matrix, projection_matrix: &Matrix, viewport_matrix: &Matrix) {
+    let Some((x, y, _)) = project_to_screen(barycenter, view_matrix, projection_matrix, viewport_matrix) else {
+        return;
+    };
+    let color = Color::new(255, 90, 200, 255);
+    let size = BARYCENTER_MARKER_PIXELS;
+    framebuffer.draw_line_with_depth(x - size, y, x + size, y, color, OVERLAY_DEPTH);
+    framebuffer.draw_line_with_depth(x, y - size, x, y + size, color, OVERLAY_DEPTH);
+}
+
+// Posición y radio del Sol, la Tierra y la Luna en `time`, si están los tres en el catálogo
+fn sun_earth_moon(celestial_bodies: &[CelestialBody], time: f32) -> Option<[(Vector3, f32); 3]> {
+    let place = |body: &CelestialBody| (body_position(body, time), body_radius(body));
+    let find = |name: &str| celestial_bodies.iter().find(|body| body.name == name).map(place);
+    Some([celestial_bodies.iter().find(|body| is_sun(body)).map(place)?, find("Earth")?, find("Moon")?])
+}
+
+// Umbra y penumbra que `caster` proyecta hacia `target` (posición y radio de cada uno) y si
+// `target` está en la umbra; `None` si ni siquiera toca la penumbra (no hay eclipse)
+fn shadow_on(sun: (Vector3, f32), caster: (Vector3, f32), target: (Vector3, f32)) -> Option<(ShadowCone, ShadowCone, bool)> {
+    let penumbra = ShadowCone::penumbra(sun.0, sun.1, caster.0, caster.1);
+    if !penumbra.touches_sphere(target.0, target.1) {
+        return None;
+    }
+    let umbra = ShadowCone::umbra(sun.0, sun.1, caster.0, caster.1);
+    let in_umbra = umbra.touches_sphere(target.0, target.1);
+    Some((umbra, penumbra, in_umbra))
+}
+
+const SHADOW_CONE_GENERATRICES: u32 = 12;
+
+// Contorno del cono entre `t_near` y `t_far` (distancias desde el vértice): las dos
+// circunferencias de los extremos y unas cuantas generatrices
+#[allow(clippy::too_many_arguments)]
+fn draw_shadow_cone(framebuffer: &mut Framebuffer, cone: &ShadowCone, t_near: f32, t_far: f32, color: Color, view_matrix: &Matrix, projection_matrix: &Matrix, viewport_matrix: &Matrix) {
+    let helper = if cone.axis.y.abs() < 0.9 { Vector3::new(0.0_f32, 1.0_f32, 0.0_f32) } else { Vector3::new(1.0_f32, 0.0_f32, 0.0_f32) };
+    let u = normalize_vec3(cone.axis.cross(helper));
+    let v = cone.axis.cross(u);
+    let ring = |t: f32| -> Vec<Vector3> {
+        let center = add_vec3(cone.apex, mul_vec3_scalar(cone.axis, t));
+        (0..=SHADOW_CONE_GENERATRICES * 4)
+            .map(|i| {
+                let angle = i as f32 / (SHADOW_CONE_GENERATRICES * 4) as f32 * 2.0_f32 * PI;
+                add_vec3(center, mul_vec3_scalar(add_vec3(mul_vec3_scalar(u, angle.cos()), mul_vec3_scalar(v, angle.sin())), cone.radius_at(t)))
+            })
+            .collect()
+    };
+    let near = ring(t_near);
+    let far = ring(t_far);
+    draw_path_3d(framebuffer, &near, color, view_matrix, projection_matrix, viewport_matrix);
+    draw_path_3d(framebuffer, &far, color, view_matrix, projection_matrix, viewport_matrix);
+    for i in (0..near.len() - 1).step_by(4) {
+        draw_path_3d(framebuffer, &[near[i], far[i]], color, view_matrix, projection_matrix, viewport_matrix);
+    }
+}
+
+// Profundidad para elementos de interfaz: siempre pasan el test de profundidad
+const OVERLAY_DEPTH: f32 = f32::NEG_INFINITY;
+
+// Indicador del FOV actual: un arco tenue en la esquina inferior derecha cuya apertura es el FOV
+fn draw_fov_indicator(framebuffer: &mut Framebuffer, fov: f32) {
+    let color = Color::new(120, 120, 140, 255);
+    let radius = 40.0_f32;
+    let cx = framebuffer.width - 60;
+    let cy = framebuffer.height - 15;
+    let segments = 24;
+    let start = -PI / 2.0 - fov / 2.0;
+    let point_at = |angle: f32| (cx + (angle.cos() * radius) as i32, cy + (angle.sin() * radius) as i32);
+
+    let (first_x, first_y) = point_at(start);
+    framebuffer.draw_line_with_depth(cx, cy, first_x, first_y, color, OVERLAY_DEPTH);
+    let (mut prev_x, mut prev_y) = (first_x, first_y);
+    for i in 1..=segments {
+        let (x, y) = point_at(start + fov * i as f32 / segments as f32);
+        framebuffer.draw_line_with_depth(prev_x, prev_y, x, y, color, OVERLAY_DEPTH);
+        prev_x = x;
+        prev_y = y;
+    }
+    framebuffer.draw_line_with_depth(cx, cy, prev_x, prev_y, color, OVERLAY_DEPTH);
+}
+
+// 🏆 Banner de logro desbloqueado: segundos en pantalla, los últimos `ACHIEVEMENT_BANNER_FADE` fundiéndose
+const ACHIEVEMENT_BANNER_TIME: f32 = 4.0;
+const ACHIEVEMENT_BANNER_FADE: f32 = 1.0;
+
+fn draw_achievement_banner(framebuffer: &mut Framebuffer, achievement: &str, age: f32, y: i32) {
+    let fade = clamp_f32((ACHIEVEMENT_BANNER_TIME - age) / ACHIEVEMENT_BANNER_FADE, 0.0_f32, 1.0_f32);
+    let label = format!("LOGRO DESBLOQUEADO  {}", achievement.to_uppercase());
+    let width = text_width(&label, 2);
+    let x = (framebuffer.width - width) / 2;
+    // El texto y las líneas no mezclan con alfa: el fundido oscurece el color
+    let shade = |c: f32| (c * fade) as u8;
+    let frame_color = Color::new(shade(255.0_f32), shade(200.0_f32), shade(60.0_f32), 255);
+    framebuffer.draw_line_with_depth(x - 12, y - 8, x + width + 12, y - 8, frame_color, OVERLAY_DEPTH);
+    framebuffer.draw_line_with_depth(x - 12, y + 22, x + width + 12, y + 22, frame_color, OVERLAY_DEPTH);
+    let text_color = Color::new(shade(255.0_f32), shade(230.0_f32), shade(150.0_f32), 255);
+    draw_text(framebuffer, &label, x, y, 2, text_color);
+}
+
+const PROFILE_TRACE_PATH: &str = "./profile_trace.json";
+// Las capturas (F12) van a `./screenshot_<milisegundos>.png`
+const SCREENSHOT_PREFIX: &str = "./";
+// Exportación de órbitas (`--export-orbits`): archivo por defecto, segundos simulados desde
+// t = 0 (una vuelta de la Tierra son unos 12.6) y paso entre filas
+const ORBIT_EXPORT_PATH: &str = "./orbits.csv";
+const ORBIT_EXPORT_DURATION: f32 = 100.0;
+const ORBIT_EXPORT_STEP: f32 = 0.1;
+
+// ⏱️ Desglose del frame anterior (F3): una barra vertical por etapa, alta según su parte del frame
+fn draw_stats_overlay(framebuffer: &mut Framebuffer, profile: &FrameProfiler, frame_us: u64) {
+    let label_color = Color::new(200, 200, 220, 255);
+    let bar_color = Color::new(90, 200, 230, 255);
+    let (left, bottom) = (20, 320);
+    let (bar_width, bar_spacing, max_bar_height) = (14, 64, 120.0_f32);
+    let header = format!("FRAME {:.2} MS  ETAPAS {:.2} MS", frame_us as f32 / 1000.0_f32, profile.total_us() as f32 / 1000.0_f32);
+    draw_text(framebuffer, &header, left, bottom - max_bar_height as i32 - 30, 2, label_color);
+    for (i, (name, us)) in profile.stages().iter().enumerate() {
+        let x = left + i as i32 * bar_spacing;
+        let share = *us as f32 / frame_us.max(1) as f32;
+        let height = (clamp_f32(share, 0.0_f32, 1.0_f32) * max_bar_height) as i32;
+        for dx in 0..bar_width {
+            framebuffer.draw_line_with_depth(x + dx, bottom, x + dx, bottom - height, bar_color, OVERLAY_DEPTH);
+        }
+        draw_text(framebuffer, &format!("{:.1}", *us as f32 / 1000.0_f32), x, bottom - height - 12, 1, label_color);
+        draw_text(framebuffer, name, x, bottom + 6, 1, label_color);
+    }
+}
+
+// 🖱️ Cuerpo bajo el cursor: el más cercano a la cámara cuyo disco proyectado contiene el punto
+#[allow(clippy::too_many_arguments)]
+fn pick_body_at_screen(mouse_x: i32, mouse_y: i32, camera: &Camera, celestial_bodies: &[CelestialBody], time: f32, pixels_per_unit: f32, view_matrix: &Matrix, projection_matrix: &Matrix, viewport_matrix: &Matrix) -> Option<usize> {
+    let mut best: Option<(usize, f32)> = None;
+    for (i, body) in celestial_bodies.iter().enumerate() {
+        let position = body_position(body, time);
+        let Some((sx, sy, _)) = project_to_screen(position, view_matrix, projection_matrix, viewport_matrix) else {
+            continue;
+        };
+        let distance = length_vec3(sub_vec3(position, camera.eye));
+        let radius_pixels = pick_radius_pixels(body, distance, pixels_per_unit);
+        let (dx, dy) = ((mouse_x - sx) as f32, (mouse_y - sy) as f32);
+        if dx * dx + dy * dy <= radius_pixels * radius_pixels && best.is_none_or(|(_, d)| distance < d) {
+            best = Some((i, distance));
+        }
+    }
+    best.map(|(i, _)| i)
+}
+
+// Radio en píxeles del disco que prueba la selección; mínimo de unos píxeles para poder
+// seleccionar cuerpos lejanos
+fn pick_radius_pixels(body: &CelestialBody, distance: f32, pixels_per_unit: f32) -> f32 {
+    (body_radius(body) / distance.max(0.001_f32) * pixels_per_unit).max(6.0_f32)
+}
+
+// 🔍 Depuración de la selección (mantener Alt): el rayo del último clic en amarillo, un punto cian
+// donde toca un cuerpo y el contorno del disco contra el que se prueba cada cuerpo
+const PICK_RAY_LENGTH: f32 = 500.0;
+const PICK_RAY_SEGMENTS: usize = 64;
+
+#[derive(Clone, Copy)]
+struct PickRay {
+    origin: Vector3,
+    direction: Vector3, // normalizada
+}
+
+// Distancia a lo largo del rayo hasta la primera intersección con la esfera, si la hay
+fn ray_sphere_intersection(origin: Vector3, direction: Vector3, center: Vector3, radius: f32) -> Option<f32> {
+    let to_center = sub_vec3(center, origin);
+    let along = to_center.dot(direction);
+    let closest_sq = to_center.dot(to_center) - along * along;
+    if closest_sq > radius * radius {
+        return None;
+    }
+    let half_chord = (radius * radius - closest_sq).sqrt();
+    let t = if along - half_chord >= 0.0_f32 { along - half_chord } else { along + half_chord };
+    (t >= 0.0_f32).then_some(t)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn draw_pick_debug(framebuffer: &mut Framebuffer, ray: Option<PickRay>, camera: &Camera, celestial_bodies: &[CelestialBody], time: f32, pixels_per_unit: f32, view_matrix: &Matrix, projection_matrix: &Matrix, viewport_matrix: &Matrix) {
+    for body in celestial_bodies {
+        let position = body_position(body, time);
+        if let Some((sx, sy, _)) = project_to_screen(position, view_matrix, projection_matrix, viewport_matrix) {
+            let distance = length_vec3(sub_vec3(position, camera.eye));
+            framebuffer.draw_circle_outline(sx, sy, pick_radius_pixels(body, distance, pixels_per_unit), Vector3::new(0.4_f32, 0.9_f32, 0.5_f32), 0.6_f32);
+        }
+    }
+    let Some(ray) = ray else {
+        return;
+    };
+    let points: Vec<Vector3> = (0..=PICK_RAY_SEGMENTS)
+        .map(|i| add_vec3(ray.origin, mul_vec3_scalar(ray.direction, PICK_RAY_LENGTH * i as f32 / PICK_RAY_SEGMENTS as f32)))
+        .collect();
+    draw_path_3d(framebuffer, &points, Color::new(255, 230, 0, 255), view_matrix, projection_matrix, viewport_matrix);
+    let hit = celestial_bodies
+        .iter()
+        .filter_map(|body| ray_sphere_intersection(ray.origin, ray.direction, body_position(body, time), body_radius(body)))
+        .filter(|&t| t <= PICK_RAY_LENGTH)
+        .min_by(f32::total_cmp);
+    if let Some(t) = hit {
+        let point = add_vec3(ray.origin, mul_vec3_scalar(ray.direction, t));
+        if let Some((sx, sy, _)) = project_to_screen(point, view_matrix, projection_matrix, viewport_matrix) {
+            framebuffer.draw_filled_circle(sx, sy, 4.0_f32, Color::new(0, 255, 255, 255), OVERLAY_DEPTH);
+        }
+    }
+}
+
+// 🧭 Gizmo de rotación: tres anillos (rojo = X, verde = Y, azul = Z) alrededor del cuerpo seleccionado.
+// Cada anillo se orienta con la rotación de la vista y se dibuja ortográfico alrededor del centro;
+// la proyección no deforma el anillo a esta escala, así que solo se usa la matriz de vista.
+// La mitad trasera (alejándose de la cámara) se dibuja atenuada y a trazos.
+fn draw_rotation_gizmo(framebuffer: &mut Framebuffer, center_screen: (i32, i32), radius: i32, view_matrix: &Matrix, _proj_matrix: &Matrix) {
+    let segments = 64;
+    let rings = [
+        // (eje del anillo, color): el anillo del eje X está en el plano YZ, etc.
+        (Vector3::new(1.0_f32, 0.0_f32, 0.0_f32), Color::new(230, 60, 60, 255)),
+        (Vector3::new(0.0_f32, 1.0_f32, 0.0_f32), Color::new(60, 220, 80, 255)),
+        (Vector3::new(0.0_f32, 0.0_f32, 1.0_f32), Color::new(70, 110, 240, 255)),
+    ];
+    for (axis, color) in rings {
+        let dimmed = Color::new(color.r / 3, color.g / 3, color.b / 3, 255);
+        let mut prev: Option<(i32, i32, bool)> = None;
+        for i in 0..=segments {
+            let angle = i as f32 / segments as f32 * 2.0_f32 * PI;
+            let (x, y, is_back) = gizmo_ring_point(axis, angle, center_screen, radius, view_matrix);
+            if let Some((px, py, prev_back)) = prev {
+                if !(is_back && prev_back) {
+                    framebuffer.draw_line_with_depth(px, py, x, y, color, OVERLAY_DEPTH);
+                } else if i % 2 == 0 {
+                    framebuffer.draw_line_with_depth(px, py, x, y, dimmed, OVERLAY_DEPTH);
+                }
+            }
+            prev = Some((x, y, is_back));
+        }
+    }
+}
+
+// Punto del anillo del gizmo perpendicular a `axis` en el ángulo `angle`, en pantalla, y si queda
+// detrás del centro
+fn gizmo_ring_point(axis: Vector3, angle: f32, center_screen: (i32, i32), radius: i32, view_matrix: &Matrix) -> (i32, i32, bool) {
+    let (sin_a, cos_a) = angle.sin_cos();
+    // Punto del círculo unitario perpendicular al eje
+    let point = if axis.x > 0.5 {
+        Vector3::new(0.0_f32, cos_a, sin_a)
+    } else if axis.y > 0.5 {
+        Vector3::new(cos_a, 0.0_f32, sin_a)
+    } else {
+        Vector3::new(cos_a, sin_a, 0.0_f32)
+    };
+    // Solo rotación (w = 0): dirección en espacio de vista
+    let view_dir = multiply_matrix_vector4(view_matrix, &Vector4::new(point.x, point.y, point.z, 0.0_f32));
+    let x = center_screen.0 + (view_dir.x * radius as f32) as i32;
+    let y = center_screen.1 - (view_dir.y * radius as f32) as i32;
+    // La cámara mira hacia -Z en espacio de vista: z < 0 queda detrás del centro
+    (x, y, view_dir.z < 0.0)
+}
+
+// 🧲 Inclinación del eje magnético respecto al de rotación (rad); el resto de cuerpos lo tiene alineado
+fn magnetic_tilt(body: &CelestialBody) -> f32 {
+    match body.name.as_str() {
+        "Earth" => 11.0_f32.to_radians(),
+        "Jupiter" => 10.0_f32.to_radians(),
+        "Uranus" => 59.0_f32.to_radians(),
+        _ => 0.0_f32,
+    }
+}
+
+// Eje del dipolo en mundo: inclinado desde +Y y girando con el planeta
+fn dipole_axis(body: &CelestialBody, time: f32) -> Vector3 {
+    let tilt = magnetic_tilt(body);
+    let spin = angle_at(time, body.rotation_speed);
+    Vector3::new(tilt.sin() * spin.cos(), tilt.cos(), tilt.sin() * spin.sin())
+}
+
+// Capas L (en radios del cuerpo) y planos de longitud de las líneas de campo
+const FIELD_L_SHELLS: [f32; 3] = [2.0, 3.0, 4.5];
+const FIELD_LONGITUDE_PLANES: u32 = 8;
+const FIELD_LATITUDE_STEP: f32 = 0.03; // rad por paso de integración
+
+// 🧲 Línea de campo de un dipolo en el plano de `outward` y el eje: r = L·cos²(λ) se integra
+// desde el ecuador con dr/dλ = -2·r·tan(λ) (derivada de esa misma curva) hasta tocar la superficie
+// en el hemisferio `hemisphere` (±1). Devuelve los puntos en mundo con su latitud magnética.
+fn dipole_field_line(body_pos: Vector3, body_radius: f32, axis: Vector3, outward: Vector3, l_shell: f32, hemisphere: f32) -> Vec<(Vector3, f32)> {
+    // Latitud a la que la línea entra en el cuerpo (r = 1)
+    let surface_latitude = (1.0_f32 / l_shell).sqrt().acos();
+    let mut points = Vec::new();
+    let mut latitude = 0.0_f32;
+    let mut r = l_shell;
+    let mut reached_surface = false;
+    loop {
+        let (sin_l, cos_l) = latitude.sin_cos();
+        let local = add_vec3(mul_vec3_scalar(outward, cos_l), mul_vec3_scalar(axis, sin_l));
+        points.push((add_vec3(body_pos, mul_vec3_scalar(local, r * body_radius)), latitude));
+        if reached_surface {
+            return points;
+        }
+        // Punto medio (RK2) de dr/dλ = -2·r·tan(λ), en el sentido del hemisferio
+        let step = FIELD_LATITUDE_STEP * hemisphere;
+        let mid_latitude = latitude + step * 0.5_f32;
+        let mid_r = r - 2.0_f32 * r * latitude.tan() * step * 0.5_f32;
+        r -= 2.0_f32 * mid_r * mid_latitude.tan() * step;
+        latitude += step;
+        // El último paso se pasaría bajo la superficie: la línea acaba justo en ella
+        if r <= 1.0 || latitude.abs() >= surface_latitude {
+            r = 1.0_f32;
+            latitude = surface_latitude * hemisphere;
+            reached_surface = true;
+        }
+    }
+}
+
+// 🧲 Líneas de campo de un dipolo: cada plano de longitud y capa L, en los dos hemisferios.
+// Azul en el polo sur magnético, blanco en el ecuador y rojo en el norte.
+#[allow(clippy::too_many_arguments)]
+fn draw_magnetic_field_lines(framebuffer: &mut Framebuffer, body_pos: Vector3, body_radius: f32, dipole_axis: Vector3, view_matrix: &Matrix, projection_matrix: &Matrix, viewport_matrix: &Matrix) {
+    let axis = normalize_vec3(dipole_axis);
+    // Base perpendicular al eje para recorrer los planos de longitud
+    let helper = if axis.y.abs() < 0.9 { Vector3::new(0.0_f32, 1.0_f32, 0.0_f32) } else { Vector3::new(1.0_f32, 0.0_f32, 0.0_f32) };
+    let u = normalize_vec3(axis.cross(helper));
+    let v = axis.cross(u);
+
+    for plane in 0..FIELD_LONGITUDE_PLANES {
+        let phi = plane as f32 / FIELD_LONGITUDE_PLANES as f32 * 2.0_f32 * PI;
+        let outward = add_vec3(mul_vec3_scalar(u, phi.cos()), mul_vec3_scalar(v, phi.sin()));
+        for l_shell in FIELD_L_SHELLS {
+            let surface_latitude = (1.0_f32 / l_shell).sqrt().acos();
+            for hemisphere in [-1.0_f32, 1.0_f32] {
+                let mut prev: Option<(f32, f32, f32)> = None;
+                for (point, latitude) in dipole_field_line(body_pos, body_radius, axis, outward, l_shell, hemisphere) {
+                    let current = project_to_screen_subpixel(point, view_matrix, projection_matrix, viewport_matrix);
+                    // 0 en el polo sur, 0.5 en el ecuador, 1 en el norte
+                    let t = 0.5_f32 + 0.5_f32 * latitude / surface_latitude;
+                    let color = if t < 0.5 {
+                        lerp_vec3(Vector3::new(0.2_f32, 0.4_f32, 1.0_f32), Vector3::new(1.0_f32, 1.0_f32, 1.0_f32), t * 2.0_f32)
+                    } else {
+                        lerp_vec3(Vector3::new(1.0_f32, 1.0_f32, 1.0_f32), Vector3::new(1.0_f32, 0.25_f32, 0.2_f32), t * 2.0_f32 - 1.0_f32)
+                    };
+                    if let (Some((x0, y0, z0)), Some((x1, y1, z1))) = (prev, current) {
+                        framebuffer.draw_line_wu(x0, y0, x1, y1, color, (z0 + z1) * 0.5_f32);
+                    }
+                    prev = current;
+                }
+            }
+        }
+    }
+}
+
+// 💍 Anillos (Saturno, Urano): la malla está en radios del planeta, así que se escala con el radio visible
+fn ring_uniforms<'a>(planet_uniforms: &Uniforms<'a>, body: &CelestialBody) -> Uniforms<'a> {
+    Uniforms {
+        model_matrix: create_model_matrix(body.translation, body_radius(body), Vector3::new(0.0_f32, 0.0_f32, 0.0_f32)),
+        lights: planet_uniforms.lights.clone(),
+        // La casilla del atlas es del planeta, no de sus anillos
+        atlas: None,
+        ..*planet_uniforms
+    }
+}
+
+// 🌫️ Capa de atmósfera: la esfera del planeta `atmosphere_height` radios más alta, sin relieve
+fn atmosphere_shell_uniforms<'a>(planet_uniforms: &Uniforms<'a>, body: &CelestialBody) -> Uniforms<'a> {
+    Uniforms {
+        model_matrix: create_model_matrix(body.translation, body.scale * (1.0_f32 + body.atmosphere_height), body.rotation),
+        lights: planet_uniforms.lights.clone(),
+        displacement: DisplacementType::None,
+        displacement_scale: 0.0_f32,
+        atlas: None,
+        ..*planet_uniforms
+    }
+}
+
+// 🛬 Vuelo rasante: distancia mínima a la superficie y rango (en radios del cuerpo) dentro del
+// cual se puede activar y se aplica. La altitud mínima queda por encima del plano cercano para
+// que el suelo de justo debajo no se recorte
+const TERRAIN_MIN_ALTITUDE: f32 = 0.6;
+const TERRAIN_FOLLOW_RADII: f32 = 1.5;
+
+fn terrain_follow_altitude(body: &CelestialBody) -> f32 {
+    body_radius(body) * TERRAIN_FOLLOW_RADII
+}
+
+// 🔒 Bloqueo de la mirada en un cuerpo: el objetivo de la cámara lo persigue (la posición no se
+// toca) y el bloqueo se suelta si el cuerpo queda más de 110° fuera del frente de la cámara
+const CAMERA_LOCK_SPEED: f32 = 4.0; // fracción del camino al cuerpo por segundo
+const CAMERA_LOCK_RELEASE_ANGLE: f32 = 110.0 * PI / 180.0;
+
+/// Acerca `camera.target` a la posición del cuerpo en `time`; `lock_speed` es la fracción del
+/// camino que se recorre en este frame (velocidad · dt). Yaw y pitch se recalculan para que el
+/// control manual siga mirando hacia el nuevo objetivo.
+fn lock_camera_to_body(camera: &mut Camera, body: &CelestialBody, time: f32, lock_speed: f32) {
+    let body_pos = body_position(body, time);
+    camera.target = lerp_vec3(camera.target, body_pos, clamp_f32(lock_speed, 0.0_f32, 1.0_f32));
+    let forward = normalize_vec3(sub_vec3(camera.target, camera.eye));
+    camera.yaw = forward.z.atan2(forward.x);
+    camera.pitch = clamp_f32(forward.y, -1.0_f32, 1.0_f32).asin();
+}
+
+// Ángulo entre el frente de la cámara y la dirección al cuerpo
+fn camera_angle_to_body(camera: &Camera, body: &CelestialBody, time: f32) -> f32 {
+    let forward = normalize_vec3(sub_vec3(camera.target, camera.eye));
+    let to_body = normalize_vec3(sub_vec3(body_position(body, time), camera.eye));
+    clamp_f32(forward.x * to_body.x + forward.y * to_body.y + forward.z * to_body.z, -1.0_f32, 1.0_f32).acos()
+}
+
+// Retícula de esquinas alrededor del cuerpo bloqueado
+fn draw_lock_reticle(framebuffer: &mut Framebuffer, x: i32, y: i32, color: Color) {
+    let (size, arm) = (14, 5);
+    for (sx, sy) in [(-1, -1), (1, -1), (1, 1), (-1, 1)] {
+        let (cx, cy) = (x + sx * size, y + sy * size);
+        framebuffer.draw_line_with_depth(cx, cy, cx - sx * arm, cy, color, OVERLAY_DEPTH);
+        framebuffer.draw_line_with_depth(cx, cy, cx, cy - sy * arm, color, OVERLAY_DEPTH);
+    }
+}
+
+// Cuerpo con la superficie más cercana a `eye` y la altitud sobre ella
+fn nearest_surface(eye: Vector3, celestial_bodies: &[CelestialBody], time: f32) -> Option<(usize, f32)> {
+    celestial_bodies
+        .iter()
+        .enumerate()
+        .map(|(i, body)| (i, length_vec3(sub_vec3(eye, body_position(body, time))) - body_radius(body)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+// Empuja `eye` hacia fuera de cada cuerpo que tenga cerca hasta quedar a `TERRAIN_MIN_ALTITUDE`
+// de su superficie (el punto de la esfera justo debajo de la cámara)
+fn terrain_follow(eye: Vector3, celestial_bodies: &[CelestialBody], time: f32) -> Vector3 {
+    let mut eye = eye;
+    for body in celestial_bodies {
+        let center = body_position(body, time);
+        let radius = body_radius(body);
+        let offset = sub_vec3(eye, center);
+        let distance = length_vec3(offset);
+        if distance > radius + terrain_follow_altitude(body) {
+            continue;
+        }
+        // En el centro exacto no hay "debajo": se sale por arriba
+        let outward = if distance > 1e-4_f32 { normalize_vec3(offset) } else { Vector3::new(0.0_f32, 1.0_f32, 0.0_f32) };
+        if distance < radius + TERRAIN_MIN_ALTITUDE {
+            eye = add_vec3(center, mul_vec3_scalar(outward, radius + TERRAIN_MIN_ALTITUDE));
+        }
+    }
+    eye
+}
+
+// 🏷️ Sprites del HUD: orden de dibujo (mayor Z encima) y letra del icono de cada tipo de cuerpo
+const SPRITE_Z_INDICATOR: i32 = 0;
+const SPRITE_Z_ICON: i32 = 1;
+const SPRITE_Z_WARNING: i32 = 2;
+const WARP_CHARGE_CELLS: u32 = 10;
+
+// E estrella, G gigante gaseoso, H gigante helado, D planeta enano, A agujero negro, R rocoso
+fn planet_type_symbol(shader_name: &str) -> char {
+    match shader_name {
+        "Sun" => 'E',
+        "Jupiter" | "Saturn" => 'G',
+        "Uranus" => 'H',
+        "Ceres" | "Pluto" => 'D',
+        "BlackHole" => 'A',
+        _ => 'R',
+    }
+}
+
+// Brújula abajo a la izquierda; la aguja marca hacia dónde mira la cámara en el plano XZ
+// (el norte es -Z)
+fn draw_compass_needle(framebuffer: &mut Framebuffer, center: (i32, i32), radius: f32, forward: Vector3) {
+    let heading = forward.x.atan2(-forward.z);
+    let tip = (center.0 + (heading.sin() * radius) as i32, center.1 - (heading.cos() * radius) as i32);
+    framebuffer.draw_line_with_depth(center.0, center.1, tip.0, tip.1, Color::new(255, 200, 80, 255), OVERLAY_DEPTH);
+}
+
+// Radioaltímetro: altitud sobre la superficie más cercana, con el nombre del cuerpo
+fn draw_radar_altimeter(framebuffer: &mut Framebuffer, body_name: &str, altitude: f32, active: bool) {
+    let color = if altitude < TERRAIN_MIN_ALTITUDE * 2.0_f32 {
+        Color::new(255, 90, 60, 255)
+    } else {
+        Color::new(120, 230, 140, 255)
+    };
+    let mode = if active { "RASANTE" } else { "F RASANTE" };
+    let label = format!("ALT {:.2}  {}  {}", altitude.max(0.0_f32), body_name.to_uppercase(), mode);
+    draw_text(framebuffer, &label, framebuffer.width / 2 - text_width(&label, 2) / 2, framebuffer.height - 40, 2, color);
+}
+
+fn check_collision(pos1: Vector3, radius1: f32, pos2: Vector3, radius2: f32) -> bool {
+    let dx = pos1.x - pos2.x;
+    let dy = pos1.y - pos2.y;
+    let dz = pos1.z - pos2.z;
+    let dist_sq = dx*dx + dy*dy + dz*dz;
+    dist_sq < (radius1 + radius2)*(radius1 + radius2)
+}
+
+// Devuelve también el índice del cuerpo con el que chocó la cámara, si hubo choque
+fn avoid_collision(camera_pos: Vector3, target_pos: Vector3, celestial_bodies: &[CelestialBody], time: f32) -> (Vector3, Vector3, Option<usize>) {
+    let mut new_camera_pos = camera_pos;
+    let mut new_target_pos = target_pos;
+    let mut hit_body = None;
+    for (i, body) in celestial_bodies.iter().enumerate() {
+        let body_pos = body_position(body, time);
+        let camera_radius = 2.0_f32;
+        let body_radius = body_radius(body);
+        if check_collision(new_camera_pos, camera_radius, body_pos, body_radius) {
+            hit_body = Some(i);
+            let dx = new_camera_pos.x - body_pos.x;
+            let dy = new_camera_pos.y - body_pos.y;
+            let dz = new_camera_pos.z - body_pos.z;
+            let dist = (dx*dx + dy*dy + dz*dz).sqrt();
+            if dist > 0.0 {
+                let min_dist = body_radius + camera_radius;
+                let scale = min_dist / dist;
+                new_camera_pos.x = body_pos.x + dx * scale;
+                new_camera_pos.y = body_pos.y + dy * scale;
+                new_camera_pos.z = body_pos.z + dz * scale;
+            }
+        }
+        if check_collision(new_target_pos, camera_radius, body_pos, body_radius) {
+            let dx = new_target_pos.x - body_pos.x;
+            let dy = new_target_pos.y - body_pos.y;
+            let dz = new_target_pos.z - body_pos.z;
+            let dist = (dx*dx + dy*dy + dz*dz).sqrt();
+            if dist > 0.0 {
+                let min_dist = body_radius + camera_radius;
+                let scale = min_dist / dist;
+                new_target_pos.x = body_pos.x + dx * scale;
+                new_target_pos.y = body_pos.y + dy * scale;
+                new_target_pos.z = body_pos.z + dz * scale;
+            }
+        }
+    }
+    (new_camera_pos, new_target_pos, hit_body)
+}
+
+// 💥 Nova del Sol (N): destello que decae, onda de choque que barre el sistema y un pulso de
+// calor que sube la luz ambiental. `phase` son segundos reales desde el inicio.
+struct NovaEffect {
+    phase: f32,
+    max_phase: f32,
+}
+
+impl NovaEffect {
+    const SHOCKWAVE_SPEED: f32 = 60.0;  // unidades por segundo de fase
+    const HEAT_PULSE_BOOST: f32 = 1.5;  // aumento máximo de la luz ambiental
+
+    fn new() -> Self {
+        NovaEffect { phase: 0.0, max_phase: 5.0 }
+    }
+
+    fn update(&mut self, dt: f32) {
+        self.phase += dt;
+    }
+
+    fn is_finished(&self) -> bool {
+        self.phase >= self.max_phase
+    }
+
+    fn shell_radius(sun_radius: f32, phase: f32) -> f32 {
+        sun_radius + Self::SHOCKWAVE_SPEED * phase
+    }
+}
+
+// 🌫️ Nube de polvo interestelar de fondo, detrás del sistema (vista inicial): un volumen de gas
+// que se dibuja por ray-marching. Gira muy despacio para que las volutas cambien.
+struct DustCloud {
+    center: Vector3,
+    radius: f32,
+}
+
+impl DustCloud {
+    const ROTATION_SPEED: f32 = 0.002; // rad por segundo simulado
+
+    fn new(center: Vector3, radius: f32) -> Self {
+        DustCloud { center, radius }
+    }
+
+    // Paso de la marcha: 64 muestras para cruzar la nube por el centro (con menos, el fBm de
+    // la densidad queda submuestreado y el color cambia más de un 5% al afinar el paso)
+    fn volume(&self, time: f32) -> Volume {
+        Volume {
+            center: self.center,
+            radius: self.radius,
+            rotation_y: time * Self::ROTATION_SPEED,
+            step_size: self.radius / 32.0_f32,
+            density_fn: nebula_volume_density,
+        }
+    }
+}
+
+// ☄️ Cinturón de asteroides entre Marte y Júpiter. Cada asteroide sale de una semilla (su índice):
+// órbita, tamaño, forma y ritmos de volteo
+const ASTEROID_COUNT: usize = 80;
+const ASTEROID_SHAPES: usize = 4;
+const ASTEROID_BELT_INNER: f32 = 39.0;
+const ASTEROID_BELT_OUTER: f32 = 50.0;
+// De cerca (cámara a menos de esta distancia de la superficie) cada asteroide usa su propia malla
+// detallada y choca con su forma real, con una esfera de cámara pequeña para poder posarse
+const ASTEROID_DETAIL_DISTANCE: f32 = 2.0;
+const ASTEROID_CAMERA_RADIUS: f32 = 0.02;
+
+struct Asteroid {
+    seed: u64, // forma de la malla detallada
+    orbit_radius: f32,
+    orbit_speed: f32,
+    phase: f32,
+    height: f32, // sobre la eclíptica
+    scale: f32,  // radio (la malla es de radio 1)
+    shape: usize,
+    primary_tumble: f32,   // rad/s alrededor de X
+    secondary_tumble: f32, // rad/s alrededor de Z
+}
+
+impl Asteroid {
+    fn new(index: usize) -> Self {
+        let mut rng = fastrand::Rng::with_seed(index as u64);
+        let orbit_radius = ASTEROID_BELT_INNER + rng.f32() * (ASTEROID_BELT_OUTER - ASTEROID_BELT_INNER);
+        let sign = if rng.bool() { 1.0_f32 } else { -1.0_f32 };
+        Asteroid {
+            seed: index as u64,
+            orbit_radius,
+            // Tercera ley de Kepler, tomando a Marte (radio 35, velocidad 0.3) como referencia
+            orbit_speed: 0.3_f32 * (35.0_f32 / orbit_radius).powf(1.5_f32),
+            phase: rng.f32() * 2.0_f32 * PI,
+            height: (rng.f32() - 0.5_f32) * 2.0_f32,
+            scale: 0.08_f32 + rng.f32() * 0.14_f32,
+            shape: index % ASTEROID_SHAPES,
+            primary_tumble: sign * (0.2_f32 + rng.f32() * 1.3_f32),
+            secondary_tumble: 0.1_f32 + rng.f32() * 0.7_f32,
+        }
+    }
+
+    fn position(&self, time: f32) -> Vector3 {
+        let on_ecliptic = orbital_position(self.orbit_radius, self.phase + angle_at(time, self.orbit_speed), 0.0_f32, 0.0_f32);
+        Vector3::new(on_ecliptic.x, self.height, on_ecliptic.z)
+    }
+
+    // Volteo: en vez de girar limpio alrededor de Y, dos giros a ritmos distintos sobre X y Z
+    fn tumble_rotation(&self, time: f32) -> Matrix {
+        create_rotation_matrix_x(angle_at(time, self.primary_tumble)) * create_rotation_matrix_z(angle_at(time, self.secondary_tumble))
+    }
+
+    // Dirección de mundo llevada al espacio de la malla (deshace el volteo)
+    fn to_local(&self, time: f32, dir: Vector3) -> Vector3 {
+        let inverse = create_rotation_matrix_z(-angle_at(time, self.secondary_tumble)) * create_rotation_matrix_x(-angle_at(time, self.primary_tumble));
+        let local = multiply_matrix_vector4(&inverse, &Vector4::new(dir.x, dir.y, dir.z, 0.0_f32));
+        Vector3::new(local.x, local.y, local.z)
+    }
+
+    fn in_detail_range(&self, point: Vector3, time: f32) -> bool {
+        length_vec3(sub_vec3(point, self.position(time))) - self.scale < ASTEROID_DETAIL_DISTANCE
+    }
+}
+
+// Saca `camera_pos` de los asteroides vistos de cerca, chocando con su superficie deformada en
+// vez de con la esfera; devuelve la posición corregida
+fn avoid_asteroid_collision(camera_pos: Vector3, asteroids: &[Asteroid], time: f32) -> Vector3 {
+    let mut new_camera_pos = camera_pos;
+    for asteroid in asteroids.iter().filter(|asteroid| asteroid.in_detail_range(camera_pos, time)) {
+        let position = asteroid.position(time);
+        let offset = sub_vec3(new_camera_pos, position);
+        let surface_radius = asteroid_surface_radius(asteroid.seed, asteroid.scale, asteroid.to_local(time, offset));
+        let dist = length_vec3(offset);
+        if check_collision(new_camera_pos, ASTEROID_CAMERA_RADIUS, position, surface_radius) && dist > 0.0 {
+            new_camera_pos = add_vec3(position, mul_vec3_scalar(offset, (surface_radius + ASTEROID_CAMERA_RADIUS) / dist));
+        }
+    }
+    new_camera_pos
+}
+
+#[allow(clippy::too_many_arguments)]
+fn render_asteroid_belt<'a>(queue: &mut RenderQueue<'a>, scene: &SceneFrame<'a>, camera: &Camera, view_matrix: Matrix, projection_matrix: Matrix, viewport_matrix: Matrix, projection: ViewProjection, aspect: f32, sun_radius: f32) {
+    for asteroid in scene.asteroids {
+        let position = asteroid.position(scene.time);
+        // La malla detallada sobresale algo de la esfera media
+        if !sphere_in_view(position, asteroid.scale * 1.3_f32, &view_matrix, projection, aspect, scene.clip_planes) {
+            continue;
+        }
+        // De cerca, la malla propia del asteroide (ya a su tamaño); si no, la forma compartida
+        let detail_mesh = scene.asteroid_detail_meshes.get(&asteroid.seed).filter(|_| asteroid.in_detail_range(camera.eye, scene.time));
+        let (mesh, scale) = match detail_mesh {
+            Some(mesh) => (mesh.as_slice(), 1.0_f32),
+            None => (scene.asteroid_meshes[asteroid.shape].as_slice(), asteroid.scale),
+        };
+        let uniforms = Uniforms {
+            model_matrix: create_model_matrix_with_rotation(position, scale, asteroid.tumble_rotation(scene.time)),
+            view_matrix,
+            projection_matrix,
+            viewport_matrix,
+            time: scene.time,
+            dt: scene.dt,
+            camera_pos: camera.eye,
+            lights: scene.lights.to_vec(),
+            ambient_intensity: AMBIENT_INTENSITY,
+            ambient_color: ambient_color_at(asteroid.orbit_radius),
+            atmosphere_height: 0.0_f32,
+            atmosphere_density: 0.0_f32,
+            explored_grid: scene.explored_grid,
+            earth_transmittance: scene.earth_transmittance,
+            explosion_phase: scene.nova_phase.unwrap_or(0.0_f32),
+            sun_radius,
+            shadow_quality: scene.shadow_quality,
+            displacement: DisplacementType::None,
+            displacement_scale: 0.0_f32,
+            atlas: None,
+            effect_alpha: 1.0_f32,
+        };
+        queue.push(RenderItem::opaque(SurfaceDraw { uniforms, vertices: mesh, shader: "Asteroid", script_shaders: None }));
+    }
+}
+
+// Multiplicador de la luz ambiental por el pulso de calor de la nova (1 sin nova)
+fn heat_pulse_boost(nova_phase: Option<f32>) -> f32 {
+    nova_phase.map_or(1.0_f32, |phase| 1.0_f32 + NovaEffect::HEAT_PULSE_BOOST * (-phase).exp())
+}
+
+// 💥 Onda expansiva al chocar con un cuerpo: un anillo en pantalla que crece y se desvanece
+struct ImpactEffect {
+    screen_pos: (i32, i32),
+    radius: f32,
+    max_radius: f32,
+    age: f32,
+    color: Vector3,
+}
+
+impl ImpactEffect {
+    const DURATION: f32 = 0.5; // segundos hasta alcanzar `max_radius`
+
+    fn new(screen_pos: (i32, i32), max_radius: f32, color: Vector3) -> Self {
+        ImpactEffect { screen_pos, radius: 0.0, max_radius, age: 0.0, color }
+    }
+
+    fn update(&mut self, dt: f32) {
+        self.age += dt;
+        // Ease-out: rápido al principio, frena al final
+        let t = clamp_f32(self.age / Self::DURATION, 0.0_f32, 1.0_f32);
+        self.radius = self.max_radius * (1.0_f32 - (1.0_f32 - t) * (1.0_f32 - t));
+    }
+
+    fn is_finished(&self) -> bool {
+        self.radius >= self.max_radius
+    }
+
+    fn opacity(&self) -> f32 {
+        1.0_f32 - clamp_f32(self.age / Self::DURATION, 0.0_f32, 1.0_f32)
+    }
+}
+
+// Estado para warping animado
+#[derive(Clone)]
+struct WarpTarget {
+    eye: Vector3,
+    target: Vector3,
+    up: Vector3,
+}
+
+impl WarpTarget {
+    fn to_camera_state(&self) -> Camera {
+        Camera::new(self.eye, self.target, self.up)
+    }
+}
+
+// Warps pendientes en orden: el primero es el que se está animando y los demás esperan su turno
+type WarpQueue = VecDeque<WarpTarget>;
+
+/// Segundos hasta completar todos los warps de la cola, si cada uno avanza a `speed` warps por
+/// segundo (la inversa de la duración de un warp). No descuenta lo ya recorrido del actual.
+fn estimate_arrival_time(queue: &VecDeque<WarpTarget>, speed: f32) -> f32 {
+    if speed <= 0.0_f32 {
+        return 0.0_f32;
+    }
+    queue.len() as f32 / speed
+}
+
+// Añade `destination` a la cola; si no había ningún warp en curso, empieza en `real_time`
+fn enqueue_warp(warp_queue: &mut WarpQueue, warp_start_time: &mut f32, destination: WarpTarget, real_time: f32) {
+    if warp_queue.is_empty() {
+        *warp_start_time = real_time;
+    }
+    warp_queue.push_back(destination);
+}
+
+/// Acerca la cámara al destino del warp en curso (el primero de la cola). Al llegar lo saca de
+/// la cola y el siguiente empieza en este mismo instante. Devuelve el progreso (0 a 1) del warp
+/// animado en este frame, o `None` si la cola está vacía.
+fn advance_warp(warp_queue: &mut WarpQueue, warp_start_time: &mut f32, camera: &mut Camera, real_time: f32, warp_duration: f32) -> Option<f32> {
+    let current_warp = warp_queue.front().cloned()?;
+    let t = ((real_time - *warp_start_time) / warp_duration).min(1.0_f32);
+    let eased_t = ease_in_out(t);
+
+    // en lugar de `camera.clone()` tomamos los campos directamente
+    let start_eye = camera.eye;
+    let start_target = camera.target;
+    let start_up = camera.up;
+    // Si `Camera` tiene yaw/pitch/distance expuestos los leemos directamente
+    // (tu código original los usa, así que los copiamos aquí)
+    let start_yaw = camera.yaw;
+    let start_pitch = camera.pitch;
+    let start_distance = camera.distance;
+
+    let target_cam = current_warp.to_camera_state();
+
+    // interpolamos campos
+    camera.eye = lerp_vec3(start_eye, target_cam.eye, eased_t);
+    camera.target = lerp_vec3(start_target, target_cam.target, eased_t);
+    camera.up = lerp_vec3(start_up, target_cam.up, eased_t);
+
+    camera.yaw = start_yaw + (target_cam.yaw - start_yaw) * eased_t;
+    camera.pitch = start_pitch + (target_cam.pitch - start_pitch) * eased_t;
+    camera.distance = start_distance + (target_cam.distance - start_distance) * eased_t;
+
+    if t >= 1.0 {
+        // Asegurar valores exactos al final y pasar al siguiente destino de la cola
+        *camera = current_warp.to_camera_state();
+        warp_queue.pop_front();
+        *warp_start_time = real_time;
+    }
+    Some(t)
+}
+
+// 🔎 Primer cuerpo cuyo nombre empieza por `query` (sin distinguir mayúsculas)
+fn find_body_by_prefix(celestial_bodies: &[CelestialBody], query: &str) -> Option<usize> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return None;
+    }
+    celestial_bodies.iter().position(|body| body.name.to_lowercase().starts_with(&query))
+}
+
+// Punto de vista para viajar a un cuerpo: por fuera de su órbita y algo por encima, mirándolo
+fn warp_target_for_body(body: &CelestialBody, time: f32) -> WarpTarget {
+    let position = body_position(body, time);
+    let radius = body_radius(body);
+    let outward = if length_vec3(position) > 0.001_f32 {
+        normalize_vec3(position)
+    } else {
+        Vector3::new(0.0_f32, 0.0_f32, 1.0_f32)
+    };
+    let offset = add_vec3(
+        mul_vec3_scalar(outward, radius * 6.0_f32 + 5.0_f32),
+        Vector3::new(0.0_f32, radius * 2.0_f32 + 2.0_f32, 0.0_f32),
+    );
+    WarpTarget {
+        eye: add_vec3(position, offset),
+        target: position,
+        up: Vector3::new(0.0_f32, 1.0_f32, 0.0_f32),
+    }
+}
+
+// 🎯 Menú radial de warp: mantener Tab lo abre con un sector por destino alrededor del centro de
+// la pantalla (las vistas fijas y luego cada cuerpo, el primero arriba, en sentido horario); al
+// soltarlo se salta al resaltado, o no se hace nada si el ratón quedó en el centro
+const RADIAL_MENU_FADE_TIME: f32 = 0.2; // segundos hasta verse del todo
+const RADIAL_MENU_MAX_RADIUS: i32 = 260;
+const RADIAL_MENU_DEAD_ZONE: f32 = 30.0; // px alrededor del centro que no eligen nada
+
+// Centro de la opción `index` de `count` en el anillo
+fn radial_menu_option_position(count: usize, index: usize, center: (i32, i32), radius: i32) -> (i32, i32) {
+    let angle = -PI * 0.5_f32 + index as f32 / count.max(1) as f32 * 2.0_f32 * PI;
+    (center.0 + (angle.cos() * radius as f32).round() as i32, center.1 + (angle.sin() * radius as f32).round() as i32)
+}
+
+/// Opción cuyo sector contiene la dirección del ratón vista desde el centro (la más cercana en
+/// ángulo). `None` sin opciones o con el ratón a menos de RADIAL_MENU_DEAD_ZONE del centro, así
+/// un toque rápido de Tab no salta a ningún sitio.
+fn radial_menu_selection(count: usize, center: (i32, i32), mouse: (i32, i32)) -> Option<usize> {
+    let (dx, dy) = ((mouse.0 - center.0) as f32, (mouse.1 - center.1) as f32);
+    if count == 0 || dx * dx + dy * dy < RADIAL_MENU_DEAD_ZONE * RADIAL_MENU_DEAD_ZONE {
+        return None;
+    }
+    // Ángulo desde arriba en sentido horario (en pantalla Y crece hacia abajo)
+    let angle = (dy.atan2(dx) + PI * 0.5_f32).rem_euclid(2.0_f32 * PI);
+    let sector = 2.0_f32 * PI / count as f32;
+    Some((angle / sector).round() as usize % count)
+}
+
+fn draw_radial_menu(framebuffer: &mut Framebuffer, options: &[(&str, Color)], selected: Option<usize>, center: (i32, i32), radius: i32) {
+    framebuffer.draw_circle_outline(center.0, center.1, radius as f32, Vector3::new(0.5_f32, 0.55_f32, 0.65_f32), 0.4_f32);
+    framebuffer.draw_circle_outline(center.0, center.1, RADIAL_MENU_DEAD_ZONE, Vector3::new(0.5_f32, 0.55_f32, 0.65_f32), 0.25_f32);
+    for (i, (label, color)) in options.iter().enumerate() {
+        let (x, y) = radial_menu_option_position(options.len(), i, center, radius);
+        let label = label.to_uppercase();
+        if selected == Some(i) {
+            // Aro más claro alrededor del resaltado; sale del mismo color (y no baja de un tercio
+            // de su alfa, que lleva el fundido) para desvanecerse con él
+            let lighten = |c: u8| (c as u16 * 3 / 2).min(255) as u8;
+            let highlight = Color::new(lighten(color.r).max(color.a / 3), lighten(color.g).max(color.a / 3), lighten(color.b).max(color.a / 3), 255);
+            framebuffer.draw_filled_circle(x, y, 19.0_f32, highlight, OVERLAY_DEPTH);
+            framebuffer.draw_filled_circle(x, y, 15.0_f32, *color, OVERLAY_DEPTH);
+            draw_text(framebuffer, &label, x - text_width(&label, 2) / 2, y + 24, 2, highlight);
+        } else {
+            framebuffer.draw_filled_circle(x, y, 10.0_f32, *color, OVERLAY_DEPTH);
+            draw_text(framebuffer, &label, x - text_width(&label, 1) / 2, y + 16, 1, *color);
+        }
+    }
+}
+
+// 🔎 Caja de búsqueda en la parte superior del HUD, con el cuerpo que coincide a la derecha
+fn draw_search_box(framebuffer: &mut Framebuffer, query: &str, matched: Option<&CelestialBody>) {
+    let scale = 2;
+    let (x, y) = (20, 20);
+    let text = format!("/{}_", query);
+    let hint = matched.map(|body| format!("  > {}", body.name)).unwrap_or_default();
+    let width = text_width(&text, scale) + text_width(&hint, scale) + 16;
+    let height = GLYPH_HEIGHT * scale + 12;
+    let border = Color::new(120, 160, 220, 255);
+    framebuffer.draw_line_with_depth(x, y, x + width, y, border, OVERLAY_DEPTH);
+    framebuffer.draw_line_with_depth(x + width, y, x + width, y + height, border, OVERLAY_DEPTH);
+    framebuffer.draw_line_with_depth(x + width, y + height, x, y + height, border, OVERLAY_DEPTH);
+    framebuffer.draw_line_with_depth(x, y + height, x, y, border, OVERLAY_DEPTH);
+    draw_text(framebuffer, &text, x + 8, y + 6, scale, Color::WHITE);
+    draw_text(framebuffer, &hint, x + 8 + text_width(&text, scale), y + 6, scale, Color::new(150, 150, 170, 255));
+}
+
+// 🚀 Ventanas de transferencia de Hohmann entre dos órbitas circulares
+// A esta distancia (s simulados) de la ventana la órbita de transferencia parpadea
+const TRANSFER_WINDOW_FLASH_TIME: f32 = 5.0;
+
+// Tiempo entre dos alineaciones iguales de los cuerpos
+fn synodic_period(from_body: &CelestialBody, to_body: &CelestialBody) -> f32 {
+    2.0_f32 * PI / (to_body.orbit_speed - from_body.orbit_speed).abs().max(1e-6_f32)
+}
+
+// Media órbita de la elipse de transferencia. Las velocidades de la escena no siguen una única
+// ley de gravitación, así que el periodo se escala con la 3.ª ley de Kepler desde el de origen.
+fn hohmann_transfer_time(from_body: &CelestialBody, to_body: &CelestialBody) -> f32 {
+    let origin_period = 2.0_f32 * PI / from_body.orbit_speed.abs().max(1e-6_f32);
+    let semi_major_axis = (from_body.orbit_radius + to_body.orbit_radius) * 0.5_f32;
+    0.5_f32 * origin_period * (semi_major_axis / from_body.orbit_radius).powf(1.5_f32)
+}
+
+// Tiempo hasta la próxima ventana: cuando el desfase destino - origen vale `π - n_dest * t_transfer`.
+// El desfase cambia a ritmo constante, así que el resultado está en [0, synodic_period).
+fn next_transfer_window(from_body: &CelestialBody, to_body: &CelestialBody, current_time: f32, synodic_period: f32) -> f32 {
+    let relative_rate = to_body.orbit_speed - from_body.orbit_speed;
+    if relative_rate == 0.0 {
+        return 0.0_f32;
+    }
+    let required_phase = PI - to_body.orbit_speed * hohmann_transfer_time(from_body, to_body);
+    let current_phase = angle_at(current_time, to_body.orbit_speed) - angle_at(current_time, from_body.orbit_speed);
+    ((required_phase - current_phase) / relative_rate).rem_euclid(synodic_period)
+}
+
+// Puntos de la media elipse de transferencia saliendo del origen en `departure_time`
+fn transfer_orbit_points(from_body: &CelestialBody, to_body: &CelestialBody, departure_time: f32) -> Vec<Vector3> {
+    let (r1, r2) = (from_body.orbit_radius, to_body.orbit_radius);
+    let departure_angle = angle_at(departure_time, from_body.orbit_speed);
+    let semi_major_axis = (r1 + r2) * 0.5_f32;
+    // Hacia fuera la salida es el periapsis; hacia dentro es el apoapsis
+    let eccentricity = (r2 - r1) / (r2 + r1);
+    let segments = 64;
+    (0..=segments)
+        .map(|i| {
+            let swept = i as f32 / segments as f32 * PI;
+            let radius = semi_major_axis * (1.0_f32 - eccentricity * eccentricity) / (1.0_f32 + eccentricity * swept.cos());
+            let angle = departure_angle + swept;
+            Vector3::new(angle.cos() * radius, 0.0_f32, angle.sin() * radius)
+        })
+        .collect()
+}
+
+// 🔭 Fases vistas desde el Sol: conjunciones (misma longitud), oposiciones (longitudes opuestas)
+// y elongaciones. Paso de la búsqueda de alineaciones, en segundos simulados.
+const ALIGNMENT_SEARCH_DT: f32 = 0.05;
+
+// Longitud eclíptica del cuerpo vista desde el Sol (en el origen)
+fn heliocentric_longitude(body: &CelestialBody, time: f32) -> f32 {
+    let p = body_position(body, time);
+    p.z.atan2(p.x)
+}
+
+// Ángulo de `body_b` respecto a `body_a` visto desde el Sol, en [-π, π): 0 en conjunción, ±π en
+// oposición
+fn phase_angle(body_a: &CelestialBody, body_b: &CelestialBody, time: f32) -> f32 {
+    (heliocentric_longitude(body_b, time) - heliocentric_longitude(body_a, time) + PI).rem_euclid(2.0_f32 * PI) - PI
+}
+
+// Instante de la próxima conjunción u oposición (lo que llegue antes): avanza de `search_dt` en
+// `search_dt` hasta que el ángulo de fase cambia de signo e interpola dentro del paso. Un salto de
+// signo pequeño es un paso por 0; uno de casi 2π, por ±π. Busca como mucho un periodo sinódico.
+fn find_next_conjunction(body_a: &CelestialBody, body_b: &CelestialBody, current_time: f32, search_dt: f32) -> f32 {
+    let mut previous = phase_angle(body_a, body_b, current_time);
+    if previous == 0.0 {
+        return current_time;
+    }
+    let steps = (synodic_period(body_a, body_b) / search_dt).ceil().min(100_000.0_f32) as usize + 1;
+    let mut t = current_time;
+    for _ in 0..steps {
+        let next = phase_angle(body_a, body_b, t + search_dt);
+        if (previous < 0.0) != (next < 0.0) {
+            let fraction = if (next - previous).abs() < PI {
+                previous / (previous - next)
+            } else {
+                // Oposición: se desenrolla el salto de ±π a ∓π antes de interpolar
+                let (target, unwrapped) = if previous > 0.0 { (PI, next + 2.0_f32 * PI) } else { (-PI, next - 2.0_f32 * PI) };
+                (target - previous) / (unwrapped - previous)
+            };
+            return t + search_dt * fraction;
+        }
+        previous = next;
+        t += search_dt;
+    }
+    t
+}
+
+// Próxima oposición de un planeta exterior vista desde la Tierra: la Tierra queda entre el Sol y
+// el planeta, o sea, conjunción vista desde el Sol (se salta la oposición heliocéntrica)
+fn next_opposition_from_earth(earth: &CelestialBody, planet: &CelestialBody, current_time: f32) -> f32 {
+    let first = find_next_conjunction(earth, planet, current_time, ALIGNMENT_SEARCH_DT);
+    if phase_angle(earth, planet, first).abs() < PI * 0.5_f32 {
+        first
+    } else {
+        find_next_conjunction(earth, planet, first + ALIGNMENT_SEARCH_DT * 0.5_f32, ALIGNMENT_SEARCH_DT)
+    }
+}
+
+// Elongación: ángulo entre el Sol y el cuerpo vistos desde `observer` (la Tierra), en radianes.
+// Para Mercurio y Venus su máximo es la mejor ocasión de verlos.
+fn angular_separation_from_sun(body: &CelestialBody, observer: &CelestialBody, time: f32) -> f32 {
+    let observer_position = body_position(observer, time);
+    let to_sun = normalize_vec3(mul_vec3_scalar(observer_position, -1.0_f32));
+    let to_body = normalize_vec3(sub_vec3(body_position(body, time), observer_position));
+    clamp_f32(to_sun.x * to_body.x + to_sun.y * to_body.y + to_sun.z * to_body.z, -1.0_f32, 1.0_f32).acos()
+}
+
+// Arco alrededor del Sol, a `radius`, entre las longitudes de los dos cuerpos (el ángulo de fase)
+fn phase_arc_points(body_a: &CelestialBody, body_b: &CelestialBody, time: f32, radius: f32) -> Vec<Vector3> {
+    let start = heliocentric_longitude(body_a, time);
+    let sweep = phase_angle(body_a, body_b, time);
+    let segments = 32;
+    (0..=segments)
+        .map(|i| {
+            let angle = start + sweep * i as f32 / segments as f32;
+            Vector3::new(angle.cos() * radius, 0.0_f32, angle.sin() * radius)
+        })
+        .collect()
+}
+
+// 🛤️ Avanza `progress` (distancia recorrida, 0..longitud total) a `speed` y devuelve el punto
+// correspondiente de la polilínea `waypoints`. Al llegar al final se queda en el último punto.
+fn advance_along_path(waypoints: &[Vector3], progress: &mut f32, speed: f32, dt: f32) -> Vector3 {
+    let Some(&last) = waypoints.last() else {
+        return Vector3::new(0.0_f32, 0.0_f32, 0.0_f32);
+    };
+    let total_length: f32 = waypoints.windows(2).map(|w| length_vec3(sub_vec3(w[1], w[0]))).sum();
+    *progress = clamp_f32(*progress + speed * dt, 0.0_f32, total_length);
+
+    let mut remaining = *progress;
+    for segment in waypoints.windows(2) {
+        let segment_length = length_vec3(sub_vec3(segment[1], segment[0]));
+        if remaining <= segment_length && segment_length > 0.0 {
+            return lerp_vec3(segment[0], segment[1], remaining / segment_length);
+        }
+        remaining -= segment_length;
+    }
+    last
+}
+
+// Vista cenital de la pantalla dividida: mitad derecha, a la derecha del separador
+fn top_down_viewport_rect(window_width: i32, window_height: i32) -> (i32, i32, i32, i32) {
+    (window_width / 2 + 1, 0, window_width - window_width / 2 - 1, window_height)
+}
+
+// Punto del mundo (en el plano y = 0) bajo el píxel (sx, sy) de la vista cenital ortográfica
+fn top_down_screen_to_world(sx: i32, sy: i32, viewport: (i32, i32, i32, i32), camera: &Camera, half_height: f32) -> Vector3 {
+    let (vx, vy, vw, vh) = viewport;
+    let aspect = vw as f32 / vh as f32;
+    let ndc_x = (sx - vx) as f32 / vw as f32 * 2.0_f32 - 1.0_f32;
+    let ndc_y = 1.0_f32 - (sy - vy) as f32 / vh as f32 * 2.0_f32;
+    // Ejes de la cámara cenital (mira hacia -Y): derecha = forward x up
+    let forward = normalize_vec3(sub_vec3(camera.target, camera.eye));
+    let right = normalize_vec3(Vector3::new(
+        forward.y * camera.up.z - forward.z * camera.up.y,
+        forward.z * camera.up.x - forward.x * camera.up.z,
+        forward.x * camera.up.y - forward.y * camera.up.x,
+    ));
+    let offset = add_vec3(mul_vec3_scalar(right, ndc_x * half_height * aspect), mul_vec3_scalar(camera.up, ndc_y * half_height));
+    let point = add_vec3(camera.eye, offset);
+    Vector3::new(point.x, 0.0_f32, point.z)
+}
+
+// Polilínea en coordenadas de mundo, con profundidad
+fn draw_path_3d(framebuffer: &mut Framebuffer, points: &[Vector3], color: Color, view_matrix: &Matrix, projection_matrix: &Matrix, viewport_matrix: &Matrix) {
+    let color = Vector3::new(color.r as f32 / 255.0_f32, color.g as f32 / 255.0_f32, color.b as f32 / 255.0_f32);
+    let mut prev: Option<(f32, f32, f32)> = None;
+    for point in points {
+        let current = project_to_screen_subpixel(*point, view_matrix, projection_matrix, viewport_matrix);
+        if let (Some((x0, y0, z0)), Some((x1, y1, z1))) = (prev, current) {
+            framebuffer.draw_line_wu(x0, y0, x1, y1, color, (z0 + z1) * 0.5_f32);
+        }
+        prev = current;
+    }
+}
+
+// 🛤️ Trayectoria de la nave como polilínea verde
+fn draw_trajectory(framebuffer: &mut Framebuffer, waypoints: &[Vector3], view_matrix: &Matrix, projection_matrix: &Matrix, viewport_matrix: &Matrix) {
+    draw_path_3d(framebuffer, waypoints, Color::new(60, 230, 90, 255), view_matrix, projection_matrix, viewport_matrix);
+}
+
+// 🪐 Sandbox orbital
+const SANDBOX_BODY_SCALE: f32 = 1.0;
+// Velocidad inicial por unidad de distancia entre el primer y el segundo clic
+const SANDBOX_VELOCITY_PER_UNIT: f32 = 0.5;
+const SANDBOX_SAVE_PATH: &str = "./sandbox.txt";
+
+// Punto del plano de la eclíptica (y = 0) bajo el píxel (sx, sy) de la vista en perspectiva;
+// `None` si el rayo no corta el plano por delante de la cámara
+fn screen_to_ecliptic(sx: i32, sy: i32, viewport: (i32, i32, i32, i32), camera: &Camera, fov: f32) -> Option<Vector3> {
+    let ray = camera.ray_through_pixel(sx, sy, viewport, fov);
+    if ray.y.abs() < 1e-6_f32 {
+        return None;
+    }
+    let t = -camera.eye.y / ray.y;
+    if t <= 0.0 {
+        return None;
+    }
+    Some(add_vec3(camera.eye, mul_vec3_scalar(ray, t)))
+}
+
+// Flecha de velocidad en pantalla mientras se elige la velocidad inicial, con el valor y la
+// velocidad circular a esa distancia como referencia
+fn draw_velocity_arrow(framebuffer: &mut Framebuffer, from: (i32, i32), to: (i32, i32), speed: f32, circular: f32) {
+    let color = Color::new(250, 210, 80, 255);
+    framebuffer.draw_line_with_depth(from.0, from.1, to.0, to.1, color, OVERLAY_DEPTH);
+    let (dx, dy) = ((to.0 - from.0) as f32, (to.1 - from.1) as f32);
+    let length = (dx * dx + dy * dy).sqrt();
+    if length > 1.0 {
+        let (ux, uy) = (dx / length, dy / length);
+        let head = 10.0_f32.min(length * 0.5_f32);
+        for side in [-1.0_f32, 1.0_f32] {
+            let hx = to.0 - ((ux + uy * side * 0.5_f32) * head) as i32;
+            let hy = to.1 - ((uy - ux * side * 0.5_f32) * head) as i32;
+            framebuffer.draw_line_with_depth(to.0, to.1, hx, hy, color, OVERLAY_DEPTH);
+        }
+    }
+    draw_text(framebuffer, &format!("V {:.2} (CIRC {:.2})", speed, circular), to.0 + 10, to.1 + 10, 2, color);
+}
+
+// 🛸 Piezas de la nave (en espacio del modelo: el frente es +X y la cola -X)
+const NAVE_GEAR_PIVOTS: [(&str, Vector3); 3] = [
+    ("tren_delantero", Vector3::new(0.7, -0.3, 0.0)),
+    ("tren_babor", Vector3::new(-0.6, -0.3, 0.4)),
+    ("tren_estribor", Vector3::new(-0.6, -0.3, -0.4)),
+];
+const NAVE_ENGINE_PIVOTS: [(&str, Vector3); 2] = [
+    ("motor_babor", Vector3::new(-1.1, 0.0, 0.3)),
+    ("motor_estribor", Vector3::new(-1.1, 0.0, -0.3)),
+];
+const NAVE_GEAR_DEPLOY_TIME: f32 = 1.0; // segundos para sacar o recoger el tren
+const NAVE_GIMBAL_MAX: f32 = 0.2;       // rad de giro máximo de los motores
+// Las luces de navegación se dibujan con su propio shader (emisivo, parpadeante)
+const NAV_LIGHT_PREFIX: &str = "luz_";
+
+// Tinte de la nave de reemplazo cuando `nave.obj` no se puede cargar
+const NAVE_FALLBACK_TINT: Vector3 = Vector3::new(1.0, 0.0, 1.0);
+
+// Cono apuntando a +X (el frente del modelo), centrado en el origen como `nave.obj`
+fn fallback_nave_mesh() -> Vec<Vertex> {
+    let height = 2.4_f32;
+    let mut cone = generate_cone_mesh(0.5_f32, height, 16);
+    // Girar -90° alrededor de Z: el eje +Y del cono pasa a +X
+    let to_forward = |v: Vector3| Vector3::new(v.y, -v.x, v.z);
+    for vertex in cone.iter_mut() {
+        vertex.position = add_vec3(to_forward(vertex.position), Vector3::new(-height * 0.5_f32, 0.0_f32, 0.0_f32));
+        vertex.normal = to_forward(vertex.normal);
+    }
+    cone
+}
+
+// Nave como jerarquía: el casco es la raíz y el tren, los motores y las luces cuelgan de él
+fn build_nave_model(hull: Vec<Vertex>) -> ModelNode {
+    let white = Vector3::new(1.0_f32, 1.0_f32, 1.0_f32);
+    let no_rotation = Vector3::new(0.0_f32, 0.0_f32, 0.0_f32);
+    let mut root = ModelNode::new("nave", Matrix::identity(), Some(hull));
+    for (name, pivot) in NAVE_GEAR_PIVOTS {
+        // La pata cuelga del pivote hacia -Y; desplegada queda vertical
+        let leg = generate_box(Vector3::new(-0.04_f32, -0.45_f32, -0.04_f32), Vector3::new(0.04_f32, 0.0_f32, 0.04_f32), white);
+        root = root.with_child(ModelNode::new(name, create_model_matrix(pivot, 1.0_f32, no_rotation), Some(leg)));
+    }
+    for (name, pivot) in NAVE_ENGINE_PIVOTS {
+        // Tobera cilíndrica hacia atrás: el eje +Y del cilindro pasa a -X
+        let mut nozzle = generate_cylinder_mesh(0.1_f32, 0.35_f32, 12);
+        for vertex in nozzle.iter_mut() {
+            vertex.position = Vector3::new(-vertex.position.y, vertex.position.x, vertex.position.z);
+            vertex.normal = Vector3::new(-vertex.normal.y, vertex.normal.x, vertex.normal.z);
+            vertex.color = white;
+        }
+        root = root.with_child(ModelNode::new(name, create_model_matrix(pivot, 1.0_f32, no_rotation), Some(nozzle)));
+    }
+    for (name, tip, color) in [
+        ("luz_babor", Vector3::new(-0.2_f32, 0.0_f32, 0.9_f32), Vector3::new(1.0_f32, 0.1_f32, 0.1_f32)),
+        ("luz_estribor", Vector3::new(-0.2_f32, 0.0_f32, -0.9_f32), Vector3::new(0.1_f32, 1.0_f32, 0.2_f32)),
+    ] {
+        let lamp = generate_box(Vector3::new(-0.05_f32, -0.05_f32, -0.05_f32), Vector3::new(0.05_f32, 0.05_f32, 0.05_f32), color);
+        root = root.with_child(ModelNode::new(name, create_model_matrix(tip, 1.0_f32, no_rotation), Some(lamp)));
+    }
+    root
+}
+
+// 🌟 Nueva función: interpolar entre dos cámaras (Vector3)
+fn lerp_vec3(a: Vector3, b: Vector3, t: f32) -> Vector3 {
+    Vector3::new(
+        a.x + (b.x - a.x) * t,
+        a.y + (b.y - a.y) * t,
+        a.z + (b.z - a.z) * t,
+    )
+}
+
+// 🌟 Nueva función: ease-in-out cúbico
+fn ease_in_out(t: f32) -> f32 {
+    if t < 0.5 {
+        4.0 * t * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+    }
+}
+
+fn main() {
+    let window_width = 1300;
+    let window_height = 900;
+    // 🔲 `--msaa 2` o `--msaa 4`: antialiasing con varias muestras por píxel en los bordes.
+    // `--msaa-grid N`: N x N muestras por píxel en rejilla ordenada (supermuestreo, N hasta 4).
+    // Son dos maneras de fijar las muestras del mismo framebuffer, así que no se pueden juntar
+    let msaa_level: Option<u8> = std::env::args().skip_while(|arg| arg != "--msaa").nth(1).and_then(|level| level.parse().ok());
+    let msaa_grid: Option<u8> = std::env::args().skip_while(|arg| arg != "--msaa-grid").nth(1).and_then(|samples| samples.parse().ok());
+    let msaa_mode = match (msaa_level, msaa_grid) {
+        (Some(_), Some(_)) => {
+            eprintln!("Error: --msaa and --msaa-grid can't be used together");
+            return;
+        }
+        (Some(0), None) => Some(MsaaMode::Off),
+        (Some(samples), None) => Some(MsaaMode::Rotated(samples)),
+        (None, Some(side)) => Some(MsaaMode::Grid(side)),
+        (None, None) => None,
+    };
+
+    let (mut window, raylib_thread) = raylib::init()
+        .size(window_width, window_height)
+        .title("Proyecto 3 - Sistema Solar")
+        .log_level(TraceLogLevel::LOG_WARNING)
+        .build();
+
+    #[cfg(not(feature = "hilbert"))]
+    let mut framebuffer = Framebuffer::new(window_width, window_height);
+    #[cfg(feature = "hilbert")]
+    let mut framebuffer = Framebuffer::new_hilbert(window_width, window_height);
+    if let Some(mode) = msaa_mode {
+        if let Err(e) = framebuffer.set_msaa(mode) {
+            eprintln!("Warning: {}", e);
+        }
+    }
+    // 🔆 `--hdr` (ACES) o `--hdr reinhard`: las mallas guardan el color sin recortar y se les
+    // aplica tone mapping al pasar a 8 bits, así el Sol por encima de 1.0 no satura a blanco
+    if let Some(position) = std::env::args().position(|arg| arg == "--hdr") {
+        let name = std::env::args().nth(position + 1).filter(|arg| !arg.starts_with("--"));
+        let tone_mapping = name.as_deref().map_or(Some(ToneMapping::default()), ToneMapping::from_name);
+        match tone_mapping {
+            Some(tone_mapping) => framebuffer.set_hdr(Some(tone_mapping)),
+            None => eprintln!("Warning: unknown tone mapping '{}', expected reinhard or aces", name.unwrap_or_default()),
+        }
+    }
+
+    // Alejar la cámara para ver mejor todo el sistema
+    let initial_camera_pos = Vector3::new(0.0_f32, 40.0_f32, 140.0_f32);
+    let initial_camera_target = Vector3::new(0.0_f32, 0.0_f32, 0.0_f32);
+    let initial_camera_up = Vector3::new(0.0_f32, 1.0_f32, 0.0_f32);
+    let mut camera = Camera::new(initial_camera_pos, initial_camera_target, initial_camera_up);
+    // Vistas fijas del menú radial, antes de los cuerpos: la inicial y la cenital del sistema
+    let warp_presets = [
+        ("Vista inicial", Color::new(200, 200, 210, 255), WarpTarget { eye: initial_camera_pos, target: initial_camera_target, up: initial_camera_up }),
+        (
+            "Vista cenital",
+            Color::new(150, 190, 255, 255),
+            WarpTarget {
+                eye: Vector3::new(0.0_f32, 100.0_f32, 0.0_f32),
+                target: Vector3::new(0.0_f32, 0.0_f32, 0.0_f32),
+                up: Vector3::new(0.0_f32, 0.0_f32, -1.0_f32),
+            },
+        ),
+    ];
+
+    // ☀️ El Sol: luz puntual con atenuación de inversa del cuadrado
+    let light = Light::point(Vector3::new(0.0_f32, 0.0_f32, 0.0_f32), Vector3::new(1.0_f32, 1.0_f32, 1.0_f32), SUN_LIGHT_INTENSITY, LightAttenuation::INVERSE_SQUARE);
+
+    // Cargar nave y esfera (sphere como malla de planetas). Añadir logging y comprobación.
+    // Si la nave no carga se usa un cono magenta en su lugar, bien visible para notar el fallo
+    let (mut nave_vertex_array, nave_fallback) = match Obj::load("./assets/nave.obj") {
+        Ok(o) => {
+            let (min, max, _, radius) = o.compute_bounds();
+            eprintln!("Loaded ./assets/nave.obj successfully (bounds {:?} - {:?}, radius {:.2})", min, max, radius);
+            let vertices = o.get_vertex_array();
+            eprintln!("nave.obj vertex count = {}", vertices.len());
+            (vertices, false)
+        },
+        Err(e) => {
+            eprintln!("Warning: could not load ./assets/nave.obj ({}) — using fallback cone", e);
+            (fallback_nave_mesh(), true)
+        }
+    };
+
+    // Oclusión ambiental de la nave: `--bake-ao` la calcula y la guarda en el sidecar `.col`,
+    // si no se intenta cargar el sidecar; sin AO la nave queda sin oscurecer (color blanco)
+    let nave_ao_path = "./assets/nave.col";
+    if nave_fallback {
+        // El color de vértice tiñe la nave en `nave_fragment_shader`
+        for vertex in nave_vertex_array.iter_mut() {
+            vertex.color = NAVE_FALLBACK_TINT;
+        }
+    } else if std::env::args().any(|arg| arg == "--bake-ao") {
+        bake_ao_to_vertex_colors(&mut nave_vertex_array, 64, 2.0_f32);
+        match save_vertex_colors(nave_ao_path, &nave_vertex_array) {
+            Ok(()) => eprintln!("Baked ambient occlusion saved to {}", nave_ao_path),
+            Err(e) => eprintln!("Warning: could not save {}: {}", nave_ao_path, e),
+        }
+    } else if load_vertex_colors(nave_ao_path, &mut nave_vertex_array).is_err() {
+        for vertex in nave_vertex_array.iter_mut() {
+            vertex.color = Vector3::new(1.0_f32, 1.0_f32, 1.0_f32);
+        }
+    }
+
+    // 🌐 Planetas: cube-sphere (triángulos casi uniformes). `--uv-sphere` usa el `sphere.obj` de antes.
+    // Las dos mallas quedan centradas y con radio `PLANET_MESH_RADIUS`, que es lo que supone `body_radius`.
+    // 🖼️ Atlas opcional para retocar los shaders procedurales; sin él todo sigue siendo procedural
+    let planet_atlas = if std::path::Path::new(ATLAS_PATH).exists() {
+        match TextureAtlas::load(ATLAS_PATH) {
+            Ok(atlas) => Some(atlas),
+            Err(e) => {
+                eprintln!("Warning: could not load {}: {}", ATLAS_PATH, e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let planet_vertex_array = if std::env::args().any(|arg| arg == "--uv-sphere") {
+        match Obj::load("./assets/sphere.obj") {
+            Ok(mut sphere) => {
+                eprintln!("Loaded ./assets/sphere.obj successfully");
+                sphere.normalize_to_unit_sphere();
+                let mut vertices = sphere.get_vertex_array();
+                for vertex in vertices.iter_mut() {
+                    vertex.position = mul_vec3_scalar(vertex.position, PLANET_MESH_RADIUS);
+                }
+                vertices
+            },
+            Err(_) => {
+                eprintln!("Warning: ./assets/sphere.obj not found — using nave mesh as fallback for planets");
+                nave_vertex_array.clone()
+            }
+        }
+    } else {
+        let mut cube_sphere = generate_cube_sphere(10); // 1200 triángulos, similar a sphere.obj
+        for vertex in cube_sphere.iter_mut() {
+            vertex.position = mul_vec3_scalar(vertex.position, PLANET_MESH_RADIUS);
+        }
+        cube_sphere
+    };
+    // 🛸 Nave por piezas: L saca/recoge el tren, los motores se orientan según el movimiento
+    let mut nave_model = build_nave_model(nave_vertex_array);
+    let mut gear_deployed = false;
+    let mut gear_deploy = 0.0_f32; // 0 = recogido, 1 = desplegado
+    let mut engine_gimbal = (0.0_f32, 0.0_f32); // (guiñada, cabeceo)
+    // 🛰️ Estación en órbita de la Tierra: cerca de ella la nave despliega los puntales de acoplamiento
+    let station_mesh = generate_station_mesh();
+    let strut_segment = generate_strut_segment();
+    let docking_struts = if std::path::Path::new(DOCKING_CONFIG_PATH).exists() {
+        DockingStruts::load(DOCKING_CONFIG_PATH).unwrap_or_else(|e| {
+            eprintln!("Warning: could not load {}: {}", DOCKING_CONFIG_PATH, e);
+            DockingStruts::default()
+        })
+    } else {
+        DockingStruts::default()
+    };
+    let mut docking = DockingAnimation::default();
+
+
+    framebuffer.set_background_color(Color::new(0, 0, 0, 255)); 
+    // Dithering temporal contra el banding de los degradados (G lo activa/desactiva)
+    let mut dithering = true;
+    framebuffer.set_dithering(dithering);
+    framebuffer.set_temporal_dithering(true);
+
+    // 💍 Anillos de Saturno: C+B y A por separado, así la división de Cassini queda vacía
+    let mut saturn_ring_vertex_array = generate_ring_mesh(SATURN_C_RING_INNER, SATURN_CASSINI_INNER, 128, SATURN_AXIAL_TILT);
+    saturn_ring_vertex_array.extend(generate_ring_mesh(SATURN_CASSINI_OUTER, SATURN_A_RING_OUTER, 128, SATURN_AXIAL_TILT));
+    let uranus_ring_vertex_array = generate_ring_system(&URANUS_RINGS, URANUS_RADIUS_KM, URANUS_RING_MIN_WIDTH, 256, URANUS_AXIAL_TILT);
+
+    // 📖 Cuerpos del catálogo; el Sol (shader "Sun") queda en el origen
+    let mut celestial_bodies = match load_body_catalog(BODY_CATALOG_PATH) {
+        Ok(bodies) if !bodies.is_empty() => {
+            eprintln!("Loaded {} bodies from {}", bodies.len(), BODY_CATALOG_PATH);
+            bodies
+        }
+        Ok(_) => {
+            eprintln!("Warning: {} is empty — using the minimal built-in catalog", BODY_CATALOG_PATH);
+            fallback_body_catalog()
+        }
+        Err(err) => {
+            eprintln!("Warning: could not load {} ({}) — using the minimal built-in catalog", BODY_CATALOG_PATH, err);
+            fallback_body_catalog()
+        }
+    };
+    update_orbit_centers(&mut celestial_bodies, 0.0_f32);
+    if !apply_sun_node_precession(&mut celestial_bodies) {
+        eprintln!("Warning: the body catalog has no body with shader \"Sun\" — orbits won't precess");
+    }
+    let args: Vec<String> = std::env::args().collect();
+    // 📤 `--export-orbits [archivo]`: guarda las órbitas de todos los cuerpos en CSV al arrancar
+    // (comprimido si el archivo acaba en .gz)
+    if let Some(position) = args.iter().position(|arg| arg == "--export-orbits") {
+        let path = args.get(position + 1).filter(|arg| !arg.starts_with("--")).cloned().unwrap_or_else(|| ORBIT_EXPORT_PATH.to_string());
+        match export_orbital_data(&celestial_bodies, 0.0_f32, ORBIT_EXPORT_DURATION, ORBIT_EXPORT_STEP, &path) {
+            Ok(()) => eprintln!("Orbital data exported to {}", path),
+            Err(e) => eprintln!("Warning: could not export orbital data to {}: {}", path, e),
+        }
+    }
+    // 🌅 Transmitancia de la atmósfera de la Tierra (radio 1, altura de escala = un cuarto de la
+    // atmósfera), calculada una sola vez
+    let earth_atmosphere_height = celestial_bodies.iter().find(|body| body.name == "Earth").map_or(0.02_f32, |earth| earth.atmosphere_height);
+    let earth_transmittance = precompute_transmittance_table(1.0_f32, earth_atmosphere_height, earth_atmosphere_height * 0.25_f32, TRANSMITTANCE_RESOLUTION);
+
+    let mut time = 0.0_f32;
+    let mut warp_queue: WarpQueue = VecDeque::new();
+    let mut warp_start_time = 0.0_f32;
+    // Segundos que lleva abierto el menú radial de warp (0 = cerrado)
+    let mut radial_menu_time = 0.0_f32;
+    let warp_duration = 1.0_f32; // segundos
+
+    // Posición segura inicial de cámara (para restaurar si algo sale mal)
+    let mut safe_camera_eye = camera.eye;
+    let mut safe_camera_target = camera.target;
+
+    // Parámetros para posicionar la nave relativa a la cámara (nave sigue la cámara)
+    let nave_offset_back = 6.0_f32;        // cuánto queda detrás del ojo (positivo = atrás)
+    let nave_offset_down = 2.5_f32;        // cuánto hacia abajo respecto al eye
+    let default_nave_scale = 1.0_f32;      // ajustar según tu modelo
+    let nave_model_offset_forward = 0.4_f32; // compensación por pivote del modelo (hacia el frente)
+
+    // Foco de la nave (cono doble: interior a plena intensidad, exterior con caída suave)
+    let nave_nose_offset = 1.2_f32;          // distancia del centro de la nave a la nariz
+    let nave_spot_inner_angle = 0.20_f32;    // rad
+    let nave_spot_outer_angle = 0.35_f32;    // rad
+    let nave_spot_intensity = 1.5_f32;
+
+    // Parámetros de navegación libre (control 3D)
+    let base_speed = 40.0_f32;      // unidades / s
+    let sprint_mult = 2.2_f32;
+    let yaw_speed = 1.8_f32;        // rad/s (flechas izquierda/derecha)
+    let pitch_speed = 1.2_f32;      // rad/s (flechas arriba/abajo)
+
+    // 🔭 Campo de visión (zoom): Z/X lo cambian, V encuadra el planeta seleccionado
+    let min_fov = 0.3_f32;
+    let max_fov = 1.8_f32;
+    let fov_step = 0.8_f32;          // rad/s mientras se mantiene Z/X
+    let frame_screen_fraction = 0.4_f32;
+    let mut fov = PI / 3.0;
+    let mut zoom_target_fov = fov;
+
+    // 🌠 Estelas de órbita (T para mostrar/ocultar)
+    let mut show_trails = true;
+
+    // 🧭 Cuerpo seleccionado con el ratón (índice en `celestial_bodies`)
+    let mut selected_body: Option<usize> = None;
+    // Cuerpo en el que está bloqueada la mirada (O)
+    let mut camera_lock: Option<usize> = None;
+    // 🔍 Rayo del último clic de selección, para verlo con Alt
+    let mut last_pick_ray: Option<PickRay> = None;
+
+    // 💥 Impactos: cuerpo con el que la cámara está en contacto, ondas activas y frames de destello
+    let mut colliding_with: Option<usize> = None;
+    let mut impact_effects: Vec<ImpactEffect> = Vec::new();
+    let mut impact_flash_frames = 0_u32;
+
+    // 🔎 Búsqueda por nombre: "/" abre la caja, Enter viaja al cuerpo, Esc cancela.
+    // Esc ya no cierra la ventana directamente para poder cancelar la búsqueda.
+    let mut search_input: Option<String> = None;
+
+    // 📜 Shaders en Rhai: `--script-shader Mars=assets/shaders/ejemplo.rhai` (se puede repetir).
+    // Los scripts se recargan solos al guardarlos.
+    let mut shader_overrides: HashMap<String, String> = HashMap::new();
+    for pair in args.windows(2).filter(|w| w[0] == "--script-shader") {
+        match pair[1].split_once('=') {
+            Some((body, path)) => {
+                shader_overrides.insert(body.to_string(), format!("{}{}", SCRIPT_SHADER_PREFIX, path));
+            }
+            None => eprintln!("Warning: --script-shader expects Body=file.rhai, got {}", pair[1]),
+        }
+    }
+    let mut script_shaders = ScriptShaderCache::new();
+
+    // 🌫️ Capas de atmósfera: una esfera como la de los planetas teñida con el color del cuerpo
+    let atmosphere_shells: HashMap<String, Vec<Vertex>> = celestial_bodies
+        .iter()
+        .filter(|body| body.atmosphere_density > 0.0_f32)
+        .map(|body| {
+            let tint = Vector3::new(body.color.r as f32 / 255.0_f32, body.color.g as f32 / 255.0_f32, body.color.b as f32 / 255.0_f32);
+            let mut shell = generate_cube_sphere(10);
+            for vertex in shell.iter_mut() {
+                vertex.position = mul_vec3_scalar(vertex.position, PLANET_MESH_RADIUS);
+                vertex.color = tint;
+            }
+            (body.name.clone(), shell)
+        })
+        .collect();
+
+    // 🔤 Fuente SDF para etiquetas que cambian de tamaño
+    let sdf_font = SdfFont::new();
+    // 🏷️ Sprites del HUD y un icono por cuerpo con la letra de su tipo
+    let hud_sprites = HudSprites::load(SPRITE_DIR);
+    let planet_icons: Vec<Sprite> = celestial_bodies
+        .iter()
+        .map(|body| planet_icon(Vector3::new(body.color.r as f32 / 255.0_f32, body.color.g as f32 / 255.0_f32, body.color.b as f32 / 255.0_f32), planet_type_symbol(&body.shader_name)))
+        .collect();
+
+    // ✨ Partículas del motor de la nave (salen mientras se acelera con W)
+    let mut particles = ParticleSystem::new();
+    // 💍 Polvo de impactos en los anillos de Saturno (simulado en tiempo simulado)
+    let mut ring_dust = RingDustSystem::new(saturn_ring_normal(), SATURN_C_RING_INNER, SATURN_A_RING_OUTER);
+    let mut solar_flare: Option<SolarFlare> = None;
+    let mut solar_wind = SolarWindSystem::new();
+    let mut flare_rng = fastrand::Rng::with_seed(23);
+    let comet_nucleus_vertex_array = generate_asteroid_detail_mesh(COMET_NUCLEUS_SEED, COMET_NUCLEUS_RADIUS);
+    let mut comet_jets = CometJetSystem::new();
+    let mut kuiper_belt = KuiperBeltSimulation::new(41);
+    // 🌀 Túnel del warp y cuánto han avanzado sus estelas
+    let warp_tunnel_vertex_array = generate_warp_tunnel(WARP_TUNNEL_RADIUS, WARP_TUNNEL_LENGTH, WARP_TUNNEL_RINGS, WARP_TUNNEL_SEGMENTS);
+    let mut warp_tunnel_scroll = 0.0_f32;
+
+    // 🪟 Pantalla dividida (Tab): piloto a la izquierda, vista cenital ortográfica a la derecha
+    let mut split_screen = false;
+    let top_down_camera = Camera::new(
+        Vector3::new(0.0_f32, 200.0_f32, 0.0_f32),
+        Vector3::new(0.0_f32, 0.0_f32, 0.0_f32),
+        Vector3::new(0.0_f32, 0.0_f32, -1.0_f32),
+    );
+    let top_down_half_height = 100.0_f32; // cubre hasta la órbita de Plutón
+    let minimap_resolution_scale = 0.5_f32;
+    let (_, _, minimap_width, minimap_height) = top_down_viewport_rect(window_width, window_height);
+    let mut minimap_framebuffer = Framebuffer::new(
+        ((minimap_width as f32 * minimap_resolution_scale) as i32).max(1),
+        ((minimap_height as f32 * minimap_resolution_scale) as i32).max(1),
+    );
+    minimap_framebuffer.set_background_color(Color::new(0, 0, 0, 255));
+
+    // 🛤️ Trayectoria de la nave: clic/arrastre en la vista cenital añade puntos, C la borra.
+    // Con trayectoria la cámara (y la nave) la recorre sola; solo se puede mirar con las flechas.
+    let mut ship_trajectory: Vec<Vector3> = Vec::new();
+    let mut trajectory_progress = 0.0_f32;
+    let trajectory_speed = 20.0_f32;        // unidades / s
+    let min_waypoint_spacing = 2.0_f32;     // al arrastrar, distancia mínima entre puntos
+    window.set_exit_key(None);
+
+    // ⏩ Escala de tiempo: "." duplica y "," reduce a la mitad. Solo afecta a la simulación
+    // (órbitas y giros); la cámara, los warps y las partículas van en tiempo real.
+    let mut time_scale = 1.0_f32;
+    let min_time_scale = 0.125_f32;
+    let max_time_scale = 64.0_f32;
+    let mut real_time = 0.0_f32;
+
+    // 🪐 Sandbox orbital (M): clic fija la posición, segundo clic la velocidad; clic derecho
+    // cancela, Supr borra el cuerpo seleccionado, F5 guarda y F9 carga `sandbox.txt`.
+    // En este modo solo queda el Sol, que es el único cuerpo que atrae a los nuevos.
+    let mut sandbox_mode = false;
+    let mut sandbox = Sandbox::new();
+    let mut sandbox_placing: Option<Vector3> = None;
+    let mut sandbox_selected: Option<usize> = None;
+
+    // 💥 Nova (N): malla de la onda de choque (esfera unidad densa) y estado de la nova activa
+    let nova_shell_vertex_array = generate_cube_sphere(24);
+    let mut nova: Option<NovaEffect> = None;
+
+    // 🌫️ Nebulosa de fondo, detrás del sistema desde la vista inicial
+    let dust_cloud = DustCloud::new(Vector3::new(0.0_f32, 30.0_f32, -80.0_f32), 25.0_f32);
+
+    // ☄️ Cinturón de asteroides: unas pocas formas de roca compartidas por todos
+    let asteroid_meshes: Vec<Vec<Vertex>> = (0..ASTEROID_SHAPES).map(|shape| generate_asteroid_mesh(3, 10, 0.35_f32, shape as u64)).collect();
+    let asteroids: Vec<Asteroid> = (0..ASTEROID_COUNT).map(Asteroid::new).collect();
+    // Las mallas detalladas se generan la primera vez que la cámara se acerca a cada asteroide
+    let mut asteroid_detail_meshes: HashMap<u64, Vec<Vertex>> = HashMap::new();
+    let mut near_surface_meshes: HashMap<String, NearSurfaceMesh> = HashMap::new();
+
+    // 🧲 Cuerpos con las líneas de campo magnético visibles (B sobre el seleccionado)
+    let mut magnetic_fields: HashSet<usize> = HashSet::new();
+
+    // 🚀 Ruta de transferencia (H): de la Tierra al cuerpo seleccionado, con cuenta atrás
+    let mut transfer_route: Option<(usize, usize)> = None;
+
+    // 🗺️ Niebla de guerra: celdas del plano XZ por las que ya pasó la cámara
+    let mut explored_grid: ExploredGrid = [[false; EXPLORATION_GRID_SIZE]; EXPLORATION_GRID_SIZE];
+
+    // 💾 Partida guardada: visitas, distancia, logros; los logros nuevos salen como banner
+    let mut game_save = if std::path::Path::new(SAVE_PATH).exists() {
+        GameSave::load(SAVE_PATH).unwrap_or_else(|e| {
+            eprintln!("Warning: could not load {}: {}", SAVE_PATH, e);
+            GameSave::default()
+        })
+    } else {
+        GameSave::default()
+    };
+    let mut save_tracker = SaveTracker::default();
+    let mut tracked_eye = camera.eye;
+    let mut achievement_banners: Vec<(&'static str, f32)> = Vec::new(); // (logro, tiempo real al desbloquearlo)
+
+    // ⏱️ Perfilado por etapas: F3 muestra el desglose del frame anterior y `--profile` guarda
+    // todos los frames en `profile_trace.json` (formato Chrome tracing) al salir
+    let mut show_stats = false;
+    // 🧱 F2 alterna entre render forward y diferido (pasada de profundidad antes de sombrear)
+    let mut render_mode = RenderMode::Forward;
+    let mut shadow_quality = SHADOW_QUALITY_FAST;
+    // 🌑 F6: oclusión ambiental en espacio de pantalla sobre la vista piloto
+    let mut ssao_enabled = false;
+    let clip_planes = if std::path::Path::new(PROJECTION_CONFIG_PATH).exists() {
+        ClipPlanes::load(PROJECTION_CONFIG_PATH).unwrap_or_else(|e| {
+            eprintln!("Warning: could not load {}: {}", PROJECTION_CONFIG_PATH, e);
+            ClipPlanes::default()
+        })
+    } else {
+        ClipPlanes::default()
+    };
+    if clip_planes.near > TERRAIN_MIN_ALTITUDE {
+        eprintln!("Warning: near plane {} is above the terrain-follow altitude {}; the ground will be clipped", clip_planes.near, TERRAIN_MIN_ALTITUDE);
+    }
+    let mut postprocess = PostProcessPipeline::with_default_passes();
+    if std::path::Path::new(POSTPROCESS_CONFIG_PATH).exists() {
+        if let Err(e) = postprocess.load(POSTPROCESS_CONFIG_PATH) {
+            eprintln!("Warning: could not load {}: {}", POSTPROCESS_CONFIG_PATH, e);
+        }
+    }
+    let mut last_profile = FrameProfiler::default();
+    let mut last_frame_us = 0_u64;
+    let mut profile_trace = args.iter().any(|arg| arg == "--profile").then(ProfileTrace::new);
+
+    while !window.window_should_close() {
+        let frame_start = Instant::now();
+        // `frame_dt` es tiempo real (cámara, partículas); `dt` es tiempo simulado (órbitas, giros)
+        let frame_dt = window.get_frame_time();
+        if search_input.is_none() && window.is_key_pressed(KeyboardKey::KEY_PERIOD) {
+            time_scale = (time_scale * 2.0_f32).min(max_time_scale);
+        }
+        if search_input.is_none() && window.is_key_pressed(KeyboardKey::KEY_COMMA) {
+            time_scale = (time_scale * 0.5_f32).max(min_time_scale);
+        }
+        let dt = frame_dt * time_scale;
+        time += dt;
+        update_orbit_centers(&mut celestial_bodies, time);
+        real_time += frame_dt;
+
+        if search_input.is_none() && window.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+            break;
+        }
+
+        // 🔎 Modo búsqueda: recoger caracteres tecleados
+        if let Some(query) = search_input.as_mut() {
+            while let Some(c) = window.get_char_pressed() {
+                if c.is_ascii_alphanumeric() || c == ' ' {
+                    query.push(c);
+                }
+            }
+            if window.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+                query.pop();
+            }
+            if window.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+                search_input = None;
+            } else if window.is_key_pressed(KeyboardKey::KEY_ENTER) {
+                // "pp <pase>" es un comando de consola, no una búsqueda
+                if let Some(pass_name) = query.strip_prefix("pp ") {
+                    if !postprocess.toggle(pass_name.trim()) {
+                        eprintln!("Warning: unknown post-process pass {}", pass_name.trim());
+                    }
+                } else if let Some(i) = find_body_by_prefix(&celestial_bodies, query) {
+                    enqueue_warp(&mut warp_queue, &mut warp_start_time, warp_target_for_body(&celestial_bodies[i], time), real_time);
+                }
+                search_input = None;
+            }
+        } else if window.is_key_pressed(KeyboardKey::KEY_SLASH) {
+            search_input = Some(String::new());
+            // Descartar el propio "/" de la cola de caracteres
+            while window.get_char_pressed().is_some() {}
+        }
+        // Mientras se escribe, las teclas no mueven la cámara
+        let typing = search_input.is_some();
+
+        // 🛤️ Editar la trayectoria desde la vista cenital
+        if split_screen && !typing && window.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT) {
+            let minimap = top_down_viewport_rect(window_width, window_height);
+            let (mx, my) = (window.get_mouse_x(), window.get_mouse_y());
+            if mx >= minimap.0 && mx < minimap.0 + minimap.2 && my >= minimap.1 && my < minimap.1 + minimap.3 {
+                let clicked = top_down_screen_to_world(mx, my, minimap, &top_down_camera, top_down_half_height);
+                // La trayectoria arranca donde está la cámara y se mantiene a su altura
+                let waypoint = Vector3::new(clicked.x, camera.eye.y, clicked.z);
+                if ship_trajectory.is_empty() {
+                    ship_trajectory.push(camera.eye);
+                    trajectory_progress = 0.0_f32;
+                }
+                let far_enough = ship_trajectory
+                    .last()
+                    .is_none_or(|last| length_vec3(sub_vec3(waypoint, *last)) > min_waypoint_spacing);
+                if far_enough {
+                    ship_trajectory.push(waypoint);
+                }
+            }
+        }
+        if !typing && window.is_key_pressed(KeyboardKey::KEY_G) {
+            dithering = !dithering;
+            framebuffer.set_dithering(dithering);
+        }
+        if !typing && window.is_key_pressed(KeyboardKey::KEY_M) {
+            sandbox_mode = !sandbox_mode;
+            sandbox_placing = None;
+            sandbox_selected = None;
+            selected_body = None;
+        }
+        if sandbox_mode && !typing {
+            if window.is_key_pressed(KeyboardKey::KEY_DELETE) {
+                if let Some(i) = sandbox_selected.take() {
+                    sandbox.remove_body(i);
+                }
+            }
+            if window.is_key_pressed(KeyboardKey::KEY_F5) {
+                match sandbox.save(SANDBOX_SAVE_PATH) {
+                    Ok(()) => eprintln!("Sandbox saved to {}", SANDBOX_SAVE_PATH),
+                    Err(e) => eprintln!("Warning: could not save {}: {}", SANDBOX_SAVE_PATH, e),
+                }
+            }
+            if window.is_key_pressed(KeyboardKey::KEY_F9) {
+                match sandbox.load(SANDBOX_SAVE_PATH) {
+                    Ok(()) => sandbox_selected = None,
+                    Err(e) => eprintln!("Warning: could not load {}: {}", SANDBOX_SAVE_PATH, e),
+                }
+            }
+        }
+        if sandbox_mode {
+            sandbox.update(dt);
+        }
+        if !typing && window.is_key_pressed(KeyboardKey::KEY_C) {
+            ship_trajectory.clear();
+            trajectory_progress = 0.0_f32;
+        }
+        let following_trajectory = ship_trajectory.len() >= 2;
+
+        // Guardar posición segura previa
+        let prev_eye = camera.eye;
+        let prev_target = camera.target;
+
+        // 🌟 Warping animado. Los saltos pedidos durante un warp se encolan y empiezan en cuanto
+        // termina el anterior. Tab abre el menú radial; al soltarlo se salta al cuerpo resaltado
+        let radial_menu_center = (window_width / 2, window_height / 2);
+        let radial_selection = radial_menu_selection(warp_presets.len() + celestial_bodies.len(), radial_menu_center, (window.get_mouse_x(), window.get_mouse_y()));
+        if !typing && window.is_key_down(KeyboardKey::KEY_TAB) {
+            radial_menu_time += frame_dt;
+        } else if radial_menu_time > 0.0_f32 {
+            let destination = radial_selection.filter(|_| !typing).map(|i| match warp_presets.get(i) {
+                Some((_, _, preset)) => preset.clone(),
+                None => warp_target_for_body(&celestial_bodies[i - warp_presets.len()], time),
+            });
+            if let Some(destination) = destination {
+                enqueue_warp(&mut warp_queue, &mut warp_start_time, destination, real_time);
+            }
+            radial_menu_time = 0.0_f32;
+        }
+
+        let is_warping = !warp_queue.is_empty();
+        let mut warp_tunnel_alpha = 0.0_f32;
+        // Carga del motor de warp: se vacía al saltar y se rellena a lo largo del tramo
+        let mut warp_charge = 1.0_f32;
+        let mut boosting = false;
+        if let Some(t) = advance_warp(&mut warp_queue, &mut warp_start_time, &mut camera, real_time, warp_duration) {
+            warp_tunnel_alpha = (PI * t).sin();
+            warp_charge = t;
+            warp_tunnel_scroll += WARP_TUNNEL_SPEED * frame_dt;
+        } else if !typing {
             // CONTROL 3D MANUAL: WASD = movimiento en el plano de la mirada, Q/E = down/up,
             // Shift = sprint, flechas = rotación yaw/pitch
             let mut speed = base_speed;
             if window.is_key_down(KeyboardKey::KEY_LEFT_SHIFT) {
                 speed *= sprint_mult;
+                boosting = true;
             }
 
             // Rotación con flechas
             if window.is_key_down(KeyboardKey::KEY_LEFT) {
-                camera.yaw -= yaw_speed * dt;
+                camera.yaw -= yaw_speed * frame_dt;
             }
             if window.is_key_down(KeyboardKey::KEY_RIGHT) {
-                camera.yaw += yaw_speed * dt;
+                camera.yaw += yaw_speed * frame_dt;
             }
             if window.is_key_down(KeyboardKey::KEY_UP) {
-                camera.pitch = clamp_f32(camera.pitch + pitch_speed * dt, -1.4_f32, 1.4_f32);
+                camera.pitch = clamp_f32(camera.pitch + pitch_speed * frame_dt, -1.4_f32, 1.4_f32);
             }
             if window.is_key_down(KeyboardKey::KEY_DOWN) {
-                camera.pitch = clamp_f32(camera.pitch - pitch_speed * dt, -1.4_f32, 1.4_f32);
+                camera.pitch = clamp_f32(camera.pitch - pitch_speed * frame_dt, -1.4_f32, 1.4_f32);
             }
 
             // Dirección forward a partir de yaw/pitch
@@ -534,33 +3268,123 @@ fn main() {
             let up = Vector3::new(0.0_f32, 1.0_f32, 0.0_f32);
 
             // Movimiento local: W/S adelante/atrás, A/D strafe, Q baja, E sube
-            if window.is_key_down(KeyboardKey::KEY_W) {
-                camera.eye = add_vec3(camera.eye, mul_vec3_scalar(forward_n, speed * dt));
+            if following_trajectory {
+                camera.eye = advance_along_path(&ship_trajectory, &mut trajectory_progress, trajectory_speed, frame_dt);
+            }
+            if !following_trajectory && window.is_key_down(KeyboardKey::KEY_W) {
+                camera.eye = add_vec3(camera.eye, mul_vec3_scalar(forward_n, speed * frame_dt));
             }
-            if window.is_key_down(KeyboardKey::KEY_S) {
-                camera.eye = add_vec3(camera.eye, mul_vec3_scalar(forward_n, -speed * dt));
+            if !following_trajectory && window.is_key_down(KeyboardKey::KEY_S) {
+                camera.eye = add_vec3(camera.eye, mul_vec3_scalar(forward_n, -speed * frame_dt));
             }
-            if window.is_key_down(KeyboardKey::KEY_A) {
-                camera.eye = add_vec3(camera.eye, mul_vec3_scalar(right_n, -speed * dt));
+            if !following_trajectory && window.is_key_down(KeyboardKey::KEY_A) {
+                camera.eye = add_vec3(camera.eye, mul_vec3_scalar(right_n, -speed * frame_dt));
             }
-            if window.is_key_down(KeyboardKey::KEY_D) {
-                camera.eye = add_vec3(camera.eye, mul_vec3_scalar(right_n, speed * dt));
+            if !following_trajectory && window.is_key_down(KeyboardKey::KEY_D) {
+                camera.eye = add_vec3(camera.eye, mul_vec3_scalar(right_n, speed * frame_dt));
             }
-            if window.is_key_down(KeyboardKey::KEY_E) {
-                camera.eye = add_vec3(camera.eye, mul_vec3_scalar(up, speed * dt));
+            if !following_trajectory && window.is_key_down(KeyboardKey::KEY_E) {
+                camera.eye = add_vec3(camera.eye, mul_vec3_scalar(up, speed * frame_dt));
             }
-            if window.is_key_down(KeyboardKey::KEY_Q) {
-                camera.eye = add_vec3(camera.eye, mul_vec3_scalar(up, -speed * dt));
+            if !following_trajectory && window.is_key_down(KeyboardKey::KEY_Q) {
+                camera.eye = add_vec3(camera.eye, mul_vec3_scalar(up, -speed * frame_dt));
             }
 
-            // Actualizar target para que la cámara mire en la dirección definida por yaw/pitch
-            camera.target = add_vec3(camera.eye, forward_n);
+            // Actualizar target para que la cámara mire en la dirección definida por yaw/pitch
+            camera.target = add_vec3(camera.eye, forward_n);
+        }
+
+        // 🔭 Zoom: Z acerca (FOV menor), X aleja (FOV mayor), V encuadra el planeta al centro de la vista
+        if !typing && window.is_key_down(KeyboardKey::KEY_Z) {
+            zoom_target_fov -= fov_step * frame_dt;
+        }
+        if !typing && window.is_key_down(KeyboardKey::KEY_X) {
+            zoom_target_fov += fov_step * frame_dt;
+        }
+        if !typing && window.is_key_pressed(KeyboardKey::KEY_V) {
+            if let Some(i) = body_in_view(&camera, &celestial_bodies, time) {
+                let body = &celestial_bodies[i];
+                let distance = length_vec3(sub_vec3(body_position(body, time), camera.eye));
+                zoom_target_fov = fov_to_frame(body_radius(body), distance, frame_screen_fraction);
+            }
+        }
+        zoom_target_fov = clamp_f32(zoom_target_fov, min_fov, max_fov);
+        fov += (zoom_target_fov - fov) * 0.1_f32;
+
+        // 🌠 Estelas: muestrear la posición de cada cuerpo que tenga estela
+        if !typing && window.is_key_pressed(KeyboardKey::KEY_T) {
+            show_trails = !show_trails;
+        }
+        for body in celestial_bodies.iter_mut() {
+            let position = body_position(body, time);
+            if let Some(trail) = body.trail.as_mut() {
+                trail.sample(position, time, dt < 0.0);
+            }
+        }
+
+        // Fuera del sandbox solo se ven (y chocan) los cuerpos del sistema solar; dentro, solo el Sol
+        let visible_bodies = if sandbox_mode {
+            celestial_bodies.iter().position(is_sun).map_or(&[][..], |i| std::slice::from_ref(&celestial_bodies[i]))
+        } else {
+            &celestial_bodies[..]
+        };
+
+        // 🛬 F activa el vuelo rasante cerca de un cuerpo (y lo desactiva en cualquier momento)
+        let nearest = nearest_surface(camera.eye, visible_bodies, time);
+        if !typing && window.is_key_pressed(KeyboardKey::KEY_F) {
+            camera.mode = match (camera.mode, nearest) {
+                (CameraMode::TerrainFollowMode, _) => CameraMode::Free,
+                (CameraMode::Free, Some((i, altitude))) if altitude < terrain_follow_altitude(&visible_bodies[i]) => CameraMode::TerrainFollowMode,
+                (mode, _) => mode,
+            };
+        }
+
+        // 🔒 O bloquea la mirada en el cuerpo seleccionado (o el más cercano) y la suelta al
+        // volver a pulsarla; WASD sigue moviendo la cámara
+        if !typing && window.is_key_pressed(KeyboardKey::KEY_O) {
+            camera_lock = match camera_lock {
+                Some(_) => None,
+                None => selected_body.or(nearest.map(|(i, _)| i)),
+            };
+        }
+        if let Some(i) = camera_lock {
+            if i >= visible_bodies.len() || camera_angle_to_body(&camera, &visible_bodies[i], time) > CAMERA_LOCK_RELEASE_ANGLE {
+                camera_lock = None;
+            } else if !is_warping {
+                lock_camera_to_body(&mut camera, &visible_bodies[i], time, CAMERA_LOCK_SPEED * frame_dt);
+            }
         }
 
-        // Evitar colisiones y ajustar cámara (ya existente)
-        let (adjusted_eye, adjusted_target) = avoid_collision(camera.eye, camera.target, &celestial_bodies, time);
+        // Evitar colisiones y ajustar cámara (ya existente). En vuelo rasante la esfera de choque
+        // impediría acercarse: solo se mantiene la altitud mínima sobre la superficie.
+        let (adjusted_eye, adjusted_target, hit_body) = if camera.mode == CameraMode::TerrainFollowMode {
+            let eye = terrain_follow(camera.eye, visible_bodies, time);
+            (eye, add_vec3(camera.target, sub_vec3(eye, camera.eye)), None)
+        } else {
+            avoid_collision(camera.eye, camera.target, visible_bodies, time)
+        };
+        // 💥 Solo cuenta como impacto el primer frame del choque, no mientras se sigue en contacto
+        let new_impact = hit_body.filter(|&i| colliding_with != Some(i));
+        colliding_with = hit_body;
+        // 💾 Saltos que no son vuelo (warp, empujones de las colisiones, posición segura): no
+        // cuentan como distancia recorrida en la partida
+        let mut camera_teleported = is_warping || !warp_queue.is_empty() || hit_body.is_some();
         camera.eye = adjusted_eye;
         camera.target = adjusted_target;
+        // ☄️ Asteroides cercanos: malla detallada (generada una vez) y choque con su forma real
+        for asteroid in asteroids.iter().filter(|asteroid| asteroid.in_detail_range(camera.eye, time)) {
+            asteroid_detail_meshes.entry(asteroid.seed).or_insert_with(|| generate_asteroid_detail_mesh(asteroid.seed, asteroid.scale));
+        }
+        let landed_eye = avoid_asteroid_collision(camera.eye, &asteroids, time);
+        camera_teleported |= landed_eye != camera.eye;
+        camera.target = add_vec3(camera.target, sub_vec3(landed_eye, camera.eye));
+        camera.eye = landed_eye;
+        update_near_surface_meshes(&mut near_surface_meshes, &celestial_bodies, &planet_vertex_array, camera.eye, time);
+        let altimeter = nearest_surface(camera.eye, visible_bodies, time)
+            .filter(|&(i, altitude)| camera.mode == CameraMode::TerrainFollowMode || altitude < terrain_follow_altitude(&visible_bodies[i]))
+            .map(|(i, altitude)| (visible_bodies[i].name.clone(), altitude));
+
+        mark_explored(&mut explored_grid, camera.eye);
 
         // Protección: si cámara contiene NaN/Inf o valores extremadamente grandes, restaurar a valor seguro
         let eye_ok = camera.eye.x.is_finite() && camera.eye.y.is_finite() && camera.eye.z.is_finite();
@@ -571,94 +3395,1181 @@ fn main() {
             // restaurar
             camera.eye = safe_camera_eye;
             camera.target = safe_camera_target;
+            camera_teleported = true;
         } else {
             // actualizar safe if everything is fine
             safe_camera_eye = camera.eye;
             safe_camera_target = camera.target;
         }
 
+        let unlocked = save_tracker.update(&mut game_save, visible_bodies, tracked_eye, camera.eye, camera_teleported, frame_dt, dt, real_time, time, time_scale);
+        achievement_banners.extend(unlocked.into_iter().map(|achievement| (achievement, real_time)));
+        tracked_eye = camera.eye;
+        if save_tracker.autosave_due() {
+            if let Err(e) = game_save.save(SAVE_PATH) {
+                eprintln!("Warning: could not save {}: {}", SAVE_PATH, e);
+            }
+        }
+
+        // La nave sigue a la cámara: calcular posición detrás y un poco abajo respecto a camera.eye (visible y acompañando)
+        let nave_forward = normalize_vec3(sub_vec3(camera.target, camera.eye));
+        let nave_position = {
+            let up = Vector3::new(0.0_f32, 1.0_f32, 0.0_f32);
+
+            // colocar la nave ligeramente detrás y abajo del eye para que acompañe la cámara y sea visible
+            let offset_back = mul_vec3_scalar(nave_forward, -nave_offset_back);
+            let offset_down = mul_vec3_scalar(up, -nave_offset_down);
+            let offset_model = mul_vec3_scalar(nave_forward, -nave_model_offset_forward);
+            add_vec3(camera.eye, add_vec3(add_vec3(offset_back, offset_down), offset_model))
+        };
+
+        // 🔦 Foco de la nave: sale de la nariz y apunta hacia donde mira la cámara
+        let nave_nose = add_vec3(nave_position, mul_vec3_scalar(nave_forward, nave_nose_offset));
+        // ✨ Escape del motor: sale por la cola de la nave en sentido contrario al avance
+        if !typing && !is_warping && (following_trajectory || window.is_key_down(KeyboardKey::KEY_W)) {
+            let nave_tail = add_vec3(nave_position, mul_vec3_scalar(nave_forward, -nave_nose_offset));
+            particles.emit_exhaust(nave_tail, mul_vec3_scalar(nave_forward, -1.0_f32), frame_dt);
+        }
+        particles.update(frame_dt);
+        ring_dust.update(dt);
+
+        // ☀️ Fulguraciones: empiezan al azar (TRIGGER_RATE por segundo simulado) en una longitud
+        // cualquiera y duran unos minutos simulados; no se solapan
+        if solar_flare.is_none() && flare_rng.f32() < SolarFlare::TRIGGER_RATE * dt.max(0.0_f32) {
+            solar_flare = Some(SolarFlare::new(flare_rng.f32() * 2.0_f32 * PI, time));
+        }
+        if let Some(flare) = solar_flare.as_mut() {
+            flare.update(time, dt, &mut solar_wind);
+        }
+        if solar_flare.as_ref().is_some_and(|flare| flare.is_finished(time)) {
+            solar_flare = None;
+        }
+        solar_wind.update(dt);
+        // ☄️ Chorros del cometa: solo salen cerca del perihelio, cuando la superficie sublima
+        let comet_pos = comet_position(time);
+        let sun_dir = normalize_vec3(mul_vec3_scalar(comet_pos, -1.0_f32));
+        comet_jets.emit(comet_pos, COMET_NUCLEUS_RADIUS, sun_dir, comet_surface_temperature(length_vec3(comet_pos)), time, dt);
+        comet_jets.update(dt);
+        kuiper_belt.update(dt);
+        kuiper_belt.resonate_with_neptune(NEPTUNE_ORBIT_RADIUS, dt);
+
+        if !typing && window.is_key_pressed(KeyboardKey::KEY_N) && nova.is_none() {
+            nova = Some(NovaEffect::new());
+        }
+        if let Some(effect) = nova.as_mut() {
+            effect.update(frame_dt);
+        }
+        if nova.as_ref().is_some_and(NovaEffect::is_finished) {
+            nova = None;
+        }
+
+        let lights = vec![
+            light,
+            Light::spotlight(nave_nose, nave_forward, nave_spot_inner_angle, nave_spot_outer_angle, Vector3::one(), nave_spot_intensity, LightAttenuation::NONE),
+        ];
+
         framebuffer.clear();
 
-        // 🌟 Renderizar skybox PRIMERO (más atrás)
-        let view_matrix = camera.get_view_matrix();
-        let projection_matrix = create_projection_matrix(PI / 3.0, window_width as f32 / window_height as f32, 0.1_f32, 1000.0_f32);
-        let viewport_matrix = create_viewport_matrix(0.0_f32, 0.0_f32, window_width as f32, window_height as f32);
-        render_skybox(&mut framebuffer, &view_matrix, &projection_matrix, &viewport_matrix, time);
-
-        // Renderizar planetas
-        // Renderizar planetas (se mantiene), pero añadir culling por distancia (evita renderar cuerpos demasiado próximos con triangulación muy densa)
-        let max_render_distance = 5000.0_f32; // puedes ajustar
-        for mut body in celestial_bodies.clone() {
-            if body.name != "Sun" {
-                body.translation.x = (time * body.orbit_speed).cos() * body.orbit_radius;
-                body.translation.z = (time * body.orbit_speed).sin() * body.orbit_radius;
-            }
-            body.rotation.y += dt * body.rotation_speed;
-
-            // distancia cámara <-> body
-            let dx = camera.eye.x - body.translation.x;
-            let dy = camera.eye.y - body.translation.y;
-            let dz = camera.eye.z - body.translation.z;
-            let dist_sq = dx*dx + dy*dy + dz*dz;
-            if dist_sq > max_render_distance * max_render_distance {
-                // omitimos objetos muy lejanos (mejora rendimiento)
-                continue;
+        for planet_type in shader_overrides.values() {
+            script_shaders.refresh(&planet_type[SCRIPT_SHADER_PREFIX.len()..]);
+        }
+
+        let nave_model_matrix = {
+            let yaw = nave_forward.z.atan2(nave_forward.x);
+            let fy = clamp_f32(nave_forward.y, -1.0_f32, 1.0_f32);
+            let pitch = fy.asin();
+            create_model_matrix(nave_position, default_nave_scale, Vector3::new(pitch, yaw, 0.0_f32))
+        };
+
+        // 🛸 Animar las piezas de la nave y calcular sus matrices de mundo
+        if !typing && window.is_key_pressed(KeyboardKey::KEY_L) {
+            gear_deployed = !gear_deployed;
+        }
+        let gear_step = frame_dt / NAVE_GEAR_DEPLOY_TIME;
+        gear_deploy = if gear_deployed { (gear_deploy + gear_step).min(1.0_f32) } else { (gear_deploy - gear_step).max(0.0_f32) };
+        let key_axis = |positive: KeyboardKey, negative: KeyboardKey| {
+            if typing || is_warping {
+                0.0_f32
+            } else {
+                (window.is_key_down(positive) as i32 - window.is_key_down(negative) as i32) as f32
+            }
+        };
+        let gimbal_target = (
+            -key_axis(KeyboardKey::KEY_D, KeyboardKey::KEY_A) * NAVE_GIMBAL_MAX,
+            key_axis(KeyboardKey::KEY_E, KeyboardKey::KEY_Q) * NAVE_GIMBAL_MAX,
+        );
+        let gimbal_blend = clamp_f32(frame_dt * 6.0_f32, 0.0_f32, 1.0_f32);
+        engine_gimbal.0 += (gimbal_target.0 - engine_gimbal.0) * gimbal_blend;
+        engine_gimbal.1 += (gimbal_target.1 - engine_gimbal.1) * gimbal_blend;
+
+        nave_model.local_transform = nave_model_matrix;
+        // Recogido, cada pata gira 90° alrededor de Z y queda plegada contra el casco
+        let gear_angle = ease_in_out(1.0_f32 - gear_deploy) * PI * 0.5_f32;
+        for (name, pivot) in NAVE_GEAR_PIVOTS {
+            if let Some(node) = nave_model.find_mut(name) {
+                node.local_transform = create_model_matrix(pivot, 1.0_f32, Vector3::new(0.0_f32, 0.0_f32, gear_angle));
+            }
+        }
+        for (name, pivot) in NAVE_ENGINE_PIVOTS {
+            if let Some(node) = nave_model.find_mut(name) {
+                node.local_transform = create_model_matrix(pivot, 1.0_f32, Vector3::new(0.0_f32, engine_gimbal.0, engine_gimbal.1));
+            }
+        }
+        let nave_world_matrices = build_model_hierarchy(&nave_model);
+        let mut nave_parts: Vec<(&[Vertex], Matrix, &str)> = nave_model
+            .flatten()
+            .into_iter()
+            .zip(nave_world_matrices)
+            .filter_map(|(node, (name, world))| {
+                let shader = if name.starts_with(NAV_LIGHT_PREFIX) { "LuzNavegacion" } else { "Nave" };
+                node.mesh.as_deref().map(|mesh| (mesh, world, shader))
+            })
+            .collect();
+        // La estación y los puntales usan el mismo sombreado metálico que la nave
+        if let Some(earth) = celestial_bodies.iter().find(|body| body.name == "Earth") {
+            let station_center = station_position(body_position(earth, time), time);
+            docking.update(length_vec3(sub_vec3(nave_position, station_center)) < DOCKING_RANGE, frame_dt);
+            nave_parts.push((&station_mesh, station_model_matrix(station_center, time), "Nave"));
+            for (root, elbow, end) in docking.strut_joints(&docking_struts, &nave_model_matrix, station_center) {
+                nave_parts.push((&strut_segment, strut_segment_matrix(root, elbow), "Nave"));
+                nave_parts.push((&strut_segment, strut_segment_matrix(elbow, end), "Nave"));
+            }
+        }
+        let sandbox_positions: Vec<Vector3> = sandbox.bodies.iter().map(|b| b.position).collect();
+        let sandbox_orbits: Vec<Vec<Vector3>> = if sandbox_mode {
+            sandbox.bodies.iter().map(|b| sandbox.predict_orbit(b.position, b.velocity)).collect()
+        } else {
+            Vec::new()
+        };
+        let scene = SceneFrame {
+            celestial_bodies: visible_bodies,
+            planet_vertex_array: &planet_vertex_array,
+            planet_atlas: planet_atlas.as_ref(),
+            saturn_ring_vertex_array: &saturn_ring_vertex_array,
+            uranus_ring_vertex_array: &uranus_ring_vertex_array,
+            nave_parts: &nave_parts,
+            nave_ambient_color: ambient_color_at(length_vec3(sub_vec3(nave_position, light.position))),
+            particles: &particles,
+            ring_dust: &ring_dust,
+            solar_flare: solar_flare.as_ref(),
+            solar_wind: &solar_wind,
+            comet_nucleus_vertex_array: &comet_nucleus_vertex_array,
+            comet_jets: &comet_jets,
+            kuiper_belt: &kuiper_belt,
+            warp_tunnel_vertex_array: &warp_tunnel_vertex_array,
+            warp_tunnel: (warp_tunnel_alpha > 0.0_f32).then(|| (warp_tunnel_model_matrix(camera.eye, camera.target, warp_tunnel_scroll), warp_tunnel_alpha)),
+            lights: &lights,
+            trajectory: &ship_trajectory,
+            explored_grid: &explored_grid,
+            earth_transmittance: &earth_transmittance,
+            nova_phase: nova.as_ref().map(|effect| effect.phase),
+            nova_shell_vertex_array: &nova_shell_vertex_array,
+            dust_cloud: &dust_cloud,
+            asteroids: &asteroids,
+            asteroid_meshes: &asteroid_meshes,
+            asteroid_detail_meshes: &asteroid_detail_meshes,
+            near_surface_meshes: &near_surface_meshes,
+            atmosphere_shells: &atmosphere_shells,
+            sandbox_bodies: if sandbox_mode { &sandbox_positions } else { &[] },
+            sandbox_orbits: &sandbox_orbits,
+            script_shaders: &script_shaders,
+            shader_overrides: &shader_overrides,
+            clip_planes,
+            time,
+            dt,
+            show_trails,
+            render_mode,
+            shadow_quality,
+        };
+
+        // 🪟 Vista del piloto (pantalla completa o mitad izquierda) y, si está activa, la cenital a la derecha
+        if window.is_key_pressed(KeyboardKey::KEY_P) && !typing {
+            split_screen = !split_screen;
+        }
+        let pilot_viewport = if split_screen {
+            (0, 0, window_width / 2, window_height)
+        } else {
+            (0, 0, window_width, window_height)
+        };
+        let pilot = render_scene_to_viewport(&mut framebuffer, pilot_viewport, &camera, ViewProjection::Perspective { fov }, &scene);
+        // Antes de copiar la vista cenital: su profundidad es ortográfica y el SSAO supone perspectiva
+        if ssao_enabled {
+            let visibility = ssao_pass(&framebuffer, SSAO_KERNEL_SIZE, SSAO_RADIUS, SSAO_BIAS, pilot.pixels_per_unit, clip_planes);
+            apply_ssao(&mut framebuffer, &visibility);
+        }
+        // 🕳️ Lente gravitatoria de los cuerpos con masa de lente, antes de los efectos de cámara
+        for body in celestial_bodies.iter().filter(|body| body.lensing_mass > 0.0) {
+            let position = body_position(body, time);
+            if let Some((sx, sy, _)) = project_to_screen(position, &pilot.view_matrix, &pilot.projection_matrix, &pilot.viewport_matrix) {
+                gravity_lensing_pass(&mut framebuffer, (sx as f32, sy as f32), position, body.lensing_mass, &camera, pilot.pixels_per_unit, clip_planes);
+            }
+        }
+        postprocess.apply(&mut framebuffer);
+        if split_screen {
+            // La vista cenital se dibuja a menor resolución en su propio buffer y se escala al copiarla
+            let top_down_viewport = top_down_viewport_rect(window_width, window_height);
+            minimap_framebuffer.clear();
+            let minimap_rect = (0, 0, minimap_framebuffer.width, minimap_framebuffer.height);
+            let top_down = render_scene_to_viewport(&mut minimap_framebuffer, minimap_rect, &top_down_camera, ViewProjection::Orthographic { half_height: top_down_half_height }, &scene);
+            // 🔭 Ángulo de fase Tierra-Marte como un arco alrededor del Sol
+            if let (Some(earth), Some(mars)) = (celestial_bodies.iter().find(|body| body.name == "Earth"), celestial_bodies.iter().find(|body| body.name == "Mars")) {
+                let arc = phase_arc_points(earth, mars, time, earth.orbit_radius * 0.6_f32);
+                draw_path_3d(&mut minimap_framebuffer, &arc, Color::new(230, 120, 90, 255), &top_down.view_matrix, &top_down.projection_matrix, &top_down.viewport_matrix);
             }
+            framebuffer.blit(&minimap_framebuffer, minimap_rect, top_down_viewport);
+            framebuffer.profiler.accumulate(&std::mem::take(&mut minimap_framebuffer.profiler));
+            let separator_x = window_width / 2;
+            framebuffer.draw_line_with_depth(separator_x, 0, separator_x, window_height - 1, Color::new(90, 90, 110, 255), OVERLAY_DEPTH);
+        }
+        let view_matrix = pilot.view_matrix;
+        let projection_matrix = pilot.projection_matrix;
+        let viewport_matrix = pilot.viewport_matrix;
+        let pixels_per_unit = pilot.pixels_per_unit;
+
+        // 🧭 Selección con clic izquierdo y gizmo de rotación sobre el cuerpo seleccionado
+        let (mouse_x, mouse_y) = (window.get_mouse_x(), window.get_mouse_y());
+        let mouse_in_pilot = mouse_x >= pilot_viewport.0 && mouse_x < pilot_viewport.0 + pilot_viewport.2 && mouse_y >= pilot_viewport.1 && mouse_y < pilot_viewport.1 + pilot_viewport.3;
+        if !sandbox_mode && mouse_in_pilot && window.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+            selected_body = pick_body_at_screen(mouse_x, mouse_y, &camera, &celestial_bodies, time, pixels_per_unit, &view_matrix, &projection_matrix, &viewport_matrix);
+            last_pick_ray = Some(PickRay { origin: camera.eye, direction: camera.ray_through_pixel(mouse_x, mouse_y, pilot_viewport, fov) });
+        }
 
-            let model_matrix = create_model_matrix(body.translation, body.scale, body.rotation);
-            let uniforms = Uniforms {
-                model_matrix,
-                view_matrix: camera.get_view_matrix(),
-                projection_matrix,
-                viewport_matrix,
-                time,
-                dt,
+        // 🪐 Sandbox: colocar cuerpos, elegir su velocidad y seleccionarlos
+        if sandbox_mode && window.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT) {
+            sandbox_placing = None;
+        }
+        let mouse_on_ecliptic = if mouse_in_pilot { screen_to_ecliptic(mouse_x, mouse_y, pilot_viewport, &camera, fov) } else { None };
+        if sandbox_mode && !typing && mouse_in_pilot && window.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+            if let Some(start) = sandbox_placing {
+                if let Some(end) = mouse_on_ecliptic {
+                    sandbox.add_body(start, mul_vec3_scalar(sub_vec3(end, start), SANDBOX_VELOCITY_PER_UNIT));
+                    sandbox_selected = Some(sandbox.bodies.len() - 1);
+                    sandbox_placing = None;
+                }
+            } else {
+                // Clic sobre un cuerpo existente lo selecciona; en vacío empieza uno nuevo
+                let clicked = sandbox.bodies.iter().position(|b| {
+                    project_to_screen(b.position, &view_matrix, &projection_matrix, &viewport_matrix).is_some_and(|(sx, sy, _)| {
+                        let radius = (SANDBOX_BODY_SCALE * PLANET_MESH_RADIUS / length_vec3(sub_vec3(b.position, camera.eye)).max(0.001_f32) * pixels_per_unit).max(8.0_f32);
+                        let (dx, dy) = ((mouse_x - sx) as f32, (mouse_y - sy) as f32);
+                        dx * dx + dy * dy <= radius * radius
+                    })
+                });
+                sandbox_selected = clicked;
+                if clicked.is_none() {
+                    sandbox_placing = mouse_on_ecliptic;
+                }
+            }
+        }
+        // 🚀 H abre/cierra la ruta de transferencia hacia el cuerpo seleccionado (T ya son las estelas)
+        if !typing && window.is_key_pressed(KeyboardKey::KEY_H) {
+            let origin = celestial_bodies.iter().position(|body| body.name == "Earth");
+            transfer_route = match (transfer_route, origin, selected_body) {
+                (Some(_), _, _) => None,
+                (None, Some(from), Some(to)) if to != from && !is_sun(&celestial_bodies[to]) => Some((from, to)),
+                _ => None,
+            };
+        }
+        if let Some((from, to)) = transfer_route.filter(|_| !sandbox_mode) {
+            let (from_body, to_body) = (&celestial_bodies[from], &celestial_bodies[to]);
+            let window_in = next_transfer_window(from_body, to_body, time, synodic_period(from_body, to_body));
+            // Cerca de la ventana la órbita parpadea (en tiempo real, para que se vea a cualquier escala)
+            let near_window = window_in < TRANSFER_WINDOW_FLASH_TIME;
+            let color = if near_window && (real_time * 8.0_f32).sin() < 0.0 {
+                Color::new(255, 255, 255, 255)
+            } else if near_window {
+                Color::new(255, 150, 40, 255)
+            } else {
+                Color::new(200, 110, 40, 255)
             };
-            render(&mut framebuffer, &uniforms, &planet_vertex_array, &light, &body.name);
+            let transfer = transfer_orbit_points(from_body, to_body, time + window_in);
+            framebuffer.set_scissor(Some(pilot_viewport));
+            draw_path_3d(&mut framebuffer, &transfer, color, &view_matrix, &projection_matrix, &viewport_matrix);
+            framebuffer.set_scissor(None);
+            let label = format!("VENTANA {} > {}  {:.1} S", from_body.name.to_uppercase(), to_body.name.to_uppercase(), window_in);
+            draw_text(&mut framebuffer, &label, 20, 70, 2, color);
         }
 
-        // Renderizar órbitas
-        for body in &celestial_bodies {
-            if body.name != "Sun" {
-                let orbit_color = Color::new(255, 255, 255, 50);
-                draw_orbit_3d(&mut framebuffer, body.orbit_radius, orbit_color, &view_matrix, &projection_matrix, &viewport_matrix);
+        if sandbox_mode {
+            framebuffer.set_scissor(Some(pilot_viewport));
+            if let Some(b) = sandbox_selected.and_then(|i| sandbox.bodies.get(i)) {
+                if let Some((sx, sy, _)) = project_to_screen(b.position, &view_matrix, &projection_matrix, &viewport_matrix) {
+                    framebuffer.draw_circle_outline(sx, sy, 14.0_f32, Vector3::new(0.35_f32, 0.8_f32, 0.9_f32), 1.0_f32);
+                }
+            }
+            if let (Some(start), Some(end)) = (sandbox_placing, mouse_on_ecliptic) {
+                let velocity = mul_vec3_scalar(sub_vec3(end, start), SANDBOX_VELOCITY_PER_UNIT);
+                let preview = sandbox.predict_orbit(start, velocity);
+                draw_path_3d(&mut framebuffer, &preview, Color::new(250, 210, 80, 255), &view_matrix, &projection_matrix, &viewport_matrix);
+                if let Some((sx, sy, _)) = project_to_screen(start, &view_matrix, &projection_matrix, &viewport_matrix) {
+                    draw_velocity_arrow(&mut framebuffer, (sx, sy), (mouse_x, mouse_y), length_vec3(velocity), circular_speed(length_vec3(start)));
+                }
+            }
+            framebuffer.set_scissor(None);
+            draw_text(&mut framebuffer, &format!("SANDBOX  CUERPOS {}", sandbox.bodies.len()), 20, window_height - 40, 2, Color::new(90, 200, 230, 255));
+        }
+        if !typing && window.is_key_pressed(KeyboardKey::KEY_B) {
+            if let Some(i) = selected_body {
+                if !magnetic_fields.remove(&i) {
+                    magnetic_fields.insert(i);
+                }
+            }
+        }
+        if !sandbox_mode {
+            framebuffer.set_scissor(Some(pilot_viewport));
+            for &i in &magnetic_fields {
+                let body = &celestial_bodies[i];
+                draw_magnetic_field_lines(&mut framebuffer, body_position(body, time), body_radius(body), dipole_axis(body, time), &view_matrix, &projection_matrix, &viewport_matrix);
+            }
+            framebuffer.set_scissor(None);
+        }
+        if !sandbox_mode && (window.is_key_down(KeyboardKey::KEY_LEFT_ALT) || window.is_key_down(KeyboardKey::KEY_RIGHT_ALT)) {
+            framebuffer.set_scissor(Some(pilot_viewport));
+            draw_pick_debug(&mut framebuffer, last_pick_ray, &camera, &celestial_bodies, time, pixels_per_unit, &view_matrix, &projection_matrix, &viewport_matrix);
+            framebuffer.set_scissor(None);
+        }
+        // 🏷️ Sprites del HUD de este frame: se dibujan juntos, ordenados por Z
+        let mut sprites = SpriteBatch::new();
+        if let Some(i) = selected_body {
+            let body = &celestial_bodies[i];
+            let position = body_position(body, time);
+            if let Some((sx, sy, _)) = project_to_screen(position, &view_matrix, &projection_matrix, &viewport_matrix) {
+                let distance = length_vec3(sub_vec3(position, camera.eye));
+                let projected_radius = body_radius(body) / distance.max(0.001_f32) * pixels_per_unit;
+                let gizmo_radius = (projected_radius * 1.4_f32).max(20.0_f32) as i32;
+                framebuffer.set_scissor(Some(pilot_viewport));
+                draw_rotation_gizmo(&mut framebuffer, (sx, sy), gizmo_radius, &view_matrix, &projection_matrix);
+                // Nombre debajo del gizmo: el texto SDF crece con el tamaño aparente del cuerpo
+                let label_size = clamp_f32(gizmo_radius as f32 * 0.4_f32, 12.0_f32, 40.0_f32);
+                let label_width = body.name.chars().count() as f32 * label_size * 0.75_f32;
+                framebuffer.draw_sdf_text(sx - (label_width * 0.5_f32) as i32, sy + gizmo_radius + 6, &body.name, label_size, Vector3::new(0.85_f32, 0.9_f32, 1.0_f32), &sdf_font);
+                // Icono del tipo de cuerpo a la izquierda del nombre, del mismo alto
+                let icon_x = sx - (label_width * 0.5_f32 + label_size * 1.25_f32) as i32;
+                sprites.push(SPRITE_Z_ICON, icon_x, sy + gizmo_radius + 6, &planet_icons[i], label_size / 16.0_f32, Vector3::new(1.0_f32, 1.0_f32, 1.0_f32));
+                if body.inclination != 0.0_f32 {
+                    let node_label = format!("NODO {:.4}", ascending_node_at(body, time).to_degrees());
+                    draw_text(&mut framebuffer, &node_label, sx - text_width(&node_label, 1) / 2, sy + gizmo_radius + 10 + label_size as i32, 1, Color::new(170, 190, 220, 255));
+                }
+                if let Some(hill_radius) = hill_radius_of(body, &celestial_bodies) {
+                    let hill_label = format!("HILL {:.2}", hill_radius);
+                    draw_text(&mut framebuffer, &hill_label, sx - text_width(&hill_label, 1) / 2, sy + gizmo_radius + 22 + label_size as i32, 1, Color::new(120, 220, 255, 255));
+                }
+                framebuffer.set_scissor(None);
             }
         }
 
-        // La nave sigue a la cámara: calcular posición detrás y un poco abajo respecto a camera.eye (visible y acompañando)
-        {
-            let mut forward = sub_vec3(camera.target, camera.eye);
-            forward = normalize_vec3(forward);
-            let up = Vector3::new(0.0_f32, 1.0_f32, 0.0_f32);
+        // ⚖️ Puntos de Lagrange del cuerpo seleccionado (la Tierra si no hay ninguno) con su primario
+        let lagrange_body = selected_body.or_else(|| celestial_bodies.iter().position(|body| body.name == "Earth"));
+        if let Some(body) = lagrange_body.map(|i| &celestial_bodies[i]).filter(|_| !sandbox_mode) {
+            if let Some(primary) = primary_body(body, &celestial_bodies).filter(|primary| primary.mass > 0.0_f32 && body.mass > 0.0_f32) {
+                let points = compute_lagrange_points(body_position(primary, time), primary.mass, body_position(body, time), body.mass);
+                let marker_tint = Vector3::new(0.3_f32, 0.7_f32, 0.75_f32);
+                framebuffer.set_scissor(Some(pilot_viewport));
+                for (n, point) in points.iter().enumerate() {
+                    if let Some((sx, sy, _)) = project_to_screen(*point, &view_matrix, &projection_matrix, &viewport_matrix) {
+                        sprites.push(SPRITE_Z_INDICATOR, sx - 8, sy - 8, &hud_sprites.lagrange, 1.0_f32, marker_tint);
+                        draw_text(&mut framebuffer, &format!("L{}", n + 1), sx + 10, sy - 12, 1, Color::new(80, 180, 190, 255));
+                    }
+                }
+                framebuffer.set_scissor(None);
+            }
+        }
 
-            // colocar la nave ligeramente detrás y abajo del eye para que acompañe la cámara y sea visible
-            let offset_back = mul_vec3_scalar(forward, -nave_offset_back);
-            let offset_down = mul_vec3_scalar(up, -nave_offset_down);
-            let offset_model = mul_vec3_scalar(forward, -nave_model_offset_forward);
-            let nave_position = add_vec3(camera.eye, add_vec3(add_vec3(offset_back, offset_down), offset_model));
+        // 💥 Nuevo impacto: onda desde el punto de contacto y destello blanco de 2 frames
+        if let Some(i) = new_impact {
+            let body = &celestial_bodies[i];
+            let body_pos = body_position(body, time);
+            let contact = add_vec3(body_pos, mul_vec3_scalar(normalize_vec3(sub_vec3(camera.eye, body_pos)), body_radius(body)));
+            let screen_pos = project_to_screen(contact, &view_matrix, &projection_matrix, &viewport_matrix)
+                .map(|(x, y, _)| (x, y))
+                .unwrap_or((pilot_viewport.0 + pilot_viewport.2 / 2, pilot_viewport.1 + pilot_viewport.3 / 2));
+            impact_effects.push(ImpactEffect::new(screen_pos, pilot_viewport.3 as f32 * 0.6_f32, Vector3::new(1.0_f32, 0.85_f32, 0.6_f32)));
+            impact_flash_frames = 2;
+        }
+        for effect in impact_effects.iter_mut() {
+            effect.update(frame_dt);
+            framebuffer.draw_circle_outline(effect.screen_pos.0, effect.screen_pos.1, effect.radius, effect.color, effect.opacity());
+        }
+        impact_effects.retain(|effect| !effect.is_finished());
+        if impact_flash_frames > 0 {
+            framebuffer.fill_overlay(Vector3::new(1.0_f32, 1.0_f32, 1.0_f32), 0.85_f32);
+            impact_flash_frames -= 1;
+        }
 
-            let yaw = forward.z.atan2(forward.x);
-            let fy = clamp_f32(forward.y, -1.0_f32, 1.0_f32);
-            let pitch = fy.asin();
+        draw_fov_indicator(&mut framebuffer, fov);
+        if let Some((name, altitude)) = &altimeter {
+            draw_radar_altimeter(&mut framebuffer, name, *altitude, camera.mode == CameraMode::TerrainFollowMode);
+            // Aviso de proximidad encima del altímetro cuando se pone en rojo
+            if *altitude < TERRAIN_MIN_ALTITUDE * 2.0_f32 {
+                sprites.push(SPRITE_Z_WARNING, window_width / 2 - 16, window_height - 80, &hud_sprites.warning, 2.0_f32, Vector3::new(1.0_f32, 0.35_f32, 0.25_f32));
+            }
+        }
 
-            let nave_model_matrix = create_model_matrix(
-                nave_position,
-                default_nave_scale,
-                Vector3::new(pitch, yaw, 0.0_f32),
-            );
+        // 🧭 Brújula, carga del warp e impulso, abajo a la izquierda
+        let compass_origin = (20, window_height - 110);
+        sprites.push(SPRITE_Z_INDICATOR, compass_origin.0, compass_origin.1, &hud_sprites.compass, 1.5_f32, Vector3::new(1.0_f32, 1.0_f32, 1.0_f32));
+        let lit_cells = (warp_charge * WARP_CHARGE_CELLS as f32) as u32;
+        for cell in 0..WARP_CHARGE_CELLS {
+            let tint = if cell < lit_cells { Vector3::new(0.47_f32, 0.78_f32, 1.0_f32) } else { Vector3::new(0.2_f32, 0.25_f32, 0.3_f32) };
+            sprites.push(SPRITE_Z_INDICATOR, 80 + cell as i32 * 8, window_height - 104, &hud_sprites.warp_cell, 1.0_f32, tint);
+        }
+        if boosting {
+            sprites.push(SPRITE_Z_ICON, 80, window_height - 84, &hud_sprites.boost, 1.0_f32, Vector3::new(1.0_f32, 0.6_f32, 0.2_f32));
+        }
+        sprites.flush(&mut framebuffer);
+        draw_compass_needle(&mut framebuffer, (compass_origin.0 + 24, compass_origin.1 + 24), 14.0_f32, sub_vec3(camera.target, camera.eye));
+        if time_scale != 1.0_f32 {
+            draw_text(&mut framebuffer, &format!("TIEMPO X{}", time_scale), window_width - 150, window_height - 75, 2, Color::new(150, 150, 170, 255));
+        }
+        // Modo de render en uso (F2); si se pidió Deferred con MSAA se avisa de que va en Forward
+        let active_render_mode = render_mode.effective(framebuffer.msaa_level);
+        let mode_label = if active_render_mode == render_mode { format!("MODO {:?}", render_mode) } else { format!("MODO {:?} ({:?} CON MSAA)", active_render_mode, render_mode) }.to_uppercase();
+        draw_text(&mut framebuffer, &mode_label, window_width - text_width(&mode_label, 1) - 20, window_height - 90, 1, Color::new(150, 150, 170, 255));
+
+        if let Some(body) = camera_lock.map(|i| &celestial_bodies[i]) {
+            let lock_color = Color::new(255, 210, 90, 255);
+            let label = format!("MIRADA BLOQUEADA EN {}", body.name.to_uppercase());
+            draw_text(&mut framebuffer, &label, (window_width - text_width(&label, 2)) / 2, 20, 2, lock_color);
+            if let Some((sx, sy, _)) = project_to_screen(body_position(body, time), &view_matrix, &projection_matrix, &viewport_matrix) {
+                draw_lock_reticle(&mut framebuffer, sx, sy, lock_color);
+            }
+        }
+
+        achievement_banners.retain(|&(_, unlocked_at)| real_time - unlocked_at < ACHIEVEMENT_BANNER_TIME);
+        for (slot, &(achievement, unlocked_at)) in achievement_banners.iter().enumerate() {
+            draw_achievement_banner(&mut framebuffer, achievement, real_time - unlocked_at, 60 + slot as i32 * 36);
+        }
+
+        if warp_queue.len() > 1 {
+            let remaining = estimate_arrival_time(&warp_queue, 1.0_f32 / warp_duration) - (real_time - warp_start_time);
+            let label = format!("WARPS EN COLA {}  LLEGADA {:.1}S", warp_queue.len() - 1, remaining.max(0.0_f32));
+            draw_text(&mut framebuffer, &label, window_width - text_width(&label, 2) - 20, 20, 2, Color::new(120, 200, 255, 255));
+        }
+
+        // 🔭 Próxima oposición de Marte y elongaciones de los planetas interiores
+        if let Some(earth) = celestial_bodies.iter().find(|body| body.name == "Earth") {
+            let hud_color = Color::new(200, 170, 150, 255);
+            if let Some(mars) = celestial_bodies.iter().find(|body| body.name == "Mars") {
+                let label = format!("OPOSICION MARTE EN {:.1} S", next_opposition_from_earth(earth, mars, time) - time);
+                draw_text(&mut framebuffer, &label, window_width - text_width(&label, 1) - 20, 45, 1, hud_color);
+            }
+            let elongations: Vec<String> = celestial_bodies
+                .iter()
+                .filter(|body| body.name == "Mercury" || body.name == "Venus")
+                .map(|body| format!("{} {:.0}", body.name.to_uppercase(), angular_separation_from_sun(body, earth, time).to_degrees()))
+                .collect();
+            if !elongations.is_empty() {
+                let label = format!("ELONGACION {} GRADOS", elongations.join(" "));
+                draw_text(&mut framebuffer, &label, window_width - text_width(&label, 1) - 20, 57, 1, hud_color);
+            }
+        }
+
+        // 🌑 Avisos de eclipse: la Tierra en la umbra de la Luna (solar) o la Luna en la de la Tierra (lunar)
+        if let Some([sun, earth, moon]) = sun_earth_moon(&celestial_bodies, time) {
+            let alerts = [(moon, earth, "ECLIPSE SOLAR EN CURSO"), (earth, moon, "ECLIPSE LUNAR EN CURSO")];
+            let mut y = 69;
+            for (caster, target, label) in alerts {
+                if let Some((_, _, true)) = shadow_on(sun, caster, target) {
+                    draw_text(&mut framebuffer, label, window_width - text_width(label, 1) - 20, y, 1, Color::new(255, 120, 80, 255));
+                    y += 12;
+                }
+            }
+        }
+
+        if let Some(query) = &search_input {
+            let matched = find_body_by_prefix(&celestial_bodies, query).map(|i| &celestial_bodies[i]);
+            draw_search_box(&mut framebuffer, query, matched);
+        }
 
-            let uniforms = Uniforms {
-                model_matrix: nave_model_matrix,
-                view_matrix: camera.get_view_matrix(),
-                projection_matrix,
-                viewport_matrix,
-                time,
-                dt,
+        if radial_menu_time > 0.0_f32 {
+            // Aparece desde negro: el fondo se oscurece y los colores suben con el fundido
+            let fade = (radial_menu_time / RADIAL_MENU_FADE_TIME).min(1.0_f32);
+            framebuffer.fill_overlay(Vector3::new(0.0_f32, 0.0_f32, 0.0_f32), 0.5_f32 * fade);
+            let scale = |c: u8| (c as f32 * fade) as u8;
+            let options: Vec<(&str, Color)> = warp_presets
+                .iter()
+                .map(|(label, color, _)| (*label, *color))
+                .chain(celestial_bodies.iter().map(|body| (body.name.as_str(), body.color)))
+                .map(|(label, color)| (label, Color::new(scale(color.r), scale(color.g), scale(color.b), scale(255))))
+                .collect();
+            let radius = RADIAL_MENU_MAX_RADIUS.min(window_height / 2 - 60);
+            draw_radial_menu(&mut framebuffer, &options, radial_selection, radial_menu_center, radius);
+        }
+
+        if !typing && window.is_key_pressed(KeyboardKey::KEY_F3) {
+            show_stats = !show_stats;
+        }
+        if !typing && window.is_key_pressed(KeyboardKey::KEY_F2) {
+            render_mode = match render_mode {
+                RenderMode::Forward => RenderMode::Deferred,
+                RenderMode::Deferred => RenderMode::Forward,
             };
-            render(&mut framebuffer, &uniforms, &nave_vertex_array, &light, "Nave");
+        }
+        if !typing && window.is_key_pressed(KeyboardKey::KEY_F4) {
+            shadow_quality = if shadow_quality == SHADOW_QUALITY_FAST { SHADOW_QUALITY_HIGH } else { SHADOW_QUALITY_FAST };
+        }
+        if !typing && window.is_key_pressed(KeyboardKey::KEY_F6) {
+            ssao_enabled = !ssao_enabled;
+        }
+        if !typing && window.is_key_pressed(KeyboardKey::KEY_F7) {
+            match postprocess.save(POSTPROCESS_CONFIG_PATH) {
+                Ok(()) => eprintln!("Post-process config saved to {}", POSTPROCESS_CONFIG_PATH),
+                Err(e) => eprintln!("Warning: could not save {}: {}", POSTPROCESS_CONFIG_PATH, e),
+            }
+        }
+        if show_stats {
+            draw_stats_overlay(&mut framebuffer, &last_profile, last_frame_us);
+            draw_text(&mut framebuffer, &format!("SOMBRAS {} RAYOS", shadow_quality), 20, 350, 1, Color::new(200, 200, 220, 255));
+            draw_text(&mut framebuffer, if ssao_enabled { "SSAO SI" } else { "SSAO NO" }, 20, 362, 1, Color::new(200, 200, 220, 255));
+            let post_label = format!("POST {}", postprocess.enabled_names().join(" ")).to_uppercase();
+            draw_text(&mut framebuffer, &post_label, 20, 374, 1, Color::new(200, 200, 220, 255));
+        }
+
+        // 📸 F12: captura del frame tal como se va a presentar, con la hora en el nombre
+        if !typing && window.is_key_pressed(KeyboardKey::KEY_F12) {
+            let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+            let path = format!("{}screenshot_{}.png", SCREENSHOT_PREFIX, stamp);
+            match framebuffer.save_screenshot(&path) {
+                Ok(()) => eprintln!("Screenshot saved to {}", path),
+                Err(e) => eprintln!("Warning: could not save screenshot: {}", e),
+            }
         }
 
         framebuffer.swap_buffers(&mut window, &raylib_thread);
+        last_frame_us = elapsed_us(frame_start);
+        last_profile = std::mem::take(&mut framebuffer.profiler);
+        if let Some(trace) = profile_trace.as_mut() {
+            trace.record_frame(frame_start, last_frame_us, &last_profile);
+        }
         thread::sleep(Duration::from_millis(16));
     }
-}
\ No newline at end of file
+
+    if let Err(e) = game_save.save(SAVE_PATH) {
+        eprintln!("Warning: could not save {}: {}", SAVE_PATH, e);
+    }
+    if let Some(trace) = &profile_trace {
+        match trace.save(PROFILE_TRACE_PATH) {
+            Ok(()) => eprintln!("Profile trace saved to {}", PROFILE_TRACE_PATH),
+            Err(e) => eprintln!("Warning: could not save profile trace: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use catalog::fallback_body_catalog;
+
+    #[test]
+    fn camera_lock_moves_the_target_towards_the_body() {
+        let bodies = fallback_body_catalog();
+        let earth = &bodies[1];
+        let time = 3.0_f32;
+        let mut camera = Camera::new(Vector3::new(0.0_f32, 40.0_f32, 140.0_f32), Vector3::zero(), Vector3::new(0.0_f32, 1.0_f32, 0.0_f32));
+        let eye = camera.eye;
+        let before = length_vec3(sub_vec3(camera.target, body_position(earth, time)));
+        for _ in 0..10 {
+            lock_camera_to_body(&mut camera, earth, time, CAMERA_LOCK_SPEED / 60.0_f32);
+        }
+        let after = length_vec3(sub_vec3(camera.target, body_position(earth, time)));
+        assert!(after < before);
+        assert_eq!(camera.eye, eye);
+    }
+
+    #[test]
+    fn radial_menu_selection_follows_the_mouse_angle() {
+        let center = (400, 300);
+        assert_eq!(radial_menu_selection(4, center, (400, 100)), Some(0));
+        assert_eq!(radial_menu_selection(4, center, (600, 300)), Some(1));
+        assert_eq!(radial_menu_selection(4, center, (400, 500)), Some(2));
+        assert_eq!(radial_menu_selection(4, center, (200, 300)), Some(3));
+        // Más cerca del sector de arriba a la derecha que del de arriba
+        assert_eq!(radial_menu_selection(8, center, (500, 190)), Some(1));
+    }
+
+    #[test]
+    fn radial_menu_center_selects_nothing() {
+        let center = (400, 300);
+        assert_eq!(radial_menu_selection(4, center, center), None);
+        assert_eq!(radial_menu_selection(4, center, (410, 290)), None);
+        assert_eq!(radial_menu_selection(0, center, (400, 100)), None);
+    }
+
+    #[test]
+    fn vertex_at_the_camera_does_not_panic() {
+        let grid = [[true; EXPLORATION_GRID_SIZE]; EXPLORATION_GRID_SIZE];
+        let mut uniforms = Uniforms::for_tests(&grid);
+        let eye = Vector3::new(0.0_f32, 0.0_f32, 10.0_f32);
+        uniforms.view_matrix = create_view_matrix(eye, Vector3::zero(), Vector3::new(0.0_f32, 1.0_f32, 0.0_f32));
+        uniforms.projection_matrix = create_projection_matrix(PI / 3.0_f32, 1.0_f32, 0.1_f32, 100.0_f32);
+        uniforms.viewport_matrix = create_viewport_matrix(0.0_f32, 0.0_f32, 64.0_f32, 64.0_f32);
+        let normal = Vector3::new(0.0_f32, 0.0_f32, 1.0_f32);
+        let vertices: Vec<Vertex> = [eye, Vector3::new(1.0_f32, 0.0_f32, 0.0_f32), Vector3::new(0.0_f32, 1.0_f32, 0.0_f32)]
+            .iter()
+            .map(|&position| Vertex::new(position, normal, Vector2::zero()))
+            .collect();
+
+        let transformed: Vec<Vertex> = vertices.iter().map(|vertex| vertex_shader(vertex, &uniforms)).collect();
+        assert!(is_degenerate_triangle(&transformed));
+
+        let mut framebuffer = Framebuffer::new(64, 64);
+        render(&mut framebuffer, &uniforms, &vertices, "Earth", None, RenderMode::Forward, &mut Vec::new());
+        assert_eq!(framebuffer.get_depth_at(32, 32), f32::INFINITY);
+    }
+
+    #[test]
+    fn huge_triangles_only_visit_the_viewport() {
+        let corner = |x: f32, y: f32| {
+            let mut vertex = Vertex::new(Vector3::zero(), Vector3::new(0.0_f32, 0.0_f32, 1.0_f32), Vector2::zero());
+            vertex.transformed_position = Vector3::new(x, y, 0.5_f32);
+            vertex
+        };
+        let (a, b, c) = (corner(-1e5_f32, -1e5_f32), corner(1e5_f32, -1e5_f32), corner(0.0_f32, 1e5_f32));
+        let fragments = triangle(&a, &b, &c, &Light::new(Vector3::zero()), &[], (16, 8));
+        assert_eq!(fragments.len(), 16 * 8);
+    }
+
+    #[test]
+    fn node_precession_uses_the_sun_wherever_it_is_listed() {
+        let mut bodies = catalog::fallback_body_catalog();
+        bodies[1].inclination = 0.1_f32;
+        let expected = j2_precession_rate(bodies[1].orbit_radius, 0.0_f32, 0.1_f32, SUN_J2, body_radius(&bodies[0]), bodies[1].orbit_speed);
+        bodies.reverse();
+        assert!(apply_sun_node_precession(&mut bodies));
+        assert_eq!(bodies[0].node_precession_rate, expected);
+        assert!(expected != 0.0_f32);
+
+        let mut without_sun = vec![bodies.remove(0)];
+        without_sun[0].node_precession_rate = 0.0_f32;
+        assert!(!apply_sun_node_precession(&mut without_sun));
+        assert_eq!(without_sun[0].node_precession_rate, 0.0_f32);
+    }
+
+    #[test]
+    fn deferred_and_forward_render_the_same_image() {
+        let grid = [[true; EXPLORATION_GRID_SIZE]; EXPLORATION_GRID_SIZE];
+        let sphere = generate_cube_sphere(6);
+        // Dos esferas opacas que se solapan: la de detrás queda tapada en parte
+        let spheres: Vec<Uniforms> = [(Vector3::new(-0.6_f32, 0.0_f32, 0.0_f32), 1.0_f32), (Vector3::new(0.5_f32, 0.2_f32, -2.0_f32), 1.3_f32)]
+            .iter()
+            .map(|&(position, scale)| {
+                let mut uniforms = Uniforms::for_tests(&grid);
+                uniforms.model_matrix = create_model_matrix(position, scale, Vector3::zero());
+                uniforms.view_matrix = create_view_matrix(Vector3::new(0.0_f32, 0.0_f32, 6.0_f32), Vector3::zero(), Vector3::new(0.0_f32, 1.0_f32, 0.0_f32));
+                uniforms.projection_matrix = create_projection_matrix(PI / 3.0_f32, 1.0_f32, 0.1_f32, 100.0_f32);
+                uniforms.viewport_matrix = create_viewport_matrix(0.0_f32, 0.0_f32, 64.0_f32, 64.0_f32);
+                uniforms.lights = vec![Light::new(Vector3::new(10.0_f32, 10.0_f32, 10.0_f32))];
+                uniforms.ambient_intensity = 0.2_f32;
+                uniforms.ambient_color = Vector3::one();
+                uniforms
+            })
+            .collect();
+
+        let mut forward = Framebuffer::new(64, 64);
+        for uniforms in &spheres {
+            render(&mut forward, uniforms, &sphere, "Mars", None, RenderMode::Forward, &mut Vec::new());
+        }
+        let mut deferred = Framebuffer::new(64, 64);
+        for uniforms in &spheres {
+            depth_prepass_render(&mut deferred, uniforms, &sphere);
+        }
+        for uniforms in &spheres {
+            render(&mut deferred, uniforms, &sphere, "Mars", None, RenderMode::Deferred, &mut Vec::new());
+        }
+
+        let (forward_pixels, deferred_pixels) = (forward.color_buffer.get_image_data(), deferred.color_buffer.get_image_data());
+        let background = Framebuffer::new(1, 1).color_buffer.get_image_data()[0];
+        assert!(forward_pixels.iter().filter(|c| **c != background).count() > 300);
+        assert!(forward_pixels.iter().zip(deferred_pixels.iter()).all(|(a, b)| a == b));
+        for (x, y) in [(16, 32), (32, 32), (40, 28)] {
+            assert_eq!(forward.get_depth_at(x, y), deferred.get_depth_at(x, y));
+        }
+    }
+
+    #[test]
+    fn msaa_renders_deferred_as_forward() {
+        assert_eq!(RenderMode::Deferred.effective(0), RenderMode::Deferred);
+        assert_eq!(RenderMode::Deferred.effective(4), RenderMode::Forward);
+        assert_eq!(RenderMode::Forward.effective(4), RenderMode::Forward);
+    }
+
+    #[test]
+    fn fov_to_frame_fits_the_planet_in_the_requested_fraction() {
+        // Esfera de radio 1 a distancia 2: sus bordes están a 30° del centro
+        assert!((fov_to_frame(1.0_f32, 2.0_f32, 1.0_f32) - PI / 3.0_f32).abs() < 1e-5_f32);
+        // Ocupando la mitad de la altura, tan(fov / 2) es el doble que tan(30°)
+        let fov = fov_to_frame(1.0_f32, 2.0_f32, 0.5_f32);
+        assert!(((fov * 0.5_f32).tan() - 2.0_f32 * (PI / 6.0_f32).tan()).abs() < 1e-5_f32);
+    }
+
+    #[test]
+    fn orbit_trail_never_exceeds_max_length() {
+        let mut trail = OrbitTrail::new(20, Color::new(255, 255, 255, 255));
+        for step in 0..100 {
+            let time = step as f32 * OrbitTrail::SAMPLE_INTERVAL;
+            trail.sample(Vector3::new(time, 0.0_f32, 0.0_f32), time, step >= 60);
+            assert!(trail.positions.len() <= 20);
+        }
+        assert_eq!(trail.positions.len(), 20);
+    }
+
+    #[test]
+    fn catalog_has_venus_pluto_and_ceres_in_orbit() {
+        let celestial_bodies = load_body_catalog(BODY_CATALOG_PATH).unwrap();
+        for name in ["Venus", "Pluto", "Ceres"] {
+            let body = celestial_bodies.iter().find(|body| body.name == name).unwrap_or_else(|| panic!("falta {}", name));
+            assert!(body.orbit_radius > 0.0_f32);
+            assert_eq!(body.shader_name, name);
+        }
+    }
+
+    #[test]
+    fn orbit_segments_follow_the_on_screen_radius() {
+        // Radio en pantalla = orbit_radius / distancia * screen_size
+        assert_eq!(orbit_line_segment_count(1.0_f32, 100.0_f32, 100.0_f32), ORBIT_MIN_SEGMENTS);
+        assert!(orbit_line_segment_count(5.0_f32, 1.0_f32, 100.0_f32) >= 512);
+        assert_eq!(orbit_line_segment_count(1e6_f32, 1.0_f32, 100.0_f32), ORBIT_MAX_SEGMENTS);
+    }
+
+    #[test]
+    fn gizmo_x_ring_projects_to_the_expected_ellipse() {
+        let (center, radius) = ((400, 300), 100);
+        let x_axis = Vector3::new(1.0_f32, 0.0_f32, 0.0_f32);
+        let extents = |eye: Vector3| {
+            let view_matrix = create_view_matrix(eye, Vector3::zero(), Vector3::new(0.0_f32, 1.0_f32, 0.0_f32));
+            (0..64).fold((0, 0), |(max_x, max_y), i| {
+                let (x, y, _) = gizmo_ring_point(x_axis, i as f32 / 64.0_f32 * 2.0_f32 * PI, center, radius, &view_matrix);
+                (max_x.max((x - center.0).abs()), max_y.max((y - center.1).abs()))
+            })
+        };
+        // Mirando por el eje X el anillo (plano YZ) es un círculo
+        assert_eq!(extents(Vector3::new(10.0_f32, 0.0_f32, 0.0_f32)), (radius, radius));
+        // Girando 60° alrededor de Y se achata horizontalmente a cos(60°)
+        let angle = PI / 3.0_f32;
+        let (half_width, half_height) = extents(Vector3::new(10.0_f32 * angle.cos(), 0.0_f32, 10.0_f32 * angle.sin()));
+        assert!((half_width - radius / 2).abs() <= 1, "semieje horizontal {}", half_width);
+        assert_eq!(half_height, radius);
+    }
+
+    #[test]
+    fn search_ear_matches_earth_not_mars() {
+        let celestial_bodies = load_body_catalog(BODY_CATALOG_PATH).unwrap();
+        let found = find_body_by_prefix(&celestial_bodies, "ear").map(|i| celestial_bodies[i].name.as_str());
+        assert_eq!(found, Some("Earth"));
+        assert_eq!(find_body_by_prefix(&celestial_bodies, "EAR").map(|i| celestial_bodies[i].name.as_str()), Some("Earth"));
+        assert_eq!(find_body_by_prefix(&celestial_bodies, "xyz"), None);
+    }
+
+    #[test]
+    fn mercury_position_at_64x_matches_the_exact_angle() {
+        let celestial_bodies = load_body_catalog(BODY_CATALOG_PATH).unwrap();
+        let mercury = celestial_bodies.iter().find(|body| body.name == "Mercury").unwrap();
+        // 1000 s simulados a x64: cada frame de 1/64 s real avanza 1 s simulado
+        let (time_scale, frame_dt) = (64.0_f32, 1.0_f32 / 64.0_f32);
+        let mut time = 0.0_f32;
+        for _ in 0..1000 {
+            time += frame_dt * time_scale;
+        }
+        assert_eq!(time, 1000.0_f32);
+        let exact_angle = (1000.0_f64 * mercury.orbit_speed as f64).rem_euclid(std::f64::consts::TAU) as f32;
+        let expected = orbital_position(mercury.orbit_radius, exact_angle, mercury.inclination, mercury.ascending_node);
+        assert!(length_vec3(sub_vec3(body_position(mercury, time), expected)) < mercury.orbit_radius * f32::EPSILON * 4.0_f32);
+    }
+
+    #[test]
+    fn advance_along_path_moves_monotonically_to_the_last_waypoint() {
+        let waypoints = [
+            Vector3::new(0.0_f32, 0.0_f32, 0.0_f32),
+            Vector3::new(10.0_f32, 1.0_f32, 2.0_f32),
+            Vector3::new(20.0_f32, 0.0_f32, -1.0_f32),
+            Vector3::new(30.0_f32, 0.0_f32, 0.0_f32),
+        ];
+        let last = waypoints[3];
+        let mut progress = 0.0_f32;
+        let mut previous_distance = f32::INFINITY;
+        for _ in 0..200 {
+            let position = advance_along_path(&waypoints, &mut progress, 5.0_f32, 0.05_f32);
+            let distance = length_vec3(sub_vec3(last, position));
+            assert!(distance <= previous_distance + 1e-5_f32);
+            previous_distance = distance;
+        }
+        assert!(previous_distance < 1e-5_f32);
+    }
+
+    #[test]
+    fn impact_effect_grows_to_max_radius_in_half_a_second() {
+        let mut effect = ImpactEffect::new((100, 100), 80.0_f32, Vector3::one());
+        effect.update(0.0_f32);
+        assert!(effect.radius < 1.0_f32);
+        assert!(!effect.is_finished());
+        for _ in 0..5 {
+            effect.update(0.1_f32);
+        }
+        assert!((effect.radius - 80.0_f32).abs() < 1e-3_f32);
+        assert!(effect.opacity() < 1e-3_f32);
+    }
+
+    #[test]
+    fn venus_limb_is_more_opaque_than_earth_limb() {
+        let celestial_bodies = load_body_catalog(BODY_CATALOG_PATH).unwrap();
+        let limb_transmittance = |name: &str| {
+            let body = celestial_bodies.iter().find(|body| body.name == name).unwrap();
+            // Visual casi rasante: 84° entre la normal y la dirección a la cámara
+            let view_dir = Vector3::new(84.0_f32.to_radians().sin(), 84.0_f32.to_radians().cos(), 0.0_f32);
+            shaders::atmosphere_transmittance(Vector3::new(0.0_f32, 1.0_f32, 0.0_f32), view_dir, body.atmosphere_height, body.atmosphere_density)
+        };
+        assert!(limb_transmittance("Venus") < limb_transmittance("Earth"));
+    }
+
+    #[test]
+    fn missing_nave_obj_falls_back_to_a_cone() {
+        assert!(Obj::load("nonexistent.obj").is_err());
+        let cone = fallback_nave_mesh();
+        assert!(!cone.is_empty() && cone.len().is_multiple_of(3));
+        // Apunta a +X y queda centrado en el origen como `nave.obj`
+        let max_x = cone.iter().map(|v| v.position.x).fold(f32::MIN, f32::max);
+        let min_x = cone.iter().map(|v| v.position.x).fold(f32::MAX, f32::min);
+        assert!((max_x + min_x).abs() < 1e-4 && max_x > 1.0);
+    }
+
+    #[test]
+    fn transfer_window_counts_down_within_a_synodic_period() {
+        let bodies = load_body_catalog(BODY_CATALOG_PATH).unwrap();
+        let earth = bodies.iter().find(|b| b.name == "Earth").unwrap();
+        let mars = bodies.iter().find(|b| b.name == "Mars").unwrap();
+        let synodic = synodic_period(earth, mars);
+        let step = synodic / 7.3_f32;
+        let mut previous: Option<f32> = None;
+        for i in 0..20 {
+            let time = 3.0_f32 + i as f32 * step;
+            let window = next_transfer_window(earth, mars, time, synodic);
+            assert!((0.0_f32..=synodic).contains(&window), "t={} ventana={}", time, window);
+            // Tras `step` segundos queda `step` menos, salvo cuando la ventana ya pasó y se salta a la siguiente
+            if let Some(previous) = previous {
+                let expected = (previous - step).rem_euclid(synodic);
+                let error = (window - expected).abs();
+                assert!(error.min(synodic - error) < synodic * 1e-3, "t={} ventana={} esperado={}", time, window, expected);
+            }
+            previous = Some(window);
+        }
+    }
+
+    #[test]
+    fn field_lines_converge_at_the_magnetic_poles() {
+        let (center, radius) = (Vector3::new(10.0_f32, -2.0_f32, 4.0_f32), 3.0_f32);
+        let tilt = 11.0_f32.to_radians();
+        let axis = Vector3::new(tilt.sin(), tilt.cos(), 0.0_f32);
+        let u = normalize_vec3(axis.cross(Vector3::new(0.0_f32, 0.0_f32, 1.0_f32)));
+        let v = axis.cross(u);
+        for hemisphere in [-1.0_f32, 1.0_f32] {
+            let mut starts = Vec::new();
+            let mut ends = Vec::new();
+            for plane in 0..FIELD_LONGITUDE_PLANES {
+                let phi = plane as f32 / FIELD_LONGITUDE_PLANES as f32 * 2.0_f32 * PI;
+                let outward = add_vec3(mul_vec3_scalar(u, phi.cos()), mul_vec3_scalar(v, phi.sin()));
+                for l_shell in FIELD_L_SHELLS {
+                    let line = dipole_field_line(center, radius, axis, outward, l_shell, hemisphere);
+                    let (end, _) = *line.last().unwrap();
+                    // Cada línea acaba en la superficie, en el hemisferio de su polo y más cerca de él que del ecuador
+                    let to_end = sub_vec3(end, center);
+                    assert!((length_vec3(to_end) - radius).abs() < radius * 0.02_f32, "L={} r={}", l_shell, length_vec3(to_end));
+                    assert!(normalize_vec3(to_end).dot(axis) * hemisphere > 45.0_f32.to_radians().sin() - 1e-3_f32);
+                    starts.push(line[0].0);
+                    ends.push(end);
+                }
+            }
+            // Las líneas salen del ecuador muy separadas y llegan juntas al polo
+            let spread = |points: &[Vector3]| points.iter().flat_map(|a| points.iter().map(move |b| length_vec3(sub_vec3(*a, *b)))).fold(0.0_f32, f32::max);
+            assert!(spread(&ends) < spread(&starts) * 0.25_f32, "{} vs {}", spread(&ends), spread(&starts));
+        }
+    }
+
+    #[test]
+    fn profiled_stages_fit_inside_the_frame_time() {
+        let grid = [[true; EXPLORATION_GRID_SIZE]; EXPLORATION_GRID_SIZE];
+        let sphere = generate_cube_sphere(8);
+        let mut uniforms = Uniforms::for_tests(&grid);
+        uniforms.view_matrix = create_view_matrix(Vector3::new(0.0_f32, 0.0_f32, 4.0_f32), Vector3::zero(), Vector3::new(0.0_f32, 1.0_f32, 0.0_f32));
+        uniforms.projection_matrix = create_projection_matrix(PI / 3.0_f32, 1.0_f32, 0.1_f32, 100.0_f32);
+        uniforms.viewport_matrix = create_viewport_matrix(0.0_f32, 0.0_f32, 128.0_f32, 128.0_f32);
+        uniforms.lights = vec![Light::new(Vector3::new(10.0_f32, 10.0_f32, 10.0_f32))];
+        let mut framebuffer = Framebuffer::new(128, 128);
+
+        let frame_start = std::time::Instant::now();
+        for _ in 0..3 {
+            framebuffer.clear();
+            render(&mut framebuffer, &uniforms, &sphere, "Earth", None, RenderMode::Forward, &mut Vec::new());
+        }
+        let frame_us = elapsed_us(frame_start);
+
+        let profile = framebuffer.profiler;
+        assert!(profile.fragment_shade_us > 0 && profile.vertex_transform_us > 0);
+        assert!(profile.total_us() as f32 <= frame_us as f32 * 1.05_f32, "etapas {} µs, frame {} µs", profile.total_us(), frame_us);
+    }
+
+    #[test]
+    fn msaa_softens_a_diagonal_edge() {
+        let corner = |x: f32, y: f32| Vertex::new(Vector3::new(x, y, 0.5_f32), Vector3::new(0.0_f32, 0.0_f32, 1.0_f32), Vector2::zero());
+        // Medio cuadrado blanco por debajo de la diagonal x = y
+        let (a, b, c) = (corner(0.0_f32, 0.0_f32), corner(16.0_f32, 16.0_f32), corner(0.0_f32, 16.0_f32));
+        let light = Light::new(Vector3::new(0.0_f32, 0.0_f32, 10.0_f32));
+        let greys_on_the_edge = |msaa_mode: MsaaMode| {
+            let mut framebuffer = Framebuffer::new(16, 16);
+            framebuffer.set_background_color(Color::BLACK);
+            framebuffer.clear();
+            framebuffer.set_msaa(msaa_mode).unwrap();
+            for fragment in triangle(&a, &b, &c, &light, framebuffer.msaa_sample_offsets(), (16, 16)) {
+                let (x, y) = (fragment.position.x as i32, fragment.position.y as i32);
+                if framebuffer.msaa_level > 0 {
+                    framebuffer.point_msaa(x, y, Vector3::one(), fragment.depth, fragment.coverage);
+                } else {
+                    framebuffer.point(x, y, Vector3::one(), fragment.depth);
+                }
+            }
+            framebuffer.resolve_msaa();
+            (0..16).map(|i| framebuffer.color_buffer.get_color(i, i).r).filter(|&r| r > 0 && r < 255).count()
+        };
+        assert_eq!(greys_on_the_edge(MsaaMode::Off), 0);
+        assert!(greys_on_the_edge(MsaaMode::Rotated(4)) >= 14, "{}", greys_on_the_edge(MsaaMode::Rotated(4)));
+    }
+
+    #[test]
+    fn terrain_follow_never_enters_a_planet() {
+        let bodies = load_body_catalog(BODY_CATALOG_PATH).unwrap();
+        let time = 12.5_f32;
+        let mut rng = fastrand::Rng::with_seed(403);
+        for body in &bodies {
+            let (center, radius) = (body_position(body, time), body_radius(body));
+            for _ in 0..200 {
+                // Desde el centro hasta el borde del rango del vuelo rasante, en cualquier dirección
+                let direction = normalize_vec3(Vector3::new(rng.f32() - 0.5_f32, rng.f32() - 0.5_f32, rng.f32() - 0.5_f32));
+                let eye = add_vec3(center, mul_vec3_scalar(direction, rng.f32() * (radius + terrain_follow_altitude(body))));
+                let followed = terrain_follow(eye, &bodies, time);
+                for other in &bodies {
+                    let distance = length_vec3(sub_vec3(followed, body_position(other, time)));
+                    assert!(distance >= body_radius(other) - 1e-3_f32, "{} dentro de {} ({} < {})", body.name, other.name, distance, body_radius(other));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn queued_warps_run_one_after_another() {
+        let bodies = load_body_catalog(BODY_CATALOG_PATH).unwrap();
+        let destinations: Vec<WarpTarget> = bodies.iter().skip(1).take(3).map(|body| warp_target_for_body(body, 0.0_f32)).collect();
+        let mut camera = Camera::new(Vector3::new(0.0_f32, 50.0_f32, 200.0_f32), Vector3::zero(), Vector3::new(0.0_f32, 1.0_f32, 0.0_f32));
+        let mut warp_queue: WarpQueue = VecDeque::new();
+        let mut warp_start_time = 0.0_f32;
+        let warp_duration = 1.0_f32;
+
+        // Teclas 1, 2 y 3 seguidas: la primera empieza ya y las otras esperan
+        for (i, destination) in destinations.iter().enumerate() {
+            enqueue_warp(&mut warp_queue, &mut warp_start_time, destination.clone(), i as f32 * 0.02_f32);
+        }
+        assert_eq!(warp_queue.len(), 3);
+        assert_eq!(warp_start_time, 0.0_f32);
+        assert_eq!(estimate_arrival_time(&warp_queue, 1.0_f32 / warp_duration), 3.0_f32);
+
+        let mut arrivals = Vec::new();
+        let mut real_time = 0.0_f32;
+        for step in 1..200 {
+            if warp_queue.is_empty() {
+                break;
+            }
+            real_time = step as f32 * 0.05_f32;
+            let before = warp_queue.len();
+            assert!(advance_warp(&mut warp_queue, &mut warp_start_time, &mut camera, real_time, warp_duration).is_some());
+            if warp_queue.len() < before {
+                arrivals.push((real_time, camera.eye));
+            }
+        }
+        assert!(advance_warp(&mut warp_queue, &mut warp_start_time, &mut camera, real_time, warp_duration).is_none());
+        // Llegan en el orden en que se pidieron, cada uno un warp completo después del anterior
+        assert_eq!(arrivals.len(), 3);
+        for (i, ((arrival, eye), destination)) in arrivals.iter().zip(&destinations).enumerate() {
+            assert!(length_vec3(sub_vec3(*eye, destination.eye)) < 1e-4_f32);
+            assert!((arrival - (i as f32 + 1.0_f32) * warp_duration).abs() < 0.06_f32, "warp {} llega en {}", i, arrival);
+        }
+    }
+
+    #[test]
+    fn translucent_sphere_in_front_is_blended_not_rejected() {
+        let grid = [[true; EXPLORATION_GRID_SIZE]; EXPLORATION_GRID_SIZE];
+        let sphere = generate_cube_sphere(6);
+        let eye = Vector3::new(0.0_f32, 0.0_f32, 6.0_f32);
+        let sphere_uniforms = |position: Vector3, scale: f32| {
+            let mut uniforms = Uniforms::for_tests(&grid);
+            uniforms.model_matrix = create_model_matrix(position, scale, Vector3::zero());
+            uniforms.view_matrix = create_view_matrix(eye, Vector3::zero(), Vector3::new(0.0_f32, 1.0_f32, 0.0_f32));
+            uniforms.projection_matrix = create_projection_matrix(PI / 3.0_f32, 1.0_f32, 0.1_f32, 100.0_f32);
+            uniforms.viewport_matrix = create_viewport_matrix(0.0_f32, 0.0_f32, 64.0_f32, 64.0_f32);
+            uniforms.camera_pos = eye;
+            uniforms.lights = vec![Light::new(Vector3::new(10.0_f32, 10.0_f32, 10.0_f32))];
+            uniforms.ambient_intensity = 0.2_f32;
+            uniforms.ambient_color = Vector3::one();
+            uniforms.explosion_phase = 0.5_f32;
+            uniforms
+        };
+        let opaque = sphere_uniforms(Vector3::new(0.0_f32, 0.0_f32, -2.0_f32), 1.5_f32);
+        let shell = sphere_uniforms(Vector3::new(0.0_f32, 0.0_f32, 1.0_f32), 1.0_f32);
+
+        let mut opaque_only = Framebuffer::new(64, 64);
+        render(&mut opaque_only, &opaque, &sphere, "Mars", None, RenderMode::Forward, &mut Vec::new());
+
+        // La cáscara translúcida se dibuja primero a propósito: no escribe profundidad, así que
+        // el planeta de detrás no la tapa y luego se compone por encima
+        let mut both = Framebuffer::new(64, 64);
+        let mut transparent = Vec::new();
+        render(&mut both, &shell, &sphere, "NovaShockwave", None, RenderMode::Forward, &mut transparent);
+        render(&mut both, &opaque, &sphere, "Mars", None, RenderMode::Forward, &mut transparent);
+        assert!(!transparent.is_empty());
+        transparency::composite_transparent(&mut both, &mut transparent);
+
+        let (behind, blended) = (opaque_only.color_buffer.get_color(32, 32), both.color_buffer.get_color(32, 32));
+        assert_ne!(blended, behind, "la cáscara no se ve delante del planeta");
+        // El planeta sigue dibujado debajo: su profundidad no cambia y su color se nota en la mezcla
+        assert_eq!(both.get_depth_at(32, 32), opaque_only.get_depth_at(32, 32));
+        let mut shell_only = Framebuffer::new(64, 64);
+        let mut shell_items = Vec::new();
+        render(&mut shell_only, &shell, &sphere, "NovaShockwave", None, RenderMode::Forward, &mut shell_items);
+        transparency::composite_transparent(&mut shell_only, &mut shell_items);
+        assert_ne!(blended, shell_only.color_buffer.get_color(32, 32));
+    }
+
+    #[test]
+    fn alt_click_at_a_body_center_selects_it() {
+        let mut bodies = load_body_catalog(BODY_CATALOG_PATH).unwrap();
+        let time = 7.0_f32;
+        update_orbit_centers(&mut bodies, time);
+        let (fov, viewport) = (PI / 3.0_f32, (0, 0, 256, 256));
+        let projection_matrix = create_projection_matrix(fov, 1.0_f32, 0.1_f32, 5000.0_f32);
+        let viewport_matrix = create_viewport_matrix(0.0_f32, 0.0_f32, 256.0_f32, 256.0_f32);
+        let pixels_per_unit = 128.0_f32 / (fov * 0.5_f32).tan();
+        for (i, body) in bodies.iter().enumerate() {
+            let warp = warp_target_for_body(body, time);
+            let camera = Camera::new(warp.eye, warp.target, warp.up);
+            let view_matrix = camera.get_view_matrix();
+            let center = body_position(body, time);
+            let (sx, sy, _) = project_to_screen(center, &view_matrix, &projection_matrix, &viewport_matrix).unwrap();
+            let picked = pick_body_at_screen(sx, sy, &camera, &bodies, time, pixels_per_unit, &view_matrix, &projection_matrix, &viewport_matrix);
+            assert_eq!(picked, Some(i), "clic en el centro de {}", body.name);
+            // El rayo de depuración que se ve con Alt también atraviesa el cuerpo
+            let direction = camera.ray_through_pixel(sx, sy, viewport, fov);
+            assert!(ray_sphere_intersection(camera.eye, direction, center, body_radius(body)).is_some(), "el rayo no toca {}", body.name);
+        }
+    }
+
+    #[test]
+    fn asteroids_with_different_tumble_rates_face_different_ways() {
+        let (a, b) = (Asteroid::new(0), Asteroid::new(1));
+        assert!(a.primary_tumble != b.primary_tumble || a.secondary_tumble != b.secondary_tumble);
+        let time = 10.0_f32;
+        let (rotation_a, rotation_b) = (a.tumble_rotation(time), b.tumble_rotation(time));
+        // Ángulo más grande entre los ejes de la malla una vez girados con cada volteo
+        let largest_angle = [Vector4::new(1.0_f32, 0.0_f32, 0.0_f32, 0.0_f32), Vector4::new(0.0_f32, 1.0_f32, 0.0_f32, 0.0_f32), Vector4::new(0.0_f32, 0.0_f32, 1.0_f32, 0.0_f32)]
+            .iter()
+            .map(|axis| {
+                let (ra, rb) = (multiply_matrix_vector4(&rotation_a, axis), multiply_matrix_vector4(&rotation_b, axis));
+                clamp_f32(ra.x * rb.x + ra.y * rb.y + ra.z * rb.z, -1.0_f32, 1.0_f32).acos()
+            })
+            .fold(0.0_f32, f32::max);
+        assert!(largest_angle > 20.0_f32.to_radians(), "orientaciones casi iguales ({} rad)", largest_angle);
+        // Al empezar los dos están sin girar
+        assert_eq!(a.tumble_rotation(0.0_f32), b.tumble_rotation(0.0_f32));
+    }
+
+    #[test]
+    fn conjunction_is_found_when_both_bodies_share_the_orbital_angle() {
+        let bodies = load_body_catalog(BODY_CATALOG_PATH).unwrap();
+        // Órbitas en la eclíptica: la longitud es directamente el ángulo orbital ω·t
+        let flat = |name: &str| {
+            let mut body = bodies.iter().find(|b| b.name == name).unwrap().clone();
+            (body.inclination, body.ascending_node, body.node_precession_rate) = (0.0_f32, 0.0_f32, 0.0_f32);
+            body
+        };
+        let (earth, mars) = (flat("Earth"), flat("Mars"));
+        let synodic = synodic_period(&earth, &mars);
+        // En t = 0 los dos están en el ángulo 0: conjunción ya
+        assert_eq!(phase_angle(&earth, &mars, 0.0_f32), 0.0_f32);
+        assert_eq!(find_next_conjunction(&earth, &mars, 0.0_f32, 0.1_f32), 0.0_f32);
+        // Pasada la oposición (media vuelta sinódica), lo siguiente es volver a coincidir
+        let found = find_next_conjunction(&earth, &mars, synodic * 0.6_f32, 0.1_f32);
+        assert!((found - synodic).abs() < 0.1_f32, "conjunción en {}, esperada en {}", found, synodic);
+        assert!(phase_angle(&earth, &mars, found).abs() < 0.01_f32);
+        let (angle_earth, angle_mars) = (angle_at(found, earth.orbit_speed), angle_at(found, mars.orbit_speed));
+        let difference = (angle_earth - angle_mars + PI).rem_euclid(2.0_f32 * PI) - PI;
+        assert!(difference.abs() < 0.01_f32);
+    }
+
+    #[test]
+    fn galilean_moons_keep_the_laplace_resonance() {
+        let bodies = load_body_catalog(BODY_CATALOG_PATH).unwrap();
+        let speed = |name: &str| bodies.iter().find(|b| b.name == name).unwrap().orbit_speed;
+        let io_period = 2.0_f32 * PI / speed("Io");
+        // Vueltas completadas en un periodo de Ío
+        let orbits = |name: &str| speed(name) * io_period / (2.0_f32 * PI);
+        assert!((orbits("Europa") - 0.5_f32).abs() < 1e-4_f32, "Europa: {}", orbits("Europa"));
+        assert!((orbits("Ganymede") - 0.25_f32).abs() < 1e-4_f32, "Ganímedes: {}", orbits("Ganymede"));
+        // Y en el ángulo que usa la simulación
+        assert!((angle_at(io_period, speed("Europa")) - PI).abs() < 1e-3_f32);
+        assert!((angle_at(io_period, speed("Ganymede")) - PI * 0.5_f32).abs() < 1e-3_f32);
+    }
+
+    #[test]
+    fn barycenter_of_the_sun_alone_is_the_sun() {
+        let mut bodies = load_body_catalog(BODY_CATALOG_PATH).unwrap();
+        update_orbit_centers(&mut bodies, 3.0_f32);
+        let mut sun = bodies.iter().find(|b| is_sun(b)).unwrap().clone();
+        sun.translation = Vector3::new(3.7_f32, -1.2_f32, 0.4_f32);
+        assert_eq!(compute_barycenter(std::slice::from_ref(&sun), 3.0_f32), sun.translation);
+        // Con Júpiter se desplaza hacia él en m_J / (m_S + m_J) de la distancia
+        let jupiter = bodies.iter().find(|b| b.name == "Jupiter").unwrap().clone();
+        let (sun_pos, jupiter_pos) = (body_position(&sun, 3.0_f32), body_position(&jupiter, 3.0_f32));
+        let expected = add_vec3(sun_pos, mul_vec3_scalar(sub_vec3(jupiter_pos, sun_pos), jupiter.mass / (sun.mass + jupiter.mass)));
+        let barycenter = compute_barycenter(&[sun, jupiter], 3.0_f32);
+        assert!(length_vec3(sub_vec3(barycenter, expected)) < 1e-4_f32, "{:?} frente a {:?}", barycenter, expected);
+    }
+
+    #[test]
+    fn depths_550_and_560_stay_distinct_in_the_depth_buffer() {
+        let projection_matrix = create_projection_matrix(PI / 3.0_f32, 1.0_f32, NEAR_PLANE, FAR_PLANE);
+        let viewport_matrix = create_viewport_matrix(0.0_f32, 0.0_f32, 64.0_f32, 64.0_f32);
+        let view_matrix = Camera::new(Vector3::zero(), Vector3::new(0.0_f32, 0.0_f32, -1.0_f32), Vector3::new(0.0_f32, 1.0_f32, 0.0_f32)).get_view_matrix();
+        let depth_at = |distance: f32| project_to_screen(Vector3::new(0.0_f32, 0.0_f32, -distance), &view_matrix, &projection_matrix, &viewport_matrix).unwrap().2;
+        let (near, far) = (depth_at(550.0_f32), depth_at(560.0_f32));
+        // El hueco entre las dos supera con mucho el paso de un f32 a esa profundidad
+        let ulp = f32::from_bits(far.to_bits() + 1) - far;
+        assert!(far - near > 100.0_f32 * ulp, "{} y {} (ulp {})", near, far, ulp);
+        // Y se recuperan las distancias al linealizar
+        let mut framebuffer = Framebuffer::new(2, 1);
+        framebuffer.depth_point(0, 0, near);
+        framebuffer.depth_point(1, 0, far);
+        let recovered = |x: i32| linearize_depth(framebuffer.get_depth_at(x, 0), NEAR_PLANE, FAR_PLANE);
+        assert!((recovered(0) - 550.0_f32).abs() < 1.0_f32 && (recovered(1) - 560.0_f32).abs() < 1.0_f32, "{} y {}", recovered(0), recovered(1));
+    }
+
+    #[test]
+    fn stars_on_the_horizon_twinkle_at_least_three_times_more() {
+        // Amplitud: el mayor desvío en 20 s de varias estrellas
+        let amplitude = |altitude_deg: f32| {
+            (0..2000)
+                .flat_map(|step| (0..8_u32).map(move |star| star_scintillation(37, 81, step as f32 * 0.01_f32, star, altitude_deg).abs()))
+                .fold(0.0_f32, f32::max)
+        };
+        let (horizon, middle, zenith) = (amplitude(0.0_f32), amplitude(45.0_f32), amplitude(90.0_f32));
+        assert!(horizon > 0.15_f32, "horizonte: {}", horizon);
+        assert!(horizon >= 3.0_f32 * zenith, "horizonte {} frente a cenit {}", horizon, zenith);
+        assert!(zenith < 1e-3_f32 && zenith < middle && middle < horizon);
+    }
+
+    #[test]
+    fn galactic_center_is_five_times_denser_than_the_pole() {
+        let center = galaxy_core_density(equatorial_to_world(GALACTIC_CENTER_RA_DEG, GALACTIC_CENTER_DEC_DEG));
+        let pole = galaxy_core_density(equatorial_to_world(GALACTIC_POLE_RA_DEG, GALACTIC_POLE_DEC_DEG));
+        assert!(center > 5.0_f32 * pole, "centro {} frente a polo {}", center, pole);
+        // El centro está sobre el disco (b ≈ 0) y en el bulbo: 1 + peso del bulbo
+        assert!((center - (1.0_f32 + GALAXY_BULGE_WEIGHT)).abs() < 0.1_f32, "centro {}", center);
+        let south_pole = galaxy_core_density(mul_vec3_scalar(equatorial_to_world(GALACTIC_POLE_RA_DEG, GALACTIC_POLE_DEC_DEG), -1.0_f32));
+        assert!(center > 5.0_f32 * south_pole);
+    }
+}
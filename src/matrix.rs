@@ -12,6 +12,88 @@ pub fn multiply_matrix_vector4(matrix: &Matrix, vector: &Vector4) -> Vector4 {
     )
 }
 
+/// SSE version of `multiply_matrix_vector4`: the four output components are computed at once
+/// as column0 * x + column1 * y + column2 * z + column3 * w. The additions happen in the same
+/// order as the scalar version, so the results are bit-identical.
+#[cfg(all(target_arch = "x86_64", target_feature = "sse4.1"))]
+pub fn multiply_matrix_vector4_simd(matrix: &Matrix, vector: &Vector4) -> Vector4 {
+    use std::arch::x86_64::*;
+    // SAFETY: the cfg guarantees SSE4.1 (and therefore SSE) is available on the target
+    unsafe {
+        let result = _mm_add_ps(
+            _mm_add_ps(
+                _mm_add_ps(
+                    _mm_mul_ps(_mm_setr_ps(matrix.m0, matrix.m1, matrix.m2, matrix.m3), _mm_set1_ps(vector.x)),
+                    _mm_mul_ps(_mm_setr_ps(matrix.m4, matrix.m5, matrix.m6, matrix.m7), _mm_set1_ps(vector.y)),
+                ),
+                _mm_mul_ps(_mm_setr_ps(matrix.m8, matrix.m9, matrix.m10, matrix.m11), _mm_set1_ps(vector.z)),
+            ),
+            _mm_mul_ps(_mm_setr_ps(matrix.m12, matrix.m13, matrix.m14, matrix.m15), _mm_set1_ps(vector.w)),
+        );
+        let mut out = [0.0_f32; 4];
+        _mm_storeu_ps(out.as_mut_ptr(), result);
+        Vector4::new(out[0], out[1], out[2], out[3])
+    }
+}
+
+/// Scalar fallback when the target has no SSE4.1.
+#[cfg(not(all(target_arch = "x86_64", target_feature = "sse4.1")))]
+pub fn multiply_matrix_vector4_simd(matrix: &Matrix, vector: &Vector4) -> Vector4 {
+    multiply_matrix_vector4(matrix, vector)
+}
+
+/// Transforms `vectors` into `output` (same length, extra elements are left untouched).
+/// Four vectors are processed at once: they are transposed into x/y/z/w lanes and every
+/// output row is a 4-wide multiply-add with the matrix entries broadcast.
+#[cfg(all(target_arch = "x86_64", target_feature = "sse4.1"))]
+pub fn multiply_matrix_vector4_batch(matrix: &Matrix, vectors: &[Vector4], output: &mut [Vector4]) {
+    use std::arch::x86_64::*;
+    let count = vectors.len().min(output.len());
+    let full = count - count % 4;
+    // Rows of the matrix (raylib stores columns: row i is m_i, m_(4+i), m_(8+i), m_(12+i))
+    let rows = [
+        [matrix.m0, matrix.m4, matrix.m8, matrix.m12],
+        [matrix.m1, matrix.m5, matrix.m9, matrix.m13],
+        [matrix.m2, matrix.m6, matrix.m10, matrix.m14],
+        [matrix.m3, matrix.m7, matrix.m11, matrix.m15],
+    ];
+    for base in (0..full).step_by(4) {
+        let v = &vectors[base..base + 4];
+        // SAFETY: the cfg guarantees SSE4.1 (and therefore SSE) is available on the target
+        unsafe {
+            let xs = _mm_setr_ps(v[0].x, v[1].x, v[2].x, v[3].x);
+            let ys = _mm_setr_ps(v[0].y, v[1].y, v[2].y, v[3].y);
+            let zs = _mm_setr_ps(v[0].z, v[1].z, v[2].z, v[3].z);
+            let ws = _mm_setr_ps(v[0].w, v[1].w, v[2].w, v[3].w);
+            let mut lanes = [[0.0_f32; 4]; 4];
+            for (row, lane) in rows.iter().zip(lanes.iter_mut()) {
+                let sum = _mm_add_ps(
+                    _mm_add_ps(
+                        _mm_add_ps(_mm_mul_ps(_mm_set1_ps(row[0]), xs), _mm_mul_ps(_mm_set1_ps(row[1]), ys)),
+                        _mm_mul_ps(_mm_set1_ps(row[2]), zs),
+                    ),
+                    _mm_mul_ps(_mm_set1_ps(row[3]), ws),
+                );
+                _mm_storeu_ps(lane.as_mut_ptr(), sum);
+            }
+            for (i, out) in output[base..base + 4].iter_mut().enumerate() {
+                *out = Vector4::new(lanes[0][i], lanes[1][i], lanes[2][i], lanes[3][i]);
+            }
+        }
+    }
+    for (out, v) in output[full..count].iter_mut().zip(&vectors[full..count]) {
+        *out = multiply_matrix_vector4(matrix, v);
+    }
+}
+
+/// Scalar fallback when the target has no SSE4.1.
+#[cfg(not(all(target_arch = "x86_64", target_feature = "sse4.1")))]
+pub fn multiply_matrix_vector4_batch(matrix: &Matrix, vectors: &[Vector4], output: &mut [Vector4]) {
+    for (vector, out) in vectors.iter().zip(output.iter_mut()) {
+        *out = multiply_matrix_vector4(matrix, vector);
+    }
+}
+
 /// Creates a 4x4 matrix from 16 float values, specified in traditional row-major order.
 pub fn new_matrix4(
     // Row 0
@@ -49,38 +131,48 @@ pub fn new_matrix3(
     )
 }
 
-/// Creates a model matrix combining translation, scale, and rotation
-pub fn create_model_matrix(translation: Vector3, scale: f32, rotation: Vector3) -> Matrix {
-    let (sin_x, cos_x) = rotation.x.sin_cos();
-    let (sin_y, cos_y) = rotation.y.sin_cos();
-    let (sin_z, cos_z) = rotation.z.sin_cos();
-
-    // Rotation around the X-axis
-    let rotation_matrix_x = new_matrix4(
+/// Rotation of `angle` radians around the X axis
+pub fn create_rotation_matrix_x(angle: f32) -> Matrix {
+    let (sin_x, cos_x) = angle.sin_cos();
+    new_matrix4(
         1.0, 0.0,    0.0,    0.0,
         0.0, cos_x,  -sin_x, 0.0,
         0.0, sin_x,  cos_x,  0.0,
         0.0, 0.0,    0.0,    1.0
-    );
+    )
+}
 
-    // Rotation around the Y-axis
-    let rotation_matrix_y = new_matrix4(
+/// Rotation of `angle` radians around the Y axis
+pub fn create_rotation_matrix_y(angle: f32) -> Matrix {
+    let (sin_y, cos_y) = angle.sin_cos();
+    new_matrix4(
         cos_y,  0.0, sin_y, 0.0,
         0.0,    1.0, 0.0,   0.0,
         -sin_y, 0.0, cos_y, 0.0,
         0.0,    0.0, 0.0,   1.0
-    );
+    )
+}
 
-    // Rotation around the Z-axis
-    let rotation_matrix_z = new_matrix4(
+/// Rotation of `angle` radians around the Z axis
+pub fn create_rotation_matrix_z(angle: f32) -> Matrix {
+    let (sin_z, cos_z) = angle.sin_cos();
+    new_matrix4(
         cos_z, -sin_z, 0.0, 0.0,
         sin_z, cos_z,  0.0, 0.0,
         0.0,   0.0,    1.0, 0.0,
         0.0,   0.0,    0.0, 1.0
-    );
+    )
+}
 
-    let rotation_matrix = rotation_matrix_z * rotation_matrix_y * rotation_matrix_x;
+/// Creates a model matrix combining translation, scale, and rotation
+pub fn create_model_matrix(translation: Vector3, scale: f32, rotation: Vector3) -> Matrix {
+    let rotation_matrix = create_rotation_matrix_z(rotation.z) * create_rotation_matrix_y(rotation.y) * create_rotation_matrix_x(rotation.x);
+    create_model_matrix_with_rotation(translation, scale, rotation_matrix)
+}
 
+/// Model matrix with an arbitrary rotation matrix (e.g. a tumbling body that does not spin
+/// around a single axis)
+pub fn create_model_matrix_with_rotation(translation: Vector3, scale: f32, rotation_matrix: Matrix) -> Matrix {
     // Scaling matrix
     let scale_matrix = new_matrix4(
         scale, 0.0,   0.0,   0.0,
@@ -97,7 +189,9 @@ pub fn create_model_matrix(translation: Vector3, scale: f32, rotation: Vector3)
         0.0, 0.0, 0.0, 1.0
     );
 
-    scale_matrix * translation_matrix * rotation_matrix 
+    // raylib aplica primero el operando izquierdo: escalar, girar sobre el propio eje y luego trasladar
+    // (girar después de trasladar haría orbitar el objeto alrededor del origen)
+    scale_matrix * rotation_matrix * translation_matrix
 }
 
 /// Creates a view matrix using camera position, target, and up vector
@@ -160,6 +254,18 @@ pub fn create_projection_matrix(fov_y: f32, aspect: f32, near: f32, far: f32) ->
     )
 }
 
+/// Creates an orthographic projection matrix (no perspective divide, w stays 1)
+/// left, right, bottom, top: View volume bounds in view space
+/// near, far: Clipping plane distances
+pub fn create_ortho_projection_matrix(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Matrix {
+    new_matrix4(
+        2.0 / (right - left), 0.0, 0.0, -(right + left) / (right - left),
+        0.0, 2.0 / (top - bottom), 0.0, -(top + bottom) / (top - bottom),
+        0.0, 0.0, -2.0 / (far - near), -(far + near) / (far - near),
+        0.0, 0.0, 0.0, 1.0,
+    )
+}
+
 /// Creates a viewport matrix to transform NDC coordinates to screen space
 /// x, y: Viewport position (typically 0, 0)
 /// width, height: Viewport dimensions in pixels
@@ -173,4 +279,35 @@ pub fn create_viewport_matrix(x: f32, y: f32, width: f32, height: f32) -> Matrix
         0.0, 0.0, 1.0, 0.0,
         0.0, 0.0, 0.0, 1.0,
     )
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simd_and_batch_match_scalar() {
+        let mut rng = fastrand::Rng::with_seed(399);
+        let mut random = || rng.f32() * 200.0 - 100.0;
+        let mut vectors = Vec::with_capacity(1000);
+        let mut matrices = Vec::with_capacity(1000);
+        for _ in 0..1000 {
+            vectors.push(Vector4::new(random(), random(), random(), random()));
+            matrices.push(create_model_matrix(Vector3::new(random(), random(), random()), random().abs() * 0.1 + 0.01, Vector3::new(random(), random(), random())));
+        }
+        let close = |a: Vector4, b: Vector4| {
+            let tolerance = f32::EPSILON * 4.0 * (1.0 + a.x.abs().max(a.y.abs()).max(a.z.abs()).max(a.w.abs()));
+            (a.x - b.x).abs() <= tolerance && (a.y - b.y).abs() <= tolerance && (a.z - b.z).abs() <= tolerance && (a.w - b.w).abs() <= tolerance
+        };
+        for (matrix, vector) in matrices.iter().zip(&vectors) {
+            let (scalar, simd) = (multiply_matrix_vector4(matrix, vector), multiply_matrix_vector4_simd(matrix, vector));
+            assert!(close(scalar, simd), "{:?} vs {:?}", scalar, simd);
+        }
+        // Batch with a length that is not a multiple of 4, to cover the tail
+        let mut output = vec![Vector4::new(0.0, 0.0, 0.0, 0.0); 999];
+        multiply_matrix_vector4_batch(&matrices[0], &vectors[..999], &mut output);
+        for (vector, result) in vectors.iter().zip(&output) {
+            assert!(close(multiply_matrix_vector4(&matrices[0], vector), *result));
+        }
+    }
+}
@@ -0,0 +1,602 @@
+// mesh.rs
+// Generación procedural de mallas (lista de triángulos, igual que `Obj::get_vertex_array`)
+
+use crate::shaders::{fbm_3d, voronoi_3d};
+use crate::vertex::Vertex;
+use raylib::math::{Vector2, Vector3};
+use std::fs;
+use std::io;
+
+/// Anillo plano entre `inner` y `outer` (en radios del planeta), inclinado `tilt` radianes
+/// alrededor del eje Z. Las UV van en `u` = ángulo y `v` = posición radial.
+pub fn generate_ring_mesh(inner: f32, outer: f32, segments: u32, tilt: f32) -> Vec<Vertex> {
+    let (sin_t, cos_t) = tilt.sin_cos();
+    // Inclinar un punto del plano XZ alrededor del eje Z
+    let tilted = |x: f32, y: f32, z: f32| Vector3::new(x * cos_t - y * sin_t, x * sin_t + y * cos_t, z);
+    let normal = tilted(0.0, 1.0, 0.0);
+
+    let mut vertices = Vec::with_capacity(segments as usize * 6);
+    for i in 0..segments {
+        let a0 = i as f32 / segments as f32 * std::f32::consts::TAU;
+        let a1 = (i + 1) as f32 / segments as f32 * std::f32::consts::TAU;
+        let u0 = i as f32 / segments as f32;
+        let u1 = (i + 1) as f32 / segments as f32;
+
+        let inner0 = Vertex::new(tilted(a0.cos() * inner, 0.0, a0.sin() * inner), normal, Vector2::new(u0, 0.0));
+        let outer0 = Vertex::new(tilted(a0.cos() * outer, 0.0, a0.sin() * outer), normal, Vector2::new(u0, 1.0));
+        let inner1 = Vertex::new(tilted(a1.cos() * inner, 0.0, a1.sin() * inner), normal, Vector2::new(u1, 0.0));
+        let outer1 = Vertex::new(tilted(a1.cos() * outer, 0.0, a1.sin() * outer), normal, Vector2::new(u1, 1.0));
+
+        vertices.extend([inner0.clone(), outer0, outer1.clone()]);
+        vertices.extend([inner0, outer1, inner1]);
+    }
+    vertices
+}
+
+/// Banda de un sistema de anillos: `(inner_km, outer_km, opacity, color)`.
+pub type RingBand = (f32, f32, f32, Vector3);
+
+/// Sistema de anillos a partir de una tabla de bandas en km. Cada banda se genera con
+/// `generate_ring_mesh` en radios del planeta (`planet_radius_km`) y guarda `color` en el color
+/// de vértice, que es el albedo que lee el shader; la opacidad la pone el alfa de los anillos
+/// (ver `uranus_ring_alpha`). Las bandas más estrechas que
+/// `min_width` (en radios del planeta) se ensanchan alrededor de su centro para que rastericen.
+pub fn generate_ring_system(bands: &[RingBand], planet_radius_km: f32, min_width: f32, segments: u32, tilt: f32) -> Vec<Vertex> {
+    let mut vertices = Vec::with_capacity(bands.len() * segments as usize * 6);
+    for &(inner_km, outer_km, _, color) in bands {
+        let center = (inner_km + outer_km) * 0.5 / planet_radius_km;
+        let half_width = ((outer_km - inner_km) / planet_radius_km).max(min_width) * 0.5;
+        vertices.extend(generate_ring_mesh(center - half_width, center + half_width, segments, tilt).into_iter().map(|mut vertex| {
+            vertex.color = color;
+            vertex
+        }));
+    }
+    vertices
+}
+
+// Punto de la cara del cubo [-1, 1]³ llevado a la esfera unidad. En vez de normalizar sin más
+// se usa la proyección "spherified cube", que reparte el área casi por igual en toda la cara.
+fn cube_to_sphere(p: Vector3) -> Vector3 {
+    let (x2, y2, z2) = (p.x * p.x, p.y * p.y, p.z * p.z);
+    Vector3::new(
+        p.x * (1.0 - y2 / 2.0 - z2 / 2.0 + y2 * z2 / 3.0).sqrt(),
+        p.y * (1.0 - z2 / 2.0 - x2 / 2.0 + z2 * x2 / 3.0).sqrt(),
+        p.z * (1.0 - x2 / 2.0 - y2 / 2.0 + x2 * y2 / 3.0).sqrt(),
+    )
+}
+
+/// Esfera unidad a partir de un cubo: cada una de las 6 caras se divide en
+/// `subdivisions`x`subdivisions` cuadrados (dos triángulos cada uno). A diferencia de la esfera UV
+/// no concentra triángulos en los polos. Las UV colocan las 6 caras una junto a otra en `u`.
+pub fn generate_cube_sphere(subdivisions: u32) -> Vec<Vertex> {
+    let n = subdivisions.max(1);
+    // (normal de la cara, eje u, eje v)
+    let faces = [
+        (Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -1.0), Vector3::new(0.0, 1.0, 0.0)),
+        (Vector3::new(-1.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0), Vector3::new(0.0, 1.0, 0.0)),
+        (Vector3::new(0.0, 1.0, 0.0), Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -1.0)),
+        (Vector3::new(0.0, -1.0, 0.0), Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0)),
+        (Vector3::new(0.0, 0.0, 1.0), Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0)),
+        (Vector3::new(0.0, 0.0, -1.0), Vector3::new(-1.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0)),
+    ];
+
+    let mut vertices = Vec::with_capacity((6 * n * n * 6) as usize);
+    for (face_index, (normal, axis_u, axis_v)) in faces.iter().enumerate() {
+        let corner = |i: u32, j: u32| {
+            let (fu, fv) = (i as f32 / n as f32, j as f32 / n as f32);
+            let on_cube = *normal + *axis_u * (fu * 2.0 - 1.0) + *axis_v * (fv * 2.0 - 1.0);
+            let position = cube_to_sphere(on_cube);
+            let uv = Vector2::new((face_index as f32 + fu) / 6.0, fv);
+            Vertex::new(position, position, uv)
+        };
+        for j in 0..n {
+            for i in 0..n {
+                let (a, b, c, d) = (corner(i, j), corner(i + 1, j), corner(i + 1, j + 1), corner(i, j + 1));
+                vertices.extend([a.clone(), b, c.clone()]);
+                vertices.extend([a, c, d]);
+            }
+        }
+    }
+    vertices
+}
+
+/// Roca irregular: cube-sphere con `dents` abolladuras al azar (semilla `seed`) que hunden el
+/// radio hasta `roughness`. El desplazamiento solo depende de la posición, así que los vértices
+/// compartidos coinciden y la malla queda cerrada; las normales son las de cada cara (facetada).
+pub fn generate_asteroid_mesh(subdivisions: u32, dents: u32, roughness: f32, seed: u64) -> Vec<Vertex> {
+    let mut rng = fastrand::Rng::with_seed(seed);
+    let dent_directions: Vec<(Vector3, f32)> = (0..dents)
+        .map(|_| {
+            let dir = Vector3::new(rng.f32() * 2.0 - 1.0, rng.f32() * 2.0 - 1.0, rng.f32() * 2.0 - 1.0);
+            (dir.normalized(), 0.4 + rng.f32() * 0.6)
+        })
+        .collect();
+    let displace = |p: Vector3| {
+        let dent: f32 = dent_directions.iter().map(|(dir, depth)| p.dot(*dir).max(0.0).powi(6) * depth).sum();
+        p * (1.0 - roughness * dent.min(1.0))
+    };
+
+    let mut vertices = generate_cube_sphere(subdivisions);
+    displace_with_flat_normals(&mut vertices, displace);
+    vertices
+}
+
+// Mueve cada vértice con `displace` y pone a cada triángulo la normal de su cara
+fn displace_with_flat_normals(vertices: &mut [Vertex], displace: impl Fn(Vector3) -> Vector3) {
+    for triangle in vertices.chunks_mut(3) {
+        for vertex in triangle.iter_mut() {
+            vertex.position = displace(vertex.position);
+        }
+        let face_normal = (triangle[1].position - triangle[0].position).cross(triangle[2].position - triangle[0].position).normalized();
+        // Hacia fuera, sea cual sea el orden de los vértices
+        let normal = if face_normal.dot(triangle[0].position) < 0.0 { face_normal * -1.0 } else { face_normal };
+        for vertex in triangle.iter_mut() {
+            vertex.normal = normal;
+            vertex.transformed_position = vertex.position;
+            vertex.transformed_normal = normal;
+        }
+    }
+}
+
+/// Esfera unidad a partir de un icosaedro: cada triángulo se parte en 4 `subdivisions` veces y
+/// los vértices nuevos se llevan a la esfera. Triángulos casi iguales en toda la superficie.
+/// Las UV son las esféricas (longitud, latitud).
+pub fn generate_icosphere(subdivisions: u32) -> Vec<Vertex> {
+    let t = (1.0 + 5.0_f32.sqrt()) / 2.0;
+    let corners = [
+        Vector3::new(-1.0, t, 0.0), Vector3::new(1.0, t, 0.0), Vector3::new(-1.0, -t, 0.0), Vector3::new(1.0, -t, 0.0),
+        Vector3::new(0.0, -1.0, t), Vector3::new(0.0, 1.0, t), Vector3::new(0.0, -1.0, -t), Vector3::new(0.0, 1.0, -t),
+        Vector3::new(t, 0.0, -1.0), Vector3::new(t, 0.0, 1.0), Vector3::new(-t, 0.0, -1.0), Vector3::new(-t, 0.0, 1.0),
+    ];
+    let faces: [(usize, usize, usize); 20] = [
+        (0, 11, 5), (0, 5, 1), (0, 1, 7), (0, 7, 10), (0, 10, 11),
+        (1, 5, 9), (5, 11, 4), (11, 10, 2), (10, 7, 6), (7, 1, 8),
+        (3, 9, 4), (3, 4, 2), (3, 2, 6), (3, 6, 8), (3, 8, 9),
+        (4, 9, 5), (2, 4, 11), (6, 2, 10), (8, 6, 7), (9, 8, 1),
+    ];
+    let mut triangles: Vec<[Vector3; 3]> = faces.iter().map(|&(a, b, c)| [corners[a].normalized(), corners[b].normalized(), corners[c].normalized()]).collect();
+    for _ in 0..subdivisions {
+        triangles = triangles
+            .iter()
+            .flat_map(|&[a, b, c]| {
+                let (ab, bc, ca) = (((a + b) * 0.5).normalized(), ((b + c) * 0.5).normalized(), ((c + a) * 0.5).normalized());
+                [[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    let uv = |p: Vector3| {
+        Vector2::new(0.5 + p.z.atan2(p.x) / std::f32::consts::TAU, 0.5 - p.y.clamp(-1.0, 1.0).asin() / std::f32::consts::PI)
+    };
+    triangles.iter().flat_map(|triangle| triangle.map(|p| Vertex::new(p, p, uv(p)))).collect()
+}
+
+// ☄️ Asteroides de cerca: subdivisiones de la icosfera y amplitud (en radios) de cada escala
+const ASTEROID_DETAIL_SUBDIVISIONS: u32 = 4;
+const ASTEROID_SHAPE_STRENGTH: f32 = 0.45;
+const ASTEROID_CRATER_DEPTH: f32 = 0.12;
+const ASTEROID_ROUGHNESS: f32 = 0.04;
+
+/// Radio de la superficie del asteroide de semilla `seed` (radio medio `radius`) en la dirección
+/// `dir` de su espacio local: la misma cuenta con la que se desplazan los vértices de
+/// `generate_asteroid_detail_mesh`, así que sirve también para las colisiones. Tres escalas:
+/// ruido de onda larga para la forma, Voronoi de escala media para los cráteres y ruido fino
+/// para la rugosidad.
+pub fn asteroid_surface_radius(seed: u64, radius: f32, dir: Vector3) -> f32 {
+    let p = dir.normalized();
+    // Cada semilla lee una zona distinta del ruido
+    let offset = Vector3::new((seed as f32 * 12.9898) % 97.0, (seed as f32 * 78.233) % 89.0, (seed as f32 * 37.719) % 83.0);
+    let q = p + offset;
+    let shape = (fbm_3d(q.x * 1.2, q.y * 1.2, q.z * 1.2, 3, 2.0, 0.5) - 0.5) * ASTEROID_SHAPE_STRENGTH;
+    let (f1, _) = voronoi_3d(q.x * 4.0, q.y * 4.0, q.z * 4.0);
+    let crater = (1.0 - f1 * 2.5).max(0.0).powi(2) * ASTEROID_CRATER_DEPTH;
+    let roughness = (fbm_3d(q.x * 12.0, q.y * 12.0, q.z * 12.0, 3, 2.0, 0.5) - 0.5) * ASTEROID_ROUGHNESS;
+    radius * (1.0 + shape - crater + roughness)
+}
+
+/// Malla detallada (para verla de cerca) del asteroide de semilla `seed` y radio medio `radius`:
+/// icosfera desplazada con `asteroid_surface_radius` y normales de cara.
+pub fn generate_asteroid_detail_mesh(seed: u64, radius: f32) -> Vec<Vertex> {
+    let mut vertices = generate_icosphere(ASTEROID_DETAIL_SUBDIVISIONS);
+    displace_with_flat_normals(&mut vertices, |p| p * asteroid_surface_radius(seed, radius, p));
+    vertices
+}
+
+// 🔍 Subdivisión adaptativa: una arista se parte si desde la cámara abarca más de este ángulo (rad)
+const ADAPTIVE_MAX_EDGE_ANGLE: f32 = 0.04;
+
+// Punto medio de la arista llevado a la esfera centrada en `center` (radio, el medio de los
+// extremos); normal, UV y color interpolados. Solo depende de la arista, así los dos triángulos
+// que la comparten obtienen el mismo vértice y no se abren grietas.
+fn sphere_midpoint(a: &Vertex, b: &Vertex, center: Vector3) -> Vertex {
+    let radius = ((a.position - center).length() + (b.position - center).length()) * 0.5;
+    let position = center + ((a.position + b.position) * 0.5 - center).normalized() * radius;
+    let mut vertex = Vertex::new(position, (a.normal + b.normal).normalized(), (a.tex_coords + b.tex_coords) * 0.5);
+    vertex.color = (a.color + b.color) * 0.5;
+    vertex
+}
+
+fn subdivide_triangle(triangle: [Vertex; 3], camera_pos: Vector3, center: Vector3, depth: u32, max_subdivision: u32, out: &mut Vec<Vertex>) {
+    let [a, b, c] = &triangle;
+    // Normal de la cara hacia fuera de la esfera, sea cual sea el orden de los vértices
+    let face_normal = (b.position - a.position).cross(c.position - a.position);
+    let outward = if face_normal.dot(a.position - center) < 0.0 { face_normal * -1.0 } else { face_normal };
+    let facing = outward.dot(camera_pos - a.position) > 0.0;
+    // Cada arista decide por sí misma (longitud angular vista desde la cámara)
+    let split = |p: &Vertex, q: &Vertex| {
+        let distance = ((p.position + q.position) * 0.5 - camera_pos).length().max(1e-6);
+        (q.position - p.position).length() / distance > ADAPTIVE_MAX_EDGE_ANGLE
+    };
+    let edges = [split(a, b), split(b, c), split(c, a)];
+    if depth >= max_subdivision || !facing || !edges.contains(&true) {
+        out.extend(triangle);
+        return;
+    }
+    // Se rota el triángulo para que las aristas partidas vayan primero (ab, luego bc)
+    let rotation = match edges {
+        [true, true, true] | [true, true, false] | [true, false, false] => 0,
+        [false, true, true] | [false, true, false] => 1,
+        _ => 2,
+    };
+    let [a, b, c] = [&triangle[rotation], &triangle[(rotation + 1) % 3], &triangle[(rotation + 2) % 3]];
+    let children: Vec<[Vertex; 3]> = match edges.iter().filter(|&&e| e).count() {
+        1 => {
+            let ab = sphere_midpoint(a, b, center);
+            vec![[a.clone(), ab.clone(), c.clone()], [ab, b.clone(), c.clone()]]
+        }
+        2 => {
+            let (ab, bc) = (sphere_midpoint(a, b, center), sphere_midpoint(b, c, center));
+            vec![[ab.clone(), b.clone(), bc.clone()], [a.clone(), ab, bc.clone()], [a.clone(), bc, c.clone()]]
+        }
+        _ => {
+            let (ab, bc, ca) = (sphere_midpoint(a, b, center), sphere_midpoint(b, c, center), sphere_midpoint(c, a, center));
+            vec![
+                [a.clone(), ab.clone(), ca.clone()],
+                [ab.clone(), b.clone(), bc.clone()],
+                [ca.clone(), bc.clone(), c.clone()],
+                [ab, bc, ca],
+            ]
+        }
+    };
+    for child in children {
+        subdivide_triangle(child, camera_pos, center, depth + 1, max_subdivision, out);
+    }
+}
+
+/// Parte los triángulos de la esfera `vertices` (centrada en `planet_pos`) que se ven grandes
+/// desde `camera_pos`, todo en el mismo espacio: una arista se parte si su longitud angular supera
+/// `ADAPTIVE_MAX_EDGE_ANGLE`, y solo en triángulos de cara a la cámara. Los vértices nuevos van
+/// sobre la esfera, así la silueta de cerca deja de verse facetada. Como mucho `max_subdivision`
+/// niveles.
+pub fn adaptive_subdivide(vertices: &[Vertex], camera_pos: Vector3, planet_pos: Vector3, max_subdivision: u32) -> Vec<Vertex> {
+    let mut out = Vec::with_capacity(vertices.len());
+    for triangle in vertices.chunks_exact(3) {
+        subdivide_triangle([triangle[0].clone(), triangle[1].clone(), triangle[2].clone()], camera_pos, planet_pos, 0, max_subdivision, &mut out);
+    }
+    out
+}
+
+// Intersección rayo-triángulo (Möller–Trumbore). Devuelve la distancia `t` si hay impacto.
+fn ray_triangle_intersect(origin: Vector3, dir: Vector3, v0: Vector3, v1: Vector3, v2: Vector3) -> Option<f32> {
+    let edge1 = v1 - v0;
+    let edge2 = v2 - v0;
+    let p = dir.cross(edge2);
+    let det = edge1.dot(p);
+    if det.abs() < 1e-8 {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+    let s = origin - v0;
+    let u = s.dot(p) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = s.cross(edge1);
+    let v = dir.dot(q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = edge2.dot(q) * inv_det;
+    if t > 0.0 { Some(t) } else { None }
+}
+
+// Dirección aleatoria uniforme en el hemisferio alrededor de `normal`
+fn random_hemisphere_direction(rng: &mut fastrand::Rng, normal: Vector3) -> Vector3 {
+    let z = rng.f32() * 2.0 - 1.0;
+    let angle = rng.f32() * std::f32::consts::TAU;
+    let r = (1.0 - z * z).sqrt();
+    let dir = Vector3::new(r * angle.cos(), r * angle.sin(), z);
+    if dir.dot(normal) < 0.0 { -dir } else { dir }
+}
+
+/// Oclusión ambiental precalculada: por cada vértice lanza `rays` rayos en el hemisferio de su
+/// normal contra todos los triángulos de la malla (fuerza bruta) y guarda en `vertex.color` la
+/// fracción de rayos que no chocan antes de `max_distance` (en escala de grises).
+pub fn bake_ao_to_vertex_colors(vertices: &mut [Vertex], rays: u32, max_distance: f32) {
+    let positions: Vec<Vector3> = vertices.iter().map(|v| v.position).collect();
+    for (i, vertex) in vertices.iter_mut().enumerate() {
+        let normal = vertex.normal.normalized();
+        // Semilla por vértice: el resultado es estable entre ejecuciones
+        let mut rng = fastrand::Rng::with_seed(i as u64);
+        // Desplazar el origen para no chocar con la propia superficie
+        let origin = vertex.position + normal * 1e-3;
+        let mut unoccluded = 0;
+        for _ in 0..rays {
+            let dir = random_hemisphere_direction(&mut rng, normal);
+            let occluded = positions.chunks_exact(3).any(|tri| {
+                matches!(ray_triangle_intersect(origin, dir, tri[0], tri[1], tri[2]), Some(t) if t < max_distance)
+            });
+            if !occluded {
+                unoccluded += 1;
+            }
+        }
+        let ao = if rays > 0 { unoccluded as f32 / rays as f32 } else { 1.0 };
+        vertex.color = Vector3::new(ao, ao, ao);
+    }
+}
+
+/// Guarda los colores de vértice en un archivo `.col` (una línea "r g b" por vértice).
+pub fn save_vertex_colors(path: &str, vertices: &[Vertex]) -> io::Result<()> {
+    let mut contents = String::with_capacity(vertices.len() * 24);
+    for v in vertices {
+        contents.push_str(&format!("{} {} {}\n", v.color.x, v.color.y, v.color.z));
+    }
+    fs::write(path, contents)
+}
+
+/// Carga colores de vértice desde un `.col`. Falla si no coincide el número de vértices.
+pub fn load_vertex_colors(path: &str, vertices: &mut [Vertex]) -> io::Result<()> {
+    let contents = fs::read_to_string(path)?;
+    let colors: Vec<Vector3> = contents
+        .lines()
+        .map(|line| {
+            let mut channels = line.split_whitespace().map(|c| c.parse::<f32>().unwrap_or(1.0));
+            let r = channels.next().unwrap_or(1.0);
+            Vector3::new(r, channels.next().unwrap_or(r), channels.next().unwrap_or(r))
+        })
+        .collect();
+    if colors.len() != vertices.len() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "vertex count mismatch"));
+    }
+    for (v, c) in vertices.iter_mut().zip(colors) {
+        v.color = c;
+    }
+    Ok(())
+}
+
+/// Caja alineada a los ejes entre `min` y `max` (12 triángulos con normales planas). El color de
+/// vértice va en `color`: la nave lo usa como oclusión ambiental y las luces como su color.
+pub fn generate_box(min: Vector3, max: Vector3, color: Vector3) -> Vec<Vertex> {
+    let corner = |x: bool, y: bool, z: bool| {
+        Vector3::new(if x { max.x } else { min.x }, if y { max.y } else { min.y }, if z { max.z } else { min.z })
+    };
+    // (normal, cuatro esquinas en sentido antihorario vistas desde fuera)
+    let faces = [
+        (Vector3::new(1.0, 0.0, 0.0), [corner(true, false, false), corner(true, true, false), corner(true, true, true), corner(true, false, true)]),
+        (Vector3::new(-1.0, 0.0, 0.0), [corner(false, false, true), corner(false, true, true), corner(false, true, false), corner(false, false, false)]),
+        (Vector3::new(0.0, 1.0, 0.0), [corner(false, true, false), corner(false, true, true), corner(true, true, true), corner(true, true, false)]),
+        (Vector3::new(0.0, -1.0, 0.0), [corner(false, false, true), corner(false, false, false), corner(true, false, false), corner(true, false, true)]),
+        (Vector3::new(0.0, 0.0, 1.0), [corner(true, false, true), corner(true, true, true), corner(false, true, true), corner(false, false, true)]),
+        (Vector3::new(0.0, 0.0, -1.0), [corner(false, false, false), corner(false, true, false), corner(true, true, false), corner(true, false, false)]),
+    ];
+
+    let mut vertices = Vec::with_capacity(36);
+    for (normal, quad) in faces {
+        let vertex = |p: Vector3| {
+            let mut v = Vertex::new(p, normal, Vector2::new(0.0, 0.0));
+            v.color = color;
+            v
+        };
+        vertices.extend([vertex(quad[0]), vertex(quad[1]), vertex(quad[2])]);
+        vertices.extend([vertex(quad[0]), vertex(quad[2]), vertex(quad[3])]);
+    }
+    vertices
+}
+
+/// Cono con la base (radio `radius`) en y = 0 y la punta en y = `height`, con tapa inferior.
+pub fn generate_cone_mesh(radius: f32, height: f32, segments: u32) -> Vec<Vertex> {
+    let n = segments.max(3);
+    let apex = Vector3::new(0.0, height, 0.0);
+    let base_center = Vector3::new(0.0, 0.0, 0.0);
+    let down = Vector3::new(0.0, -1.0, 0.0);
+    // Normal del lateral: perpendicular a la generatriz
+    let slope = radius / height.max(0.0001);
+    let side_normal = |angle: f32| Vector3::new(angle.cos(), slope, angle.sin()).normalized();
+
+    let mut vertices = Vec::with_capacity(n as usize * 6);
+    for i in 0..n {
+        let a0 = i as f32 / n as f32 * std::f32::consts::TAU;
+        let a1 = (i + 1) as f32 / n as f32 * std::f32::consts::TAU;
+        let (u0, u1) = (i as f32 / n as f32, (i + 1) as f32 / n as f32);
+        let p0 = Vector3::new(a0.cos() * radius, 0.0, a0.sin() * radius);
+        let p1 = Vector3::new(a1.cos() * radius, 0.0, a1.sin() * radius);
+
+        let tip_normal = side_normal((a0 + a1) * 0.5);
+        vertices.extend([
+            Vertex::new(p0, side_normal(a0), Vector2::new(u0, 0.0)),
+            Vertex::new(apex, tip_normal, Vector2::new((u0 + u1) * 0.5, 1.0)),
+            Vertex::new(p1, side_normal(a1), Vector2::new(u1, 0.0)),
+        ]);
+        vertices.extend([
+            Vertex::new(base_center, down, Vector2::new(0.5, 0.5)),
+            Vertex::new(p0, down, Vector2::new(u0, 0.0)),
+            Vertex::new(p1, down, Vector2::new(u1, 0.0)),
+        ]);
+    }
+    vertices
+}
+
+/// Túnel abierto: cilindro de radio `radius` a lo largo de +Y, de 0 a `length`, en `rings`
+/// tramos de `segments` gajos, con las normales hacia dentro (se ve desde el interior). El
+/// ángulo del último gajo se toma módulo `segments`: la costura cierra con exactamente los
+/// mismos vértices que el primero.
+pub fn generate_warp_tunnel(radius: f32, length: f32, rings: u32, segments: u32) -> Vec<Vertex> {
+    let (n, rings) = (segments.max(3), rings.max(1));
+    let point = |ring: u32, segment: u32| {
+        let angle = (segment % n) as f32 / n as f32 * std::f32::consts::TAU;
+        let (sin_a, cos_a) = angle.sin_cos();
+        let y = ring as f32 / rings as f32 * length;
+        Vertex::new(Vector3::new(cos_a * radius, y, sin_a * radius), Vector3::new(-cos_a, 0.0, -sin_a), Vector2::new(segment as f32 / n as f32, ring as f32 / rings as f32))
+    };
+
+    let mut vertices = Vec::with_capacity((rings * n * 6) as usize);
+    for ring in 0..rings {
+        for segment in 0..n {
+            let (a, b, c, d) = (point(ring, segment), point(ring, segment + 1), point(ring + 1, segment + 1), point(ring + 1, segment));
+            vertices.extend([a.clone(), b, c.clone()]);
+            vertices.extend([a, c, d]);
+        }
+    }
+    vertices
+}
+
+/// Cilindro de y = 0 a y = `height` con ambas tapas.
+pub fn generate_cylinder_mesh(radius: f32, height: f32, segments: u32) -> Vec<Vertex> {
+    let n = segments.max(3);
+    let up = Vector3::new(0.0, 1.0, 0.0);
+    let down = Vector3::new(0.0, -1.0, 0.0);
+    let bottom_center = Vector3::new(0.0, 0.0, 0.0);
+    let top_center = Vector3::new(0.0, height, 0.0);
+
+    let mut vertices = Vec::with_capacity(n as usize * 12);
+    for i in 0..n {
+        let a0 = i as f32 / n as f32 * std::f32::consts::TAU;
+        let a1 = (i + 1) as f32 / n as f32 * std::f32::consts::TAU;
+        let (u0, u1) = (i as f32 / n as f32, (i + 1) as f32 / n as f32);
+        let (n0, n1) = (Vector3::new(a0.cos(), 0.0, a0.sin()), Vector3::new(a1.cos(), 0.0, a1.sin()));
+        let (b0, b1) = (n0 * radius, n1 * radius);
+        let (t0, t1) = (b0 + top_center, b1 + top_center);
+
+        vertices.extend([
+            Vertex::new(b0, n0, Vector2::new(u0, 0.0)),
+            Vertex::new(t0, n0, Vector2::new(u0, 1.0)),
+            Vertex::new(t1, n1, Vector2::new(u1, 1.0)),
+        ]);
+        vertices.extend([
+            Vertex::new(b0, n0, Vector2::new(u0, 0.0)),
+            Vertex::new(t1, n1, Vector2::new(u1, 1.0)),
+            Vertex::new(b1, n1, Vector2::new(u1, 0.0)),
+        ]);
+        vertices.extend([
+            Vertex::new(top_center, up, Vector2::new(0.5, 0.5)),
+            Vertex::new(t1, up, Vector2::new(u1, 1.0)),
+            Vertex::new(t0, up, Vector2::new(u0, 1.0)),
+        ]);
+        vertices.extend([
+            Vertex::new(bottom_center, down, Vector2::new(0.5, 0.5)),
+            Vertex::new(b0, down, Vector2::new(u0, 0.0)),
+            Vertex::new(b1, down, Vector2::new(u1, 0.0)),
+        ]);
+    }
+    vertices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quad(a: Vector3, b: Vector3, c: Vector3, d: Vector3, normal: Vector3) -> Vec<Vertex> {
+        [a, b, c, a, c, d].iter().map(|&p| Vertex::new(p, normal, Vector2::zero())).collect()
+    }
+
+    #[test]
+    fn concave_vertex_bakes_darker_than_convex_one() {
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        // Suelo de x = 0 a x = 2 y una pared justo detrás de x = 0: rincón en x = 0, borde libre en x = 2
+        let mut mesh = quad(Vector3::new(0.0, 0.0, -1.0), Vector3::new(2.0, 0.0, -1.0), Vector3::new(2.0, 0.0, 1.0), Vector3::new(0.0, 0.0, 1.0), up);
+        let wall_x = -0.05;
+        mesh.extend(quad(Vector3::new(wall_x, 0.0, -1.0), Vector3::new(wall_x, 0.0, 1.0), Vector3::new(wall_x, 2.0, 1.0), Vector3::new(wall_x, 2.0, -1.0), Vector3::new(1.0, 0.0, 0.0)));
+        bake_ao_to_vertex_colors(&mut mesh, 256, 1.0);
+
+        let ao_at = |x: f32| mesh.iter().find(|v| v.normal == up && v.position.x == x).unwrap().color.x;
+        let (corner, edge) = (ao_at(0.0), ao_at(2.0));
+        assert!(corner < edge, "rincón {} borde {}", corner, edge);
+        assert_eq!(edge, 1.0);
+    }
+
+    // Mayor área de triángulo entre la menor (lista de triángulos)
+    fn triangle_area_ratio(vertices: &[Vertex]) -> f32 {
+        let areas: Vec<f32> = vertices.chunks_exact(3).map(|t| (t[1].position - t[0].position).cross(t[2].position - t[0].position).length() * 0.5).filter(|&a| a > 0.0).collect();
+        areas.iter().cloned().fold(0.0, f32::max) / areas.iter().cloned().fold(f32::INFINITY, f32::min)
+    }
+
+    // Esfera UV de `stacks` x `slices` quads; en los polos cada quad se cierra en una astilla casi
+    // sin área
+    fn uv_sphere(stacks: u32, slices: u32) -> Vec<Vertex> {
+        let point = |i: u32, j: u32| {
+            let latitude = -std::f32::consts::FRAC_PI_2 + std::f32::consts::PI * i as f32 / stacks as f32;
+            let longitude = std::f32::consts::TAU * j as f32 / slices as f32;
+            let p = Vector3::new(latitude.cos() * longitude.cos(), latitude.sin(), latitude.cos() * longitude.sin());
+            Vertex::new(p, p, Vector2::new(j as f32 / slices as f32, i as f32 / stacks as f32))
+        };
+        let mut vertices = Vec::with_capacity((stacks * slices * 6) as usize);
+        for i in 0..stacks {
+            for j in 0..slices {
+                let (a, b, c, d) = (point(i, j), point(i + 1, j), point(i + 1, j + 1), point(i, j + 1));
+                vertices.extend([a.clone(), b, c.clone(), a, c, d]);
+            }
+        }
+        vertices
+    }
+
+    #[test]
+    fn cube_sphere_triangles_are_nearly_uniform() {
+        let cube_sphere = generate_cube_sphere(10);
+        assert_eq!(cube_sphere.len() / 3, 1200);
+        let ratio = triangle_area_ratio(&cube_sphere);
+        assert!(ratio < 3.0, "cube-sphere {}", ratio);
+        // Una esfera UV con los mismos triángulos los amontona en los polos
+        let uv = uv_sphere(20, 30);
+        assert_eq!(uv.len() / 3, 1200);
+        let uv_ratio = triangle_area_ratio(&uv);
+        assert!(uv_ratio > 100.0, "esfera UV {}", uv_ratio);
+    }
+
+    #[test]
+    fn asteroid_seeds_give_different_shapes() {
+        let average = |vertices: &[Vertex]| vertices.iter().fold(Vector3::zero(), |sum, v| sum + v.position) / vertices.len() as f32;
+        let (a, b) = (generate_asteroid_detail_mesh(1, 0.2), generate_asteroid_detail_mesh(2, 0.2));
+        assert_eq!(a.len(), b.len());
+        let (center_a, center_b) = (average(&a), average(&b));
+        assert!((center_a - center_b).length() > 1e-3, "centros {:?} y {:?}", center_a, center_b);
+        // La misma semilla da la misma malla (la caché puede reutilizarla)
+        let again = generate_asteroid_detail_mesh(1, 0.2);
+        assert!(a.iter().zip(&again).all(|(p, q)| p.position == q.position));
+    }
+
+    #[test]
+    fn adaptive_subdivide_splits_near_triangles_only() {
+        // Un triángulo de la esfera unidad alrededor del polo +Z
+        let on_sphere = |x: f32, y: f32| Vector3::new(x, y, 1.0).normalized();
+        let triangle: Vec<Vertex> = [on_sphere(-0.2, -0.1), on_sphere(0.2, -0.1), on_sphere(0.0, 0.2)]
+            .iter()
+            .map(|&p| Vertex::new(p, p, Vector2::zero()))
+            .collect();
+        let near = adaptive_subdivide(&triangle, Vector3::new(0.0, 0.0, 1.5), Vector3::zero(), 3);
+        // De cerca se parte todo hasta el tope: 4³ triángulos, con los vértices nuevos en la esfera
+        assert_eq!(near.len(), 3 * 64);
+        assert!(near.iter().all(|v| (v.position.length() - 1.0).abs() < 1e-5));
+        // Lejos (o de espaldas a la cámara) se queda igual
+        assert_eq!(adaptive_subdivide(&triangle, Vector3::new(0.0, 0.0, 100.0), Vector3::zero(), 3).len(), 3);
+        assert_eq!(adaptive_subdivide(&triangle, Vector3::new(0.0, 0.0, -1.5), Vector3::zero(), 3).len(), 3);
+    }
+
+    #[test]
+    fn warp_tunnel_seam_closes_on_every_ring() {
+        let (rings, segments) = (6, 12);
+        let tunnel = generate_warp_tunnel(30.0, 500.0, rings, segments);
+        assert_eq!(tunnel.len(), (rings * segments * 6) as usize);
+        for ring in 0..rings as usize {
+            // Cada gajo son dos triángulos (a, b, c) y (a, c, d): a y d en el borde de su ángulo,
+            // b y c en el del siguiente
+            let first = &tunnel[ring * segments as usize * 6..][..6];
+            let last = &tunnel[(ring * segments as usize + segments as usize - 1) * 6..][..6];
+            assert_eq!(last[1].position, first[0].position, "tramo {}: el último gajo no vuelve al primer vértice", ring);
+            assert_eq!(last[2].position, first[5].position);
+            assert_eq!(last[1].normal, first[0].normal);
+        }
+        // Los anillos extremos van de y = 0 a y = length
+        assert!(tunnel.iter().all(|v| (0.0..=500.0).contains(&v.position.y)));
+        assert!(tunnel.iter().any(|v| v.position.y == 500.0));
+    }
+}
@@ -0,0 +1,74 @@
+// model_node.rs
+// 🛸 Jerarquía de piezas de un modelo: cada nodo se transforma relativo a su padre, así una pieza
+// (p. ej. el tren de aterrizaje) se anima sola y sigue moviéndose con la nave.
+use crate::vertex::Vertex;
+use raylib::prelude::*;
+
+pub struct ModelNode {
+    pub name: String,
+    pub local_transform: Matrix,
+    pub mesh: Option<Vec<Vertex>>,
+    pub children: Vec<ModelNode>,
+}
+
+impl ModelNode {
+    pub fn new(name: &str, local_transform: Matrix, mesh: Option<Vec<Vertex>>) -> Self {
+        ModelNode { name: name.to_string(), local_transform, mesh, children: Vec::new() }
+    }
+
+    pub fn with_child(mut self, child: ModelNode) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Busca un nodo por nombre en todo el subárbol (para animar su transformación local)
+    pub fn find_mut(&mut self, name: &str) -> Option<&mut ModelNode> {
+        if self.name == name {
+            return Some(self);
+        }
+        self.children.iter_mut().find_map(|child| child.find_mut(name))
+    }
+
+    /// Nodos en profundidad (padre antes que hijos), el mismo orden que `build_model_hierarchy`
+    pub fn flatten(&self) -> Vec<&ModelNode> {
+        let mut nodes = vec![self];
+        for child in &self.children {
+            nodes.extend(child.flatten());
+        }
+        nodes
+    }
+}
+
+/// Matriz de mundo de cada nodo: la local del hijo seguida de la de mundo del padre (en raylib
+/// `a * b` aplica primero `a`). Se recorre en profundidad, igual que `ModelNode::flatten`.
+pub fn build_model_hierarchy(root: &ModelNode) -> Vec<(String, Matrix)> {
+    let mut world_matrices = Vec::new();
+    collect_world_matrices(root, Matrix::identity(), &mut world_matrices);
+    world_matrices
+}
+
+fn collect_world_matrices(node: &ModelNode, parent_world: Matrix, out: &mut Vec<(String, Matrix)>) {
+    let world = node.local_transform * parent_world;
+    out.push((node.name.clone(), world));
+    for child in &node.children {
+        collect_world_matrices(child, world, out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::matrix::{create_model_matrix, multiply_matrix_vector4};
+
+    #[test]
+    fn child_follows_its_parent_translation() {
+        let parent_position = Vector3::new(5.0, -2.0, 3.0);
+        let root = ModelNode::new("nave", create_model_matrix(parent_position, 1.0, Vector3::zero()), None)
+            .with_child(ModelNode::new("tren", create_model_matrix(Vector3::new(1.0, 0.0, 0.0), 1.0, Vector3::zero()), None));
+        let world = build_model_hierarchy(&root);
+        assert_eq!(world.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(), vec!["nave", "tren"]);
+        let origin = multiply_matrix_vector4(&world[1].1, &Vector4::new(0.0, 0.0, 0.0, 1.0));
+        assert!((Vector3::new(origin.x, origin.y, origin.z) - Vector3::new(6.0, -2.0, 3.0)).length() < 1e-5);
+    }
+}
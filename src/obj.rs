@@ -1,61 +1,279 @@
 // obj.rs
 use crate::vertex::Vertex;
 use raylib::math::{Vector2, Vector3};
+use std::collections::HashSet;
+use std::f32::consts::PI;
+use std::fmt;
 use tobj;
 
+#[derive(Debug)]
+pub enum ObjError {
+    // El archivo no existe o no se pudo interpretar como OBJ
+    Load(tobj::LoadError),
+    // Cargó bien pero no tiene ninguna cara
+    Empty,
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Load(e) => write!(f, "{}", e),
+            ObjError::Empty => write!(f, "the model has no faces"),
+        }
+    }
+}
+
+impl From<tobj::LoadError> for ObjError {
+    fn from(e: tobj::LoadError) -> Self {
+        ObjError::Load(e)
+    }
+}
+
+// Índices de una esquina de cara. En OBJ cada `v/vt/vn` puede apuntar a una entrada distinta,
+// así que se guardan por separado en lugar de un único índice por vértice.
+#[derive(Clone, Copy, Debug)]
+pub struct FaceIndex {
+    pub position: u32,
+    pub tex_coord: Option<u32>,
+    pub normal: Option<u32>,
+}
+
 pub struct Obj {
-    pub vertices: Vec<Vertex>,
-    pub indices: Vec<u32>,
+    pub positions: Vec<Vector3>,
+    pub normals: Vec<Vector3>,
+    pub tex_coords: Vec<Vector2>,
+    // Tres esquinas por triángulo (las caras se triangulan al cargar)
+    pub faces: Vec<FaceIndex>,
+}
+
+// UV esféricas para mallas sin `vt`: longitud en `u`, latitud en `v`
+fn spherical_uv(position: Vector3) -> Vector2 {
+    let length = (position.x * position.x + position.y * position.y + position.z * position.z).sqrt();
+    if length == 0.0 {
+        return Vector2::new(0.5, 0.5);
+    }
+    let (x, y, z) = (position.x / length, position.y / length, position.z / length);
+    Vector2::new(z.atan2(x) / (2.0 * PI) + 0.5, y.clamp(-1.0, 1.0).asin() / PI + 0.5)
 }
 
 impl Obj {
-    pub fn load(path: &str) -> Result<Self, tobj::LoadError> {
-        let (models, _materials) = tobj::load_obj(path, &tobj::GPU_LOAD_OPTIONS)?;
+    pub fn load(path: &str) -> Result<Self, ObjError> {
+        // Sin `single_index`: tobj devuelve los índices de posición, UV y normal por separado
+        let options = tobj::LoadOptions {
+            single_index: false,
+            triangulate: true,
+            ignore_points: true,
+            ignore_lines: true,
+        };
+        let (models, _materials) = tobj::load_obj(path, &options)?;
 
-        let mut vertices = Vec::new();
-        let mut indices = Vec::new();
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut tex_coords = Vec::new();
+        let mut faces = Vec::new();
 
         for model in models {
             let mesh = &model.mesh;
-            let num_vertices = mesh.positions.len() / 3;
-
-            for i in 0..num_vertices {
-                let x = mesh.positions[i * 3];
-                let y = mesh.positions[i * 3 + 1];
-                let z = mesh.positions[i * 3 + 2];
-                let position = Vector3::new(x, y, z);
-
-                let normal = if !mesh.normals.is_empty() {
-                    let nx = mesh.normals[i * 3];
-                    let ny = mesh.normals[i * 3 + 1];
-                    let nz = mesh.normals[i * 3 + 2];
-                    Vector3::new(nx, ny, nz)
-                } else {
-                    Vector3::zero()
-                };
-
-                let tex_coords = if !mesh.texcoords.is_empty() {
-                    let u = mesh.texcoords[i * 2];
-                    let v = mesh.texcoords[i * 2 + 1];
-                    Vector2::new(u, v)
-                } else {
-                    Vector2::zero()
-                };
-
-                vertices.push(Vertex::new(position, normal, tex_coords));
+            // Cada modelo numera desde 0: desplazar al concatenar
+            let position_offset = positions.len() as u32;
+            let normal_offset = normals.len() as u32;
+            let tex_coord_offset = tex_coords.len() as u32;
+
+            positions.extend(mesh.positions.chunks_exact(3).map(|p| Vector3::new(p[0], p[1], p[2])));
+            normals.extend(mesh.normals.chunks_exact(3).map(|n| Vector3::new(n[0], n[1], n[2])));
+            tex_coords.extend(mesh.texcoords.chunks_exact(2).map(|t| Vector2::new(t[0], t[1])));
+
+            for (corner, &position) in mesh.indices.iter().enumerate() {
+                faces.push(FaceIndex {
+                    position: position + position_offset,
+                    tex_coord: mesh.texcoord_indices.get(corner).map(|&t| t + tex_coord_offset),
+                    normal: mesh.normal_indices.get(corner).map(|&n| n + normal_offset),
+                });
             }
-            // tobj::Mesh.indices is Vec<u32>, extend directly
-            indices.extend_from_slice(&mesh.indices);
         }
 
-        Ok(Obj { vertices, indices })
+        if faces.is_empty() {
+            return Err(ObjError::Empty);
+        }
+        Ok(Obj { positions, normals, tex_coords, faces })
+    }
+
+    /// Caja y esfera envolventes: `(mínimo, máximo, centro, radio)`. El centro es el de la caja
+    /// y el radio la mayor distancia desde él a un vértice.
+    pub fn compute_bounds(&self) -> (Vector3, Vector3, Vector3, f32) {
+        if self.positions.is_empty() {
+            return (Vector3::zero(), Vector3::zero(), Vector3::zero(), 0.0);
+        }
+        let mut min = Vector3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY);
+        let mut max = Vector3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for p in &self.positions {
+            min = Vector3::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z));
+            max = Vector3::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z));
+        }
+        let center = (min + max) * 0.5;
+        let radius = self.positions.iter().map(|p| (*p - center).length()).fold(0.0, f32::max);
+        (min, max, center, radius)
     }
 
+    /// Centra la malla en el origen y la escala para que quepa justo en la esfera unidad,
+    /// sin importar la escala ni el origen con que se exportó.
+    pub fn normalize_to_unit_sphere(&mut self) {
+        let (_, _, center, radius) = self.compute_bounds();
+        if radius <= 0.0 {
+            return;
+        }
+        for p in self.positions.iter_mut() {
+            *p = (*p - center) / radius;
+        }
+    }
+
+    /// Un vértice por esquina de triángulo, con el orden de las caras corregido (ver
+    /// `ensure_consistent_winding`) y sin triángulos repetidos.
     pub fn get_vertex_array(&self) -> Vec<Vertex> {
-        let mut vertex_array = Vec::new();
-        for &index in &self.indices {
-            vertex_array.push(self.vertices[index as usize].clone());
+        let mut vertex_array = Vec::with_capacity(self.faces.len());
+        for face in &self.faces {
+            let position = self.positions[face.position as usize];
+            let normal = face
+                .normal
+                .and_then(|n| self.normals.get(n as usize).copied())
+                .unwrap_or_else(Vector3::zero);
+            let tex_coords = face
+                .tex_coord
+                .and_then(|t| self.tex_coords.get(t as usize).copied())
+                .unwrap_or_else(|| spherical_uv(position));
+            vertex_array.push(Vertex::new(position, normal, tex_coords));
         }
+        remove_duplicate_triangles(&mut vertex_array);
+        ensure_consistent_winding(&mut vertex_array);
         vertex_array
     }
 }
+
+/// OBJ no garantiza un orden de vértices por cara: deja todos los triángulos en sentido
+/// antihorario visto desde fuera, es decir, con `(v1 - v0) × (v2 - v0)` apuntando en sentido
+/// contrario al centro de la malla (la media de los vértices). Si no, intercambia `v1` y `v2`.
+/// Los triángulos degenerados o cuyo centro coincide con el de la malla se dejan como están.
+fn ensure_consistent_winding(vertices: &mut [Vertex]) {
+    if vertices.is_empty() {
+        return;
+    }
+    let center = vertices.iter().fold(Vector3::zero(), |sum, v| sum + v.position) / vertices.len() as f32;
+    for triangle in vertices.chunks_exact_mut(3) {
+        let (p0, p1, p2) = (triangle[0].position, triangle[1].position, triangle[2].position);
+        let face_normal = (p1 - p0).cross(p2 - p0);
+        let outward = (p0 + p1 + p2) / 3.0 - center;
+        if face_normal.dot(outward) < 0.0 {
+            triangle.swap(1, 2);
+        }
+    }
+}
+
+/// Quita los triángulos repetidos (las mismas tres posiciones en cualquier orden) que dejan
+/// algunos exportadores; se queda con el primero.
+fn remove_duplicate_triangles(vertices: &mut Vec<Vertex>) {
+    let mut seen = HashSet::new();
+    let mut kept = Vec::with_capacity(vertices.len());
+    for triangle in vertices.chunks_exact(3) {
+        let mut key: Vec<[u32; 3]> = triangle.iter().map(|v| [v.position.x.to_bits(), v.position.y.to_bits(), v.position.z.to_bits()]).collect();
+        key.sort_unstable();
+        if seen.insert(key) {
+            kept.extend_from_slice(triangle);
+        }
+    }
+    *vertices = kept;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cubo de lado 2 con UV explícitas: cada cara usa el cuadrado [0.25, 0.75]² de la textura
+    const CUBE_OBJ: &str = "\
+v -1 -1 -1
+v 1 -1 -1
+v 1 1 -1
+v -1 1 -1
+v -1 -1 1
+v 1 -1 1
+v 1 1 1
+v -1 1 1
+vt 0.25 0.25
+vt 0.75 0.25
+vt 0.75 0.75
+vt 0.25 0.75
+f 5/1 6/2 7/3 8/4
+f 2/1 1/2 4/3 3/4
+f 6/1 2/2 3/3 7/4
+f 1/1 5/2 8/3 4/4
+f 8/1 7/2 3/3 4/4
+f 1/1 2/2 6/3 5/4
+";
+
+    // Escribe `contents` en un .obj temporal y lo carga
+    fn load_obj_text(name: &str, contents: &str) -> Result<Obj, ObjError> {
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, contents).unwrap();
+        let obj = Obj::load(&path.to_string_lossy());
+        std::fs::remove_file(&path).unwrap();
+        obj
+    }
+
+    #[test]
+    fn cube_with_explicit_uvs_keeps_them() {
+        let obj = load_obj_text("proyecto3_uv_cube.obj", CUBE_OBJ).unwrap();
+        let vertices = obj.get_vertex_array();
+        assert_eq!(vertices.len(), 36);
+        for vertex in &vertices {
+            let uv = vertex.tex_coords;
+            assert!((0.0..=1.0).contains(&uv.x) && (0.0..=1.0).contains(&uv.y));
+            assert!(uv.x != 0.0 && uv.y != 0.0);
+        }
+    }
+
+    #[test]
+    fn normalized_mesh_fits_in_the_unit_sphere() {
+        let mut cube = load_obj_text("proyecto3_bounds_cube.obj", CUBE_OBJ).unwrap();
+        // Exportado lejos del origen y a otra escala
+        for p in cube.positions.iter_mut() {
+            *p = Vector3::new(p.x * 7.0 + 30.0, p.y * 3.0 - 12.0, p.z * 0.5 + 4.0);
+        }
+        let mut sphere = Obj::load("./assets/sphere.obj").unwrap();
+        for p in sphere.positions.iter_mut() {
+            *p = *p * 40.0 + Vector3::new(-5.0, 9.0, 2.0);
+        }
+        for obj in [&mut cube, &mut sphere] {
+            obj.normalize_to_unit_sphere();
+            let (_, _, center, radius) = obj.compute_bounds();
+            assert!(center.length() < 1e-4 && (radius - 1.0).abs() < 1e-4);
+            assert!(obj.positions.iter().all(|p| p.length() <= 1.0 + 1e-5));
+        }
+    }
+
+    #[test]
+    fn inside_out_faces_are_flipped_and_the_rest_left_alone() {
+        let outward = load_obj_text("proyecto3_winding_cube.obj", CUBE_OBJ).unwrap().get_vertex_array();
+        // Un triángulo de cada cara par del cubo, vuelto hacia dentro
+        let inverted = [0, 4, 8];
+        let mut vertices = outward.clone();
+        for &t in &inverted {
+            vertices.swap(t * 3 + 1, t * 3 + 2);
+        }
+        let before = vertices.clone();
+        ensure_consistent_winding(&mut vertices);
+
+        for (t, (fixed, original)) in vertices.chunks_exact(3).zip(before.chunks_exact(3)).enumerate() {
+            let touched = fixed.iter().zip(original).any(|(a, b)| a.position != b.position);
+            assert_eq!(touched, inverted.contains(&t), "triángulo {}", t);
+            let (p0, p1, p2) = (fixed[0].position, fixed[1].position, fixed[2].position);
+            assert!((p1 - p0).cross(p2 - p0).dot(p0 + p1 + p2) > 0.0, "triángulo {} hacia dentro", t);
+        }
+        let positions = |v: &[Vertex]| v.iter().map(|v| v.position).collect::<Vec<_>>();
+        assert_eq!(positions(&vertices), positions(&outward));
+
+        let mut doubled = [outward.clone(), outward[..6].to_vec()].concat();
+        doubled[37].position = outward[2].position;
+        doubled[38].position = outward[1].position;
+        remove_duplicate_triangles(&mut doubled);
+        assert_eq!(positions(&doubled), positions(&outward));
+    }
+}
@@ -0,0 +1,156 @@
+// orbit.rs
+// 🛰️ Elementos orbitales: posición en una órbita inclinada y precesión secular del nodo por J2
+use raylib::math::Vector3;
+
+// Achatamiento (J2) del Sol, el cuerpo central de todas las órbitas de la escena
+pub const SUN_J2: f32 = 2.2e-7;
+
+/// Velocidad de precesión del nodo ascendente (rad por segundo simulado) por el achatamiento del
+/// cuerpo central: dΩ/dt = -3/2 · n · J2 · (R/a)² / (1 - e²)² · cos(i). `mean_motion` (n) en
+/// rad/s; `inclination` en radianes. Una órbita ecuatorial no tiene nodo definido: devuelve 0.
+pub fn j2_precession_rate(semi_major_axis: f32, eccentricity: f32, inclination: f32, planet_j2: f32, planet_radius: f32, mean_motion: f32) -> f32 {
+    if inclination.abs() < 1e-6 || semi_major_axis <= 0.0 {
+        return 0.0;
+    }
+    let p = 1.0 - eccentricity * eccentricity;
+    let ratio = planet_radius / semi_major_axis;
+    -1.5 * mean_motion * planet_j2 * ratio * ratio / (p * p) * inclination.cos()
+}
+
+/// Posición en una órbita circular de radio `radius` inclinada `inclination` sobre el plano XZ,
+/// con el nodo ascendente a `ascending_node` rad de +X. `argument_of_latitude` es el ángulo
+/// recorrido desde el nodo. Con inclinación y nodo 0 es el círculo de siempre en y = 0.
+pub fn orbital_position(radius: f32, argument_of_latitude: f32, inclination: f32, ascending_node: f32) -> Vector3 {
+    let (sin_u, cos_u) = argument_of_latitude.sin_cos();
+    let (sin_i, cos_i) = inclination.sin_cos();
+    let (sin_node, cos_node) = ascending_node.sin_cos();
+    Vector3::new(
+        radius * (cos_node * cos_u - sin_node * sin_u * cos_i),
+        radius * sin_u * sin_i,
+        radius * (sin_node * cos_u + cos_node * sin_u * cos_i),
+    )
+}
+
+/// Posición en una órbita elíptica de semieje mayor `semi_major_axis` y excentricidad
+/// `eccentricity` (< 1), con el Sol en un foco y el perihelio en el nodo ascendente. Resuelve la
+/// ecuación de Kepler M = E - e·sin(E) por Newton a partir de la anomalía media `mean_anomaly`.
+pub fn kepler_position(semi_major_axis: f32, eccentricity: f32, mean_anomaly: f32, inclination: f32, ascending_node: f32) -> Vector3 {
+    let mut eccentric_anomaly = if eccentricity > 0.8 { std::f32::consts::PI } else { mean_anomaly };
+    for _ in 0..12 {
+        let f = eccentric_anomaly - eccentricity * eccentric_anomaly.sin() - mean_anomaly;
+        eccentric_anomaly -= f / (1.0 - eccentricity * eccentric_anomaly.cos());
+    }
+    let (sin_e, cos_e) = eccentric_anomaly.sin_cos();
+    let true_anomaly = ((1.0 - eccentricity * eccentricity).sqrt() * sin_e).atan2(cos_e - eccentricity);
+    let radius = semi_major_axis * (1.0 - eccentricity * cos_e);
+    orbital_position(radius, true_anomaly, inclination, ascending_node)
+}
+
+/// Radio de la esfera de Hill de un cuerpo de masa `mass` en órbita de semieje `semi_major_axis`
+/// alrededor de uno de masa `primary_mass`: r = a·(m / 3M)^(1/3). Dentro de ella manda su
+/// gravedad y no la del primario (la Tierra: ~0.01 UA, unas 0.25 unidades en esta escala).
+pub fn hill_sphere_radius(semi_major_axis: f32, mass: f32, primary_mass: f32) -> f32 {
+    if mass <= 0.0 || primary_mass <= 0.0 {
+        return 0.0;
+    }
+    semi_major_axis * (mass / (3.0 * primary_mass)).cbrt()
+}
+
+// Fuerza efectiva en el eje que une los dos cuerpos, en el sistema que gira con ellos (unidades
+// con separación 1, baricentro en 0, el primario en -μ y el secundario en 1 - μ). Sus ceros son
+// los puntos colineales: la quíntica de L1, L2 y L3.
+fn collinear_force(x: f64, mu: f64) -> f64 {
+    let to_primary = x + mu;
+    let to_secondary = x - 1.0 + mu;
+    x - (1.0 - mu) * to_primary / to_primary.abs().powi(3) - mu * to_secondary / to_secondary.abs().powi(3)
+}
+
+// Bisección en [low, high] con la fuerza negativa en `low` y positiva en `high`
+fn bisect_collinear_point(mut low: f64, mut high: f64, mu: f64) -> f64 {
+    for _ in 0..80 {
+        let mid = 0.5 * (low + high);
+        if collinear_force(mid, mu) < 0.0 {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    0.5 * (low + high)
+}
+
+/// Puntos de Lagrange L1–L5 del sistema de `body1` (el primario) y `body2`, en mundo. L1 y L2 se
+/// buscan por bisección sobre la quíntica, L3 por simetría (al otro lado del primario, con la
+/// corrección de primer orden en μ) y L4 / L5 forman triángulos equiláteros con los dos cuerpos,
+/// 60° por delante y por detrás de `body2`. Como todas las órbitas de la escena, se supone que
+/// `body2` gira en sentido directo alrededor de -Y, en el plano que contiene -Y más cercano.
+pub fn compute_lagrange_points(body1_pos: Vector3, body1_mass: f32, body2_pos: Vector3, body2_mass: f32) -> [Vector3; 5] {
+    let offset = body2_pos - body1_pos;
+    let separation = offset.length();
+    let total_mass = body1_mass + body2_mass;
+    if separation <= 0.0 || total_mass <= 0.0 {
+        return [body2_pos; 5];
+    }
+    let axis = offset / separation;
+    let mu = (body2_mass / total_mass) as f64;
+    let barycenter = body1_pos + offset * (body2_mass / total_mass);
+    let on_axis = |x: f64| barycenter + axis * (x as f32 * separation);
+
+    let epsilon = 1e-9;
+    let l1 = bisect_collinear_point(-mu + epsilon, 1.0 - mu - epsilon, mu);
+    let l2 = bisect_collinear_point(1.0 - mu + epsilon, 2.0, mu);
+    let l3 = -(1.0 + 5.0 * mu / 12.0);
+
+    // Dirección de avance de `body2` en su órbita: perpendicular al eje dentro del plano orbital
+    let down = Vector3::new(0.0, -1.0, 0.0);
+    let mut normal = down - axis * axis.dot(down);
+    if normal.length() < 1e-6 {
+        normal = Vector3::new(1.0, 0.0, 0.0) - axis * axis.x;
+    }
+    let ahead = normal.normalized().cross(axis);
+    let (sin_60, cos_60) = std::f32::consts::FRAC_PI_3.sin_cos();
+    let l4 = body1_pos + (axis * cos_60 + ahead * sin_60) * separation;
+    let l5 = body1_pos + (axis * cos_60 - ahead * sin_60) * separation;
+
+    [on_axis(l1), on_axis(l2), on_axis(l3), l4, l5]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EARTH_J2: f32 = 0.001_082_63;
+
+    #[test]
+    fn equatorial_orbit_has_no_j2_precession() {
+        assert_eq!(j2_precession_rate(2.0, 0.0, 0.0, EARTH_J2, 1.0, 0.5), 0.0);
+        assert_eq!(j2_precession_rate(2.0, 0.3, 0.0, EARTH_J2, 1.0, 0.5), 0.0);
+        // Inclinada y prógrada, el nodo retrocede: -3/2 · n · J2 · (R/a)² · cos(i)
+        let inclination = 30.0_f32.to_radians();
+        let rate = j2_precession_rate(2.0, 0.0, inclination, EARTH_J2, 1.0, 0.5);
+        let expected = -1.5 * 0.5 * EARTH_J2 * 0.25 * inclination.cos();
+        assert!(rate < 0.0 && (rate - expected).abs() < 1e-9);
+        // Polar: cos(90°) = 0, el plano no gira
+        assert!(j2_precession_rate(2.0, 0.0, 90.0_f32.to_radians(), EARTH_J2, 1.0, 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn l4_and_l5_sit_sixty_degrees_ahead_and_behind() {
+        let sun = Vector3::new(3.0, 0.0, -2.0);
+        let radius = 40.0;
+        for i in 0..12 {
+            let u = i as f32 * 0.55;
+            let planet = sun + orbital_position(radius, u, 0.0, 0.0);
+            let [_, _, _, l4, l5] = compute_lagrange_points(sun, 333_000.0, planet, 1.0);
+            // Los puntos de la misma órbita un sexto de vuelta después y antes
+            let ahead = sun + orbital_position(radius, u + std::f32::consts::FRAC_PI_3, 0.0, 0.0);
+            let behind = sun + orbital_position(radius, u - std::f32::consts::FRAC_PI_3, 0.0, 0.0);
+            assert!((l4 - ahead).length() < 1e-3 * radius, "L4 en u = {}: {:?} vs {:?}", u, l4, ahead);
+            assert!((l5 - behind).length() < 1e-3 * radius, "L5 en u = {}: {:?} vs {:?}", u, l5, behind);
+            // Triángulos equiláteros con el Sol y el planeta
+            for point in [l4, l5] {
+                assert!(((point - sun).length() - radius).abs() < 1e-3 * radius);
+                assert!(((point - planet).length() - radius).abs() < 1e-3 * radius);
+            }
+        }
+    }
+}
@@ -0,0 +1,607 @@
+// particles.rs
+// Partículas simples (escape del motor de la nave) simuladas en coordenadas de mundo
+
+use crate::orbit::orbital_position;
+use crate::shaders::{sublimation_jet, COMET_JET_COLOR, COMET_SUBLIMATION_TEMP};
+use raylib::prelude::*;
+
+#[derive(Clone, Debug)]
+pub struct Particle {
+    pub position: Vector3,
+    pub velocity: Vector3,
+    pub color: Vector3,
+    pub size: f32,     // radio en unidades de mundo
+    pub life: f32,     // segundos restantes
+    pub max_life: f32,
+}
+
+impl Particle {
+    // 1 al nacer, 0 al morir
+    pub fn life_ratio(&self) -> f32 {
+        (self.life / self.max_life).clamp(0.0, 1.0)
+    }
+}
+
+pub struct ParticleSystem {
+    pub particles: Vec<Particle>,
+    rng: fastrand::Rng,
+    spawn_accumulator: f32,
+}
+
+impl ParticleSystem {
+    const EXHAUST_RATE: f32 = 90.0; // partículas por segundo
+    const EXHAUST_SPEED: f32 = 6.0;
+    const MAX_PARTICLES: usize = 2000;
+
+    pub fn new() -> Self {
+        ParticleSystem {
+            particles: Vec::new(),
+            rng: fastrand::Rng::with_seed(7),
+            spawn_accumulator: 0.0,
+        }
+    }
+
+    /// Emite el escape del motor durante `dt` segundos desde `origin`, saliendo en `direction`.
+    pub fn emit_exhaust(&mut self, origin: Vector3, direction: Vector3, dt: f32) {
+        self.spawn_accumulator += dt * Self::EXHAUST_RATE;
+        while self.spawn_accumulator >= 1.0 {
+            self.spawn_accumulator -= 1.0;
+            if self.particles.len() >= Self::MAX_PARTICLES {
+                continue;
+            }
+            let jitter = Vector3::new(self.rng.f32() - 0.5, self.rng.f32() - 0.5, self.rng.f32() - 0.5);
+            let max_life = 0.4 + self.rng.f32() * 0.4;
+            self.particles.push(Particle {
+                position: origin,
+                velocity: direction.normalized() * Self::EXHAUST_SPEED + jitter * 1.5,
+                color: Vector3::new(1.0, 0.55 + self.rng.f32() * 0.3, 0.2),
+                size: 0.12 + self.rng.f32() * 0.08,
+                life: max_life,
+                max_life,
+            });
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for p in self.particles.iter_mut() {
+            p.position += p.velocity * dt;
+            p.life -= dt;
+            // Las partículas se expanden al enfriarse
+            p.size += dt * 0.3;
+        }
+        self.particles.retain(|p| p.life > 0.0);
+    }
+}
+
+/// 💍 Polvo de los anillos de Saturno levantado por impactos de micrometeoritos. Las posiciones
+/// y velocidades son relativas al centro de Saturno y en radios del planeta; cada partícula
+/// orbita con gravedad de dos cuerpos, así que las interiores adelantan a las exteriores.
+pub struct RingDustSystem {
+    pub particles: Vec<Particle>,
+    ring_normal: Vector3,
+    ring_u: Vector3,
+    ring_v: Vector3,
+    inner_radius: f32,
+    outer_radius: f32,
+    rng: fastrand::Rng,
+}
+
+impl RingDustSystem {
+    // G·M de Saturno en radios³/s²: a 2 radios la órbita circular tarda unos 13 s
+    pub const GM: f32 = 2.0;
+    const IMPACTS_PER_AREA: f32 = 0.3; // impactos por segundo y radio² de anillo
+    const LIFE: f32 = 2.0;
+    const EJECTION_SPEED: f32 = 0.08;
+    const MAX_PARTICLES: usize = 1500;
+
+    pub fn new(ring_normal: Vector3, inner_radius: f32, outer_radius: f32) -> Self {
+        let ring_normal = ring_normal.normalized();
+        // Base del plano de los anillos
+        let reference = if ring_normal.y.abs() < 0.9 { Vector3::new(0.0, 1.0, 0.0) } else { Vector3::new(1.0, 0.0, 0.0) };
+        let ring_u = ring_normal.cross(reference).normalized();
+        let ring_v = ring_normal.cross(ring_u);
+        RingDustSystem {
+            particles: Vec::new(),
+            ring_normal,
+            ring_u,
+            ring_v,
+            inner_radius,
+            outer_radius,
+            rng: fastrand::Rng::with_seed(11),
+        }
+    }
+
+    /// Nuevos impactos durante `dt` (proporcionales al área de los anillos) y un paso de la órbita
+    /// de cada partícula. Si `dt` no es positivo no hace nada.
+    pub fn update(&mut self, dt: f32) {
+        if dt <= 0.0 {
+            return;
+        }
+        let area = std::f32::consts::PI * (self.outer_radius * self.outer_radius - self.inner_radius * self.inner_radius);
+        let expected = area * Self::IMPACTS_PER_AREA * dt;
+        let mut impacts = expected.floor() as u32;
+        if self.rng.f32() < expected.fract() {
+            impacts += 1;
+        }
+        for _ in 0..impacts {
+            self.spawn_burst();
+        }
+
+        for p in self.particles.iter_mut() {
+            // Euler semi-implícito: estable para órbitas durante la vida corta del polvo
+            let r = p.position.length().max(0.1);
+            p.velocity -= p.position * (Self::GM / (r * r * r) * dt);
+            p.position += p.velocity * dt;
+            p.life -= dt;
+        }
+        self.particles.retain(|p| p.life > 0.0);
+    }
+
+    // Ráfaga de 5 a 20 partículas desde un punto al azar de los anillos (uniforme en área)
+    fn spawn_burst(&mut self) {
+        let (inner_sq, outer_sq) = (self.inner_radius * self.inner_radius, self.outer_radius * self.outer_radius);
+        let radius = (inner_sq + (outer_sq - inner_sq) * self.rng.f32()).sqrt();
+        let angle = self.rng.f32() * std::f32::consts::TAU;
+        let radial = self.ring_u * angle.cos() + self.ring_v * angle.sin();
+        let tangent = self.ring_normal.cross(radial);
+        let impact = radial * radius;
+        let orbital_velocity = tangent * (Self::GM / radius).sqrt();
+        for _ in 0..self.rng.u32(5..=20) {
+            if self.particles.len() >= Self::MAX_PARTICLES {
+                return;
+            }
+            // Salen hacia fuera en el plano, con algo de componente vertical
+            let ejection = radial * (self.rng.f32() * 2.0 - 0.5) + tangent * (self.rng.f32() - 0.5) + self.ring_normal * ((self.rng.f32() - 0.5) * 0.6);
+            let grey = 0.6 + self.rng.f32() * 0.4;
+            self.particles.push(Particle {
+                position: impact,
+                velocity: orbital_velocity + ejection * Self::EJECTION_SPEED,
+                color: Vector3::new(grey, grey, grey * 0.95),
+                size: 0.004 + self.rng.f32() * 0.004,
+                life: Self::LIFE,
+                max_life: Self::LIFE,
+            });
+        }
+    }
+}
+
+/// ☀️ Fulguración solar: un chorro de plasma que sale de la superficie del Sol en la longitud
+/// `base_longitude` (sobre el ecuador) y vuelve a caer en arcos por la gravedad. Como el polvo de
+/// los anillos, las posiciones y velocidades son relativas al centro del Sol y en radios solares.
+/// Lo que llega a `WIND_RADIUS` escapa y pasa al viento solar.
+pub struct SolarFlare {
+    pub base_longitude: f32,
+    pub start_time: f32, // tiempo simulado
+    pub duration: f32,
+    pub particles: Vec<Particle>,
+    emitted: usize,
+    rng: fastrand::Rng,
+}
+
+impl SolarFlare {
+    // G·M del Sol en radios³/s²: un arco de 1.5 radios de alto tarda ~1 minuto simulado y la
+    // velocidad de escape en la superficie es ~0.084
+    pub const GM: f32 = 0.0035;
+    pub const PARTICLE_COUNT: usize = 200;
+    pub const WIND_RADIUS: f32 = 3.0;
+    pub const DURATION: f32 = 180.0; // tres minutos simulados
+    // Probabilidad por segundo simulado de que empiece una fulguración
+    pub const TRIGGER_RATE: f32 = 0.01;
+    // Las partículas salen durante la primera parte de la fulguración
+    const EMISSION_FRACTION: f32 = 0.3;
+    const MIN_SPEED: f32 = 0.05;
+    const MAX_SPEED: f32 = 0.1;
+
+    pub fn new(base_longitude: f32, start_time: f32) -> Self {
+        SolarFlare {
+            base_longitude,
+            start_time,
+            duration: Self::DURATION,
+            particles: Vec::with_capacity(Self::PARTICLE_COUNT),
+            emitted: 0,
+            rng: fastrand::Rng::with_seed(start_time.to_bits() as u64),
+        }
+    }
+
+    pub fn is_finished(&self, time: f32) -> bool {
+        time - self.start_time >= self.duration
+    }
+
+    /// Emite lo que toca hasta `time`, avanza las partículas `dt` y entrega a `wind` las que
+    /// escapan. Con `dt` negativo no se simula, como el polvo de los anillos.
+    pub fn update(&mut self, time: f32, dt: f32, wind: &mut SolarWindSystem) {
+        if dt <= 0.0 {
+            return;
+        }
+        let emission_progress = ((time - self.start_time) / (self.duration * Self::EMISSION_FRACTION)).clamp(0.0, 1.0);
+        let target = (Self::PARTICLE_COUNT as f32 * emission_progress).ceil() as usize;
+        while self.emitted < target {
+            self.emit(time);
+            self.emitted += 1;
+        }
+
+        for p in self.particles.iter_mut() {
+            let r = p.position.length().max(0.5);
+            p.velocity -= p.position * (Self::GM / (r * r * r) * dt);
+            p.position += p.velocity * dt;
+            p.life -= dt;
+        }
+        // Las que vuelven a la superficie se apagan; las que llegan lejos son ya viento solar
+        let (escaped, kept): (Vec<Particle>, Vec<Particle>) = std::mem::take(&mut self.particles)
+            .into_iter()
+            .filter(|p| p.life > 0.0 && p.position.length() >= 1.0)
+            .partition(|p| p.position.length() >= Self::WIND_RADIUS);
+        self.particles = kept;
+        for p in escaped {
+            wind.capture(p);
+        }
+    }
+
+    // Una partícula en la superficie, hacia fuera con algo de inclinación en el sentido de giro
+    // (así sube y cae en arco) y un poco de dispersión alrededor de la longitud
+    fn emit(&mut self, time: f32) {
+        let longitude = self.base_longitude + (self.rng.f32() - 0.5) * 0.1;
+        let latitude = (self.rng.f32() - 0.5) * 0.1;
+        let radial = Vector3::new(longitude.cos() * latitude.cos(), latitude.sin(), longitude.sin() * latitude.cos());
+        let tangent = Vector3::new(-longitude.sin(), 0.0, longitude.cos());
+        let speed = Self::MIN_SPEED + self.rng.f32() * (Self::MAX_SPEED - Self::MIN_SPEED);
+        let max_life = self.duration - (time - self.start_time);
+        self.particles.push(Particle {
+            position: radial * (1.0 + self.rng.f32() * 0.02),
+            velocity: (radial + tangent * (self.rng.f32() * 0.4)).normalized() * speed,
+            color: Vector3::new(1.0, 0.45 + self.rng.f32() * 0.25, 0.1),
+            size: 0.02 + self.rng.f32() * 0.02,
+            life: max_life,
+            max_life,
+        });
+    }
+}
+
+/// 🌬️ Viento solar: plasma que se aleja del Sol en línea recta y se va apagando. De momento solo
+/// lo alimentan las fulguraciones; mismas unidades que `SolarFlare`.
+pub struct SolarWindSystem {
+    pub particles: Vec<Particle>,
+}
+
+impl SolarWindSystem {
+    const MIN_SPEED: f32 = 0.15;
+    const LIFE: f32 = 120.0;
+    const MAX_PARTICLES: usize = 1000;
+
+    pub fn new() -> Self {
+        SolarWindSystem { particles: Vec::new() }
+    }
+
+    /// Recoge una partícula que escapó: sigue hacia fuera, como mínimo a la velocidad del viento.
+    pub fn capture(&mut self, mut particle: Particle) {
+        if self.particles.len() >= Self::MAX_PARTICLES {
+            return;
+        }
+        particle.velocity = particle.position.normalized() * particle.velocity.length().max(Self::MIN_SPEED);
+        particle.color = Vector3::new(1.0, 0.8, 0.5);
+        particle.life = Self::LIFE;
+        particle.max_life = Self::LIFE;
+        self.particles.push(particle);
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if dt <= 0.0 {
+            return;
+        }
+        for p in self.particles.iter_mut() {
+            p.position += p.velocity * dt;
+            p.life -= dt;
+        }
+        self.particles.retain(|p| p.life > 0.0);
+    }
+}
+
+/// Factor de "partícula suave": 0 cuando la partícula está a la misma profundidad que la
+/// superficie de detrás, 1 cuando está al menos `fade_distance` por delante.
+pub fn soft_particle_factor(particle_depth: f32, background_depth: f32, fade_distance: f32) -> f32 {
+    ((background_depth - particle_depth) / fade_distance).clamp(0.0, 1.0)
+}
+
+/// Convierte la profundidad NDC del depth buffer a distancia lineal desde la cámara.
+pub fn linearize_depth(ndc_depth: f32, near: f32, far: f32) -> f32 {
+    2.0 * far * near / ((far + near) - ndc_depth * (far - near))
+}
+
+/// ☄️ Chorros de sublimación del núcleo de un cometa: gas y polvo que salen de las fuentes
+/// activas de la cara iluminada (ver `sublimation_jet`) hacia el Sol, con algo de dispersión.
+/// Coordenadas de mundo, como el escape de la nave.
+pub struct CometJetSystem {
+    pub particles: Vec<Particle>,
+    rng: fastrand::Rng,
+    spawn_accumulator: f32,
+}
+
+impl CometJetSystem {
+    // Partículas por segundo simulado con el núcleo a plena actividad
+    const EMISSION_RATE: f32 = 60.0;
+    const SPEED: f32 = 0.6;
+    const SPREAD: f32 = 0.35;
+    const LIFE: f32 = 4.0;
+    const MAX_PARTICLES: usize = 800;
+    // Intentos por partícula de caer en una fuente activa
+    const VENT_TRIES: u32 = 8;
+
+    pub fn new() -> Self {
+        CometJetSystem {
+            particles: Vec::new(),
+            rng: fastrand::Rng::with_seed(31),
+            spawn_accumulator: 0.0,
+        }
+    }
+
+    /// Emite durante `dt` desde el núcleo (`nucleus_pos`, `nucleus_radius`) con el Sol en
+    /// `sun_dir` (unitaria) y la superficie a `temperature` K. No sale nada por debajo de la
+    /// temperatura de sublimación, y cuanto más caliente, más chorro.
+    pub fn emit(&mut self, nucleus_pos: Vector3, nucleus_radius: f32, sun_dir: Vector3, temperature: f32, time: f32, dt: f32) {
+        if temperature <= COMET_SUBLIMATION_TEMP || dt <= 0.0 {
+            return;
+        }
+        let activity = ((temperature - COMET_SUBLIMATION_TEMP) / COMET_SUBLIMATION_TEMP).min(1.0);
+        self.spawn_accumulator += dt * Self::EMISSION_RATE * activity;
+        while self.spawn_accumulator >= 1.0 {
+            self.spawn_accumulator -= 1.0;
+            if self.particles.len() >= Self::MAX_PARTICLES {
+                continue;
+            }
+            // Un punto al azar de la cara iluminada que esté sobre una fuente activa
+            let Some(normal) = (0..Self::VENT_TRIES).find_map(|_| {
+                let random = Vector3::new(self.rng.f32() - 0.5, self.rng.f32() - 0.5, self.rng.f32() - 0.5).normalized();
+                let dir = if random.dot(sun_dir) < 0.0 { random * -1.0 } else { random };
+                (sublimation_jet(dir, sun_dir, time * 2.0) > self.rng.f32()).then_some(dir)
+            }) else {
+                continue;
+            };
+            let jitter = Vector3::new(self.rng.f32() - 0.5, self.rng.f32() - 0.5, self.rng.f32() - 0.5) * Self::SPREAD;
+            let max_life = Self::LIFE * (0.6 + self.rng.f32() * 0.4);
+            self.particles.push(Particle {
+                position: nucleus_pos + normal * nucleus_radius,
+                velocity: (normal + sun_dir + jitter).normalized() * Self::SPEED,
+                color: COMET_JET_COLOR * (0.5 + self.rng.f32() * 0.3),
+                size: 0.04 + self.rng.f32() * 0.04,
+                life: max_life,
+                max_life,
+            });
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if dt <= 0.0 {
+            return;
+        }
+        for p in self.particles.iter_mut() {
+            p.position += p.velocity * dt;
+            p.life -= dt;
+            // El gas se expande al alejarse del núcleo
+            p.size += dt * 0.05;
+        }
+        self.particles.retain(|p| p.life > 0.0);
+    }
+}
+
+/// 🧊 Objeto del cinturón de Kuiper: posición y velocidad heliocéntricas en unidades de escena.
+pub struct KuiperObject {
+    pub position: Vector3,
+    pub velocity: Vector3,
+    pub radius: f32,
+    pub color: Vector3,
+}
+
+/// Cinturón de Kuiper como partículas con gravedad de dos cuerpos (solo el Sol, en el origen)
+/// integrada con leapfrog (kick-drift-kick), que conserva la energía en órbitas largas. Las
+/// resonancias con Neptuno van vaciando su zona poco a poco (`resonate_with_neptune`).
+pub struct KuiperBeltSimulation {
+    pub objects: Vec<KuiperObject>,
+    rng: fastrand::Rng,
+}
+
+impl KuiperBeltSimulation {
+    pub const OBJECT_COUNT: usize = 500;
+    pub const INNER_RADIUS: f32 = 75.0;
+    pub const OUTER_RADIUS: f32 = 90.0;
+    const MAX_INCLINATION: f32 = 0.06; // rad
+    // G·M del Sol en unidades³/s²: una órbita a 80 unidades tarda ~5 minutos simulados
+    pub const GM: f32 = 200.0;
+    // Paso máximo de integración; con `time_scale` alto el frame se parte en varios pasos
+    const MAX_STEP: f32 = 0.5;
+    // Resonancias p:q (el objeto da p vueltas mientras Neptuno da q) y su semiancho en unidades
+    const RESONANCES: [(u32, u32); 2] = [(2, 3), (3, 5)];
+    const RESONANCE_HALF_WIDTH: f32 = 0.8;
+    // Probabilidad por segundo simulado de que un objeto en resonancia salga despedido
+    const CLEARING_RATE: f32 = 0.01;
+
+    /// Toro de objetos en órbitas casi circulares entre INNER_RADIUS y OUTER_RADIUS, con
+    /// inclinaciones de hasta MAX_INCLINATION y algo de excentricidad.
+    pub fn new(seed: u64) -> Self {
+        let mut rng = fastrand::Rng::with_seed(seed);
+        let objects = (0..Self::OBJECT_COUNT)
+            .map(|_| {
+                let radius = Self::INNER_RADIUS + rng.f32() * (Self::OUTER_RADIUS - Self::INNER_RADIUS);
+                let angle = rng.f32() * std::f32::consts::TAU;
+                let inclination = (rng.f32() * 2.0 - 1.0) * Self::MAX_INCLINATION;
+                let node = rng.f32() * std::f32::consts::TAU;
+                // La tangente de una órbita circular está 90° más adelante en la misma órbita
+                let tangent = orbital_position(1.0, angle + std::f32::consts::FRAC_PI_2, inclination, node);
+                let speed = (Self::GM / radius).sqrt() * (0.98 + rng.f32() * 0.04);
+                let grey = 0.5 + rng.f32() * 0.3;
+                KuiperObject {
+                    position: orbital_position(radius, angle, inclination, node),
+                    velocity: tangent * speed,
+                    radius: 0.05 + rng.f32() * 0.1,
+                    // Hielos rojizos por la irradiación
+                    color: Vector3::new(grey, grey * 0.85, grey * 0.75),
+                }
+            })
+            .collect();
+        KuiperBeltSimulation { objects, rng }
+    }
+
+    fn acceleration(position: Vector3) -> Vector3 {
+        let r = position.length().max(1.0);
+        position * (-Self::GM / (r * r * r))
+    }
+
+    /// Un paso de leapfrog: medio impulso, deriva completa y otro medio impulso.
+    pub fn step(&mut self, dt: f32) {
+        for object in self.objects.iter_mut() {
+            object.velocity += Self::acceleration(object.position) * (dt * 0.5);
+            object.position += object.velocity * dt;
+            object.velocity += Self::acceleration(object.position) * (dt * 0.5);
+        }
+    }
+
+    /// Avanza `dt` en pasos de como mucho MAX_STEP. Con `dt` negativo no se simula.
+    pub fn update(&mut self, dt: f32) {
+        if dt <= 0.0 {
+            return;
+        }
+        let steps = (dt / Self::MAX_STEP).ceil().max(1.0) as u32;
+        for _ in 0..steps {
+            self.step(dt / steps as f32);
+        }
+    }
+
+    /// Energía total (cinética + potencial por unidad de masa) de todos los objetos.
+    #[allow(dead_code)]
+    pub fn total_energy(&self) -> f32 {
+        self.objects.iter().map(|object| 0.5 * object.velocity.length_sqr() - Self::GM / object.position.length()).sum()
+    }
+
+    /// Semieje mayor de la órbita actual a partir de su energía: a = -GM / (2E).
+    pub fn semi_major_axis(object: &KuiperObject) -> f32 {
+        let energy = 0.5 * object.velocity.length_sqr() - Self::GM / object.position.length();
+        -Self::GM / (2.0 * energy)
+    }
+
+    /// Saca poco a poco los objetos cuyo semieje cae en las resonancias 2:3 y 3:5 con Neptuno
+    /// (semieje `neptune_orbit_radius`), en a = a_N · (q/p)^(2/3): con el tiempo simulado se
+    /// abren huecos en el cinturón.
+    pub fn resonate_with_neptune(&mut self, neptune_orbit_radius: f32, dt: f32) {
+        if dt <= 0.0 {
+            return;
+        }
+        let resonances = Self::RESONANCES.map(|(p, q)| neptune_orbit_radius * (q as f32 / p as f32).powf(2.0 / 3.0));
+        let removal_chance = Self::CLEARING_RATE * dt;
+        let rng = &mut self.rng;
+        self.objects.retain(|object| {
+            let a = Self::semi_major_axis(object);
+            let resonant = resonances.iter().any(|resonance| (a - resonance).abs() < Self::RESONANCE_HALF_WIDTH);
+            !(resonant && rng.f32() < removal_chance)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn soft_particle_fades_against_the_background() {
+        assert_eq!(soft_particle_factor(10.0, 10.0, 0.5), 0.0);
+        assert_eq!(soft_particle_factor(9.5, 10.0, 0.5), 1.0);
+        assert_eq!(soft_particle_factor(9.75, 10.0, 0.5), 0.5);
+        // Sin nada detrás (profundidad infinita) la partícula se ve entera
+        assert_eq!(soft_particle_factor(9.5, f32::INFINITY, 0.5), 1.0);
+        assert!((linearize_depth(-1.0, 0.5, 600.0) - 0.5).abs() < 1e-4);
+        assert!((linearize_depth(1.0, 0.5, 600.0) - 600.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn ring_dust_spreads_out_after_a_second() {
+        let mut dust = RingDustSystem::new(Vector3::new(0.0, 1.0, 0.0), 1.2, 2.3);
+        dust.spawn_burst();
+        let burst = dust.particles.len();
+        assert!((5..=20).contains(&burst));
+        // Dos granos en órbita circular que salen del mismo ángulo a distinta altura
+        for radius in [1.3_f32, 2.2] {
+            dust.particles.push(Particle {
+                position: Vector3::new(radius, 0.0, 0.0),
+                velocity: Vector3::new(0.0, 0.0, (RingDustSystem::GM / radius).sqrt()),
+                color: Vector3::one(),
+                size: 0.005,
+                life: RingDustSystem::LIFE,
+                max_life: RingDustSystem::LIFE,
+            });
+        }
+        let spread = |particles: &[Particle]| {
+            particles.iter().flat_map(|a| particles.iter().map(move |b| (a.position - b.position).length())).fold(0.0_f32, f32::max)
+        };
+        assert_eq!(spread(&dust.particles[..burst]), 0.0);
+
+        for _ in 0..60 {
+            dust.update(1.0 / 60.0);
+        }
+        // La ráfaga sale de un punto y se abre a distintas alturas orbitales
+        let radii: Vec<f32> = dust.particles[..burst].iter().map(|p| p.position.length()).collect();
+        let (min_r, max_r) = (radii.iter().copied().fold(f32::MAX, f32::min), radii.iter().copied().fold(f32::MIN, f32::max));
+        assert!(max_r - min_r > 0.01, "radios entre {} y {}", min_r, max_r);
+        assert!(spread(&dust.particles[..burst]) > 0.05);
+        // Kepler: el grano interior recorre más ángulo que el exterior
+        let angle = |p: &Particle| p.position.z.atan2(p.position.x);
+        let (inner, outer) = (&dust.particles[burst], &dust.particles[burst + 1]);
+        assert!((inner.position.length() - 1.3).abs() < 0.02 && (outer.position.length() - 2.2).abs() < 0.02);
+        assert!(angle(inner) > angle(outer) * 1.5, "{} vs {}", angle(inner), angle(outer));
+    }
+
+    #[test]
+    fn flare_particles_start_on_the_sun_surface_at_its_longitude() {
+        let longitude = 1.3_f32;
+        let mut flare = SolarFlare::new(longitude, 50.0);
+        // Emisión de toda la fulguración con un paso ínfimo: las partículas apenas se mueven
+        let mut wind = SolarWindSystem::new();
+        flare.update(50.0 + SolarFlare::DURATION * SolarFlare::EMISSION_FRACTION, 1e-4, &mut wind);
+        assert_eq!(flare.particles.len(), SolarFlare::PARTICLE_COUNT);
+        let outward = Vector3::new(longitude.cos(), 0.0, longitude.sin());
+        for p in &flare.particles {
+            // Posiciones en radios solares: el Sol mide 1
+            let r = p.position.length();
+            assert!((1.0..=1.1).contains(&r), "partícula a {} radios", r);
+            assert!(p.position.normalized().dot(outward) > 0.99);
+            assert!(p.velocity.dot(p.position) > 0.0, "la partícula no sale hacia fuera");
+        }
+    }
+
+    #[test]
+    fn comet_jets_only_leave_from_the_sunlit_side() {
+        let (nucleus, radius) = (Vector3::new(10.0, -2.0, 5.0), 0.5);
+        let sun_dir = Vector3::new(-1.0, 0.3, 0.2).normalized();
+        let mut jets = CometJetSystem::new();
+        for step in 0..1000 {
+            jets.emit(nucleus, radius, sun_dir, COMET_SUBLIMATION_TEMP * 2.0, step as f32 * 0.05, 0.05);
+        }
+        assert!(jets.particles.len() > 20, "{} partículas", jets.particles.len());
+        for p in &jets.particles {
+            let from_center = p.position - nucleus;
+            assert!((from_center.length() - radius).abs() < 1e-4);
+            assert!(from_center.dot(sun_dir) > 0.0, "chorro desde la cara oscura");
+        }
+        // La intensidad de las fuentes es nula en todo el hemisferio de noche
+        let mut rng = fastrand::Rng::with_seed(434);
+        for _ in 0..500 {
+            let dir = Vector3::new(rng.f32() - 0.5, rng.f32() - 0.5, rng.f32() - 0.5).normalized();
+            if dir.dot(sun_dir) <= 0.0 {
+                assert_eq!(sublimation_jet(dir, sun_dir, rng.f32() * 10.0), 0.0);
+            }
+        }
+        // Por debajo de la temperatura de sublimación no sale nada
+        let mut cold = CometJetSystem::new();
+        cold.emit(nucleus, radius, sun_dir, COMET_SUBLIMATION_TEMP * 0.9, 0.0, 10.0);
+        assert!(cold.particles.is_empty());
+    }
+
+    #[test]
+    fn kuiper_leapfrog_conserves_energy_over_a_thousand_steps() {
+        let mut belt = KuiperBeltSimulation::new(437);
+        assert_eq!(belt.objects.len(), KuiperBeltSimulation::OBJECT_COUNT);
+        let initial = belt.total_energy();
+        for _ in 0..1000 {
+            belt.step(KuiperBeltSimulation::MAX_STEP);
+        }
+        let drift = ((belt.total_energy() - initial) / initial).abs();
+        assert!(drift < 1e-3, "la energía cambió un {}%", drift * 100.0);
+    }
+}
@@ -0,0 +1,626 @@
+// postprocess.rs
+// 🌑 Post-proceso en espacio de pantalla sobre la imagen ya dibujada: oclusión ambiental (SSAO).
+// Solo usa el depth buffer, así que oscurece por igual los huecos de la nave y los cañones del
+// relieve desplazado sin saber nada de las mallas.
+// Además, la cadena configurable de pases (bloom, tonemap, FXAA, viñeta, grano).
+// También la lente gravitatoria de los agujeros negros, que deforma el fondo ya dibujado.
+use crate::camera::{Camera, ClipPlanes};
+use crate::config::load_toml;
+use crate::framebuffer::Framebuffer;
+use crate::hdr::{tone_map, ToneMapping};
+use crate::light::smoothstep;
+use crate::particles::linearize_depth;
+use raylib::prelude::{Color, Vector3};
+use serde::Deserialize;
+use std::cell::Cell;
+use std::fs;
+use std::io;
+
+// Lado de la textura de ruido (repetida en mosaico) que gira el núcleo de muestras por píxel
+const SSAO_NOISE_SIZE: i32 = 4;
+
+// Muestras del hemisferio +Z (espacio tangente), más densas cerca del centro
+fn ssao_kernel(kernel_size: u32) -> Vec<Vector3> {
+    let mut rng = fastrand::Rng::with_seed(42);
+    (0..kernel_size)
+        .map(|i| {
+            let dir = Vector3::new(rng.f32() * 2.0 - 1.0, rng.f32() * 2.0 - 1.0, rng.f32()).normalized();
+            let t = i as f32 / kernel_size as f32;
+            dir * (rng.f32() * (0.1 + 0.9 * t * t))
+        })
+        .collect()
+}
+
+// Giros aleatorios en el plano tangente, uno por celda del mosaico de 4x4
+fn ssao_noise() -> Vec<Vector3> {
+    let mut rng = fastrand::Rng::with_seed(7);
+    (0..SSAO_NOISE_SIZE * SSAO_NOISE_SIZE)
+        .map(|_| Vector3::new(rng.f32() * 2.0 - 1.0, rng.f32() * 2.0 - 1.0, 0.0))
+        .collect()
+}
+
+/// Factor de visibilidad ambiental por píxel (por filas; 1.0 = sin oclusión) de la vista en
+/// perspectiva (planos `clip_planes`) con `pixels_per_unit` píxeles por unidad a distancia 1.
+/// Para cada píxel se reconstruye la normal con el gradiente de profundidad y se prueban
+/// `kernel_size` puntos del hemisferio de radio `radius` (unidades de mundo) a su alrededor:
+/// cuenta como ocluido el que queda detrás de lo que hay dibujado en su píxel (con margen
+/// `bias`). Las muestras se proyectan con la escala del píxel central, suficiente para radios
+/// pequeños. Al final se promedia en bloques de 4x4 para borrar el patrón del ruido.
+pub fn ssao_pass(framebuffer: &Framebuffer, kernel_size: u32, radius: f32, bias: f32, pixels_per_unit: f32, clip_planes: ClipPlanes) -> Vec<f32> {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let kernel = ssao_kernel(kernel_size);
+    let noise = ssao_noise();
+    let depth_at = |x: i32, y: i32| {
+        let depth = framebuffer.get_depth_at(x, y);
+        if depth.is_finite() { linearize_depth(depth, clip_planes.near, clip_planes.far) } else { f32::INFINITY }
+    };
+    // Derivada de la profundidad por el lado más parecido (no cruza bordes de objetos)
+    let slope = |center: f32, before: f32, after: f32| {
+        let (back, forward) = (center - before, after - center);
+        if back.abs() < forward.abs() { back } else { forward }
+    };
+
+    let mut visibility = vec![1.0_f32; (width * height) as usize];
+    for y in 0..height {
+        for x in 0..width {
+            let depth = depth_at(x, y);
+            if !depth.is_finite() {
+                continue;
+            }
+            // Espacio de vista local: x a la derecha, y hacia abajo, z hacia dentro de la pantalla
+            let pixel_size = depth / pixels_per_unit;
+            let ddx = slope(depth, depth_at(x - 1, y), depth_at(x + 1, y));
+            let ddy = slope(depth, depth_at(x, y - 1), depth_at(x, y + 1));
+            let (ddx, ddy) = (if ddx.is_finite() { ddx } else { 0.0 }, if ddy.is_finite() { ddy } else { 0.0 });
+            let normal = Vector3::new(ddx * pixel_size, ddy * pixel_size, -pixel_size * pixel_size).normalized();
+
+            let random = noise[((y % SSAO_NOISE_SIZE) * SSAO_NOISE_SIZE + x % SSAO_NOISE_SIZE) as usize];
+            let tangent = (random - normal * random.dot(normal)).normalized();
+            let bitangent = normal.cross(tangent);
+
+            let mut occluded = 0.0;
+            for sample in &kernel {
+                let offset = (tangent * sample.x + bitangent * sample.y + normal * sample.z) * radius;
+                let sx = x + (offset.x / pixel_size).round() as i32;
+                let sy = y + (offset.y / pixel_size).round() as i32;
+                let scene_depth = depth_at(sx, sy);
+                if scene_depth <= depth + offset.z - bias {
+                    // Lo que está mucho más cerca (otro objeto delante) no cuenta entero
+                    occluded += smoothstep(0.0, 1.0, radius / (depth - scene_depth).abs());
+                }
+            }
+            visibility[(y * width + x) as usize] = 1.0 - occluded / kernel.len().max(1) as f32;
+        }
+    }
+    blur_ssao(&visibility, width, height)
+}
+
+// Media en un bloque de SSAO_NOISE_SIZE x SSAO_NOISE_SIZE alrededor de cada píxel
+fn blur_ssao(visibility: &[f32], width: i32, height: i32) -> Vec<f32> {
+    let half = SSAO_NOISE_SIZE / 2;
+    let mut blurred = vec![1.0_f32; visibility.len()];
+    for y in 0..height {
+        for x in 0..width {
+            let (mut sum, mut count) = (0.0, 0);
+            for by in (y - half).max(0)..(y + half).min(height) {
+                for bx in (x - half).max(0)..(x + half).min(width) {
+                    sum += visibility[(by * width + bx) as usize];
+                    count += 1;
+                }
+            }
+            blurred[(y * width + x) as usize] = sum / count.max(1) as f32;
+        }
+    }
+    blurred
+}
+
+/// Multiplica cada píxel de la imagen por su factor de `ssao_pass`
+pub fn apply_ssao(framebuffer: &mut Framebuffer, visibility: &[f32]) {
+    // Con la imagen al día; con HDR se oscurece también la radiancia, que es lo que lee el bloom
+    framebuffer.resolve_msaa();
+    framebuffer.resolve_hdr();
+    for y in 0..framebuffer.height {
+        for x in 0..framebuffer.width {
+            let factor = visibility[(y * framebuffer.width + x) as usize];
+            if factor >= 1.0 {
+                continue;
+            }
+            let color = framebuffer.color_buffer.get_color(x, y);
+            let scale = |channel: u8| (channel as f32 * factor).round() as u8;
+            framebuffer.color_buffer.draw_pixel(x, y, Color::new(scale(color.r), scale(color.g), scale(color.b), color.a));
+            if let Some(hdr) = framebuffer.hdr_mut() {
+                hdr.set_color(x, y, hdr.get_color_at(x, y) * factor);
+            }
+        }
+    }
+}
+
+// 🎞️ Cadena de post-procesos configurable: cada pase modifica la imagen ya dibujada en orden.
+// Los pases trabajan en [0, 1] sobre la imagen de 8 bits por canal, que con `--hdr` ya pasó por
+// el tone mapping.
+
+pub trait PostProcessPass {
+    fn apply(&self, fb: &mut Framebuffer);
+    fn name(&self) -> &str;
+}
+
+pub struct PostProcessPipeline {
+    pub passes: Vec<Box<dyn PostProcessPass>>,
+    disabled: Vec<String>,
+}
+
+// Contenido de `postprocess.toml`: una tabla `[[pass]]` por pase, en orden
+#[derive(Deserialize)]
+struct PipelineConfig {
+    #[serde(default)]
+    pass: Vec<PassConfig>,
+}
+
+#[derive(Deserialize)]
+struct PassConfig {
+    name: String,
+    #[serde(default = "pass_enabled_by_default")]
+    enabled: bool,
+}
+
+fn pass_enabled_by_default() -> bool {
+    true
+}
+
+impl Default for PostProcessPipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PostProcessPipeline {
+    pub fn new() -> Self {
+        PostProcessPipeline { passes: Vec::new(), disabled: Vec::new() }
+    }
+
+    /// Los cinco pases en su orden habitual, todos apagados: la imagen sale igual que sin cadena.
+    pub fn with_default_passes() -> Self {
+        let mut pipeline = Self::new();
+        // `apply_bloom` solo toma el exceso sobre el umbral y desenfoca con una gaussiana: con
+        // estos valores un blanco brilla como con el antiguo 0.75 / 0.6 / caja de radio 6
+        pipeline.add(Box::new(BloomPass { threshold: 0.6, intensity: 1.5, radius: 8 }));
+        pipeline.add(Box::new(TonemapPass { exposure: 1.2 }));
+        pipeline.add(Box::new(FxaaPass { edge_threshold: 0.125 }));
+        pipeline.add(Box::new(VignettePass { strength: 0.45, radius: 0.75 }));
+        pipeline.add(Box::new(GrainPass { amount: 0.04, frame: Cell::new(0) }));
+        for pass in &pipeline.passes {
+            pipeline.disabled.push(pass.name().to_string());
+        }
+        pipeline
+    }
+
+    pub fn add(&mut self, pass: Box<dyn PostProcessPass>) {
+        self.passes.push(pass);
+    }
+
+    /// Quita el pase `name`; devuelve si estaba en la cadena.
+    #[allow(dead_code)]
+    pub fn remove_by_name(&mut self, name: &str) -> bool {
+        let before = self.passes.len();
+        self.passes.retain(|p| p.name() != name);
+        self.disabled.retain(|n| n != name);
+        self.passes.len() != before
+    }
+
+    /// Reordena la cadena según `names`; los nombres desconocidos se ignoran y los pases que no
+    /// aparecen quedan al final en su orden actual.
+    pub fn reorder(&mut self, names: &[&str]) {
+        let mut remaining = std::mem::take(&mut self.passes);
+        for name in names {
+            if let Some(i) = remaining.iter().position(|p| p.name() == *name) {
+                self.passes.push(remaining.remove(i));
+            }
+        }
+        self.passes.append(&mut remaining);
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        !self.disabled.iter().any(|n| n == name)
+    }
+
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) {
+        self.disabled.retain(|n| n != name);
+        if !enabled {
+            self.disabled.push(name.to_string());
+        }
+    }
+
+    /// Enciende o apaga el pase `name`; devuelve `false` si no está en la cadena.
+    pub fn toggle(&mut self, name: &str) -> bool {
+        if !self.passes.iter().any(|p| p.name() == name) {
+            return false;
+        }
+        let enabled = self.is_enabled(name);
+        self.set_enabled(name, !enabled);
+        true
+    }
+
+    pub fn apply(&self, framebuffer: &mut Framebuffer) {
+        for pass in self.passes.iter().filter(|p| self.is_enabled(p.name())) {
+            pass.apply(framebuffer);
+        }
+    }
+
+    /// Nombres de los pases encendidos, en orden (para el overlay de estadísticas).
+    pub fn enabled_names(&self) -> Vec<&str> {
+        self.passes.iter().map(|p| p.name()).filter(|n| self.is_enabled(n)).collect()
+    }
+
+    /// Configuración como TOML: una tabla `[[pass]]` por pase, en orden.
+    pub fn to_toml(&self) -> String {
+        let mut contents = String::new();
+        for pass in &self.passes {
+            contents.push_str(&format!("[[pass]]\nname = \"{}\"\nenabled = {}\n\n", pass.name(), self.is_enabled(pass.name())));
+        }
+        contents
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_toml())
+    }
+
+    /// Aplica un TOML guardado con `save` (orden y encendido) a los pases que ya hay en la cadena.
+    pub fn load(&mut self, path: &str) -> io::Result<()> {
+        let config: PipelineConfig = load_toml(path)?;
+        let names: Vec<&str> = config.pass.iter().map(|entry| entry.name.as_str()).collect();
+        self.reorder(&names);
+        for entry in &config.pass {
+            self.set_enabled(&entry.name, entry.enabled);
+        }
+        Ok(())
+    }
+}
+
+// Imagen completa en [0, 1] por filas, y de vuelta
+fn read_colors(framebuffer: &mut Framebuffer) -> Vec<Vector3> {
+    let mut colors = Vec::with_capacity((framebuffer.width * framebuffer.height) as usize);
+    for y in 0..framebuffer.height {
+        for x in 0..framebuffer.width {
+            let c = framebuffer.color_buffer.get_color(x, y);
+            colors.push(Vector3::new(c.r as f32, c.g as f32, c.b as f32) / 255.0);
+        }
+    }
+    colors
+}
+
+fn write_colors(framebuffer: &mut Framebuffer, colors: &[Vector3]) {
+    let to_byte = |v: f32| (v.clamp(0.0, 1.0) * 255.0 + 0.5) as u8;
+    for y in 0..framebuffer.height {
+        for x in 0..framebuffer.width {
+            let c = colors[(y * framebuffer.width + x) as usize];
+            framebuffer.color_buffer.draw_pixel(x, y, Color::new(to_byte(c.x), to_byte(c.y), to_byte(c.z), 255));
+        }
+    }
+}
+
+fn luma(c: Vector3) -> f32 {
+    c.x * 0.299 + c.y * 0.587 + c.z * 0.114
+}
+
+/// Resplandor: lo que pasa de `threshold` se desenfoca y se suma a la imagen
+/// (ver `Framebuffer::apply_bloom`).
+pub struct BloomPass {
+    pub threshold: f32,
+    pub intensity: f32,
+    pub radius: u32,
+}
+
+impl PostProcessPass for BloomPass {
+    fn apply(&self, fb: &mut Framebuffer) {
+        fb.apply_bloom(self.threshold, self.radius, self.intensity);
+    }
+
+    fn name(&self) -> &str {
+        "bloom"
+    }
+}
+
+/// Curva fílmica ACES (ajuste de Narkowicz) con `exposure`; sobre 8 bits solo cambia el contraste.
+pub struct TonemapPass {
+    pub exposure: f32,
+}
+
+impl PostProcessPass for TonemapPass {
+    fn apply(&self, fb: &mut Framebuffer) {
+        let result: Vec<Vector3> = read_colors(fb).iter().map(|&c| tone_map(c, ToneMapping::Aces, self.exposure)).collect();
+        write_colors(fb, &result);
+    }
+
+    fn name(&self) -> &str {
+        "tonemap"
+    }
+}
+
+/// Antialiasing por contraste de luma: en los bordes (contraste > `edge_threshold`) se mezcla el
+/// píxel con sus vecinos a lo largo del borde, no a través de él.
+pub struct FxaaPass {
+    pub edge_threshold: f32,
+}
+
+impl PostProcessPass for FxaaPass {
+    fn apply(&self, fb: &mut Framebuffer) {
+        let (width, height) = (fb.width, fb.height);
+        let colors = read_colors(fb);
+        let at = |x: i32, y: i32| colors[(y.clamp(0, height - 1) * width + x.clamp(0, width - 1)) as usize];
+        let mut result = colors.clone();
+        for y in 0..height {
+            for x in 0..width {
+                let center = at(x, y);
+                let (n, s, w, e) = (luma(at(x, y - 1)), luma(at(x, y + 1)), luma(at(x - 1, y)), luma(at(x + 1, y)));
+                let l = luma(center);
+                let contrast = n.max(s).max(w).max(e).max(l) - n.min(s).min(w).min(e).min(l);
+                if contrast < self.edge_threshold {
+                    continue;
+                }
+                // Borde horizontal (cambia en vertical) → mezclar con izquierda/derecha
+                let blend = if (n + s - 2.0 * l).abs() >= (w + e - 2.0 * l).abs() {
+                    (at(x - 1, y) + at(x + 1, y)) * 0.5
+                } else {
+                    (at(x, y - 1) + at(x, y + 1)) * 0.5
+                };
+                result[(y * width + x) as usize] = center.lerp(blend, 0.5);
+            }
+        }
+        write_colors(fb, &result);
+    }
+
+    fn name(&self) -> &str {
+        "fxaa"
+    }
+}
+
+/// Oscurece las esquinas a partir de `radius` (fracción de la semidiagonal) hasta `strength`.
+pub struct VignettePass {
+    pub strength: f32,
+    pub radius: f32,
+}
+
+impl PostProcessPass for VignettePass {
+    fn apply(&self, fb: &mut Framebuffer) {
+        let (cx, cy) = (fb.width as f32 * 0.5, fb.height as f32 * 0.5);
+        let half_diagonal = (cx * cx + cy * cy).sqrt();
+        let mut colors = read_colors(fb);
+        for y in 0..fb.height {
+            for x in 0..fb.width {
+                let dist = ((x as f32 - cx).powi(2) + (y as f32 - cy).powi(2)).sqrt() / half_diagonal;
+                colors[(y * fb.width + x) as usize] *= 1.0 - self.strength * smoothstep(self.radius, 1.0, dist);
+            }
+        }
+        write_colors(fb, &colors);
+    }
+
+    fn name(&self) -> &str {
+        "vignette"
+    }
+}
+
+/// Grano de película: ruido de ±`amount` por píxel que cambia en cada fotograma.
+pub struct GrainPass {
+    pub amount: f32,
+    pub frame: Cell<u64>,
+}
+
+impl PostProcessPass for GrainPass {
+    fn apply(&self, fb: &mut Framebuffer) {
+        let frame = self.frame.get();
+        self.frame.set(frame.wrapping_add(1));
+        let mut rng = fastrand::Rng::with_seed(frame);
+        let mut colors = read_colors(fb);
+        for c in colors.iter_mut() {
+            let noise = (rng.f32() * 2.0 - 1.0) * self.amount;
+            *c += Vector3::new(noise, noise, noise);
+        }
+        write_colors(fb, &colors);
+    }
+
+    fn name(&self) -> &str {
+        "grain"
+    }
+}
+
+// 🕳️ Lente gravitatoria: radio de la zona deformada (en radios de Einstein) y parámetro de
+// impacto, en masas, bajo el que la luz cae en el agujero (3√3 M, la esfera de fotones vista)
+const LENSING_EXTENT: f32 = 4.0;
+const PHOTON_CAPTURE_RADIUS: f32 = 5.196;
+
+/// Deforma la imagen alrededor de un cuerpo de masa `mass` (G = c = 1) en `body_world_pos`,
+/// cuyo centro cae en `body_screen_pos` de la vista en perspectiva con `pixels_per_unit` (y los
+/// planos `clip_planes` con los que se dibujó, para saber qué queda delante). Cada
+/// píxel del fondo a ángulo θ del centro se pasa por la ecuación de la lente con la fuente en el
+/// infinito, β = θ − α con α = 4M / b (b = parámetro de impacto), y toma el color que había en
+/// β: en el radio de Einstein β = 0 y se ve lo que está justo detrás, estirado en un anillo.
+/// Dentro del radio de captura se pinta la sombra negra; lo que está delante del cuerpo no cambia.
+pub fn gravity_lensing_pass(framebuffer: &mut Framebuffer, body_screen_pos: (f32, f32), body_world_pos: Vector3, mass: f32, camera: &Camera, pixels_per_unit: f32, clip_planes: ClipPlanes) {
+    let lens_distance = (body_world_pos - camera.eye).length();
+    let capture_radius = PHOTON_CAPTURE_RADIUS * mass;
+    if mass <= 0.0 || lens_distance <= capture_radius {
+        return;
+    }
+    let einstein_angle = (4.0 * mass / lens_distance).sqrt();
+    let max_angle = (einstein_angle * LENSING_EXTENT).min(1.2);
+    let radius_px = max_angle.tan() * pixels_per_unit;
+    let (cx, cy) = body_screen_pos;
+    let x0 = ((cx - radius_px).floor() as i32).max(0);
+    let y0 = ((cy - radius_px).floor() as i32).max(0);
+    let x1 = ((cx + radius_px).ceil() as i32).min(framebuffer.width - 1);
+    let y1 = ((cy + radius_px).ceil() as i32).min(framebuffer.height - 1);
+    if x0 > x1 || y0 > y1 {
+        return;
+    }
+
+    // Copia sin deformar de la zona (y de su radiancia, con HDR), muestreada con interpolación
+    // bilineal
+    framebuffer.resolve_msaa();
+    framebuffer.resolve_hdr();
+    let (w, h) = (x1 - x0 + 1, y1 - y0 + 1);
+    let mut source = Vec::with_capacity((w * h) as usize);
+    let mut hdr_source = Vec::new();
+    for y in y0..=y1 {
+        for x in x0..=x1 {
+            let c = framebuffer.color_buffer.get_color(x, y);
+            source.push(Vector3::new(c.r as f32, c.g as f32, c.b as f32) / 255.0);
+            if let Some(hdr) = framebuffer.hdr() {
+                hdr_source.push(hdr.get_color_at(x, y));
+            }
+        }
+    }
+    let sample = |source: &[Vector3], px: f32, py: f32| {
+        let (px, py) = (px.clamp(0.0, (w - 1) as f32), py.clamp(0.0, (h - 1) as f32));
+        let (ix, iy) = (px.floor() as i32, py.floor() as i32);
+        let (fx, fy) = (px - ix as f32, py - iy as f32);
+        let at = |x: i32, y: i32| source[(y.min(h - 1) * w + x.min(w - 1)) as usize];
+        at(ix, iy).lerp(at(ix + 1, iy), fx).lerp(at(ix, iy + 1).lerp(at(ix + 1, iy + 1), fx), fy)
+    };
+
+    let to_byte = |v: f32| (v.clamp(0.0, 1.0) * 255.0 + 0.5) as u8;
+    let foreground_depth = lens_distance - capture_radius;
+    for y in y0..=y1 {
+        for x in x0..=x1 {
+            let (dx, dy) = (x as f32 - cx, y as f32 - cy);
+            let dist_px = (dx * dx + dy * dy).sqrt();
+            if dist_px > radius_px {
+                continue;
+            }
+            let depth = framebuffer.get_depth_at(x, y);
+            if depth.is_finite() && linearize_depth(depth, clip_planes.near, clip_planes.far) < foreground_depth {
+                continue;
+            }
+            let theta = (dist_px / pixels_per_unit).atan();
+            let impact = lens_distance * theta.sin();
+            let from = if impact < capture_radius {
+                None
+            } else {
+                // Desviación de campo débil; se apaga hacia el borde para no dejar costura
+                let deflection = 4.0 * mass / impact * (1.0 - smoothstep(0.75, 1.0, theta / max_angle));
+                // β < 0: la imagen secundaria, tomada del otro lado del agujero
+                let source_angle = (theta - deflection).max(-max_angle);
+                let scale = if dist_px > 0.0 { source_angle.tan() * pixels_per_unit / dist_px } else { 0.0 };
+                Some((cx + dx * scale - x0 as f32, cy + dy * scale - y0 as f32))
+            };
+            let color = from.map_or(Vector3::zero(), |(px, py)| sample(&source, px, py));
+            framebuffer.color_buffer.draw_pixel(x, y, Color::new(to_byte(color.x), to_byte(color.y), to_byte(color.z), 255));
+            if let Some(hdr) = framebuffer.hdr_mut() {
+                hdr.set_color(x, y, from.map_or(Vector3::zero(), |(px, py)| sample(&hdr_source, px, py)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pipeline_config_round_trips_through_toml() {
+        let mut pipeline = PostProcessPipeline::with_default_passes();
+        pipeline.set_enabled("fxaa", true);
+        pipeline.reorder(&["vignette", "fxaa"]);
+        let path = std::env::temp_dir().join("proyecto3_postprocess.toml");
+        let path = path.to_string_lossy().into_owned();
+        pipeline.save(&path).unwrap();
+
+        let mut loaded = PostProcessPipeline::with_default_passes();
+        loaded.load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.to_toml(), pipeline.to_toml());
+        assert_eq!(loaded.enabled_names(), vec!["fxaa"]);
+    }
+
+    // Framebuffer con la profundidad lineal `depth(x, y)` (pasada a NDC) en cada píxel
+    fn depth_framebuffer(size: i32, clip_planes: ClipPlanes, depth: impl Fn(i32, i32) -> f32) -> Framebuffer {
+        let (near, far) = (clip_planes.near, clip_planes.far);
+        let mut framebuffer = Framebuffer::new(size, size);
+        for y in 0..size {
+            for x in 0..size {
+                framebuffer.depth_point(x, y, ((far + near) - 2.0 * far * near / depth(x, y)) / (far - near));
+            }
+        }
+        framebuffer
+    }
+
+    #[test]
+    fn concave_corner_is_more_occluded_than_a_flat_wall() {
+        let clip_planes = ClipPlanes { near: 1.0, far: 100.0 };
+        let (size, pixels_per_unit) = (64, 32.0);
+        // Pared de frente a 20 unidades frente a un rincón en V cuyo fondo, en la columna
+        // central, está a la misma distancia
+        let flat = depth_framebuffer(size, clip_planes, |_, _| 20.0);
+        let corner = depth_framebuffer(size, clip_planes, |x, _| 20.0 - 0.5 * (x - size / 2).abs() as f32);
+        let flat_visibility = ssao_pass(&flat, 16, 2.0, 0.02, pixels_per_unit, clip_planes);
+        let corner_visibility = ssao_pass(&corner, 16, 2.0, 0.02, pixels_per_unit, clip_planes);
+        let center = (size / 2 * size + size / 2) as usize;
+        assert!(flat_visibility[center] > 0.99, "la pared plana no debería ocluirse ({})", flat_visibility[center]);
+        assert!(corner_visibility[center] < flat_visibility[center] - 0.1, "rincón {} frente a plano {}", corner_visibility[center], flat_visibility[center]);
+    }
+
+    #[test]
+    fn ssao_darkens_the_hdr_radiance_too() {
+        let mut framebuffer = Framebuffer::new(2, 1);
+        framebuffer.set_hdr(Some(ToneMapping::Reinhard));
+        framebuffer.point_hdr(0, 0, Vector3::new(4.0, 2.0, 0.0), 0.5);
+        apply_ssao(&mut framebuffer, &[0.5, 1.0]);
+        assert_eq!(framebuffer.hdr().unwrap().get_color_at(0, 0), Vector3::new(2.0, 1.0, 0.0));
+        // Reinhard lleva 4.0 a 0.8 (204); la radiancia oscurecida no vuelve a pisar la imagen al resolver
+        framebuffer.resolve_hdr();
+        assert_eq!(framebuffer.color_buffer.get_color(0, 0).r, 102);
+    }
+
+    // Cadena aplicada a un degradado fijo de 32x32; devuelve los colores resultantes
+    fn run_pipeline(pipeline: &PostProcessPipeline) -> Vec<Color> {
+        let mut framebuffer = Framebuffer::new(32, 32);
+        for y in 0..32 {
+            for x in 0..32 {
+                framebuffer.color_buffer.draw_pixel(x, y, Color::new((x * 8) as u8, (y * 8) as u8, 128, 255));
+            }
+        }
+        pipeline.apply(&mut framebuffer);
+        (0..32 * 32).map(|i| framebuffer.color_buffer.get_color(i % 32, i / 32)).collect()
+    }
+
+    #[test]
+    fn removing_a_pass_changes_the_image_and_re_adding_it_restores_it() {
+        let mut pipeline = PostProcessPipeline::new();
+        pipeline.add(Box::new(TonemapPass { exposure: 1.2 }));
+        pipeline.add(Box::new(VignettePass { strength: 0.45, radius: 0.75 }));
+        let with_vignette = run_pipeline(&pipeline);
+
+        assert!(pipeline.remove_by_name("vignette"));
+        assert_eq!(pipeline.enabled_names(), vec!["tonemap"]);
+        assert_ne!(run_pipeline(&pipeline), with_vignette);
+
+        pipeline.add(Box::new(VignettePass { strength: 0.45, radius: 0.75 }));
+        assert_eq!(run_pipeline(&pipeline), with_vignette);
+    }
+
+    #[test]
+    fn einstein_ring_shows_what_is_directly_behind_the_lens() {
+        // Lente de masa 0.05 a 100 unidades: radio de Einstein √(4M/D) ≈ 11.4 px con 256 px por unidad
+        let (mass, distance, pixels_per_unit) = (0.05, 100.0, 256.0);
+        let camera = Camera::new(Vector3::zero(), Vector3::new(0.0, 0.0, -1.0), Vector3::new(0.0, 1.0, 0.0));
+        let ring_px = (4.0_f32 * mass / distance).sqrt().tan() * pixels_per_unit;
+        for (dir_x, dir_y) in [(1.0, 0.0), (-1.0, 0.0), (0.0, 1.0), (0.0, -1.0)] {
+            // El centro se coloca para que el píxel (64, 64) caiga justo sobre el anillo
+            let center = (64.0 - dir_x * ring_px, 64.0 - dir_y * ring_px);
+            let mut framebuffer = Framebuffer::new(128, 128);
+            // Fondo en degradado: el color de cada píxel dice de dónde se tomó
+            for y in 0..128 {
+                for x in 0..128 {
+                    framebuffer.color_buffer.draw_pixel(x, y, Color::new((x * 2) as u8, (y * 2) as u8, 0, 255));
+                }
+            }
+            gravity_lensing_pass(&mut framebuffer, center, Vector3::new(0.0, 0.0, -distance), mass, &camera, pixels_per_unit, ClipPlanes::default());
+            let ring = framebuffer.color_buffer.get_color(64, 64);
+            let (from_x, from_y) = (ring.r as f32 / 2.0, ring.g as f32 / 2.0);
+            assert!((from_x - center.0).abs() < 1.0 && (from_y - center.1).abs() < 1.0, "tomado de ({}, {}), centro en {:?}", from_x, from_y, center);
+            // Por dentro del anillo, la imagen secundaria: viene del otro lado del agujero
+            let inner = framebuffer.color_buffer.get_color((center.0 + dir_x * ring_px * 0.6).round() as i32, (center.1 + dir_y * ring_px * 0.6).round() as i32);
+            let along = (inner.r as f32 / 2.0 - center.0) * dir_x + (inner.g as f32 / 2.0 - center.1) * dir_y;
+            assert!(along < -1.0, "imagen secundaria a {} px del centro", along);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+// profiler.rs
+// ⏱️ Tiempo por etapa del render (en microsegundos), acumulado durante un frame
+use std::fs;
+use std::io;
+use std::time::Instant;
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct FrameProfiler {
+    pub vertex_transform_us: u64,
+    pub triangle_raster_us: u64,
+    pub fragment_shade_us: u64,
+    pub depth_test_us: u64,
+    // Conversión a 8 bits (dithering y cuantización) al escribir cada píxel
+    pub tonemap_us: u64,
+    // Subida de la imagen a textura y dibujado con raylib
+    pub present_us: u64,
+}
+
+impl FrameProfiler {
+    /// (nombre, microsegundos) de cada etapa, en orden del pipeline
+    pub fn stages(&self) -> [(&'static str, u64); 6] {
+        [
+            ("VERTEX", self.vertex_transform_us),
+            ("RASTER", self.triangle_raster_us),
+            ("SHADE", self.fragment_shade_us),
+            ("DEPTH", self.depth_test_us),
+            ("TONEMAP", self.tonemap_us),
+            ("PRESENT", self.present_us),
+        ]
+    }
+
+    pub fn total_us(&self) -> u64 {
+        self.stages().iter().map(|(_, us)| us).sum()
+    }
+
+    /// Suma los tiempos de otro perfil (p. ej. el del framebuffer de la vista cenital)
+    pub fn accumulate(&mut self, other: &FrameProfiler) {
+        self.vertex_transform_us += other.vertex_transform_us;
+        self.triangle_raster_us += other.triangle_raster_us;
+        self.fragment_shade_us += other.fragment_shade_us;
+        self.depth_test_us += other.depth_test_us;
+        self.tonemap_us += other.tonemap_us;
+        self.present_us += other.present_us;
+    }
+}
+
+// Microsegundos transcurridos desde `start`
+pub fn elapsed_us(start: Instant) -> u64 {
+    start.elapsed().as_micros() as u64
+}
+
+/// Traza en formato Chrome tracing (`chrome://tracing` o Perfetto): cada etapa de cada frame
+/// como un evento "X" consecutivo dentro del frame.
+pub struct ProfileTrace {
+    start: Instant,
+    events: Vec<String>,
+}
+
+impl ProfileTrace {
+    pub fn new() -> Self {
+        ProfileTrace { start: Instant::now(), events: Vec::new() }
+    }
+
+    pub fn record_frame(&mut self, frame_start: Instant, frame_us: u64, profile: &FrameProfiler) {
+        let frame_ts = frame_start.duration_since(self.start).as_micros() as u64;
+        self.events.push(format!(
+            "{{\"name\":\"frame\",\"ph\":\"X\",\"pid\":1,\"tid\":1,\"ts\":{},\"dur\":{}}}",
+            frame_ts, frame_us
+        ));
+        // Las etapas se acumulan durante el frame: se dibujan una tras otra desde su inicio
+        let mut ts = frame_ts;
+        for (name, us) in profile.stages() {
+            self.events.push(format!(
+                "{{\"name\":\"{}\",\"ph\":\"X\",\"pid\":1,\"tid\":2,\"ts\":{},\"dur\":{}}}",
+                name, ts, us
+            ));
+            ts += us;
+        }
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        fs::write(path, format!("{{\"traceEvents\":[\n{}\n]}}\n", self.events.join(",\n")))
+    }
+}
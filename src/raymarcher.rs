@@ -0,0 +1,168 @@
+// raymarcher.rs
+// ☁️ Volúmenes por ray-marching: por cada píxel que cubre el volumen se avanza a pasos fijos a lo
+// largo del rayo de la cámara, acumulando la emisión del gas atenuada por lo que ya se atravesó.
+// Sirve para la nube de polvo de fondo, que vista de cerca deja de parecer una cáscara pintada.
+use crate::camera::Camera;
+use crate::framebuffer::Framebuffer;
+use crate::matrix::multiply_matrix_vector4;
+use crate::transparency::{composite_transparent, BlendMode, TransparentItem};
+use raylib::math::{Matrix, Vector3, Vector4};
+
+// Emisión y absorción del gas por unidad de mundo y de densidad (ajustadas para una nube de
+// radio ~25: con densidad media el brillo acumulado queda por debajo de ~0.3)
+pub const VOLUME_EMISSION_COLOR: Vector3 = Vector3::new(0.011, 0.0035, 0.0025);
+pub const VOLUME_ABSORPTION: f32 = 0.03;
+// Se lanza un rayo por bloque de VOLUME_PIXEL_STRIDE x VOLUME_PIXEL_STRIDE píxeles
+const VOLUME_PIXEL_STRIDE: i32 = 2;
+
+pub struct Volume {
+    pub center: Vector3,
+    pub radius: f32,
+    pub rotation_y: f32, // giro alrededor del eje Y del centro
+    pub step_size: f32,  // en unidades de mundo
+    // Densidad en [0, 1] en el espacio del volumen: relativa al centro y en radios
+    pub density_fn: fn(Vector3) -> f32,
+}
+
+/// Color acumulado a lo largo del rayo a través de la esfera (`volume_center`, `volume_radius`).
+/// La marcha empieza donde el rayo entra en la esfera (o en `ray_origin` si ya está dentro) y
+/// recorre como mucho `volume_radius * 2`, muestreando en el punto medio de cada paso:
+/// `color += step_size * density * emission * transmittance`, con
+/// `transmittance *= exp(-density * step_size * absorption)`. `ray_dir` debe estar normalizada.
+pub fn raymarch_volume(ray_origin: Vector3, ray_dir: Vector3, volume_center: Vector3, volume_radius: f32, step_size: f32, density_fn: fn(Vector3) -> f32) -> Vector3 {
+    let mut color = Vector3::zero();
+    let Some((t_enter, t_exit)) = ray_sphere_span(ray_origin, ray_dir, volume_center, volume_radius) else {
+        return color;
+    };
+    let t_end = t_exit.min(t_enter + volume_radius * 2.0);
+    let mut transmittance = 1.0;
+    let mut t = t_enter;
+    while t < t_end && transmittance > 0.01 {
+        let step = step_size.min(t_end - t);
+        let sample = ray_origin + ray_dir * (t + step * 0.5);
+        let density = density_fn((sample - volume_center) / volume_radius);
+        if density > 0.0 {
+            color += VOLUME_EMISSION_COLOR * (step * density * transmittance);
+            transmittance *= (-density * step * VOLUME_ABSORPTION).exp();
+        }
+        t += step;
+    }
+    color
+}
+
+// Tramo [entrada, salida] del rayo dentro de la esfera, recortado a t >= 0
+fn ray_sphere_span(origin: Vector3, dir: Vector3, center: Vector3, radius: f32) -> Option<(f32, f32)> {
+    let to_center = center - origin;
+    let along = to_center.dot(dir);
+    let closest_sq = to_center.dot(to_center) - along * along;
+    if closest_sq >= radius * radius {
+        return None;
+    }
+    let half_chord = (radius * radius - closest_sq).sqrt();
+    let t_exit = along + half_chord;
+    (t_exit > 0.0).then(|| ((along - half_chord).max(0.0), t_exit))
+}
+
+// Giro de `v` alrededor del eje Y
+fn rotate_y(v: Vector3, angle: f32) -> Vector3 {
+    let (sin_a, cos_a) = angle.sin_cos();
+    Vector3::new(v.x * cos_a + v.z * sin_a, v.y, -v.x * sin_a + v.z * cos_a)
+}
+
+// Rectángulo de pantalla (x0, y0, x1, y1) que cubre la esfera, recortado a `viewport`: las
+// esquinas de su cubo envolvente proyectadas. Si alguna queda detrás de la cámara, toda la vista.
+fn screen_bounds(volume: &Volume, view_matrix: &Matrix, projection_matrix: &Matrix, viewport_matrix: &Matrix, viewport: (i32, i32, i32, i32)) -> (i32, i32, i32, i32) {
+    let (vx, vy, vw, vh) = viewport;
+    let (mut x0, mut y0, mut x1, mut y1) = (f32::INFINITY, f32::INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
+    for corner in 0..8 {
+        let sign = |bit: i32| if corner & bit != 0 { 1.0 } else { -1.0 };
+        let p = volume.center + Vector3::new(sign(1), sign(2), sign(4)) * volume.radius;
+        let view_pos = multiply_matrix_vector4(view_matrix, &Vector4::new(p.x, p.y, p.z, 1.0));
+        let clip = multiply_matrix_vector4(projection_matrix, &view_pos);
+        if clip.w <= 0.0 {
+            return (vx, vy, vx + vw - 1, vy + vh - 1);
+        }
+        let ndc = Vector4::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w, 1.0);
+        let screen = multiply_matrix_vector4(viewport_matrix, &ndc);
+        x0 = x0.min(screen.x);
+        y0 = y0.min(screen.y);
+        x1 = x1.max(screen.x);
+        y1 = y1.max(screen.y);
+    }
+    (
+        (x0.floor() as i32).max(vx),
+        (y0.floor() as i32).max(vy),
+        (x1.ceil() as i32).min(vx + vw - 1),
+        (y1.ceil() as i32).min(vy + vh - 1),
+    )
+}
+
+/// Dibuja el volumen en la vista en perspectiva (`viewport`, `fov`) sumando su emisión a lo que
+/// ya hay en el framebuffer, detrás de todo (se dibuja como fondo, antes que el resto).
+#[allow(clippy::too_many_arguments)]
+pub fn render_volume(framebuffer: &mut Framebuffer, volume: &Volume, camera: &Camera, view_matrix: &Matrix, projection_matrix: &Matrix, viewport_matrix: &Matrix, viewport: (i32, i32, i32, i32), fov: f32) {
+    let (x0, y0, x1, y1) = screen_bounds(volume, view_matrix, projection_matrix, viewport_matrix, viewport);
+    // La densidad gira con el volumen: se lleva el rayo a su espacio en vez de girar el ruido
+    let origin = rotate_y(camera.eye - volume.center, -volume.rotation_y);
+    let mut items = Vec::new();
+    for by in (y0..=y1).step_by(VOLUME_PIXEL_STRIDE as usize) {
+        for bx in (x0..=x1).step_by(VOLUME_PIXEL_STRIDE as usize) {
+            let center_x = bx + VOLUME_PIXEL_STRIDE / 2;
+            let center_y = by + VOLUME_PIXEL_STRIDE / 2;
+            let dir = rotate_y(camera.ray_through_pixel(center_x, center_y, viewport, fov), -volume.rotation_y);
+            let color = raymarch_volume(origin, dir, Vector3::zero(), volume.radius, volume.step_size, volume.density_fn);
+            if color.x + color.y + color.z <= 0.0 {
+                continue;
+            }
+            for y in by..(by + VOLUME_PIXEL_STRIDE).min(y1 + 1) {
+                for x in bx..(bx + VOLUME_PIXEL_STRIDE).min(x1 + 1) {
+                    items.push(TransparentItem { depth: f32::MAX, x, y, color, alpha: 1.0, blend: BlendMode::Additive });
+                }
+            }
+        }
+    }
+    composite_transparent(framebuffer, &mut items);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::shaders::nebula_volume_density;
+
+    #[test]
+    fn dust_cloud_stays_translucent_along_every_ray() {
+        // La nube de fondo: radio 25 y 64 muestras para cruzarla por el centro
+        let (radius, step) = (25.0, 25.0 / 32.0);
+        let mut rng = fastrand::Rng::with_seed(410);
+        let mut brightest: f32 = 0.0;
+        for _ in 0..400 {
+            let mut random_dir = || Vector3::new(rng.f32() - 0.5, rng.f32() - 0.5, rng.f32() - 0.5).normalized();
+            // Desde fuera hacia un punto cualquiera de la nube, o desde dentro en cualquier dirección
+            let origin = random_dir() * 80.0;
+            let aim = random_dir() * 20.0;
+            for (from, dir) in [(origin, (aim - origin).normalized()), (aim * 0.5, random_dir())] {
+                let color = raymarch_volume(from, dir, Vector3::zero(), radius, step, nebula_volume_density);
+                brightest = brightest.max(color.x).max(color.y).max(color.z);
+            }
+        }
+        assert!(brightest > 0.0);
+        assert!(brightest < 0.3, "brillo acumulado {}", brightest);
+    }
+
+    #[test]
+    fn halving_the_step_barely_changes_the_color() {
+        // El paso de la nebulosa de fondo (radio / 32) frente a la mitad
+        let (radius, step) = (25.0, 25.0 / 32.0);
+        let mut rng = fastrand::Rng::with_seed(419);
+        for _ in 0..50 {
+            let origin = Vector3::new(rng.f32() - 0.5, rng.f32() - 0.5, rng.f32() - 0.5).normalized() * 80.0;
+            let aim = Vector3::new(rng.f32() - 0.5, rng.f32() - 0.5, rng.f32() - 0.5) * 20.0;
+            let dir = (aim - origin).normalized();
+            let coarse = raymarch_volume(origin, dir, Vector3::zero(), radius, step, nebula_volume_density);
+            let fine = raymarch_volume(origin, dir, Vector3::zero(), radius, step * 0.5, nebula_volume_density);
+            let change = (fine - coarse).length() / fine.length().max(1e-6);
+            assert!(change < 0.05, "cambio relativo {} ({:?} frente a {:?})", change, coarse, fine);
+        }
+    }
+}
@@ -0,0 +1,194 @@
+// render_queue.rs
+// 🗂️ Cola de dibujo de una vista: cada elemento lleva una prioridad y `flush` los dibuja de menor
+// a mayor (cielo, cuerpos opacos, lo translúcido), así el orden no depende de en qué orden se
+// fueron encolando. Dentro de la misma prioridad se respeta el orden de llegada.
+use crate::camera::Camera;
+use crate::framebuffer::Framebuffer;
+use crate::raymarcher::{render_volume, Volume};
+use crate::scripted_shader::ScriptShaderCache;
+use crate::transparency::{composite_transparent, TransparentItem};
+use crate::vertex::Vertex;
+use crate::{depth_prepass_render, draw_orbit_3d, draw_orbit_trail, render, render_skybox, OrbitTrail, RenderMode, Uniforms, ViewProjection};
+use raylib::prelude::{Color, Matrix, Vector3};
+
+pub const PRIORITY_SKYBOX: i32 = -100;
+pub const PRIORITY_OPAQUE: i32 = 0;
+pub const PRIORITY_TRANSPARENT: i32 = 100;
+
+// Una malla con su shader
+pub struct SurfaceDraw<'a> {
+    pub uniforms: Uniforms<'a>,
+    pub vertices: &'a [Vertex],
+    pub shader: &'a str,
+    pub script_shaders: Option<&'a ScriptShaderCache>,
+}
+
+// Órbita circular (inclinada) alrededor de `center`, ver `draw_orbit_3d`
+pub struct OrbitLine {
+    pub center: Vector3,
+    pub radius: f32,
+    pub inclination: f32,
+    pub ascending_node: f32,
+    pub segments: u32,
+    pub color: Color,
+    pub dashed: bool,
+}
+
+pub enum RenderKind<'a> {
+    // Estrellas en `time` y, delante de ellas, la nebulosa (solo en perspectiva)
+    Skybox { time: f32, nebula: Option<Volume> },
+    OpaqueBody(SurfaceDraw<'a>),
+    TransparentSurface(SurfaceDraw<'a>),
+    OrbitLine(OrbitLine),
+    Trail(&'a OrbitTrail),
+}
+
+pub struct RenderItem<'a> {
+    pub priority: i32,
+    pub kind: RenderKind<'a>,
+}
+
+impl<'a> RenderItem<'a> {
+    pub fn skybox(time: f32, nebula: Option<Volume>) -> Self {
+        RenderItem { priority: PRIORITY_SKYBOX, kind: RenderKind::Skybox { time, nebula } }
+    }
+
+    pub fn opaque(surface: SurfaceDraw<'a>) -> Self {
+        RenderItem { priority: PRIORITY_OPAQUE, kind: RenderKind::OpaqueBody(surface) }
+    }
+
+    pub fn transparent(surface: SurfaceDraw<'a>) -> Self {
+        RenderItem { priority: PRIORITY_TRANSPARENT, kind: RenderKind::TransparentSurface(surface) }
+    }
+
+    pub fn orbit_line(line: OrbitLine) -> Self {
+        RenderItem { priority: PRIORITY_TRANSPARENT, kind: RenderKind::OrbitLine(line) }
+    }
+
+    pub fn trail(trail: &'a OrbitTrail) -> Self {
+        RenderItem { priority: PRIORITY_TRANSPARENT, kind: RenderKind::Trail(trail) }
+    }
+}
+
+// Vista en la que se vacía la cola
+pub struct RenderTarget<'a> {
+    pub camera: &'a Camera,
+    pub view_matrix: Matrix,
+    pub projection_matrix: Matrix,
+    pub viewport_matrix: Matrix,
+    pub viewport: (i32, i32, i32, i32),
+    pub projection: ViewProjection,
+    pub render_mode: RenderMode,
+}
+
+#[derive(Default)]
+pub struct RenderQueue<'a> {
+    pub items: Vec<RenderItem<'a>>,
+}
+
+impl<'a> RenderQueue<'a> {
+    pub fn new() -> Self {
+        RenderQueue { items: Vec::new() }
+    }
+
+    pub fn push(&mut self, item: RenderItem<'a>) {
+        self.items.push(item);
+    }
+
+    /// Ordena por prioridad (estable).
+    pub fn sort(&mut self) {
+        self.items.sort_by_key(|item| item.priority);
+    }
+
+    /// Dibuja todo en orden de prioridad. Al llegar a lo opaco, en diferido se hace primero la
+    /// pasada de profundidad de todos los cuerpos opacos (con MSAA no: va en Forward); al pasar de lo opaco se resuelve el MSAA
+    /// y lo translúcido se junta y se compone al final: primero las capas por píxel del
+    /// framebuffer (anillos, atmósferas) y encima el resto, ordenado por profundidad.
+    pub fn flush(mut self, framebuffer: &mut Framebuffer, target: &RenderTarget) {
+        self.sort();
+        let mut transparent: Vec<TransparentItem> = Vec::new();
+        let render_mode = target.render_mode.effective(framebuffer.msaa_level);
+        let mut depth_prepass_done = render_mode != RenderMode::Deferred;
+        let mut opaque_resolved = false;
+        for item in &self.items {
+            if !depth_prepass_done && item.priority >= PRIORITY_OPAQUE {
+                for other in &self.items {
+                    if let RenderKind::OpaqueBody(surface) = &other.kind {
+                        depth_prepass_render(framebuffer, &surface.uniforms, surface.vertices);
+                    }
+                }
+                depth_prepass_done = true;
+            }
+            // Con MSAA, lo opaco se promedia ya para que lo translúcido se mezcle encima
+            if !opaque_resolved && item.priority > PRIORITY_OPAQUE {
+                framebuffer.resolve_msaa();
+                opaque_resolved = true;
+            }
+            match &item.kind {
+                RenderKind::Skybox { time, nebula } => {
+                    if let (Some(volume), ViewProjection::Perspective { fov }) = (nebula, target.projection) {
+                        render_volume(framebuffer, volume, target.camera, &target.view_matrix, &target.projection_matrix, &target.viewport_matrix, target.viewport, fov);
+                    }
+                    render_skybox(framebuffer, &target.view_matrix, &target.projection_matrix, &target.viewport_matrix, *time);
+                }
+                RenderKind::OpaqueBody(surface) => {
+                    render(framebuffer, &surface.uniforms, surface.vertices, surface.shader, surface.script_shaders, render_mode, &mut transparent);
+                }
+                RenderKind::TransparentSurface(surface) => {
+                    render(framebuffer, &surface.uniforms, surface.vertices, surface.shader, surface.script_shaders, RenderMode::Forward, &mut transparent);
+                }
+                RenderKind::OrbitLine(line) => {
+                    draw_orbit_3d(&mut transparent, line.center, line.radius, line.inclination, line.ascending_node, line.segments, line.color, line.dashed, &target.view_matrix, &target.projection_matrix, &target.viewport_matrix);
+                }
+                RenderKind::Trail(trail) => {
+                    draw_orbit_trail(&mut transparent, trail, &target.view_matrix, &target.projection_matrix, &target.viewport_matrix);
+                }
+            }
+        }
+        if !opaque_resolved {
+            framebuffer.resolve_msaa();
+        }
+        framebuffer.resolve_transparency();
+        composite_transparent(framebuffer, &mut transparent);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exploration::{ExploredGrid, EXPLORATION_GRID_SIZE};
+
+    fn orbit(radius: f32) -> OrbitLine {
+        OrbitLine { center: Vector3::zero(), radius, inclination: 0.0, ascending_node: 0.0, segments: 16, color: Color::WHITE, dashed: false }
+    }
+
+    // Qué se dibujaría en cada posición: la prioridad y, para las órbitas, su radio
+    fn order(queue: &RenderQueue) -> Vec<(i32, Option<f32>)> {
+        queue
+            .items
+            .iter()
+            .map(|item| (item.priority, if let RenderKind::OrbitLine(line) = &item.kind { Some(line.radius) } else { None }))
+            .collect()
+    }
+
+    #[test]
+    fn sort_goes_from_the_skybox_to_the_transparent_items_keeping_arrival_order() {
+        const GRID: ExploredGrid = [[true; EXPLORATION_GRID_SIZE]; EXPLORATION_GRID_SIZE];
+        let surface = || SurfaceDraw { uniforms: Uniforms::for_tests(&GRID), vertices: &[], shader: "Earth", script_shaders: None };
+        let mut queue = RenderQueue::new();
+        queue.push(RenderItem::orbit_line(orbit(1.0)));
+        queue.push(RenderItem::opaque(surface()));
+        queue.push(RenderItem { priority: 50, kind: RenderKind::OrbitLine(orbit(2.0)) });
+        queue.push(RenderItem::skybox(0.0, None));
+        queue.push(RenderItem::orbit_line(orbit(3.0)));
+        queue.push(RenderItem::transparent(surface()));
+        queue.sort();
+        assert_eq!(
+            order(&queue),
+            vec![(PRIORITY_SKYBOX, None), (PRIORITY_OPAQUE, None), (50, Some(2.0)), (PRIORITY_TRANSPARENT, Some(1.0)), (PRIORITY_TRANSPARENT, Some(3.0)), (PRIORITY_TRANSPARENT, None)]
+        );
+        assert!(matches!(queue.items[0].kind, RenderKind::Skybox { .. }));
+        assert!(matches!(queue.items[1].kind, RenderKind::OpaqueBody(_)));
+        assert!(matches!(queue.items[5].kind, RenderKind::TransparentSurface(_)));
+    }
+}
@@ -0,0 +1,185 @@
+// sandbox.rs
+// 🪐 Sandbox orbital: cuerpos colocados por el usuario que se mueven por gravedad N-cuerpos
+// alrededor del Sol (fijo en el origen) y entre sí.
+use raylib::math::Vector3;
+use std::fs;
+use std::io;
+
+// Parámetro gravitatorio del Sol (G·M). Con 500 la órbita circular a 25 unidades (la de la
+// Tierra) tiene velocidad sqrt(500 / 25) ≈ 4.47 u/s y un periodo de unos 35 s.
+pub const SUN_GM: f32 = 500.0;
+// G·m de cada cuerpo nuevo: pequeño frente al Sol, pero se atraen entre ellos
+pub const BODY_GM: f32 = 1.0;
+// Paso fijo de integración (s de simulación) y pasos de la órbita prevista
+pub const STEP: f32 = 0.02;
+pub const PREDICTION_STEPS: usize = 500;
+// Suavizado para que dos cuerpos muy cercanos no se disparen
+const SOFTENING: f32 = 0.5;
+// Sin pasos por frame ilimitados cuando la escala de tiempo es muy alta
+const MAX_STEPS_PER_UPDATE: usize = 2000;
+
+#[derive(Clone, Copy, Debug)]
+pub struct SandboxBody {
+    pub position: Vector3,
+    pub velocity: Vector3,
+    pub gm: f32,
+}
+
+pub struct Sandbox {
+    pub bodies: Vec<SandboxBody>,
+    accumulator: f32,
+}
+
+// Aceleración en `position` por el Sol y por los cuerpos del sandbox (salvo `skip`)
+fn acceleration_at(position: Vector3, bodies: &[SandboxBody], skip: Option<usize>) -> Vector3 {
+    let mut acceleration = attraction(position, Vector3::zero(), SUN_GM);
+    for (i, body) in bodies.iter().enumerate() {
+        if Some(i) != skip {
+            acceleration += attraction(position, body.position, body.gm);
+        }
+    }
+    acceleration
+}
+
+fn attraction(position: Vector3, source: Vector3, gm: f32) -> Vector3 {
+    let offset = source - position;
+    let distance_sq = offset.dot(offset) + SOFTENING * SOFTENING;
+    offset * (gm / (distance_sq * distance_sq.sqrt()))
+}
+
+/// Un paso de velocity Verlet para todos los cuerpos. Es simpléctico: la energía oscila en vez
+/// de derivar, así que las órbitas cerradas no se abren ni caen con el tiempo.
+pub fn step_bodies(bodies: &mut [SandboxBody], dt: f32) {
+    let accelerations: Vec<Vector3> = (0..bodies.len()).map(|i| acceleration_at(bodies[i].position, bodies, Some(i))).collect();
+    for (body, acceleration) in bodies.iter_mut().zip(&accelerations) {
+        body.position += body.velocity * dt + *acceleration * (0.5 * dt * dt);
+    }
+    let new_accelerations: Vec<Vector3> = (0..bodies.len()).map(|i| acceleration_at(bodies[i].position, bodies, Some(i))).collect();
+    for ((body, a0), a1) in bodies.iter_mut().zip(&accelerations).zip(&new_accelerations) {
+        body.velocity += (*a0 + *a1) * (0.5 * dt);
+    }
+}
+
+/// Velocidad de órbita circular alrededor del Sol a la distancia `radius`
+pub fn circular_speed(radius: f32) -> f32 {
+    (SUN_GM / radius.max(0.001)).sqrt()
+}
+
+impl Sandbox {
+    pub fn new() -> Self {
+        Sandbox { bodies: Vec::new(), accumulator: 0.0 }
+    }
+
+    pub fn add_body(&mut self, position: Vector3, velocity: Vector3) {
+        self.bodies.push(SandboxBody { position, velocity, gm: BODY_GM });
+    }
+
+    pub fn remove_body(&mut self, index: usize) {
+        if index < self.bodies.len() {
+            self.bodies.remove(index);
+        }
+    }
+
+    /// Avanza la simulación `dt` segundos con pasos fijos de `STEP`. Con `dt` negativo no hace
+    /// nada: la gravedad N-cuerpos no se puede recalcular hacia atrás como las órbitas fijas.
+    pub fn update(&mut self, dt: f32) {
+        if dt <= 0.0 {
+            return;
+        }
+        self.accumulator += dt;
+        let mut steps = 0;
+        while self.accumulator >= STEP && steps < MAX_STEPS_PER_UPDATE {
+            step_bodies(&mut self.bodies, STEP);
+            self.accumulator -= STEP;
+            steps += 1;
+        }
+        if steps == MAX_STEPS_PER_UPDATE {
+            self.accumulator = 0.0;
+        }
+    }
+
+    /// Órbita prevista de un cuerpo que empezara en `position` con `velocity`, integrando
+    /// `PREDICTION_STEPS` pasos junto con los cuerpos actuales.
+    pub fn predict_orbit(&self, position: Vector3, velocity: Vector3) -> Vec<Vector3> {
+        let mut bodies = self.bodies.clone();
+        bodies.push(SandboxBody { position, velocity, gm: BODY_GM });
+        let index = bodies.len() - 1;
+        let mut path = Vec::with_capacity(PREDICTION_STEPS + 1);
+        path.push(position);
+        for _ in 0..PREDICTION_STEPS {
+            step_bodies(&mut bodies, STEP * 4.0);
+            path.push(bodies[index].position);
+        }
+        path
+    }
+
+    /// Guarda el sandbox como texto: una línea "px py pz vx vy vz gm" por cuerpo.
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut contents = String::with_capacity(self.bodies.len() * 64);
+        for b in &self.bodies {
+            contents.push_str(&format!(
+                "{} {} {} {} {} {} {}\n",
+                b.position.x, b.position.y, b.position.z, b.velocity.x, b.velocity.y, b.velocity.z, b.gm
+            ));
+        }
+        fs::write(path, contents)
+    }
+
+    /// Carga un sandbox guardado con `save`, reemplazando los cuerpos actuales.
+    pub fn load(&mut self, path: &str) -> io::Result<()> {
+        let contents = fs::read_to_string(path)?;
+        let mut bodies = Vec::new();
+        for line in contents.lines().filter(|l| !l.trim().is_empty()) {
+            let values = line
+                .split_whitespace()
+                .map(|v| v.parse::<f32>())
+                .collect::<Result<Vec<f32>, _>>()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("bad sandbox line: {} ({})", line, e)))?;
+            // gm es opcional para poder leer los guardados anteriores, de seis columnas
+            if values.len() != 6 && values.len() != 7 {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!("bad sandbox line: {}", line)));
+            }
+            bodies.push(SandboxBody {
+                position: Vector3::new(values[0], values[1], values[2]),
+                velocity: Vector3::new(values[3], values[4], values[5]),
+                gm: values.get(6).copied().unwrap_or(BODY_GM),
+            });
+        }
+        self.bodies = bodies;
+        self.accumulator = 0.0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn circular_speed_at_earth_radius_keeps_a_stable_orbit() {
+        let radius = 25.0;
+        let mut bodies = [SandboxBody { position: Vector3::new(radius, 0.0, 0.0), velocity: Vector3::new(0.0, 0.0, circular_speed(radius)), gm: BODY_GM }];
+        for _ in 0..1000 {
+            step_bodies(&mut bodies, STEP);
+            let distance = bodies[0].position.length();
+            assert!((distance - radius).abs() < radius * 0.02, "radio {}", distance);
+            assert!(bodies[0].position.y.abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn load_rejects_a_corrupt_value_instead_of_shifting_columns() {
+        let path = std::env::temp_dir().join("proyecto3_sandbox_corrupt.txt");
+        let path = path.to_string_lossy().into_owned();
+        let mut sandbox = Sandbox::new();
+        for contents in ["1 2 3 x 5 6 7\n", "1 2 3 4 5\n", "1 2 3 4 5 6 7 8\n"] {
+            fs::write(&path, contents).unwrap();
+            let error = sandbox.load(&path).err().unwrap();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{}", contents);
+        }
+        fs::write(&path, "1 2 3 4 5 6\n").unwrap();
+        sandbox.load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(sandbox.bodies[0].gm, BODY_GM);
+    }
+}
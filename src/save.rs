@@ -0,0 +1,185 @@
+// save.rs
+// 💾 Partida guardada en `save.json`: cuerpos visitados, distancia recorrida por la cámara, tiempo
+// simulado, historial de posiciones y logros. Se guarda sola cada AUTOSAVE_INTERVAL segundos
+// reales y al cerrar; `SaveTracker` lleva lo que no se guarda (ventana del Speed Demon, relojes).
+use crate::{body_position, is_sun, CelestialBody};
+use raylib::prelude::Vector3;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{HashSet, VecDeque};
+use std::fs;
+use std::io;
+
+pub const SAVE_PATH: &str = "save.json";
+// Segundos reales entre guardados automáticos
+pub const AUTOSAVE_INTERVAL: f32 = 60.0;
+// Un cuerpo cuenta como visitado con la cámara a menos de estas veces su escala del centro
+const VISIT_RANGE_SCALES: f32 = 5.0;
+// Un punto del historial de la cámara por segundo real, hasta los últimos diez minutos
+const CAMERA_HISTORY_INTERVAL: f32 = 1.0;
+const CAMERA_HISTORY_LENGTH: usize = 600;
+// Speed Demon: más de esta distancia en menos de esta ventana (s reales), con tiempo a x1
+const SPEED_DEMON_DISTANCE: f32 = 1000.0;
+const SPEED_DEMON_WINDOW: f32 = 10.0;
+
+pub const ACHIEVEMENT_FIRST_CONTACT: &str = "First Contact";
+pub const ACHIEVEMENT_GRAND_TOUR: &str = "Grand Tour";
+pub const ACHIEVEMENT_SPEED_DEMON: &str = "Speed Demon";
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct GameSave {
+    pub visited_bodies: HashSet<String>,
+    pub total_distance_traveled: f32,
+    pub simulation_time_elapsed: f32,
+    // (tiempo simulado, posición de la cámara)
+    #[serde(with = "camera_history_format")]
+    pub camera_history: Vec<(f32, Vector3)>,
+    pub achievements: Vec<String>,
+}
+
+// Vector3 de raylib no es serializable: el historial se escribe como [t, [x, y, z]]
+mod camera_history_format {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(history: &[(f32, Vector3)], serializer: S) -> Result<S::Ok, S::Error> {
+        let points: Vec<(f32, [f32; 3])> = history.iter().map(|&(t, p)| (t, [p.x, p.y, p.z])).collect();
+        points.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<(f32, Vector3)>, D::Error> {
+        let points: Vec<(f32, [f32; 3])> = Vec::deserialize(deserializer)?;
+        Ok(points.into_iter().map(|(t, [x, y, z])| (t, Vector3::new(x, y, z))).collect())
+    }
+}
+
+impl GameSave {
+    pub fn load(path: &str) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        serde_json::from_str(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, err)))
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let text = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, text)
+    }
+
+    // Devuelve true si el logro es nuevo
+    fn unlock(&mut self, achievement: &str) -> bool {
+        if self.achievements.iter().any(|a| a == achievement) {
+            return false;
+        }
+        self.achievements.push(achievement.to_string());
+        true
+    }
+}
+
+// Planetas para los logros: lo que orbita directamente al Sol, sin contar estrellas ni agujeros
+fn is_planet(body: &CelestialBody) -> bool {
+    body.parent.is_none() && !is_sun(body) && body.shader_name != "BlackHole"
+}
+
+#[derive(Default)]
+pub struct SaveTracker {
+    // (tiempo real, distancia) de cada frame dentro de la ventana del Speed Demon
+    recent_moves: VecDeque<(f32, f32)>,
+    last_history_time: Option<f32>,
+    since_autosave: f32,
+}
+
+impl SaveTracker {
+    /// Apunta el movimiento de la cámara de `previous_eye` a `eye` y los cuerpos a los que se ha
+    /// acercado. `frame_dt` y `real_time` son tiempo real, `dt` y `time` simulado. Con `teleported`
+    /// (warp, empujón de una colisión o vuelta a la posición segura) la cámara no ha volado y el
+    /// salto no cuenta para la distancia ni para el Speed Demon. Devuelve los logros desbloqueados
+    /// en este frame.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update(&mut self, save: &mut GameSave, bodies: &[CelestialBody], previous_eye: Vector3, eye: Vector3, teleported: bool, frame_dt: f32, dt: f32, real_time: f32, time: f32, time_scale: f32) -> Vec<&'static str> {
+        let moved = if teleported { 0.0 } else { (eye - previous_eye).length() };
+        save.total_distance_traveled += moved;
+        save.simulation_time_elapsed += dt;
+        self.since_autosave += frame_dt;
+
+        if self.last_history_time.is_none_or(|last| real_time - last >= CAMERA_HISTORY_INTERVAL) {
+            self.last_history_time = Some(real_time);
+            save.camera_history.push((time, eye));
+            if save.camera_history.len() > CAMERA_HISTORY_LENGTH {
+                save.camera_history.remove(0);
+            }
+        }
+
+        let mut unlocked = Vec::new();
+        for body in bodies {
+            if (eye - body_position(body, time)).length() < body.scale * VISIT_RANGE_SCALES && save.visited_bodies.insert(body.name.clone()) && is_planet(body) && save.unlock(ACHIEVEMENT_FIRST_CONTACT) {
+                unlocked.push(ACHIEVEMENT_FIRST_CONTACT);
+            }
+        }
+        let mut planets = bodies.iter().filter(|body| is_planet(body)).peekable();
+        if planets.peek().is_some() && planets.all(|body| save.visited_bodies.contains(&body.name)) && save.unlock(ACHIEVEMENT_GRAND_TOUR) {
+            unlocked.push(ACHIEVEMENT_GRAND_TOUR);
+        }
+
+        // Con el tiempo acelerado o frenado no vale: se vacía la ventana
+        if time_scale == 1.0 {
+            self.recent_moves.push_back((real_time, moved));
+            while self.recent_moves.front().is_some_and(|&(t, _)| real_time - t > SPEED_DEMON_WINDOW) {
+                self.recent_moves.pop_front();
+            }
+            let window_distance: f32 = self.recent_moves.iter().map(|&(_, d)| d).sum();
+            if window_distance > SPEED_DEMON_DISTANCE && save.unlock(ACHIEVEMENT_SPEED_DEMON) {
+                unlocked.push(ACHIEVEMENT_SPEED_DEMON);
+            }
+        } else {
+            self.recent_moves.clear();
+        }
+        unlocked
+    }
+
+    /// true una vez cada AUTOSAVE_INTERVAL segundos reales.
+    pub fn autosave_due(&mut self) -> bool {
+        if self.since_autosave < AUTOSAVE_INTERVAL {
+            return false;
+        }
+        self.since_autosave = 0.0;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let mut save = GameSave::default();
+        save.visited_bodies.insert("Earth".to_string());
+        save.total_distance_traveled = 1234.5;
+        save.simulation_time_elapsed = 67.0;
+        save.camera_history.push((1.5, Vector3::new(1.0, 2.0, 3.0)));
+        save.unlock(ACHIEVEMENT_FIRST_CONTACT);
+
+        let path = std::env::temp_dir().join("proyecto3_save_round_trip.json");
+        let path = path.to_string_lossy().into_owned();
+        save.save(&path).unwrap();
+        let loaded = GameSave::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.visited_bodies, save.visited_bodies);
+        assert_eq!(loaded.total_distance_traveled, 1234.5);
+        assert_eq!(loaded.simulation_time_elapsed, 67.0);
+        assert_eq!(loaded.camera_history, vec![(1.5, Vector3::new(1.0, 2.0, 3.0))]);
+        assert_eq!(loaded.achievements, vec![ACHIEVEMENT_FIRST_CONTACT.to_string()]);
+    }
+
+    #[test]
+    fn teleports_do_not_count_as_distance() {
+        let mut save = GameSave::default();
+        let mut tracker = SaveTracker::default();
+        let (start, far) = (Vector3::new(0.0, 500.0, 0.0), Vector3::new(5000.0, 500.0, 0.0));
+        let unlocked = tracker.update(&mut save, &[], start, far, true, 0.1, 0.1, 0.1, 0.1, 1.0);
+        assert!(unlocked.is_empty());
+        assert_eq!(save.total_distance_traveled, 0.0);
+
+        let unlocked = tracker.update(&mut save, &[], far, start, false, 0.1, 0.1, 0.2, 0.2, 1.0);
+        assert_eq!(unlocked, vec![ACHIEVEMENT_SPEED_DEMON]);
+        assert_eq!(save.total_distance_traveled, 5000.0);
+    }
+}
@@ -0,0 +1,120 @@
+// scripted_shader.rs
+// Shaders de planeta escritos en Rhai: se cargan en tiempo de ejecución y se recargan al guardarlos
+
+use crate::fragment::Fragment;
+use crate::Uniforms;
+use raylib::prelude::*;
+use rhai::{Array, Dynamic, Engine, Scope, AST};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::time::SystemTime;
+
+// Prefijo de `planet_type` que indica un shader en script: "Script:archivo.rhai"
+pub const SCRIPT_SHADER_PREFIX: &str = "Script:";
+
+pub struct ScriptShaderCache {
+    engine: Engine,
+    scripts: HashMap<String, AST>,
+    // Fecha de modificación de cada archivo en el último intento (`None` si no existía), para
+    // reintentar solo cuando aparece o cambia
+    modified: HashMap<String, Option<SystemTime>>,
+    // Scripts cuyo error de ejecución ya se avisó (se avisa una vez por carga)
+    reported_errors: RefCell<HashSet<String>>,
+}
+
+impl ScriptShaderCache {
+    pub fn new() -> Self {
+        ScriptShaderCache {
+            engine: Engine::new(),
+            scripts: HashMap::new(),
+            modified: HashMap::new(),
+            reported_errors: RefCell::new(HashSet::new()),
+        }
+    }
+
+    /// Compila `path` si no se había intentado o si el archivo apareció o cambió desde la última
+    /// vez. Si no se puede leer o compilar, se descarta y el planeta usa el shader por defecto.
+    pub fn refresh(&mut self, path: &str) {
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+        if self.modified.get(path) == Some(&modified) {
+            return;
+        }
+        self.modified.insert(path.to_string(), modified);
+        self.reported_errors.borrow_mut().remove(path);
+        let compiled = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|source| self.engine.compile(source).map_err(|e| e.to_string()));
+        match compiled {
+            Ok(ast) => {
+                eprintln!("Loaded shader script {}", path);
+                self.scripts.insert(path.to_string(), ast);
+            }
+            Err(e) => {
+                eprintln!("Warning: shader script {} failed: {}", path, e);
+                self.scripts.remove(path);
+            }
+        }
+    }
+
+    /// Ejecuta el script con `pos` y `normal` como arrays [x, y, z] y `time`; el script
+    /// devuelve [r, g, b]. `None` si el script no está cargado o falla.
+    pub fn shade(&self, path: &str, fragment: &Fragment, uniforms: &Uniforms) -> Option<Vector3> {
+        let ast = self.scripts.get(path)?;
+        let to_array = |v: Vector3| -> Array {
+            vec![Dynamic::from_float(v.x as rhai::FLOAT), Dynamic::from_float(v.y as rhai::FLOAT), Dynamic::from_float(v.z as rhai::FLOAT)]
+        };
+        let mut scope = Scope::new();
+        scope.push("pos", to_array(fragment.world_position));
+        scope.push("normal", to_array(fragment.normal));
+        scope.push("time", uniforms.time as rhai::FLOAT);
+
+        let result = match self.engine.eval_ast_with_scope::<Array>(&mut scope, ast) {
+            Ok(result) => result,
+            Err(e) => {
+                if self.reported_errors.borrow_mut().insert(path.to_string()) {
+                    eprintln!("Warning: shader script {} failed: {}", path, e);
+                }
+                return None;
+            }
+        };
+        let channel = |i: usize| result.get(i).and_then(|c| c.as_float().ok()).map(|c| c as f32);
+        Some(Vector3::new(channel(0)?, channel(1)?, channel(2)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exploration::{ExploredGrid, EXPLORATION_GRID_SIZE};
+
+    #[test]
+    fn red_script_shades_red() {
+        let path = std::env::temp_dir().join("proyecto3_red_shader.rhai");
+        fs::write(&path, "[1.0, 0.0, 0.0]").unwrap();
+        let path = path.to_string_lossy().into_owned();
+        let mut cache = ScriptShaderCache::new();
+        cache.refresh(&path);
+        let grid: ExploredGrid = [[true; EXPLORATION_GRID_SIZE]; EXPLORATION_GRID_SIZE];
+        let uniforms = Uniforms::for_tests(&grid);
+        let fragment = Fragment::new(0.0, 0.0, Vector3::zero(), 0.5, Vector3::new(0.0, 1.0, 0.0), Vector3::new(0.0, 1.0, 0.0), Vector3::one());
+        assert_eq!(cache.shade(&path, &fragment, &uniforms), Some(Vector3::new(1.0, 0.0, 0.0)));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn missing_script_is_not_retried_until_it_appears() {
+        let path = std::env::temp_dir().join("proyecto3_missing_shader.rhai");
+        let _ = fs::remove_file(&path);
+        let path = path.to_string_lossy().into_owned();
+        let mut cache = ScriptShaderCache::new();
+        cache.refresh(&path);
+        assert_eq!(cache.modified.get(&path), Some(&None));
+        assert!(!cache.scripts.contains_key(&path));
+
+        fs::write(&path, "[0.0, 1.0, 0.0]").unwrap();
+        cache.refresh(&path);
+        assert!(cache.scripts.contains_key(&path));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,148 @@
+// sdf_font.rs
+// Fuente de campo de distancia con signo (SDF): se escala a cualquier tamaño sin pixelarse
+
+use crate::font::glyph_8x8;
+use std::collections::HashMap;
+
+// Resolución del SDF de cada glifo
+pub const SDF_GLYPH_SIZE: u32 = 32;
+// Tamaño de la celda de origen (bitmap 8x8)
+const BITMAP_SIZE: u32 = 8;
+
+pub struct SdfFont {
+    glyphs: HashMap<char, Vec<f32>>,
+}
+
+impl SdfFont {
+    // Genera el SDF de todos los caracteres ASCII imprimibles
+    pub fn new() -> Self {
+        let glyphs = (' '..='~')
+            .map(|c| (c, generate_sdf_from_bitmap(&glyph_8x8(c), SDF_GLYPH_SIZE)))
+            .collect();
+        SdfFont { glyphs }
+    }
+
+    /// Distancia con signo (en píxeles del bitmap original, positiva dentro del trazo) en
+    /// coordenadas normalizadas de la celda `u`, `v` en [0, 1], con interpolación bilineal.
+    pub fn sample(&self, c: char, u: f32, v: f32) -> f32 {
+        let Some(field) = self.glyphs.get(&c).or_else(|| self.glyphs.get(&'?')) else {
+            return -1.0;
+        };
+        let size = SDF_GLYPH_SIZE as usize;
+        let fx = (u * SDF_GLYPH_SIZE as f32 - 0.5).clamp(0.0, (size - 1) as f32);
+        let fy = (v * SDF_GLYPH_SIZE as f32 - 0.5).clamp(0.0, (size - 1) as f32);
+        let (x0, y0) = (fx.floor() as usize, fy.floor() as usize);
+        let (x1, y1) = ((x0 + 1).min(size - 1), (y0 + 1).min(size - 1));
+        let (tx, ty) = (fx - x0 as f32, fy - y0 as f32);
+        let top = field[y0 * size + x0] * (1.0 - tx) + field[y0 * size + x1] * tx;
+        let bottom = field[y1 * size + x0] * (1.0 - tx) + field[y1 * size + x1] * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+}
+
+/// SDF de `out_size`x`out_size` a partir de un bitmap 8x8 (bit 7 = columna izquierda), con la
+/// transformada de distancia "dead reckoning" (dos pasadas propagando el borde más cercano).
+/// Las distancias se devuelven en píxeles del bitmap: positivas dentro, negativas fuera.
+pub fn generate_sdf_from_bitmap(bitmap: &[u8; 8], out_size: u32) -> Vec<f32> {
+    let n = out_size as i32;
+    let idx = |x: i32, y: i32| (y * n + x) as usize;
+    let inside_at = |x: i32, y: i32| {
+        let bx = (x as u32 * BITMAP_SIZE / out_size) as usize;
+        let by = (y as u32 * BITMAP_SIZE / out_size) as usize;
+        bitmap[by] & (0x80 >> bx) != 0
+    };
+    let inside: Vec<bool> = (0..n * n).map(|i| inside_at(i % n, i / n)).collect();
+
+    // Los píxeles de borde (algún vecino con distinto estado) son la semilla con distancia 0
+    let mut distance = vec![f32::INFINITY; (n * n) as usize];
+    let mut nearest = vec![(-1_i32, -1_i32); (n * n) as usize];
+    for y in 0..n {
+        for x in 0..n {
+            let here = inside[idx(x, y)];
+            let is_edge = [(-1, 0), (1, 0), (0, -1), (0, 1)].iter().any(|(dx, dy)| {
+                let (nx, ny) = (x + dx, y + dy);
+                // Fuera de la celda cuenta como vacío
+                let neighbor = nx >= 0 && nx < n && ny >= 0 && ny < n && inside[idx(nx, ny)];
+                neighbor != here
+            });
+            if is_edge {
+                distance[idx(x, y)] = 0.0;
+                nearest[idx(x, y)] = (x, y);
+            }
+        }
+    }
+
+    let diagonal = std::f32::consts::SQRT_2;
+    let mut relax = |x: i32, y: i32, dx: i32, dy: i32, step: f32| {
+        let (nx, ny) = (x + dx, y + dy);
+        if nx < 0 || nx >= n || ny < 0 || ny >= n {
+            return;
+        }
+        if distance[idx(nx, ny)] + step < distance[idx(x, y)] {
+            let p = nearest[idx(nx, ny)];
+            nearest[idx(x, y)] = p;
+            distance[idx(x, y)] = (((x - p.0).pow(2) + (y - p.1).pow(2)) as f32).sqrt();
+        }
+    };
+    // Pasada hacia delante (vecinos ya visitados: arriba e izquierda)
+    for y in 0..n {
+        for x in 0..n {
+            relax(x, y, -1, -1, diagonal);
+            relax(x, y, 0, -1, 1.0);
+            relax(x, y, 1, -1, diagonal);
+            relax(x, y, -1, 0, 1.0);
+        }
+    }
+    // Pasada hacia atrás (abajo y derecha)
+    for y in (0..n).rev() {
+        for x in (0..n).rev() {
+            relax(x, y, 1, 0, 1.0);
+            relax(x, y, -1, 1, diagonal);
+            relax(x, y, 0, 1, 1.0);
+            relax(x, y, 1, 1, diagonal);
+        }
+    }
+
+    // El borde real queda a medio píxel de los píxeles semilla
+    let to_bitmap_units = BITMAP_SIZE as f32 / out_size as f32;
+    (0..(n * n) as usize)
+        .map(|i| {
+            let d = (distance[i] + 0.5) * to_bitmap_units;
+            if inside[i] { d } else { -d }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::framebuffer::Framebuffer;
+    use raylib::prelude::Vector3;
+
+    // Caja (ancho, alto) de los píxeles que quedan a más de medio brillo al dibujar `text` en blanco
+    fn ink_box(text: &str, size: f32, font: &SdfFont) -> (i32, i32) {
+        let mut fb = Framebuffer::new(48, 48);
+        fb.draw_sdf_text(4, 4, text, size, Vector3::one(), font);
+        let (mut min, mut max) = ((i32::MAX, i32::MAX), (i32::MIN, i32::MIN));
+        for y in 0..48 {
+            for x in 0..48 {
+                if fb.color_buffer.get_color(x, y).r > 127 {
+                    min = (min.0.min(x), min.1.min(y));
+                    max = (max.0.max(x), max.1.max(y));
+                }
+            }
+        }
+        (max.0 - min.0 + 1, max.1 - min.1 + 1)
+    }
+
+    #[test]
+    fn glyph_keeps_its_shape_at_any_size() {
+        let font = SdfFont::new();
+        let (small, large) = (ink_box("A", 8.0, &font), ink_box("A", 32.0, &font));
+        assert!(small.0 > 0 && large.0 > 0);
+        let aspect = |(w, h): (i32, i32)| w as f32 / h as f32;
+        assert!((aspect(small) - aspect(large)).abs() < 0.1, "8 px {:?}, 32 px {:?}", small, large);
+        assert!((large.1 as f32 / small.1 as f32 - 4.0).abs() < 0.5);
+    }
+}
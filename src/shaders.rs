@@ -2,9 +2,14 @@
 
 use raylib::prelude::*;
 use crate::vertex::Vertex;
+use crate::mesh::RingBand;
 use crate::Uniforms;
-use crate::matrix::multiply_matrix_vector4;
+use crate::matrix::{multiply_matrix_vector4, multiply_matrix_vector4_simd};
 use crate::fragment::Fragment;
+use crate::light::{Light, LightKind, smoothstep};
+use crate::exploration::{is_explored, EXPLORATION_REVEAL_DISTANCE};
+use crate::atmosphere::{sample_transmittance, TRANSMITTANCE_RESOLUTION};
+use crate::texture::sample_bilinear;
 
 // Helper para normalizar vector3
 fn normalize_vec3(v: Vector3) -> Vector3 {
@@ -16,6 +21,15 @@ fn normalize_vec3(v: Vector3) -> Vector3 {
     }
 }
 
+// Fracción del color de superficie usada como reflectividad ambiental (Ka)
+const AMBIENT_REFLECTIVITY: f32 = 0.05;
+// Reflectividad especular para cuerpos rocosos / gaseosos
+const PLANET_SPECULAR: Vector3 = Vector3::new(0.2, 0.2, 0.2);
+const PLANET_SHININESS: f32 = 16.0;
+// La nave es metálica: brillo especular más fuerte y concentrado
+const NAVE_SPECULAR: Vector3 = Vector3::new(0.8, 0.8, 0.85);
+const NAVE_SHININESS: f32 = 64.0;
+
 // Helper para latitud
 fn lat_factor(lat: f32) -> f32 {
     (lat - 0.5).abs() * 2.0
@@ -29,25 +43,382 @@ fn solar_noise(x: f32, y: f32, z: f32, time: f32) -> f32 {
     (n1 * 0.5 + n2 * 0.3 + n3 * 0.2).abs()
 }
 
-pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
-    let position_vec4 = Vector4::new(vertex.position.x, vertex.position.y, vertex.position.z, 1.0);
-    let world_position = multiply_matrix_vector4(&uniforms.model_matrix, &position_vec4);
-    let view_position = multiply_matrix_vector4(&uniforms.view_matrix, &world_position);
-    let clip_position = multiply_matrix_vector4(&uniforms.projection_matrix, &view_position);
-    let ndc = if clip_position.w != 0.0 {
-        Vector3::new(clip_position.x / clip_position.w, clip_position.y / clip_position.w, clip_position.z / clip_position.w)
+/// Modelo de iluminación de Phong: ambiente + difusa (Lambert) + especular.
+/// `ka` es el término ambiental ya escalado por la intensidad ambiental.
+/// El vector reflejado se calcula como `R = 2*(N·L)*N - L`.
+#[allow(clippy::too_many_arguments)]
+pub fn phong_light(
+    normal: Vector3,
+    light_dir: Vector3,
+    view_dir: Vector3,
+    kd: Vector3,
+    ks: Vector3,
+    ka: Vector3,
+    shininess: f32,
+    light_color: Vector3,
+) -> Vector3 {
+    let n = normalize_vec3(normal);
+    let l = normalize_vec3(light_dir);
+    let v = normalize_vec3(view_dir);
+
+    let n_dot_l = n.dot(l);
+    let diffuse = kd * light_color * n_dot_l.max(0.0);
+
+    // Sin especular en la cara que no recibe luz
+    let specular = if n_dot_l > 0.0 {
+        let r = n * (2.0 * n_dot_l) - l;
+        let r_dot_v = r.dot(v).max(0.0);
+        ks * light_color * r_dot_v.powf(shininess)
     } else {
-        Vector3::new(clip_position.x, clip_position.y, clip_position.z)
+        Vector3::zero()
+    };
+
+    ka + diffuse + specular
+}
+
+// `fragment.world_position` está en espacio de objeto (para que los patrones giren con el planeta),
+// así que se lleva a mundo con la matriz de modelo.
+fn fragment_world_position(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
+    let p = fragment.world_position;
+    let world = multiply_matrix_vector4(&uniforms.model_matrix, &Vector4::new(p.x, p.y, p.z, 1.0));
+    Vector3::new(world.x, world.y, world.z)
+}
+
+// El Sol es la primera luz puntual de la escena
+fn sun_light<'a>(uniforms: &'a Uniforms) -> Option<&'a Light> {
+    uniforms.lights.iter().find(|light| matches!(light.kind, LightKind::Point))
+}
+
+fn sun_position(uniforms: &Uniforms) -> Vector3 {
+    sun_light(uniforms).map(|light| light.position).unwrap_or(Vector3::zero())
+}
+
+// Luz del Sol que llega al fragmento, ya atenuada por la distancia (Mercurio recibe mucha más que Urano)
+fn sun_light_color(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
+    let world_pos = fragment_world_position(fragment, uniforms);
+    sun_light(uniforms).map(|light| light.radiance_at(world_pos)).unwrap_or(Vector3::one())
+}
+
+// Normal, dirección hacia el Sol y dirección hacia la cámara en espacio de mundo.
+fn lighting_vectors(fragment: &Fragment, uniforms: &Uniforms) -> (Vector3, Vector3, Vector3) {
+    let world_pos = fragment_world_position(fragment, uniforms);
+    let light_dir = normalize_vec3(sun_position(uniforms) - world_pos);
+    let view_dir = normalize_vec3(uniforms.camera_pos - world_pos);
+    (normalize_vec3(fragment.normal), light_dir, view_dir)
+}
+
+// Aporte difuso de todos los focos activos (p. ej. el de la nave) sobre el fragmento
+fn spotlights_contribution(fragment: &Fragment, uniforms: &Uniforms, normal: Vector3, surface_color: Vector3) -> Vector3 {
+    let world_pos = fragment_world_position(fragment, uniforms);
+    let mut total = Vector3::zero();
+    for light in &uniforms.lights {
+        if matches!(light.kind, LightKind::Spotlight { .. }) {
+            let spot_factor = light.spot_factor(world_pos);
+            if spot_factor <= 0.0 {
+                continue;
+            }
+            let to_light = normalize_vec3(light.position - world_pos);
+            let diffuse = normal.dot(to_light).max(0.0);
+            total += surface_color * light.radiance_at(world_pos) * (diffuse * spot_factor);
+        }
+    }
+    total
+}
+
+// Phong con los parámetros comunes de los planetas, más los focos
+fn planet_lighting(fragment: &Fragment, uniforms: &Uniforms, surface_color: Vector3) -> Vector3 {
+    let (normal, light_dir, view_dir) = lighting_vectors(fragment, uniforms);
+    let ambient = ambient_term(uniforms, surface_color);
+    phong_light(normal, light_dir, view_dir, surface_color, PLANET_SPECULAR, ambient, PLANET_SHININESS, sun_light_color(fragment, uniforms))
+        + spotlights_contribution(fragment, uniforms, normal, surface_color)
+}
+
+/// 🗺️ Niebla de guerra: en celdas sin explorar el fragmento es gris liso, salvo cerca de la
+/// cámara, donde se va descubriendo hasta `EXPLORATION_REVEAL_DISTANCE`.
+pub fn apply_fog_of_war(fragment: &Fragment, uniforms: &Uniforms, color: Vector3) -> Vector3 {
+    let world_pos = fragment_world_position(fragment, uniforms);
+    if is_explored(uniforms.explored_grid, world_pos) {
+        return color;
+    }
+    let undiscovered = Vector3::new(0.35, 0.35, 0.38);
+    let distance = (uniforms.camera_pos - world_pos).length();
+    let reveal = 1.0 - smoothstep(EXPLORATION_REVEAL_DISTANCE * 0.5, EXPLORATION_REVEAL_DISTANCE, distance);
+    undiscovered * (1.0 - reveal) + color * reveal
+}
+
+// Peso del atlas frente al shader procedural en los cuerpos que tienen casilla
+pub const ATLAS_BLEND_FACTOR: f32 = 0.75;
+
+/// 🖼️ Mezcla el color procedural con la casilla del atlas de `uniforms` (si hay):
+/// `lerp(procedural, atlas, ATLAS_BLEND_FACTOR)`. Las UV son las esféricas de la posición en la
+/// malla, igual que las de `generate_icosphere`; el atlas se multiplica por la luz del Sol que
+/// recibe la superficie para no quedar plano en la cara nocturna.
+pub fn apply_texture_atlas(fragment: &Fragment, uniforms: &Uniforms, procedural: Vector3) -> Vector3 {
+    let Some((atlas, tile)) = uniforms.atlas else {
+        return procedural;
+    };
+    let p = normalize_vec3(fragment.world_position);
+    let u = 0.5 + p.z.atan2(p.x) / std::f32::consts::TAU;
+    let v = 0.5 - p.y.clamp(-1.0, 1.0).asin() / std::f32::consts::PI;
+    let albedo = sample_bilinear(atlas, u, v, tile);
+    let lit = planet_lighting(fragment, uniforms, albedo);
+    procedural * (1.0 - ATLAS_BLEND_FACTOR) + lit * ATLAS_BLEND_FACTOR
+}
+
+/// Fracción de luz de la superficie que atraviesa la atmósfera hasta la cámara. En el limbo la
+/// visual entra rasante y recorre mucha más atmósfera que mirando de frente.
+pub fn atmosphere_transmittance(normal: Vector3, view_dir: Vector3, atmosphere_height: f32, atmosphere_density: f32) -> f32 {
+    let view_path_length = atmosphere_height / normal.dot(view_dir).max(0.001);
+    let optical_depth = atmosphere_density * view_path_length;
+    (-optical_depth).exp()
+}
+
+// Mezcla la superficie ya iluminada con la bruma de la atmósfera (iluminada por el Sol)
+// según la transmitancia de `uniforms`
+fn apply_atmosphere(fragment: &Fragment, uniforms: &Uniforms, lit_color: Vector3, haze_color: Vector3) -> Vector3 {
+    if uniforms.atmosphere_density <= 0.0 {
+        return lit_color;
+    }
+    let (normal, light_dir, view_dir) = lighting_vectors(fragment, uniforms);
+    let transmittance = atmosphere_transmittance(normal, view_dir, uniforms.atmosphere_height, uniforms.atmosphere_density);
+    // La bruma dispersa algo de luz incluso un poco más allá del terminador
+    let haze_light = (normal.dot(light_dir) + 0.2).clamp(0.0, 1.0);
+    lit_color * transmittance + haze_color * sun_light_color(fragment, uniforms) * (haze_light * (1.0 - transmittance))
+}
+
+// Altitud (fracción de la atmósfera) a la que se evalúa la luz solar que ilumina la bruma terrestre
+const EARTH_HAZE_ALTITUDE: f32 = 0.3;
+
+// 🌅 Atmósfera de la Tierra con la tabla de transmitancia precalculada: la luz de la superficie
+// pierde azul al subir hacia la cámara, y lo que se pierde es bruma iluminada por un Sol que ya
+// llega enrojecido cerca del terminador (cielo azul de día, naranja en el horizonte)
+fn apply_earth_atmosphere(fragment: &Fragment, uniforms: &Uniforms, lit_color: Vector3) -> Vector3 {
+    let (normal, light_dir, view_dir) = lighting_vectors(fragment, uniforms);
+    let table = uniforms.earth_transmittance;
+    let view_transmittance = sample_transmittance(table, TRANSMITTANCE_RESOLUTION, 0.0, normal.dot(view_dir));
+    let sun_transmittance = sample_transmittance(table, TRANSMITTANCE_RESOLUTION, EARTH_HAZE_ALTITUDE, normal.dot(light_dir));
+    let scattered = Vector3::one() - view_transmittance;
+    lit_color * view_transmittance + scattered * sun_transmittance * sun_light_color(fragment, uniforms)
+}
+
+// Término ambiental Ka: luz solar dispersada en el medio interplanetario.
+// Es lo único que recibe la cara opuesta al Sol.
+fn ambient_term(uniforms: &Uniforms, surface_color: Vector3) -> Vector3 {
+    surface_color * uniforms.ambient_color * (AMBIENT_REFLECTIVITY * uniforms.ambient_intensity)
+}
+
+/// Hash entero 3D (Jenkins one-at-a-time sobre los 12 bytes de la celda), normalizado a [0, 1].
+pub fn hash_3d(ix: i32, iy: i32, iz: i32) -> f32 {
+    let mut hash: u32 = 0;
+    for value in [ix, iy, iz] {
+        for byte in value.to_le_bytes() {
+            hash = hash.wrapping_add(byte as u32);
+            hash = hash.wrapping_add(hash << 10);
+            hash ^= hash >> 6;
+        }
+    }
+    hash = hash.wrapping_add(hash << 3);
+    hash ^= hash >> 11;
+    hash = hash.wrapping_add(hash << 15);
+    hash as f32 / u32::MAX as f32
+}
+
+/// Ruido de valor 3D: interpola trilinealmente (con curva suave) los valores hasheados
+/// de las 8 esquinas del vóxel entero. Sin periodo visible, a diferencia de `sin(x * escala)`.
+pub fn value_noise_3d(x: f32, y: f32, z: f32) -> f32 {
+    let (x0, y0, z0) = (x.floor(), y.floor(), z.floor());
+    let (ix, iy, iz) = (x0 as i32, y0 as i32, z0 as i32);
+    let fade = |t: f32| t * t * (3.0 - 2.0 * t);
+    let (tx, ty, tz) = (fade(x - x0), fade(y - y0), fade(z - z0));
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+
+    let c000 = hash_3d(ix, iy, iz);
+    let c100 = hash_3d(ix + 1, iy, iz);
+    let c010 = hash_3d(ix, iy + 1, iz);
+    let c110 = hash_3d(ix + 1, iy + 1, iz);
+    let c001 = hash_3d(ix, iy, iz + 1);
+    let c101 = hash_3d(ix + 1, iy, iz + 1);
+    let c011 = hash_3d(ix, iy + 1, iz + 1);
+    let c111 = hash_3d(ix + 1, iy + 1, iz + 1);
+
+    let x00 = lerp(c000, c100, tx);
+    let x10 = lerp(c010, c110, tx);
+    let x01 = lerp(c001, c101, tx);
+    let x11 = lerp(c011, c111, tx);
+    lerp(lerp(x00, x10, ty), lerp(x01, x11, ty), tz)
+}
+
+/// Movimiento browniano fraccional sobre `value_noise_3d`, normalizado a [0, 1].
+pub fn fbm_3d(x: f32, y: f32, z: f32, octaves: u32, lacunarity: f32, gain: f32) -> f32 {
+    let mut sum = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut total_amplitude = 0.0;
+    for _ in 0..octaves {
+        sum += value_noise_3d(x * frequency, y * frequency, z * frequency) * amplitude;
+        total_amplitude += amplitude;
+        amplitude *= gain;
+        frequency *= lacunarity;
+    }
+    if total_amplitude > 0.0 { sum / total_amplitude } else { 0.0 }
+}
+
+/// Nubes lenticulares: ruido de cresta (1 - |2·ruido - 1|) muy estirado en longitud, así salen
+/// bandas largas y finas paralelas a los paralelos. Longitud y latitud en radianes; `elevation`
+/// (en radios) separa capas a distintas alturas. Evolucionan despacio con `time`. Devuelve [0, 1].
+pub fn lenticular_cloud(longitude: f32, latitude: f32, elevation: f32, time: f32) -> f32 {
+    // La longitud entra por su círculo (cos, sin) para no dejar costura en ±π
+    let (lon_sin, lon_cos) = longitude.sin_cos();
+    let drift = time * 0.0006;
+    let noise = |freq: f32, stretch: f32| {
+        let n = value_noise_3d(lon_cos * freq + drift, lon_sin * freq - drift, latitude * freq * stretch + elevation * 50.0);
+        1.0 - (n * 2.0 - 1.0).abs()
     };
+    let ridges = noise(2.0, 12.0) * 0.65 + noise(4.0, 12.0) * 0.35;
+    smoothstep(0.75, 0.95, ridges)
+}
+
+/// Gradiente de `fbm_3d` en `p` por diferencias centrales (apunta hacia donde el fBm sube).
+pub fn fbm_gradient(p: Vector3, octaves: u32, lacunarity: f32, gain: f32) -> Vector3 {
+    const EPSILON: f32 = 0.01;
+    let sample = |q: Vector3| fbm_3d(q.x, q.y, q.z, octaves, lacunarity, gain);
+    Vector3::new(
+        sample(p + Vector3::new(EPSILON, 0.0, 0.0)) - sample(p - Vector3::new(EPSILON, 0.0, 0.0)),
+        sample(p + Vector3::new(0.0, EPSILON, 0.0)) - sample(p - Vector3::new(0.0, EPSILON, 0.0)),
+        sample(p + Vector3::new(0.0, 0.0, EPSILON)) - sample(p - Vector3::new(0.0, 0.0, EPSILON)),
+    ) / (2.0 * EPSILON)
+}
+
+/// Ruido celular (Voronoi/Worley) 3D: devuelve la distancia al punto característico más cercano
+/// y al segundo más cercano (`f2 - f1` marca los bordes de celda).
+pub fn voronoi_3d(x: f32, y: f32, z: f32) -> (f32, f32) {
+    let (f1, f2, _) = voronoi_3d_cell(x, y, z);
+    (f1, f2)
+}
+
+/// Como `voronoi_3d`, y además la celda del punto más cercano (para dar a cada celda sus
+/// propios valores con `hash_3d`).
+pub fn voronoi_3d_cell(x: f32, y: f32, z: f32) -> (f32, f32, (i32, i32, i32)) {
+    let (ix, iy, iz) = (x.floor() as i32, y.floor() as i32, z.floor() as i32);
+    let mut f1 = f32::MAX;
+    let mut f2 = f32::MAX;
+    let mut nearest = (ix, iy, iz);
+    for dz in -1..=1 {
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (cx, cy, cz) = (ix + dx, iy + dy, iz + dz);
+                // Punto característico de la celda, estable gracias al hash
+                let px = cx as f32 + hash_3d(cx, cy, cz);
+                let py = cy as f32 + hash_3d(cx + 31, cy + 17, cz + 7);
+                let pz = cz as f32 + hash_3d(cx + 59, cy + 43, cz + 23);
+                let d = ((px - x).powi(2) + (py - y).powi(2) + (pz - z).powi(2)).sqrt();
+                if d < f1 {
+                    f2 = f1;
+                    f1 = d;
+                    nearest = (cx, cy, cz);
+                } else if d < f2 {
+                    f2 = d;
+                }
+            }
+        }
+    }
+    (f1, f2, nearest)
+}
+
+// ⛰️ Relieve procedural en el vertex shader: desplaza cada vértice a lo largo de la dirección
+// radial según un campo de alturas en [0, 1] (0.5 = radio original)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DisplacementType {
+    None,
+    TerrainFbm, // `compute_terrain_height`, la misma altitud que colorea Marte y Mercurio
+}
+
+impl DisplacementType {
+    fn height_fn(self) -> Option<fn(Vector3) -> f32> {
+        match self {
+            DisplacementType::None => None,
+            DisplacementType::TerrainFbm => Some(compute_terrain_height),
+        }
+    }
+}
+
+// Relieve y amplitud (en unidades de la malla, radio ~0.52) de cada shader de planeta
+pub fn displacement_for_shader(planet_type: &str) -> (DisplacementType, f32) {
+    match planet_type {
+        "Mars" => (DisplacementType::TerrainFbm, 0.04),
+        "Mercury" => (DisplacementType::TerrainFbm, 0.03),
+        _ => (DisplacementType::None, 0.0),
+    }
+}
+
+fn displaced_position(pos: Vector3, height_fn: fn(Vector3) -> f32, scale: f32) -> Vector3 {
+    pos + normalize_vec3(pos) * ((height_fn(pos) - 0.5) * scale)
+}
+
+/// Normal de la esfera desplazada radialmente `scale * (height_fn(p) - 0.5)`. El gradiente del
+/// campo se estima por diferencias centrales (±`epsilon` en X, Y y Z); los dos vectores tangentes
+/// de la esfera se inclinan según su componente del gradiente y la normal es su producto
+/// vectorial. Con un campo constante devuelve la normal de la esfera.
+pub fn compute_displaced_normal(pos: Vector3, displacement_fn: fn(Vector3) -> f32, scale: f32, epsilon: f32) -> Vector3 {
+    let n = normalize_vec3(pos);
+    let dx = Vector3::new(epsilon, 0.0, 0.0);
+    let dy = Vector3::new(0.0, epsilon, 0.0);
+    let dz = Vector3::new(0.0, 0.0, epsilon);
+    let gradient = Vector3::new(
+        displacement_fn(pos + dx) - displacement_fn(pos - dx),
+        displacement_fn(pos + dy) - displacement_fn(pos - dy),
+        displacement_fn(pos + dz) - displacement_fn(pos - dz),
+    ) * (scale / (2.0 * epsilon));
+    let helper = if n.y.abs() < 0.9 { Vector3::new(0.0, 1.0, 0.0) } else { Vector3::new(1.0, 0.0, 0.0) };
+    let t1 = normalize_vec3(helper.cross(n));
+    let t2 = n.cross(t1);
+    let tangent_u = t1 + n * gradient.dot(t1);
+    let tangent_v = t2 + n * gradient.dot(t2);
+    normalize_vec3(tangent_u.cross(tangent_v))
+}
+
+// Paso de las diferencias centrales del relieve (unidades de la malla)
+const DISPLACEMENT_NORMAL_EPSILON: f32 = 0.005;
+
+// Posición centinela para vértices sin división de perspectiva válida: queda muy fuera del
+// viewport y `render` descarta los triángulos que la usan
+const DEGENERATE_NDC: Vector3 = Vector3::new(-1e9, -1e9, -1e9);
+
+/// Divide por w. Con w ≈ 0 (vértice en el plano de la cámara) o negativo (detrás de ella) el
+/// resultado no tiene sentido, así que devuelve `DEGENERATE_NDC` en vez de las coordenadas de
+/// recorte sin dividir o reflejadas al otro lado de la pantalla.
+pub fn safe_perspective_divide(clip: Vector4) -> Vector3 {
+    if clip.w < 1e-7 {
+        return DEGENERATE_NDC;
+    }
+    Vector3::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w)
+}
+
+pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
+    let (position, normal) = match uniforms.displacement.height_fn() {
+        Some(height_fn) if uniforms.displacement_scale != 0.0 => (
+            displaced_position(vertex.position, height_fn, uniforms.displacement_scale),
+            compute_displaced_normal(vertex.position, height_fn, uniforms.displacement_scale, DISPLACEMENT_NORMAL_EPSILON),
+        ),
+        _ => (vertex.position, vertex.normal),
+    };
+    let position_vec4 = Vector4::new(position.x, position.y, position.z, 1.0);
+    let world_position = multiply_matrix_vector4_simd(&uniforms.model_matrix, &position_vec4);
+    let view_position = multiply_matrix_vector4_simd(&uniforms.view_matrix, &world_position);
+    let clip_position = multiply_matrix_vector4_simd(&uniforms.projection_matrix, &view_position);
+    let ndc = safe_perspective_divide(clip_position);
     let ndc_vec4 = Vector4::new(ndc.x, ndc.y, ndc.z, 1.0);
-    let screen_position = multiply_matrix_vector4(&uniforms.viewport_matrix, &ndc_vec4);
+    let screen_position = multiply_matrix_vector4_simd(&uniforms.viewport_matrix, &ndc_vec4);
+    // Normal a espacio de mundo (w = 0 para ignorar la traslación)
+    let normal_vec4 = Vector4::new(normal.x, normal.y, normal.z, 0.0);
+    let world_normal = multiply_matrix_vector4_simd(&uniforms.model_matrix, &normal_vec4);
     Vertex {
-        position: vertex.position,
-        normal: vertex.normal,
+        position,
+        normal,
         tex_coords: vertex.tex_coords,
         color: vertex.color,
         transformed_position: Vector3::new(screen_position.x, screen_position.y, screen_position.z),
-        transformed_normal: vertex.normal,
+        transformed_normal: normalize_vec3(Vector3::new(world_normal.x, world_normal.y, world_normal.z)),
     }
 }
 
@@ -94,13 +465,178 @@ pub fn sun_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3
     color = color * (1.0 + flare_effect * 0.5) + Vector3::new(1.0, 1.0, 0.8) * flare_effect * 0.6;
     color = color + Vector3::new(1.0, 0.9, 0.6) * center_glow;
 
+    // Manchas solares (latitud y longitud en el espacio del Sol, que gira con él)
+    let dir = pos.normalized();
+    let spots = sunspot_pattern(dir.y.clamp(-1.0, 1.0).asin(), dir.z.atan2(dir.x), time, SUNSPOT_CYCLE_PERIOD);
+    color *= 1.0 - 0.7 * spots;
+
     Vector3::new(
         color.x.min(2.0),
         color.y.min(1.8),
         color.z.min(1.5),
-    )
+    ) * nova_intensity(uniforms.explosion_phase)
+}
+
+// 🌞 Ciclo de las manchas solares: 11 años de la Tierra (una órbita son 2π / 0.5 ≈ 12.6 s)
+pub const SUNSPOT_CYCLE_PERIOD: f32 = 138.0;
+// En el máximo del ciclo las manchas llegan a ±30° del ecuador
+const SUNSPOT_MAX_LATITUDE: f32 = 30.0 * std::f32::consts::PI / 180.0;
+// Celdas de Voronoi por radio solar, vida de cada generación de manchas (unas semanas, en s
+// simulados) y radio máximo de una mancha (en unidades de celda)
+const SUNSPOT_CELL_SCALE: f32 = 8.0;
+const SUNSPOT_LIFETIME: f32 = 1.5;
+const SUNSPOT_MAX_RADIUS: f32 = 0.35;
+
+/// Latitudes (rad, con signo) entre las que salen manchas en `time`: la banda va del ecuador a
+/// ±30° · |sin(2π t / P)|, así que su centro sube y baja con la actividad (±15° en t = P/4).
+pub fn sunspot_latitude_band(time: f32, cycle_period: f32) -> (f32, f32) {
+    let range = SUNSPOT_MAX_LATITUDE * (std::f32::consts::TAU * time / cycle_period).sin().abs();
+    (0.0, range)
+}
+
+/// Oscurecimiento por manchas solares en [0, 1] en la latitud `theta` y longitud `phi` (rad).
+/// La densidad de manchas es |sin(2π t / P)| (máxima en el máximo solar) y solo salen en la
+/// banda de `sunspot_latitude_band`; en la segunda mitad de cada semiciclo la banda se estrecha
+/// hacia el ecuador (ley de Spörer). Cada celda de Voronoi es una mancha posible: dos
+/// generaciones desfasadas nacen, crecen y se apagan en SUNSPOT_LIFETIME. Dentro de cada mancha,
+/// umbra oscura (1) y penumbra más clara (0.5).
+pub fn sunspot_pattern(theta: f32, phi: f32, time: f32, cycle_period: f32) -> f32 {
+    let density = (std::f32::consts::TAU * time / cycle_period).sin().abs();
+    let (band_min, band_max) = sunspot_latitude_band(time, cycle_period);
+    let (center, half_width) = ((band_min + band_max) * 0.5, (band_max - band_min) * 0.5);
+    let in_band = 1.0 - smoothstep(half_width * 0.6, half_width, (theta.abs() - center).abs());
+    if density <= 0.0 || in_band <= 0.0 {
+        return 0.0;
+    }
+
+    let p = Vector3::new(theta.cos() * phi.cos(), theta.sin(), theta.cos() * phi.sin()) * SUNSPOT_CELL_SCALE;
+    let mut spot: f32 = 0.0;
+    for generation_offset in [0.0, 0.5] {
+        let age = time / SUNSPOT_LIFETIME + generation_offset;
+        let generation = age.floor() as i32;
+        // Cada generación desplaza la rejilla: sus manchas salen en otros sitios
+        let shift = generation as f32 * 17.3;
+        let (f1, _, (cx, cy, cz)) = voronoi_3d_cell(p.x + shift, p.y, p.z - shift);
+        if hash_3d(cx + generation * 13, cy, cz) > density {
+            continue;
+        }
+        let life = (age.fract() * std::f32::consts::PI).sin();
+        let radius = SUNSPOT_MAX_RADIUS * life * (0.5 + 0.5 * hash_3d(cz, cx, cy + generation));
+        let umbra = 1.0 - smoothstep(radius * 0.4, radius * 0.5, f1);
+        let penumbra = 1.0 - smoothstep(radius * 0.85, radius, f1);
+        spot = spot.max(umbra.max(penumbra * 0.5));
+    }
+    spot * in_band
+}
+
+/// Brillo del Sol durante una nova: destello rápido (máximo en `phase` = 1/4 s) y caída
+/// exponencial. Vale 1 sin nova.
+pub fn nova_intensity(explosion_phase: f32) -> f32 {
+    1.0 + 10.0 * explosion_phase * (-explosion_phase * 4.0).exp()
+}
+
+// 💥 Onda de choque de la nova: cáscara naranja-rojiza, más opaca en el borde visto de canto
+pub fn nova_shockwave_fragment_shader(_fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
+    let cooling = (-uniforms.explosion_phase * 0.6).exp();
+    Vector3::new(1.0, 0.35, 0.1) * (1.0 - cooling) + Vector3::new(1.0, 0.8, 0.4) * cooling
+}
+
+// 🌀 Túnel del warp: cilindro alrededor de la cámara, con la boca lejana en +Y de su malla
+pub const WARP_TUNNEL_RADIUS: f32 = 30.0;
+pub const WARP_TUNNEL_LENGTH: f32 = 500.0;
+pub const WARP_TUNNEL_RINGS: u32 = 40;
+// Las estelas se repiten cada tramo entre anillos: el túnel puede saltar un tramo hacia delante
+// mientras "pasa" junto a la cámara sin que se note el salto
+pub const WARP_STREAK_PERIOD: f32 = WARP_TUNNEL_LENGTH / WARP_TUNNEL_RINGS as f32;
+const WARP_STREAK_BANDS: f32 = 48.0;
+
+// Estelas azul eléctrico y blancas a lo largo del túnel (una por banda angular, con su propio
+// desfase y brillo) que convergen en un resplandor blanco hacia el fondo. Se suma a la escena
+// (ver `render`), así que el negro no tapa nada.
+pub fn warp_tunnel_fragment_shader(fragment: &Fragment, _uniforms: &Uniforms) -> Vector3 {
+    let p = fragment.world_position;
+    let around = (p.z.atan2(p.x) / std::f32::consts::TAU + 0.5) * WARP_STREAK_BANDS;
+    let band_hash = hash_3d(around.floor() as i32, 17, 91);
+    let across = 1.0 - smoothstep(0.05, 0.3, (around.fract() - 0.5).abs());
+    let phase = (p.y / WARP_STREAK_PERIOD + band_hash).fract();
+    // Cabeza brillante y cola que se apaga hacia atrás
+    let streak = smoothstep(0.3, 0.9, phase) * (1.0 - smoothstep(0.9, 1.0, phase));
+    let intensity = across * streak * (0.4 + 0.6 * band_hash);
+
+    let along = (p.y / WARP_TUNNEL_LENGTH).clamp(0.0, 1.0);
+    let glow = smoothstep(0.5, 1.0, along);
+    let electric_blue = Vector3::new(0.25, 0.55, 1.0);
+    electric_blue * (intensity * (1.0 - glow)) + Vector3::one() * (intensity * 0.4 + glow * glow * 0.5)
 }
 
+pub fn nova_shockwave_alpha(fragment: &Fragment, uniforms: &Uniforms) -> f32 {
+    let (normal, _, view_dir) = lighting_vectors(fragment, uniforms);
+    let rim = 1.0 - normal.dot(view_dir).abs();
+    let fade = (-uniforms.explosion_phase * 0.5).exp();
+    (0.08 + rim * rim * 0.6) * fade
+}
+
+// 🌫️ Nube de polvo interestelar: hidrógeno molecular rojizo con franjas oscuras de polvo. Es un
+// volumen (ver `raymarcher.rs`); `p` va relativo al centro y en radios de la nube, y la densidad
+// se desvanece hacia el borde para que no se note la esfera.
+pub fn nebula_volume_density(p: Vector3) -> f32 {
+    let r = p.length();
+    if r >= 1.0 {
+        return 0.0;
+    }
+    let q = p * 2.0;
+    let wisps = fbm_3d(q.x, q.y, q.z, 4, 2.0, 0.55);
+    let lanes = 1.0 - (fbm_3d(q.x * 0.75 + 11.0, q.y * 3.0, q.z * 0.75, 3, 2.0, 0.5) * 2.0 - 1.0).abs();
+    let density = smoothstep(0.4, 0.75, wisps) * (1.0 - smoothstep(0.8, 0.95, lanes) * 0.85);
+    (density * (1.0 - smoothstep(0.5, 1.0, r))).clamp(0.0, 1.0)
+}
+
+// ⛰️ Altitud virtual de los planetas rocosos: fBm sobre la dirección del punto, en [0, 1]
+pub fn compute_terrain_height(world_pos: Vector3) -> f32 {
+    let dir = normalize_vec3(world_pos);
+    fbm_3d(dir.x * 4.0 + 7.0, dir.y * 4.0, dir.z * 4.0, 5, 2.0, 0.5)
+}
+
+/// Color del terreno según la altura: interpola linealmente entre las paradas `(altura, color)`
+/// de `palette` (ordenadas de menor a mayor altura) y satura en los extremos.
+fn terrain_palette_color(height: f32, palette: &[(f32, Vector3)]) -> Vector3 {
+    let (first, last) = (palette[0], palette[palette.len() - 1]);
+    if height <= first.0 {
+        return first.1;
+    }
+    for pair in palette.windows(2) {
+        let ((h0, c0), (h1, c1)) = (pair[0], pair[1]);
+        if height <= h1 {
+            let t = (height - h0) / (h1 - h0).max(1e-6);
+            return c0 * (1.0 - t) + c1 * t;
+        }
+    }
+    last.1
+}
+
+/// Mezcla `color` con el de la paleta de altitudes; `altitude_factor` 0 lo deja plano y 1 usa
+/// el degradado completo.
+fn apply_altitude_shading(color: Vector3, world_pos: Vector3, palette: &[(f32, Vector3)], altitude_factor: f32) -> Vector3 {
+    let gradient = terrain_palette_color(compute_terrain_height(world_pos), palette);
+    color * (1.0 - altitude_factor) + gradient * altitude_factor
+}
+
+// Paletas de altitud (el fBm se concentra alrededor de 0.5, de ahí las paradas)
+const MARS_ALTITUDE_PALETTE: [(f32, Vector3); 4] = [
+    (0.30, Vector3::new(0.28, 0.20, 0.17)), // tierras bajas: basalto oscuro
+    (0.45, Vector3::new(0.75, 0.38, 0.22)), // media altura: roca roja
+    (0.60, Vector3::new(0.88, 0.64, 0.38)), // tierras altas: ocre claro
+    (0.72, Vector3::new(0.80, 0.78, 0.75)), // cumbres: gris pálido
+];
+const MARS_ALTITUDE_FACTOR: f32 = 0.6;
+const MERCURY_ALTITUDE_PALETTE: [(f32, Vector3); 4] = [
+    (0.30, Vector3::new(0.17, 0.16, 0.17)),
+    (0.45, Vector3::new(0.33, 0.31, 0.31)),
+    (0.60, Vector3::new(0.50, 0.48, 0.46)),
+    (0.72, Vector3::new(0.66, 0.65, 0.64)),
+];
+const MERCURY_ALTITUDE_FACTOR: f32 = 0.5;
+
 // 🪐 Mercurio (agregado ahora — más realista que gris plano)
 pub fn mercury_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
     let pos = fragment.world_position;
@@ -121,15 +657,71 @@ pub fn mercury_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vect
 
     let base_surface = dark_rock * (1.0 - terrain_factor) + light_rock * terrain_factor;
     let cratered_surface = base_surface * (1.0 - crater_factor * 0.5) + crater_deep * crater_factor * 0.5;
+    let cratered_surface = apply_altitude_shading(cratered_surface, pos, &MERCURY_ALTITUDE_PALETTE, MERCURY_ALTITUDE_FACTOR);
 
-    // Iluminación simple
-    let light_dir = normalize_vec3(Vector3::new(1.0, 1.0, 1.0));
-    let dot = pos.dot(light_dir).max(0.0); // ✅ sin & aquí
-    let lit_color = cratered_surface * dot.max(0.3);
+    let lit_color = apply_atmosphere(fragment, uniforms, planet_lighting(fragment, uniforms, cratered_surface), Vector3::new(0.6, 0.6, 0.6));
 
     Vector3::new(lit_color.x.min(1.0), lit_color.y.min(1.0), lit_color.z.min(1.0))
 }
 
+// 🌊 Brillo del Sol sobre el océano (glint)
+const OCEAN_SHININESS: f32 = 512.0;
+const OCEAN_WAVE_HEIGHT: f32 = 0.06;
+
+/// Especular de Blinn-Phong del agua. `wave_normal_perturbation` desvía la normal para simular
+/// el oleaje; con perturbación nula el máximo está justo donde `view_dir` es el reflejo del Sol.
+pub fn ocean_specular(normal: Vector3, sun_dir: Vector3, view_dir: Vector3, wave_normal_perturbation: Vector3) -> f32 {
+    let n = normalize_vec3(normal + wave_normal_perturbation);
+    if n.dot(sun_dir) <= 0.0 {
+        return 0.0;
+    }
+    let half_vector = normalize_vec3(sun_dir + view_dir);
+    n.dot(half_vector).max(0.0).powf(OCEAN_SHININESS)
+}
+
+// ☁️ Nubes de la Tierra: altitud (en radios), umbral de nube espesa y oscurecimiento de su sombra
+const EARTH_CLOUD_ALTITUDE: f32 = 0.02;
+const EARTH_CLOUD_SHADOW_THRESHOLD: f32 = 0.55;
+const EARTH_CLOUD_SHADOW_STRENGTH: f32 = 0.5;
+const EARTH_CLOUD_MAX_SHADOW_TAN: f32 = 4.0;
+// Altitud de las nubes lenticulares, por encima de la capa convectiva
+const EARTH_LENTICULAR_ALTITUDE: f32 = 0.04;
+
+// Cobertura de nubes en un punto de la malla (espacio de objeto), entre 0.2 y 1
+fn earth_cloud_cover(pos: Vector3, time: f32) -> f32 {
+    let cloud_noise =
+        ((pos.x * 4.0 + time * 0.2).cos() * 0.4 +
+         (pos.y * 5.0).sin() * 0.3 +
+         (pos.z * 3.0 + time * 0.15).sin() * 0.3).abs() * 0.6 + 0.2;
+    cloud_noise.min(1.0)
+}
+
+// Dirección de mundo llevada a espacio de objeto. La matriz de modelo es escala uniforme más
+// rotación, así que basta la traspuesta de su parte 3x3 y normalizar.
+fn world_dir_to_object(uniforms: &Uniforms, dir: Vector3) -> Vector3 {
+    let m = &uniforms.model_matrix;
+    normalize_vec3(Vector3::new(
+        m.m0 * dir.x + m.m1 * dir.y + m.m2 * dir.z,
+        m.m4 * dir.x + m.m5 * dir.y + m.m6 * dir.z,
+        m.m8 * dir.x + m.m9 * dir.y + m.m10 * dir.z,
+    ))
+}
+
+/// Cuánto tapa una nube el Sol sobre `pos` (0 despejado, 1 nube espesa), con `sun_local` en
+/// espacio de objeto. La nube que hace sombra está desplazada hacia el Sol en el plano tangente.
+fn earth_cloud_shadow(pos: Vector3, sun_local: Vector3, time: f32) -> f32 {
+    let up = normalize_vec3(pos);
+    let cos_zenith = up.dot(sun_local);
+    if cos_zenith <= 0.0 {
+        return 0.0;
+    }
+    // Desplazamiento horizontal = altitud * tan(ángulo cenital), limitado cerca del terminador
+    let tan_zenith = ((1.0 - cos_zenith * cos_zenith).max(0.0).sqrt() / cos_zenith).min(EARTH_CLOUD_MAX_SHADOW_TAN);
+    let toward_sun = normalize_vec3(sun_local - up * cos_zenith);
+    let shadow_pos = normalize_vec3(up + toward_sun * (EARTH_CLOUD_ALTITUDE * tan_zenith)) * pos.length();
+    smoothstep(EARTH_CLOUD_SHADOW_THRESHOLD - 0.1, EARTH_CLOUD_SHADOW_THRESHOLD + 0.1, earth_cloud_cover(shadow_pos, time))
+}
+
 // 🌍 Tierra
 pub fn earth_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
     let pos = fragment.world_position;
@@ -145,11 +737,7 @@ pub fn earth_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector
 
     let is_land = land_noise.max(0.0).min(1.0);
 
-    let cloud_noise = 
-        ((pos.x * 4.0 + time * 0.2).cos() * 0.4 +
-         (pos.y * 5.0).sin() * 0.3 +
-         (pos.z * 3.0 + time * 0.15).sin() * 0.3).abs() * 0.6 + 0.2;
-    let cloud_factor = cloud_noise.min(1.0);
+    let cloud_factor = earth_cloud_cover(pos, time);
 
     let ocean_color = Vector3::new(0.05, 0.15, 0.5);
     let shallow_ocean = Vector3::new(0.2, 0.4, 0.8);
@@ -169,29 +757,242 @@ pub fn earth_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector
     let coast_blend = (0.2 - (is_land - 0.1).abs()).max(0.0) * 5.0;
     let blended_surface = surface_color * (1.0 - coast_blend) + shallow_ocean * coast_blend;
 
+    // ☁️ Sombra de las nubes: la nube que tapa el Sol está desplazada hacia él en el plano tangente
+    let (normal, sun_dir, view_dir) = lighting_vectors(fragment, uniforms);
+    let up = normalize_vec3(pos);
+    let cloud_shadow = earth_cloud_shadow(pos, world_dir_to_object(uniforms, sun_dir), time);
+    let blended_surface = blended_surface * (1.0 - cloud_shadow * EARTH_CLOUD_SHADOW_STRENGTH);
+
     let cloud_color = Vector3::new(0.95, 0.97, 1.0);
     let final_color = blended_surface * (1.0 - cloud_factor * 0.6) + cloud_color * cloud_factor * 0.6;
 
-    // ✅ Corregido: sin &
-    let light_dir = normalize_vec3(Vector3::new(1.0, 1.0, 1.0));
-    let dot = pos.dot(light_dir).max(0.0); // ✅ aquí estaba el error
-    let lit_color = final_color * dot.max(0.2);
+    // 🌫️ Capa lenticular por encima de los cúmulos, solo en latitudes altas
+    let high_latitude = smoothstep(0.5, 0.75, up.y.abs());
+    let lenticular = lenticular_cloud(up.z.atan2(up.x), up.y.asin(), EARTH_LENTICULAR_ALTITUDE, time) * high_latitude;
+    let final_color = final_color * (1.0 - lenticular * 0.5) + cloud_color * (lenticular * 0.5);
+
+    // 🌗 Terminador (ver `earth_twilight_light`)
+    let cos_sza = normal.dot(sun_dir);
+    let astronomical = smoothstep(-0.309, -0.208, cos_sza);
+    let twilight_light = earth_twilight_light(cos_sza);
+    let horizon_glow = Vector3::new(0.9, 0.4, 0.1) * ((-cos_sza.abs() / 0.05).exp() * 0.35);
+
+    // 🌃 Luces de ciudades en tierra firme; aparecen al terminar el crepúsculo astronómico
+    let city_noise = value_noise_3d(up.x * 60.0, up.y * 60.0, up.z * 60.0);
+    let temperate = 1.0 - smoothstep(0.6, 0.8, up.y.abs());
+    let cities = smoothstep(0.7, 0.85, city_noise) * is_land * temperate * (1.0 - cloud_factor * 0.7) * (1.0 - astronomical);
+    let city_lights = Vector3::new(1.0, 0.8, 0.45) * (cities * 0.9);
+
+    // Oleaje animado: fbm desplazado en el tiempo, una muestra por eje de la normal
+    let wave_pos = up * 25.0;
+    let wave = Vector3::new(
+        fbm_3d(wave_pos.x + time * 0.3, wave_pos.y, wave_pos.z, 3, 2.0, 0.5) - 0.5,
+        fbm_3d(wave_pos.x, wave_pos.y + time * 0.3, wave_pos.z + 17.0, 3, 2.0, 0.5) - 0.5,
+        fbm_3d(wave_pos.x + 31.0, wave_pos.y, wave_pos.z + time * 0.3, 3, 2.0, 0.5) - 0.5,
+    ) * OCEAN_WAVE_HEIGHT;
+    let ocean_mask = 1.0 - smoothstep(0.0, 0.1, is_land);
+    let glint = ocean_specular(normal, sun_dir, view_dir, wave) * ocean_mask * (1.0 - cloud_factor * 0.6);
+    let sun_glint = Vector3::new(1.0, 0.95, 0.75) * sun_light_color(fragment, uniforms) * (glint * 1.5);
+
+    let day_color = planet_lighting(fragment, uniforms, final_color);
+    let lit_color = day_color + sun_glint + final_color * twilight_light + horizon_glow + city_lights;
+    let lit_color = apply_earth_atmosphere(fragment, uniforms, lit_color);
+
+    Vector3::new(lit_color.x.min(1.0), lit_color.y.min(1.0), lit_color.z.min(1.0))
+}
+
+/// Luz del cielo crepuscular (fracción del color de la superficie) según el coseno del ángulo
+/// cenital solar: crepúsculo civil (0° a -6°), náutico (-6° a -12°) y astronómico (-12° a -18°).
+/// Se apaga mientras sube el Sol sin que la luz total (difusa + crepúsculo) baje en ningún punto.
+fn earth_twilight_light(cos_sza: f32) -> f32 {
+    let civil_twilight = smoothstep(-0.105, 0.0, cos_sza);
+    let nautical = smoothstep(-0.208, -0.105, cos_sza);
+    let astronomical = smoothstep(-0.309, -0.208, cos_sza);
+    let night_side = 1.0 - smoothstep(0.0, 0.3, cos_sza);
+    (civil_twilight * 0.5 + nautical * 0.3 + astronomical * 0.2) * night_side * 0.18
+}
+
+// 🌊 Mundo océano (Kepler-22b): sin tierra firme, con oleaje animado, espuma en las tormentas y
+// plancton bioluminiscente en el lado nocturno
+const OCEAN_WORLD_WAVE_FREQUENCY: f32 = 14.0;
+const OCEAN_WORLD_WAVE_SPEED: f32 = 0.3;
+// Cuánto inclina el gradiente del oleaje la normal de la esfera
+const OCEAN_WORLD_WAVE_SLOPE: f32 = 0.08;
+const OCEAN_WORLD_BIOLUM_SCALE: f32 = 40.0;
+const OCEAN_WORLD_BIOLUM_SPEED: f32 = 0.6;
+// Distancias a la estrella entre las que el agua pasa de turquesa templado a azul profundo y frío
+const OCEAN_WORLD_WARM_DISTANCE: f32 = 25.0;
+const OCEAN_WORLD_COLD_DISTANCE: f32 = 90.0;
+
+// Oleaje: la altura es un fBm que se desplaza con el tiempo; su gradiente da la normal de la ola.
+// Devuelve la perturbación de la normal (tangente a `up`) y la altura de la ola
+fn ocean_world_waves(up: Vector3, time: f32) -> (Vector3, f32) {
+    let wave_pos = up * OCEAN_WORLD_WAVE_FREQUENCY + Vector3::one() * (time * OCEAN_WORLD_WAVE_SPEED);
+    let wave_normal = normalize_vec3(fbm_gradient(wave_pos, 4, 2.0, 0.5));
+    let perturbation = (wave_normal - up * wave_normal.dot(up)) * -OCEAN_WORLD_WAVE_SLOPE;
+    (perturbation, fbm_3d(wave_pos.x, wave_pos.y, wave_pos.z, 4, 2.0, 0.5))
+}
+
+pub fn ocean_world_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
+    let up = normalize_vec3(fragment.world_position);
+    let time = uniforms.time;
+
+    let sun_distance = (sun_position(uniforms) - fragment_world_position(fragment, uniforms)).length();
+    let cold = smoothstep(OCEAN_WORLD_WARM_DISTANCE, OCEAN_WORLD_COLD_DISTANCE, sun_distance);
+    let base = Vector3::new(0.05, 0.35, 0.45) * (1.0 - cold) + Vector3::new(0.01, 0.06, 0.25) * cold;
+
+    let (perturbation, wave_height) = ocean_world_waves(up, time);
+
+    // Espuma en las crestas más altas, solo dentro de las tormentas (fBm de gran escala)
+    let storms = fbm_3d(up.x * 2.0 + time * 0.01, up.y * 2.0, up.z * 2.0, 3, 2.0, 0.5);
+    let foam = smoothstep(0.6, 0.7, wave_height) * smoothstep(0.5, 0.65, storms);
+    let surface_color = base * (1.0 - foam) + Vector3::new(0.9, 0.95, 1.0) * foam;
+
+    // Brillo del Sol sobre el agua, en espacio de objeto como la normal de la ola
+    let (_, sun_dir, view_dir) = lighting_vectors(fragment, uniforms);
+    let sun_local = world_dir_to_object(uniforms, sun_dir);
+    let view_local = world_dir_to_object(uniforms, view_dir);
+    let glint = ocean_specular(up, sun_local, view_local, perturbation) * (1.0 - foam);
+    let sun_glint = Vector3::new(1.0, 0.97, 0.9) * sun_light_color(fragment, uniforms) * (glint * 1.5);
+
+    // 🦠 Plancton bioluminiscente: franjas que se encienden en las zonas de floración, de noche
+    let night_side = 1.0 - smoothstep(-0.1, 0.05, up.dot(sun_local));
+    let swirl = fbm_3d(up.x * 5.0, up.y * 5.0 + time * 0.02, up.z * 5.0, 3, 2.0, 0.5) * 6.0;
+    let phase = (up.x + up.z * 0.7) * OCEAN_WORLD_BIOLUM_SCALE + swirl + time * OCEAN_WORLD_BIOLUM_SPEED;
+    let bloom = smoothstep(0.5, 0.6, fbm_3d(up.x * 3.0 + 7.0, up.y * 3.0, up.z * 3.0, 3, 2.0, 0.5));
+    let biolum = (if phase.sin() > 0.8 { 1.0 } else { 0.0 }) * bloom * night_side;
+    let plankton = Vector3::new(0.1, 0.9, 0.7) * (biolum * 0.6);
 
+    let lit_color = planet_lighting(fragment, uniforms, surface_color) + sun_glint + plankton;
+    let lit_color = apply_atmosphere(fragment, uniforms, lit_color, Vector3::new(0.6, 0.75, 0.95));
+    Vector3::new(lit_color.x.min(1.0), lit_color.y.min(1.0), lit_color.z.min(1.0))
+}
+
+// 🌌 Océano bioluminiscente (Gliese 1214b): oleaje de Gerstner con cuatro trenes de olas cuyas
+// amplitudes salen del espectro de Phillips para el viento dado. De día, azul profundo con espuma
+// en las crestas; de noche, las crestas se encienden de cian verdoso al ritmo de un latido lento
+const BIOLUM_OCEAN_WIND_SPEED: f32 = 12.0; // m / s
+// Dirección del viento y trenes de olas: (dirección, número de onda en radios⁻¹, inclinación Q)
+const BIOLUM_OCEAN_WIND_DIR: Vector3 = Vector3::new(0.8, 0.0, 0.6);
+const BIOLUM_OCEAN_WAVES: [(Vector3, f32, f32); 4] = [
+    (Vector3::new(0.8, 0.0, 0.6), 24.0, 0.6),
+    (Vector3::new(0.6, 0.28, 0.75), 31.0, 0.5),
+    (Vector3::new(0.96, -0.28, 0.0), 43.0, 0.4),
+    (Vector3::new(0.28, 0.0, 0.96), 57.0, 0.3),
+];
+// Metros por radio del planeta: pasa los números de onda de la malla a m⁻¹ para el espectro
+const BIOLUM_OCEAN_METERS_PER_RADIUS: f32 = 300.0;
+const PHILLIPS_CONSTANT: f32 = 3e-4;
+const OCEAN_GRAVITY: f32 = 9.81;
+// Gravedad en radios / s², para la relación de dispersión ω = √(g·k) en unidades de la malla
+const BIOLUM_OCEAN_GRAVITY: f32 = 0.2;
+// Altura normalizada de cresta a partir de la cual brilla el plancton / aparece la espuma
+const BIOLUM_OCEAN_CREST_THRESHOLD: f32 = 0.45;
+const BIOLUM_OCEAN_FOAM_THRESHOLD: f32 = 0.6;
+const BIOLUM_OCEAN_HEARTBEAT_SPEED: f32 = 0.5;
+
+/// Amplitud (m) de una ola de número de onda `wavenumber` (m⁻¹) según el espectro de Phillips:
+/// P(k) = A·exp(-1/(kL)²)/k⁴·|k̂·ŵ|², con L = V²/g la ola más larga que levanta un viento de
+/// `ocean_wind_speed` m/s. `alignment` es el coseno entre la ola y el viento; las olas contra el
+/// viento no crecen.
+pub fn phillips_amplitude(wavenumber: f32, alignment: f32, ocean_wind_speed: f32) -> f32 {
+    let largest_wave = ocean_wind_speed * ocean_wind_speed / OCEAN_GRAVITY;
+    if wavenumber <= 0.0 || largest_wave <= 0.0 || alignment <= 0.0 {
+        return 0.0;
+    }
+    let kl = wavenumber * largest_wave;
+    let spectrum = PHILLIPS_CONSTANT * (-1.0 / (kl * kl)).exp() / wavenumber.powi(4) * alignment * alignment;
+    spectrum.sqrt()
+}
+
+/// Suma de Gerstner de los cuatro trenes de olas en el punto `up` de la esfera unidad (espacio
+/// de objeto). Devuelve (altura normalizada en [-1, 1], altura máxima en metros, gradiente de la
+/// altura en m por radio, tangente a la esfera). Cada ola es A·sin(k·(D·p) - ωt) con ω = √(g·k),
+/// así que a t = 0 las crestas caen donde k·(D·p) = π/2 + 2πn.
+pub fn gerstner_ocean_waves(up: Vector3, time: f32, ocean_wind_speed: f32) -> (f32, f32, Vector3) {
+    gerstner_wave_sum(&BIOLUM_OCEAN_WAVES, up, time, ocean_wind_speed)
+}
+
+// La suma de `gerstner_ocean_waves` para cualquier tabla de trenes (dirección, k, Q)
+fn gerstner_wave_sum(waves: &[(Vector3, f32, f32)], up: Vector3, time: f32, ocean_wind_speed: f32) -> (f32, f32, Vector3) {
+    let wind = normalize_vec3(BIOLUM_OCEAN_WIND_DIR);
+    let mut height = 0.0;
+    let mut max_height = 0.0;
+    let mut gradient = Vector3::zero();
+    let mut crest_pinch = 0.0;
+    for &(dir, k, steepness) in waves {
+        let dir = normalize_vec3(dir);
+        let amplitude = phillips_amplitude(k / BIOLUM_OCEAN_METERS_PER_RADIUS, dir.dot(wind), ocean_wind_speed);
+        let omega = (BIOLUM_OCEAN_GRAVITY * k).sqrt();
+        let phase = k * dir.dot(up) - omega * time;
+        height += amplitude * phase.sin();
+        max_height += amplitude;
+        gradient += dir * (amplitude * k * phase.cos());
+        // El desplazamiento horizontal de Gerstner junta los puntos en la cresta: la afila
+        crest_pinch += steepness * amplitude * phase.sin();
+    }
+    if max_height <= 0.0 {
+        return (0.0, 0.0, Vector3::zero());
+    }
+    let normalized = (height + crest_pinch * (height / max_height).max(0.0)) / max_height;
+    let tangent_gradient = gradient - up * gradient.dot(up);
+    (normalized.clamp(-1.0, 1.0), max_height, tangent_gradient)
+}
+
+pub fn bioluminescent_ocean_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
+    let up = normalize_vec3(fragment.world_position);
+    let time = uniforms.time;
+
+    let (wave_height, max_height, gradient) = gerstner_ocean_waves(up, time, BIOLUM_OCEAN_WIND_SPEED);
+    // El gradiente va en metros por radio: se pasa a pendiente dividiendo por los metros por radio
+    let perturbation = gradient * (-1.0 / BIOLUM_OCEAN_METERS_PER_RADIUS);
+
+    // Día: azul profundo, más claro en las crestas, con espuma blanca en las más altas
+    let crest = wave_height * 0.5 + 0.5;
+    let base = Vector3::new(0.01, 0.07, 0.28) * (1.0 - crest) + Vector3::new(0.03, 0.2, 0.45) * crest;
+    let foam = smoothstep(BIOLUM_OCEAN_FOAM_THRESHOLD, BIOLUM_OCEAN_FOAM_THRESHOLD + 0.15, wave_height) * smoothstep(0.0, 0.5, max_height);
+    let surface_color = base * (1.0 - foam) + Vector3::new(0.92, 0.96, 1.0) * foam;
+
+    let (_, sun_dir, view_dir) = lighting_vectors(fragment, uniforms);
+    let sun_local = world_dir_to_object(uniforms, sun_dir);
+    let view_local = world_dir_to_object(uniforms, view_dir);
+    let glint = ocean_specular(up, sun_local, view_local, perturbation) * (1.0 - foam);
+    let sun_glint = Vector3::new(1.0, 0.97, 0.9) * sun_light_color(fragment, uniforms) * (glint * 1.5);
+
+    // 🦠 Noche (Sol bajo el horizonte): manchas cian verdosas sobre las crestas que laten despacio
+    let night_side = 1.0 - smoothstep(-0.1, 0.05, up.dot(sun_local));
+    let heartbeat = ((time * BIOLUM_OCEAN_HEARTBEAT_SPEED).sin() + 1.0) / 2.0;
+    let on_crest = if wave_height > BIOLUM_OCEAN_CREST_THRESHOLD { smoothstep(BIOLUM_OCEAN_CREST_THRESHOLD, 1.0, wave_height) } else { 0.0 };
+    let spots = smoothstep(0.45, 0.7, value_noise_3d(up.x * 60.0, up.y * 60.0 + time * 0.05, up.z * 60.0));
+    let biolum = on_crest * (0.4 + 0.6 * spots) * (0.25 + 0.75 * heartbeat) * night_side;
+    let glow = Vector3::new(0.15, 1.0, 0.8) * biolum;
+
+    let lit_color = planet_lighting(fragment, uniforms, surface_color) + sun_glint + glow;
+    let lit_color = apply_atmosphere(fragment, uniforms, lit_color, Vector3::new(0.55, 0.75, 0.95));
     Vector3::new(lit_color.x.min(1.0), lit_color.y.min(1.0), lit_color.z.min(1.0))
 }
 
 // 🔴 Marte
+// Velocidad del ciclo de estaciones de Marte (rad / s de simulación)
+const MARS_SEASON_SPEED: f32 = 0.05;
+
+// Casquetes polares con borde irregular: umbral de `lat_factor` (|latitud| / 90°) a partir del
+// que hay hielo, variado con ruido sobre la posición en la malla. Alrededor de 76° y con más de
+// ±5° de oscilación; nunca tan cerca del polo que no quede hielo en él
+fn mars_ice_cap_threshold(pos: Vector3) -> f32 {
+    (0.85 + (fbm_3d(pos.x * 5.0, pos.z * 5.0, 0.0, 3, 2.0, 0.5) - 0.5) * 0.3).min(0.95)
+}
+
 pub fn mars_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
     let pos = fragment.world_position;
     let time = uniforms.time;
 
-    let longitude = (pos.z.atan2(pos.x) + std::f32::consts::PI) / (2.0 * std::f32::consts::PI);
-    let latitude = (pos.y.asin() + std::f32::consts::PI / 2.0) / std::f32::consts::PI;
+    // Latitud sobre la dirección normalizada: la malla tiene radio ~0.52, no 1
+    let latitude = (normalize_vec3(pos).y.asin() + std::f32::consts::PI / 2.0) / std::f32::consts::PI;
 
-    let terrain_base = 
-        ((longitude * 10.0 + latitude * 3.0).sin() * 0.4 +
-         (longitude * 5.0 + time * 0.02).cos() * 0.3 +
-         (latitude * 7.0).sin() * 0.3).abs() * 1.2 - 0.5;
+    // Terreno con fBm de ruido de valor (sin el tiling de las sinusoides)
+    let terrain_base =
+        fbm_3d(pos.x * 4.0 + time * 0.02, pos.y * 4.0, pos.z * 4.0, 5, 2.0, 0.5) * 1.2 - 0.5;
 
     let crater_noise = 
         ((pos.x * 15.0).sin() * (pos.y * 12.0).cos() * (pos.z * 10.0).sin() * 0.6).abs().powf(1.5);
@@ -210,21 +1011,174 @@ pub fn mars_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3
 
     let rocky_color = base_mars * (1.0 - terrain_factor) + dark_rock * terrain_factor;
     let cratered_color = rocky_color * (1.0 - crater_factor * 0.5) + dark_rock * crater_factor * 0.5;
+    // Basalto en las tierras bajas y gris en las cumbres, antes del hielo y el polvo
+    let cratered_color = apply_altitude_shading(cratered_color, pos, &MARS_ALTITUDE_PALETTE, MARS_ALTITUDE_FACTOR);
+
+    let ice_threshold = mars_ice_cap_threshold(pos);
+    let polar_blend = smoothstep(ice_threshold, ice_threshold + 0.03, lat_factor(latitude));
+    let capped_surface = cratered_color * (1.0 - polar_blend) + ice_caps * polar_blend;
 
-    let polar_blend = (lat_factor(latitude) - 0.8).max(0.0) * 5.0;
-    let final_surface = cratered_color * (1.0 - polar_blend) + ice_caps * polar_blend;
+    // Escarcha de CO2: manchas pasajeras en latitudes medias del hemisferio en invierno
+    let season = (time * MARS_SEASON_SPEED).sin();
+    let winter = if pos.y > 0.0 { season.max(0.0) } else { (-season).max(0.0) };
+    let mid_latitudes = smoothstep(0.45, 0.6, lat_factor(latitude)) * (1.0 - polar_blend);
+    let co2_frost = (fbm_3d(pos.x * 8.0 + time * 0.001, pos.y * 8.0, pos.z * 8.0, 3, 2.0, 0.5) * 2.0 - 1.5).max(0.0);
+    let frost = (co2_frost * 2.0 * winter * mid_latitudes).min(1.0);
+    let final_surface = capped_surface * (1.0 - frost) + ice_caps * frost;
 
     let dusty_color = final_surface * (1.0 - dust * 0.3) + light_dust * dust * 0.3;
 
-    // ✅ Corregido: sin &
-    let light_dir = normalize_vec3(Vector3::new(1.0, 1.0, 1.0));
-    let dot = pos.dot(light_dir).max(0.0); // ✅ aquí estaba el error
-    let lit_color = dusty_color * dot.max(0.2);
+    let lit_color = apply_atmosphere(fragment, uniforms, planet_lighting(fragment, uniforms, dusty_color), Vector3::new(0.8, 0.55, 0.4));
+
+    Vector3::new(lit_color.x.min(1.0), lit_color.y.min(1.0), lit_color.z.min(1.0))
+}
+
+/// SDF exacto de un hexágono regular centrado en el origen con apotema `size` y lados planos
+/// arriba y abajo (la fórmula habitual: se pliega el plano a un solo sector por simetría).
+/// Negativo dentro, positivo fuera.
+pub fn hexagon_distance(uv: Vector2, size: f32) -> f32 {
+    // (-cos 30°, sin 30°) es la normal del lado inclinado; tan 30° da la media arista
+    let (kx, ky, kz) = (-0.866_025_4, 0.5, 0.577_350_3);
+    let mut p = Vector2::new(uv.x.abs(), uv.y.abs());
+    let fold = 2.0 * (kx * p.x + ky * p.y).min(0.0);
+    p = Vector2::new(p.x - fold * kx, p.y - fold * ky);
+    p = Vector2::new(p.x - p.x.clamp(-kz * size, kz * size), p.y - size);
+    p.length() * p.y.signum()
+}
+
+// 🟠 Júpiter: auroras de la corriente de Ío, un óvalo continuo a 15° del polo
+const JUPITER_AURORA_COLATITUDE: f32 = 15.0 * std::f32::consts::PI / 180.0;
+const JUPITER_AURORA_HALF_WIDTH: f32 = 5.0 * std::f32::consts::PI / 180.0;
+
+/// Emisión de la aurora (se suma después de la iluminación, brilla también de noche)
+pub fn jupiter_polar_aurora(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
+    let dir = normalize_vec3(fragment.world_position);
+    let latitude = dir.y.clamp(-1.0, 1.0).asin();
+    let colatitude = std::f32::consts::FRAC_PI_2 - latitude.abs();
+    let longitude = dir.z.atan2(dir.x);
+
+    // Óvalo: banda a colatitud fija con bordes suaves y cortinas que ondulan en longitud
+    let offset = (colatitude - JUPITER_AURORA_COLATITUDE).abs();
+    let oval = 1.0 - smoothstep(JUPITER_AURORA_HALF_WIDTH * 0.4, JUPITER_AURORA_HALF_WIDTH, offset);
+    let curtains = 0.6 + 0.4 * (longitude * 12.0 + uniforms.time * 1.5).sin() * (longitude * 5.0 - uniforms.time * 0.7).cos();
+    // Brillo difuso dentro del óvalo, por encima de 75° de latitud
+    let polar = smoothstep(75.0_f32.to_radians(), 85.0_f32.to_radians(), latitude.abs()) * 0.3;
+
+    Vector3::new(0.75, 0.35, 1.0) * (oval * curtains + polar)
+}
+
+// 🌊 Inestabilidad de Kelvin-Helmholtz en los chorros de Júpiter: los bordes de banda están donde
+// sin(latitud) · JUPITER_BAND_FREQUENCY es múltiplo de π
+const JUPITER_BAND_FREQUENCY: f32 = 14.0;
+// Longitud de onda de la relación de dispersión (k = v / λ), amplitud máxima del desplazamiento
+// en latitud (rad) y ancho de la capa de cizalla alrededor del chorro (en fracción de banda)
+const KH_WAVE_LENGTH: f32 = 0.05;
+const KH_AMPLITUDE: f32 = 0.012;
+const KH_SHEAR_WIDTH: f32 = 0.18;
+// Velocidad de los chorros (rad / s simulado); alternan prógrados y retrógrados
+const JUPITER_JET_SPEED: f32 = 0.8;
+
+/// Número de onda dominante k = v / λ, redondeado a un entero para que el patrón cierre en la
+/// vuelta completa de longitud (el periodo en longitud es 2π / k).
+pub fn kelvin_helmholtz_wavenumber(band_velocity: f32) -> f32 {
+    (band_velocity.abs() / KH_WAVE_LENGTH).round().max(1.0)
+}
+
+/// Ondas de Kelvin-Helmholtz en el borde de banda más cercano a `latitude` (rad). Devuelve
+/// (desplazamiento en latitud, rizo de nube en [0, 1], peso de la capa de cizalla en [0, 1]).
+/// El desplazamiento es `A · sin(k · longitud + t · v)`, con A máxima en el centro del chorro y
+/// que decae en gaussiana al alejarse; el rizo marca las crestas que se enrollan.
+pub fn kelvin_helmholtz_instability(latitude: f32, longitude: f32, band_velocity: f32, time: f32) -> Vector3 {
+    let band_position = latitude.sin() * JUPITER_BAND_FREQUENCY / std::f32::consts::PI;
+    let jet_offset = band_position - band_position.round();
+    let shear = (-(jet_offset / KH_SHEAR_WIDTH).powi(2)).exp();
+
+    let k = kelvin_helmholtz_wavenumber(band_velocity);
+    let phase = k * longitude + time * band_velocity;
+    let displacement = KH_AMPLITUDE * shear * phase.sin();
+    // La cresta se enrolla hacia el lado lento: el segundo armónico la vuelve asimétrica
+    let curl = (phase.sin() + 0.35 * (2.0 * phase).sin()).max(0.0) / 1.2;
+    Vector3::new(displacement, curl * shear, shear)
+}
+
+pub fn jupiter_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
+    let dir = normalize_vec3(fragment.world_position);
+    let time = uniforms.time;
+
+    // Ondas de Kelvin-Helmholtz en el chorro más cercano: cada uno sopla en sentido contrario al
+    // anterior y a su propia velocidad
+    let latitude = dir.y.clamp(-1.0, 1.0).asin();
+    let longitude = dir.z.atan2(dir.x);
+    let jet = (latitude.sin() * JUPITER_BAND_FREQUENCY / std::f32::consts::PI).round() as i32;
+    let jet_direction = if jet.rem_euclid(2) == 0 { 1.0 } else { -1.0 };
+    let jet_velocity = jet_direction * JUPITER_JET_SPEED * (0.7 + 0.6 * hash_3d(jet, 0, 7));
+    let kh = kelvin_helmholtz_instability(latitude, longitude, jet_velocity, time);
+    let perturbed_y = (latitude + kh.x).sin();
+
+    // Bandas (zonas claras y cinturones oscuros) deformadas por turbulencia
+    let turbulence = fbm_3d(dir.x * 4.0 + time * 0.02, dir.y * 12.0, dir.z * 4.0, 4, 2.0, 0.5);
+    let bands = ((perturbed_y * JUPITER_BAND_FREQUENCY + turbulence * 1.5).sin() * 0.5 + 0.5).powf(1.5);
+    let zone = Vector3::new(0.93, 0.87, 0.75);
+    let belt = Vector3::new(0.70, 0.50, 0.35);
+    let mut surface_color = zone * (1.0 - bands) + belt * bands;
+    // Las crestas enrolladas levantan nube clara sobre el borde
+    surface_color = surface_color * (1.0 - kh.y * 0.35) + zone * (kh.y * 0.35);
+
+    // Gran Mancha Roja a unos 22° S
+    let spot_center = normalize_vec3(Vector3::new(0.93, -0.37, 0.0));
+    let spot_offset = dir - spot_center;
+    let spot_distance = (spot_offset.x * spot_offset.x * 0.5 + spot_offset.y * spot_offset.y * 2.0 + spot_offset.z * spot_offset.z * 0.5).sqrt();
+    let spot = 1.0 - smoothstep(0.08, 0.14, spot_distance);
+    surface_color = surface_color * (1.0 - spot) + Vector3::new(0.75, 0.32, 0.2) * spot;
+
+    let lit_color = apply_atmosphere(fragment, uniforms, planet_lighting(fragment, uniforms, surface_color), Vector3::new(0.9, 0.8, 0.65));
+    let lit_color = lit_color + jupiter_polar_aurora(fragment, uniforms);
+
+    Vector3::new(lit_color.x.min(1.0), lit_color.y.min(1.0), lit_color.z.min(1.0))
+}
+
+// 👽 Planeta alienígena (Kepler-186f): manchas tipo Turing (reacción-difusión de Gray-Scott)
+// aproximadas con interferencia de ondas anidadas a varias escalas, sin simular nada.
+// (a, b, c, d, e, peso) de cada capa
+const TURING_LAYERS: [(f32, f32, f32, f32, f32, f32); 4] = [
+    (7.0, 5.0, 6.0, 8.0, 4.0, 0.4),
+    (13.0, 11.0, 9.0, 12.0, 10.0, 0.3),
+    (23.0, 19.0, 21.0, 17.0, 22.0, 0.2),
+    (41.0, 37.0, 43.0, 39.0, 35.0, 0.1),
+];
+
+/// Patrón de Turing en [-1, 1] en un punto de la esfera unidad
+pub fn turing_pattern(pos: Vector3, time: f32) -> f32 {
+    TURING_LAYERS
+        .iter()
+        .map(|&(a, b, c, d, e, weight)| {
+            (pos.x * a + (pos.y * b + pos.z * c).sin()).sin() * (pos.y * d + (pos.z * e + time * 0.1).cos()).cos() * weight
+        })
+        .sum()
+}
+
+pub fn alien_planet_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
+    let pos = normalize_vec3(fragment.world_position);
+    let pattern = turing_pattern(pos, uniforms.time);
+
+    // Bioma frío (cian/magenta) en los polos y cálido (verde/naranja) en el ecuador
+    let warm = 1.0 - smoothstep(0.3, 0.7, pos.y.abs());
+    let cells = smoothstep(-0.15, 0.15, pattern);
+    let cold_color = Vector3::new(0.1, 0.75, 0.8) * (1.0 - cells) + Vector3::new(0.8, 0.2, 0.7) * cells;
+    let warm_color = Vector3::new(0.25, 0.7, 0.2) * (1.0 - cells) + Vector3::new(0.95, 0.5, 0.1) * cells;
+    let surface_color = cold_color * (1.0 - warm) + warm_color * warm;
+    // Bordes de las células algo más oscuros
+    let membrane = 1.0 - (1.0 - pattern.abs() / 0.15).max(0.0) * 0.4;
+
+    let lit_color = apply_atmosphere(fragment, uniforms, planet_lighting(fragment, uniforms, surface_color * membrane), Vector3::new(0.5, 0.9, 0.8));
 
     Vector3::new(lit_color.x.min(1.0), lit_color.y.min(1.0), lit_color.z.min(1.0))
 }
 
 // 🪐 Urano
+// Altitud de las nubes de metano (en radios)
+const URANUS_METHANE_CLOUD_ALTITUDE: f32 = 0.03;
+
 pub fn uranus_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
     let pos = fragment.world_position;
     let time = uniforms.time;
@@ -232,7 +1186,9 @@ pub fn uranus_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vecto
     let latitude = (pos.y.asin() / (std::f32::consts::PI / 2.0)).abs();
 
     let band_noise = ((latitude * 10.0 + time * 0.1).sin() * 0.4 + 0.6).max(0.0).min(1.0);
-    let small_clouds = ((pos.x * 12.0 + time * 0.3).cos() * (pos.z * 8.0).sin() * 0.5 + 0.5).max(0.0).min(1.0);
+    // Nubes altas de metano en bandas lenticulares a lo largo de los paralelos
+    let dir = normalize_vec3(pos);
+    let small_clouds = lenticular_cloud(dir.z.atan2(dir.x), dir.y.asin(), URANUS_METHANE_CLOUD_ALTITUDE, time);
 
     let base = Vector3::new(0.55, 0.80, 0.88);
     let band_dark = Vector3::new(0.45, 0.70, 0.80);
@@ -247,14 +1203,557 @@ pub fn uranus_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vecto
     let polar_glow = (1.0 - latitude).powf(4.0) * 0.3;
     let glow_color = Vector3::new(0.7, 0.9, 1.0) * polar_glow;
 
-    // ✅ Corregido: sin &
-    let light_dir = normalize_vec3(Vector3::new(1.0, 1.0, 1.0));
-    let dot = pos.dot(light_dir).max(0.0); // ✅ aquí estaba el error
-    let lit_color = (final_color + glow_color) * dot.max(0.3);
+    let lit_color = apply_atmosphere(fragment, uniforms, planet_lighting(fragment, uniforms, final_color + glow_color), Vector3::new(0.6, 0.85, 0.9));
+
+    Vector3::new(lit_color.x.min(1.0), lit_color.y.min(1.0), lit_color.z.min(1.0))
+}
+
+// 🟡 Venus: capa de nubes espesa y amarillenta con remolinos lentos
+pub fn venus_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
+    let pos = normalize_vec3(fragment.world_position);
+    let time = uniforms.time;
+
+    // Las nubes giran más rápido que el planeta (superrotación): desplazar en longitud con el tiempo
+    let swirl = fbm_3d(pos.x * 3.0 + time * 0.05, pos.y * 6.0, pos.z * 3.0 - time * 0.05, 4, 2.0, 0.5);
+    let bands = (pos.y * 8.0 + swirl * 4.0).sin() * 0.5 + 0.5;
+
+    let pale = Vector3::new(0.95, 0.88, 0.65);
+    let ochre = Vector3::new(0.82, 0.68, 0.40);
+    let surface_color = pale * (1.0 - bands * 0.5) + ochre * (bands * 0.5);
+
+    let lit_color = apply_atmosphere(fragment, uniforms, planet_lighting(fragment, uniforms, surface_color), Vector3::new(0.95, 0.85, 0.6));
+    Vector3::new(lit_color.x.min(1.0), lit_color.y.min(1.0), lit_color.z.min(1.0))
+}
+
+// 🟤 Ceres: regolito gris con cráteres y las manchas brillantes del cráter Occator
+pub fn ceres_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
+    let pos = normalize_vec3(fragment.world_position);
+
+    let (f1, f2) = voronoi_3d(pos.x * 6.0, pos.y * 6.0, pos.z * 6.0);
+    let crater_rim = (1.0 - (f2 - f1) * 6.0).max(0.0);
+    let crater_floor = (1.0 - f1 * 2.0).max(0.0);
+    let grey = Vector3::new(0.42, 0.41, 0.40) * (0.9 + fbm_3d(pos.x * 10.0, pos.y * 10.0, pos.z * 10.0, 3, 2.0, 0.5) * 0.2);
+    let cratered = grey * (1.0 - crater_floor * 0.25) + Vector3::new(0.55, 0.54, 0.52) * (crater_rim * 0.3);
+
+    // Occator: dos sitios de Voronoi fijos (Cerealia y Vinalia Faculae), depósitos de carbonatos
+    let cerealia = normalize_vec3(Vector3::new(-0.45, 0.34, -0.82));
+    let vinalia = normalize_vec3(Vector3::new(-0.38, 0.30, -0.87));
+    let (spot, spot_size) = if (pos - cerealia).length() < (pos - vinalia).length() {
+        ((pos - cerealia).length(), 0.05)
+    } else {
+        ((pos - vinalia).length(), 0.03)
+    };
+    let brightness = (1.0 - spot / spot_size).clamp(0.0, 1.0).powf(0.5);
+    let surface_color = cratered * (1.0 - brightness) + Vector3::new(0.95, 0.95, 0.92) * brightness;
+
+    let lit_color = planet_lighting(fragment, uniforms, surface_color);
+    Vector3::new(lit_color.x.min(1.0), lit_color.y.min(1.0), lit_color.z.min(1.0))
+}
+
+// ☄️ Asteroides: basalto gris oscuro con eyecta brillante en los bordes de las celdas de Voronoi
+// (los bordes de los cráteres), con el fondo de cada cráter algo más oscuro
+pub fn asteroid_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
+    let pos = normalize_vec3(fragment.world_position);
 
+    let basalt = Vector3::new(0.2, 0.19, 0.18) * (0.85 + fbm_3d(pos.x * 8.0, pos.y * 8.0, pos.z * 8.0, 3, 2.0, 0.5) * 0.3);
+    let (f1, f2) = voronoi_3d(pos.x * 4.0, pos.y * 4.0, pos.z * 4.0);
+    let ejecta = 1.0 - smoothstep(0.0, 0.15, f2 - f1);
+    let crater_floor = (1.0 - f1 * 1.8).max(0.0);
+    let surface_color = basalt * (1.0 - crater_floor * 0.3) + Vector3::new(0.55, 0.53, 0.5) * (ejecta * 0.5);
+
+    let lit_color = planet_lighting(fragment, uniforms, surface_color);
     Vector3::new(lit_color.x.min(1.0), lit_color.y.min(1.0), lit_color.z.min(1.0))
 }
 
+// ☄️ Núcleo de cometa: hielo sucio casi negro. La temperatura de la superficie cae con la
+// distancia al Sol como T_SUN / sqrt(d) (en unidades de escena); por encima de
+// COMET_SUBLIMATION_TEMP el hielo sublima en chorros desde la cara iluminada.
+pub const COMET_ALBEDO: f32 = 0.04;
+pub const COMET_T_SUN: f32 = 600.0;
+pub const COMET_SUBLIMATION_TEMP: f32 = 150.0;
+// Emisión de los chorros: azul blanquecino de CN y CO2
+pub const COMET_JET_COLOR: Vector3 = Vector3::new(0.6, 0.8, 1.0);
+
+/// Temperatura (K) de la superficie del núcleo a `distance_to_sun` unidades del Sol.
+pub fn comet_surface_temperature(distance_to_sun: f32) -> f32 {
+    COMET_T_SUN / distance_to_sun.max(1e-3).sqrt()
+}
+
+/// Intensidad [0, 1] del chorro de sublimación en el punto `pos` (dirección desde el centro del
+/// núcleo) con el Sol en la dirección `sun_dir`, ambas unitarias. Solo hay chorros en la cara
+/// iluminada, desde unas pocas fuentes activas (celdas de Voronoi) que laten con `jet_phase`.
+pub fn sublimation_jet(pos: Vector3, sun_dir: Vector3, jet_phase: f32) -> f32 {
+    let insolation = pos.dot(sun_dir);
+    if insolation <= 0.0 {
+        return 0.0;
+    }
+    let (f1, _) = voronoi_3d(pos.x * 3.0, pos.y * 3.0, pos.z * 3.0);
+    let vent = 1.0 - smoothstep(0.05, 0.2, f1);
+    let pulse = 0.6 + 0.4 * (jet_phase + fbm_3d(pos.x * 2.0, pos.y * 2.0, pos.z * 2.0, 2, 2.0, 0.5) * std::f32::consts::TAU).sin();
+    insolation * vent * pulse
+}
+
+// El núcleo no gira, así que su espacio de malla tiene la orientación del mundo y la dirección
+// al Sol vale tal cual para situar las fuentes
+pub fn comet_nucleus_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
+    let pos = normalize_vec3(fragment.world_position);
+    let grime = 0.7 + fbm_3d(pos.x * 6.0, pos.y * 6.0, pos.z * 6.0, 4, 2.0, 0.5) * 0.6;
+    let surface_color = Vector3::new(COMET_ALBEDO, COMET_ALBEDO, COMET_ALBEDO * 1.1) * grime;
+    let lit_color = planet_lighting(fragment, uniforms, surface_color);
+
+    let world_pos = fragment_world_position(fragment, uniforms);
+    let to_sun = sun_position(uniforms) - world_pos;
+    let temperature = comet_surface_temperature(to_sun.length());
+    let activity = ((temperature - COMET_SUBLIMATION_TEMP) / COMET_SUBLIMATION_TEMP).clamp(0.0, 1.0);
+    let jet = sublimation_jet(pos, normalize_vec3(to_sun), uniforms.time * 2.0) * activity;
+    let color = lit_color + COMET_JET_COLOR * (jet * 0.8);
+    Vector3::new(color.x.min(1.0), color.y.min(1.0), color.z.min(1.0))
+}
+
+// 🟫 Plutón: Tombaugh Regio (el "corazón" de hielo de nitrógeno) centrado en 25°N 175°E, con un
+// tamaño de cardioide (radianes) que le da ~1000 km de ancho
+const PLUTO_HEART_LAT: f32 = 0.436;
+const PLUTO_HEART_LON: f32 = 3.054;
+const PLUTO_HEART_SIZE: f32 = 0.3;
+// Fuera del corazón Plutón es oscuro: brillo de la superficie relativo al de la Tierra
+const PLUTO_DARK_BRIGHTNESS: f32 = 0.4;
+// Latitud (rad) desde la que empieza el casquete norte de hielo de metano
+const PLUTO_POLAR_CAP_LAT: f32 = 1.05;
+
+/// Distancia con signo aproximada (en radianes) a un corazón centrado en (`center_lat`,
+/// `center_lon`): la cardioide r = a(1 − sin θ), con el pico hacia el sur y la hendidura arriba.
+/// El origen polar (la hendidura) se sube 5a/6 para que el centroide caiga en el centro.
+/// Negativa dentro. Las longitudes se comparan por el camino corto y se corrigen con la latitud.
+pub fn heart_sdf(lat: f32, lon: f32, center_lat: f32, center_lon: f32) -> f32 {
+    let d_lon = (lon - center_lon + std::f32::consts::PI).rem_euclid(2.0 * std::f32::consts::PI) - std::f32::consts::PI;
+    let x = d_lon * center_lat.cos();
+    let y = lat - center_lat - PLUTO_HEART_SIZE * 5.0 / 6.0;
+    let r = (x * x + y * y).sqrt();
+    let sin_theta = if r > 0.0 { y / r } else { 0.0 };
+    r - PLUTO_HEART_SIZE * (1.0 - sin_theta)
+}
+
+// Tolinas marrón rojizo con cráteres, el corazón de hielo de nitrógeno (celdas de convección de
+// Sputnik Planitia) y el casquete norte de metano
+pub fn pluto_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
+    let pos = normalize_vec3(fragment.world_position);
+    let lat = pos.y.clamp(-1.0, 1.0).asin();
+    let lon = pos.z.atan2(pos.x);
+
+    let tholins = Vector3::new(0.35, 0.22, 0.15);
+    let light_tholins = Vector3::new(0.55, 0.40, 0.30);
+    let terrain = fbm_3d(pos.x * 4.0, pos.y * 4.0, pos.z * 4.0, 4, 2.0, 0.5);
+    let (f1, f2) = voronoi_3d(pos.x * 7.0, pos.y * 7.0, pos.z * 7.0);
+    let crater_rim = 1.0 - smoothstep(0.0, 0.12, f2 - f1);
+    let crater_floor = (1.0 - f1 * 2.0).max(0.0);
+    let dark = (tholins * (1.0 - terrain) + light_tholins * terrain) * (1.0 - crater_floor * 0.2 + crater_rim * 0.15) * PLUTO_DARK_BRIGHTNESS;
+
+    let (cell_f1, cell_f2) = voronoi_3d(pos.x * 14.0, pos.y * 14.0, pos.z * 14.0);
+    let cell_edge = 1.0 - smoothstep(0.0, 0.08, cell_f2 - cell_f1);
+    let nitrogen_ice = Vector3::new(0.96, 0.9, 0.88) * (1.0 - cell_edge * 0.12);
+    let heart = 1.0 - smoothstep(-0.02, 0.02, heart_sdf(lat, lon, PLUTO_HEART_LAT, PLUTO_HEART_LON));
+    let surface_color = dark * (1.0 - heart) + nitrogen_ice * heart;
+
+    let methane_ice = Vector3::new(0.82, 0.78, 0.7);
+    let cap = smoothstep(PLUTO_POLAR_CAP_LAT, PLUTO_POLAR_CAP_LAT + 0.15, lat + (terrain - 0.5) * 0.1);
+    let surface_color = surface_color * (1.0 - cap) + methane_ice * cap;
+
+    let lit_color = apply_atmosphere(fragment, uniforms, planet_lighting(fragment, uniforms, surface_color), Vector3::new(0.55, 0.6, 0.75));
+    Vector3::new(lit_color.x.min(1.0), lit_color.y.min(1.0), lit_color.z.min(1.0))
+}
+
+// 🌋 Io: azufre amarillo con escarcha blanca de SO₂ y manchas oscuras de volcanes, cada una con
+// un halo rojizo de depósitos (como el de Pele)
+pub fn io_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
+    let pos = normalize_vec3(fragment.world_position);
+
+    let sulfur = Vector3::new(0.9, 0.8, 0.35);
+    let frost = Vector3::new(0.95, 0.93, 0.85);
+    let mottling = fbm_3d(pos.x * 3.0, pos.y * 3.0, pos.z * 3.0, 4, 2.0, 0.5);
+    let base = sulfur * (1.0 - mottling * 0.6) + frost * (mottling * 0.6);
+
+    let (f1, _) = voronoi_3d(pos.x * 5.0 + 3.0, pos.y * 5.0, pos.z * 5.0);
+    let halo = 1.0 - smoothstep(0.15, 0.35, f1);
+    let vent = 1.0 - smoothstep(0.05, 0.1, f1);
+    let with_halo = base * (1.0 - halo * 0.5) + Vector3::new(0.7, 0.35, 0.15) * (halo * 0.5);
+    let surface_color = with_halo * (1.0 - vent) + Vector3::new(0.12, 0.08, 0.05) * vent;
+
+    let lit_color = planet_lighting(fragment, uniforms, surface_color);
+    Vector3::new(lit_color.x.min(1.0), lit_color.y.min(1.0), lit_color.z.min(1.0))
+}
+
+// 🧊 Europa: hielo blanco cruzado por lineae pardo rojizas (las curvas de nivel de dos ruidos,
+// una familia ancha y otra fina)
+pub fn europa_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
+    let pos = normalize_vec3(fragment.world_position);
+
+    let ice = Vector3::new(0.92, 0.9, 0.88) * (0.95 + fbm_3d(pos.x * 8.0, pos.y * 8.0, pos.z * 8.0, 3, 2.0, 0.5) * 0.1);
+    let broad = fbm_3d(pos.x * 2.0 + 5.0, pos.y * 2.0, pos.z * 2.0, 3, 2.0, 0.5);
+    let fine = fbm_3d(pos.x * 5.0, pos.y * 5.0 + 9.0, pos.z * 5.0, 3, 2.0, 0.5);
+    let lineae = (1.0 - smoothstep(0.0, 0.02, (broad - 0.5).abs())).max((1.0 - smoothstep(0.0, 0.01, (fine - 0.5).abs())) * 0.6);
+    let surface_color = ice * (1.0 - lineae) + Vector3::new(0.55, 0.3, 0.18) * lineae;
+
+    let lit_color = planet_lighting(fragment, uniforms, surface_color);
+    Vector3::new(lit_color.x.min(1.0), lit_color.y.min(1.0), lit_color.z.min(1.0))
+}
+
+// 🌑 Ganímedes: regiones oscuras antiguas y muy crateradas junto a terreno claro surcado
+pub fn ganymede_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
+    let pos = normalize_vec3(fragment.world_position);
+
+    let regions = smoothstep(0.45, 0.55, fbm_3d(pos.x * 2.5, pos.y * 2.5, pos.z * 2.5, 4, 2.0, 0.5));
+    let (f1, f2) = voronoi_3d(pos.x * 9.0, pos.y * 9.0, pos.z * 9.0);
+    let crater_rim = 1.0 - smoothstep(0.0, 0.1, f2 - f1);
+    let crater_floor = (1.0 - f1 * 2.0).max(0.0);
+    let dark = Vector3::new(0.3, 0.28, 0.25) * (1.0 - crater_floor * 0.2) + Vector3::new(0.5, 0.48, 0.45) * (crater_rim * 0.3);
+    let grooves = ((pos.x * 40.0 + fbm_3d(pos.x * 4.0, pos.y * 4.0, pos.z * 4.0, 2, 2.0, 0.5) * 12.0).sin() * 0.5 + 0.5) * 0.12;
+    let light = Vector3::new(0.62, 0.6, 0.57) * (1.0 - grooves);
+    let surface_color = dark * (1.0 - regions) + light * regions;
+
+    let lit_color = planet_lighting(fragment, uniforms, surface_color);
+    Vector3::new(lit_color.x.min(1.0), lit_color.y.min(1.0), lit_color.z.min(1.0))
+}
+
+// Cráteres con rayos de Calisto: dirección del centro, radio del cráter (en la esfera unidad)
+// y número de rayos
+const CALLISTO_RAY_CRATERS: [(Vector3, f32, f32); 3] = [
+    (Vector3::new(0.6, 0.3, 0.74), 0.05, 11.0),
+    (Vector3::new(-0.7, -0.2, 0.68), 0.04, 9.0),
+    (Vector3::new(0.1, -0.8, -0.59), 0.06, 13.0),
+];
+
+// ☄️ Calisto: superficie uniformemente oscura, salpicada de cráteres pequeños brillantes y con
+// unos pocos cráteres jóvenes de rayos claros
+pub fn callisto_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
+    let pos = normalize_vec3(fragment.world_position);
+
+    let dark = Vector3::new(0.22, 0.2, 0.18) * (0.9 + fbm_3d(pos.x * 6.0, pos.y * 6.0, pos.z * 6.0, 3, 2.0, 0.5) * 0.2);
+    let (f1, _) = voronoi_3d(pos.x * 14.0, pos.y * 14.0, pos.z * 14.0);
+    let mut bright = (1.0 - smoothstep(0.05, 0.12, f1)) * 0.5;
+    for &(center, crater_radius, ray_count) in &CALLISTO_RAY_CRATERS {
+        let center = normalize_vec3(center);
+        let distance = (pos - center).length();
+        // Ángulo alrededor del cráter en su plano tangente
+        let tangent = center.cross(Vector3::new(0.0, 1.0, 0.0)).normalized();
+        let bitangent = center.cross(tangent);
+        let offset = pos - center * pos.dot(center);
+        let angle = offset.dot(bitangent).atan2(offset.dot(tangent));
+        let rays = (angle * ray_count + fbm_3d(pos.x * 6.0, pos.y * 6.0, pos.z * 6.0, 2, 2.0, 0.5) * 4.0).cos().max(0.0).powf(8.0);
+        let ray_fade = 1.0 - smoothstep(crater_radius, crater_radius * 8.0, distance);
+        let crater = 1.0 - smoothstep(crater_radius * 0.8, crater_radius, distance);
+        bright = bright.max(crater).max(rays * ray_fade * 0.8);
+    }
+    let surface_color = dark * (1.0 - bright) + Vector3::new(0.8, 0.78, 0.74) * bright;
+
+    let lit_color = planet_lighting(fragment, uniforms, surface_color);
+    Vector3::new(lit_color.x.min(1.0), lit_color.y.min(1.0), lit_color.z.min(1.0))
+}
+
+// 🪐 Saturno: anillos en radios de Saturno (el D y la división de Encke se omiten)
+pub const SATURN_AXIAL_TILT: f32 = 0.466; // 26.7°
+pub const SATURN_C_RING_INNER: f32 = 1.24;
+pub const SATURN_B_RING_INNER: f32 = 1.53;
+pub const SATURN_CASSINI_INNER: f32 = 1.95;
+pub const SATURN_CASSINI_OUTER: f32 = 2.03;
+pub const SATURN_A_RING_OUTER: f32 = 2.27;
+
+// Normal del plano de los anillos (el ecuador de Saturno inclinado alrededor del eje Z)
+pub fn saturn_ring_normal() -> Vector3 {
+    Vector3::new(-SATURN_AXIAL_TILT.sin(), SATURN_AXIAL_TILT.cos(), 0.0)
+}
+
+/// Sombra de los anillos sobre la superficie: traza el rayo desde `world_pos` (relativo al centro
+/// del planeta, en radios del planeta) hacia el Sol y mira si cruza el plano de los anillos entre
+/// `ring_inner` y `ring_outer`. Devuelve el multiplicador de luz directa (1.0 = sin sombra).
+pub fn ring_shadow_factor(world_pos: Vector3, ring_plane_normal: Vector3, ring_inner: f32, ring_outer: f32, sun_dir: Vector3) -> f32 {
+    let denom = ring_plane_normal.dot(sun_dir);
+    if denom.abs() < 1e-6 {
+        return 1.0; // Sol en el plano de los anillos (equinoccio): no hay sombra
+    }
+    let t = -ring_plane_normal.dot(world_pos) / denom;
+    if t <= 0.0 {
+        return 1.0; // el plano queda detrás del fragmento respecto al Sol
+    }
+    let hit = world_pos + sun_dir * t;
+    let r = hit.length();
+    if r < ring_inner || r > ring_outer {
+        1.0
+    } else {
+        ring_transmission(r)
+    }
+}
+
+// Fracción de luz que atraviesa los anillos en perpendicular a radio `r` (en radios de Saturno)
+fn ring_transmission(r: f32) -> f32 {
+    if r < SATURN_B_RING_INNER {
+        0.85 // anillo C, tenue
+    } else if r < SATURN_CASSINI_INNER {
+        0.08 // anillo B, el más denso: casi opaco (profundidad óptica ~2.5)
+    } else if r < SATURN_CASSINI_OUTER {
+        0.8 // división de Cassini
+    } else {
+        0.5 // anillo A
+    }
+}
+
+// Semiespesor de la capa de partículas de los anillos (en radios de Saturno). Muy exagerado: con
+// el real (decenas de metros) el desplazamiento radial de la sombra sería invisible.
+const SATURN_RING_HALF_THICKNESS: f32 = 0.02;
+
+/// Autosombra de los anillos. Un plano infinitamente fino no puede hacerse sombra a sí mismo, así
+/// que se trata como una capa de semiespesor `SATURN_RING_HALF_THICKNESS`: el rayo de sombra sale
+/// de `frag_pos` (en el plano medio) hacia el Sol y cruza la cara iluminada a otro radio, más
+/// hacia dentro en la mitad opuesta al Sol (donde el anillo B oscurece el A) y hacia fuera en la
+/// otra. Si ese radio está entre `ring_inner` y `ring_outer`, la luz se atenúa con la profundidad
+/// óptica de allí, mayor cuanto más rasante llega el Sol; `ring_opacity` la escala.
+/// Devuelve el multiplicador de luz directa (1.0 = sin sombra).
+pub fn ring_self_shadow(frag_pos: Vector3, ring_center: Vector3, ring_normal: Vector3, sun_dir: Vector3, ring_inner: f32, ring_outer: f32, ring_opacity: f32) -> f32 {
+    let sin_elevation = ring_normal.dot(sun_dir);
+    if sin_elevation.abs() < 1e-3 {
+        return 1.0; // Sol en el plano de los anillos: se tratan como iluminados de canto
+    }
+    let local = frag_pos - ring_center;
+    let t = SATURN_RING_HALF_THICKNESS / sin_elevation.abs();
+    let exit = local + sun_dir * t;
+    // Radio en el plano medio bajo el punto de salida
+    let radial = exit - ring_normal * exit.dot(ring_normal);
+    let r = radial.length();
+    if r < ring_inner || r > ring_outer {
+        return 1.0;
+    }
+    let optical_depth = -ring_transmission(r).ln();
+    (-ring_opacity * optical_depth * 0.5 / sin_elevation.abs()).exp()
+}
+
+/// Sombra suave de los anillos: lanza `samples` rayos de sombra hacia puntos del disco solar
+/// (direcciones desviadas hasta `sun_angular_radius` de `sun_dir`) y promedia la luz que pasa,
+/// así el borde de la sombra tiene penumbra. Con `samples` = 1 es el rayo único de siempre.
+/// `seed` fija el patrón de desviaciones por píxel para que no parpadee entre cuadros.
+#[allow(clippy::too_many_arguments)]
+pub fn is_in_shadow(
+    world_pos: Vector3,
+    ring_plane_normal: Vector3,
+    ring_inner: f32,
+    ring_outer: f32,
+    sun_dir: Vector3,
+    sun_angular_radius: f32,
+    samples: u32,
+    seed: u64,
+) -> f32 {
+    if samples <= 1 {
+        return ring_shadow_factor(world_pos, ring_plane_normal, ring_inner, ring_outer, sun_dir);
+    }
+    // Base ortonormal alrededor de la dirección al Sol para desviar los rayos dentro del disco
+    let helper = if sun_dir.y.abs() < 0.9 { Vector3::new(0.0, 1.0, 0.0) } else { Vector3::new(1.0, 0.0, 0.0) };
+    let tangent = normalize_vec3(sun_dir.cross(helper));
+    let bitangent = sun_dir.cross(tangent);
+    let mut rng = fastrand::Rng::with_seed(seed);
+    let mut light = 0.0;
+    for _ in 0..samples {
+        // Punto uniforme en el disco (sqrt para no amontonar muestras en el centro)
+        let r = rng.f32().sqrt() * sun_angular_radius;
+        let angle = rng.f32() * std::f32::consts::TAU;
+        let jittered = normalize_vec3(sun_dir + tangent * (r * angle.cos()) + bitangent * (r * angle.sin()));
+        light += ring_shadow_factor(world_pos, ring_plane_normal, ring_inner, ring_outer, jittered);
+    }
+    light / samples as f32
+}
+
+// Semilla de los rayos de sombra a partir de la posición del fragmento en pantalla
+fn shadow_seed(fragment: &Fragment) -> u64 {
+    ((fragment.position.x as u64) << 32) | (fragment.position.y as u32 as u64)
+}
+
+// Hexágono polar de Saturno: se evalúa por encima de 72° N, con el lado a 12° del polo (~78° N).
+// Se dibuja en la proyección del casquete sobre el plano ecuatorial, donde mide sin(12°).
+const SATURN_HEXAGON_MIN_LATITUDE: f32 = 72.0 * std::f32::consts::PI / 180.0;
+const SATURN_HEXAGON_APOTHEM: f32 = 12.0 * std::f32::consts::PI / 180.0;
+// Radio del ojo del vórtice y velocidad de giro del hexágono (rad por segundo simulado)
+const SATURN_VORTEX_EYE_RADIUS: f32 = 0.035;
+const SATURN_HEXAGON_ROTATION_SPEED: f32 = 0.0001;
+
+pub fn saturn_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
+    let time = uniforms.time;
+    // Saturno gira sobre Y y los anillos no: todo se calcula en ejes de mundo relativos al centro
+    // del planeta, que es donde están `saturn_ring_normal` y la dirección al Sol
+    let world_pos = fragment_world_position(fragment, uniforms);
+    let center = multiply_matrix_vector4(&uniforms.model_matrix, &Vector4::new(0.0, 0.0, 0.0, 1.0));
+    let surface = normalize_vec3(world_pos - Vector3::new(center.x, center.y, center.z));
+
+    // Latitud respecto al ecuador real (inclinado), así las bandas quedan paralelas a los anillos
+    let ring_normal = saturn_ring_normal();
+    let latitude = surface.dot(ring_normal);
+
+    let bands = ((latitude * 18.0).sin() * 0.5 + 0.5) * 0.6 + fbm_3d(surface.x * 3.0 + time * 0.01, latitude * 40.0, surface.z * 3.0, 3, 2.0, 0.5) * 0.4;
+    let cream = Vector3::new(0.92, 0.85, 0.65);
+    let gold = Vector3::new(0.80, 0.68, 0.45);
+    let polar = Vector3::new(0.65, 0.68, 0.62);
+    let banded = cream * (1.0 - bands) + gold * bands;
+    let polar_blend = (latitude.abs() - 0.8).max(0.0) * 5.0;
+    let mut surface_color = banded * (1.0 - polar_blend) + polar * polar_blend;
+
+    // ⬡ Vórtice hexagonal del polo norte: dentro, una tormenta más oscura y apretada que gira
+    // más rápido cuanto más cerca del centro, con un ojo claro en el polo
+    if latitude > SATURN_HEXAGON_MIN_LATITUDE.sin() {
+        let axis_u = normalize_vec3(ring_normal.cross(Vector3::new(0.0, 0.0, 1.0)));
+        let axis_v = ring_normal.cross(axis_u);
+        let hex_rotation_angle = time * SATURN_HEXAGON_ROTATION_SPEED;
+        let (sin_r, cos_r) = hex_rotation_angle.sin_cos();
+        let (u, v) = (surface.dot(axis_u), surface.dot(axis_v));
+        let uv = Vector2::new(u * cos_r - v * sin_r, u * sin_r + v * cos_r);
+        let inside = 1.0 - smoothstep(-0.005, 0.005, hexagon_distance(uv, SATURN_HEXAGON_APOTHEM.sin()));
+
+        let radius = uv.length();
+        let twist = uv.y.atan2(uv.x) + 6.0 / (radius * 40.0 + 1.0) + time * 0.02;
+        let storm = fbm_3d(twist.cos() * radius * 30.0, twist.sin() * radius * 30.0, time * 0.01, 4, 2.0, 0.5);
+        let storm_clouds = Vector3::new(0.30, 0.36, 0.42) * (0.7 + storm * 0.5);
+        let eye = 1.0 - smoothstep(SATURN_VORTEX_EYE_RADIUS * 0.3, SATURN_VORTEX_EYE_RADIUS, radius);
+        let vortex = storm_clouds * (1.0 - eye) + Vector3::new(0.78, 0.80, 0.76) * eye;
+        surface_color = surface_color * (1.0 - inside) + vortex * inside;
+    }
+
+    // La sombra de los anillos solo afecta la luz directa, no la ambiental
+    let (_, sun_dir, _) = lighting_vectors(fragment, uniforms);
+    // Radio angular del Sol visto desde Saturno, en radios de Saturno como `surface`
+    let sun_distance = (sun_position(uniforms) - world_pos).length();
+    let sun_angular_radius = (uniforms.sun_radius / sun_distance.max(1e-3)).min(0.5);
+    let shadow = is_in_shadow(
+        surface,
+        ring_normal,
+        SATURN_B_RING_INNER,
+        SATURN_A_RING_OUTER,
+        sun_dir,
+        sun_angular_radius,
+        uniforms.shadow_quality,
+        shadow_seed(fragment),
+    );
+    let ambient = ambient_term(uniforms, surface_color);
+    let lit_color = ambient + (planet_lighting(fragment, uniforms, surface_color) - ambient) * shadow;
+    let lit_color = apply_atmosphere(fragment, uniforms, lit_color, Vector3::new(0.85, 0.78, 0.6));
+
+    Vector3::new(lit_color.x.min(1.0), lit_color.y.min(1.0), lit_color.z.min(1.0))
+}
+
+// 💍 Anillos de Saturno (la malla está en radios de Saturno)
+pub fn saturn_ring_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
+    let pos = fragment.world_position;
+    let r = pos.length();
+
+    let base = if r < SATURN_B_RING_INNER {
+        Vector3::new(0.35, 0.32, 0.28) // anillo C, tenue
+    } else if r < SATURN_CASSINI_INNER {
+        Vector3::new(0.90, 0.84, 0.70) // anillo B, brillante
+    } else {
+        Vector3::new(0.72, 0.67, 0.56) // anillo A
+    };
+    let ringlets = 0.85 + (r * 120.0).sin() * 0.08 + value_noise_3d(r * 60.0, 0.0, 0.0) * 0.07;
+    let ring_color = base * ringlets;
+
+    // Los anillos se iluminan por ambas caras; el planeta les hace sombra en el lado nocturno
+    let (normal, sun_dir, _) = lighting_vectors(fragment, uniforms);
+    let diffuse = normal.dot(sun_dir).abs();
+    let along = pos.dot(sun_dir);
+    let closest_sq = pos.dot(pos) - along * along;
+    let planet_shadow = if along < 0.0 && closest_sq < 1.0 { 0.0 } else { 1.0 };
+    // Las zonas densas proyectan sombra sobre las vecinas cuando el Sol está alto sobre el plano
+    let self_shadow = ring_self_shadow(pos, Vector3::zero(), saturn_ring_normal(), sun_dir, SATURN_C_RING_INNER, SATURN_A_RING_OUTER, 1.0);
+    let lit_color = ambient_term(uniforms, ring_color) + ring_color * sun_light_color(fragment, uniforms) * (diffuse * planet_shadow * self_shadow);
+
+    Vector3::new(lit_color.x.min(1.0), lit_color.y.min(1.0), lit_color.z.min(1.0))
+}
+
+// 💍 Urano: sistema de anillos estrechos y oscuros, en el ecuador del planeta inclinado 97.8°
+pub const URANUS_AXIAL_TILT: f32 = 1.707; // 97.8°
+pub const URANUS_RADIUS_KM: f32 = 25_559.0;
+// Los anillos estrechos miden de 2 a 100 km; se ensanchan hasta este mínimo (en radios de Urano)
+pub const URANUS_RING_MIN_WIDTH: f32 = 0.008;
+pub const URANUS_RING_OUTER: f32 = 103_000.0 / URANUS_RADIUS_KM;
+
+const URANUS_NARROW_RING_ALBEDO: Vector3 = Vector3::new(0.05, 0.05, 0.05);
+// (inner_km, outer_km, opacidad, color): los estrechos casi negros, el ε algo más claro y los
+// polvorientos (ζ, ν, μ) tenues, el ν rojizo y el μ azulado
+pub const URANUS_RINGS: [RingBand; 13] = [
+    (37_850.0, 41_350.0, 0.3, Vector3::new(0.04, 0.04, 0.04)), // ζ
+    (41_836.0, 41_838.0, 1.0, URANUS_NARROW_RING_ALBEDO),      // 6
+    (42_233.0, 42_235.0, 1.0, URANUS_NARROW_RING_ALBEDO),      // 5
+    (42_570.0, 42_572.0, 1.0, URANUS_NARROW_RING_ALBEDO),      // 4
+    (44_714.0, 44_722.0, 1.0, URANUS_NARROW_RING_ALBEDO),      // α
+    (45_657.0, 45_665.0, 1.0, URANUS_NARROW_RING_ALBEDO),      // β
+    (47_175.0, 47_177.0, 1.0, URANUS_NARROW_RING_ALBEDO),      // η
+    (47_625.0, 47_629.0, 1.0, URANUS_NARROW_RING_ALBEDO),      // γ
+    (48_297.0, 48_303.0, 1.0, URANUS_NARROW_RING_ALBEDO),      // δ
+    (50_022.0, 50_024.0, 1.0, URANUS_NARROW_RING_ALBEDO),      // λ
+    (51_110.0, 51_188.0, 1.0, Vector3::new(0.12, 0.12, 0.115)), // ε
+    (66_100.0, 69_900.0, 0.3, Vector3::new(0.06, 0.04, 0.035)), // ν
+    (86_000.0, 103_000.0, 0.3, Vector3::new(0.035, 0.045, 0.07)), // μ
+];
+
+/// Anillos de Urano: el albedo de cada banda viene en el color de vértice (ver
+/// `generate_ring_system`), así que casi todo sale negro salvo el anillo ε.
+pub fn uranus_ring_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
+    let pos = fragment.world_position;
+    let albedo = fragment.vertex_color;
+
+    // Iluminados por ambas caras, con la sombra del planeta (radio 1) en el lado nocturno
+    let (normal, sun_dir, _) = lighting_vectors(fragment, uniforms);
+    let diffuse = normal.dot(sun_dir).abs();
+    let along = pos.dot(sun_dir);
+    let closest_sq = pos.dot(pos) - along * along;
+    let planet_shadow = if along < 0.0 && closest_sq < 1.0 { 0.0 } else { 1.0 };
+    let lit_color = ambient_term(uniforms, albedo) + albedo * sun_light_color(fragment, uniforms) * (diffuse * planet_shadow);
+
+    Vector3::new(lit_color.x.min(1.0), lit_color.y.min(1.0), lit_color.z.min(1.0))
+}
+
+/// Opacidad de los anillos de Saturno: el C es tenue y el B casi opaco (la división de Cassini
+/// no tiene malla).
+pub fn saturn_ring_alpha(fragment: &Fragment) -> f32 {
+    let r = fragment.world_position.length();
+    if r < SATURN_B_RING_INNER {
+        0.35
+    } else if r < SATURN_CASSINI_INNER {
+        0.95
+    } else {
+        0.8
+    }
+}
+
+/// Opacidad de la banda de `URANUS_RINGS` en la que cae el fragmento, con las estrechas
+/// ensanchadas igual que en `generate_ring_system`.
+pub fn uranus_ring_alpha(fragment: &Fragment) -> f32 {
+    let r_km = fragment.world_position.length() * URANUS_RADIUS_KM;
+    URANUS_RINGS
+        .iter()
+        .find(|&&(inner_km, outer_km, _, _)| {
+            let half_width = (outer_km - inner_km).max(URANUS_RING_MIN_WIDTH * URANUS_RADIUS_KM) * 0.5;
+            (r_km - (inner_km + outer_km) * 0.5).abs() <= half_width
+        })
+        .map_or(1.0, |&(_, _, opacity, _)| opacity)
+}
+
+// 🌫️ Capa de atmósfera: esfera `atmosphere_height` más alta que el planeta que pinta el halo del
+// limbo, fuera del disco. La bruma se ilumina como en `apply_atmosphere`, con su tinte en el
+// color de vértice.
+pub fn atmosphere_shell_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
+    let (normal, light_dir, _) = lighting_vectors(fragment, uniforms);
+    let haze_light = (normal.dot(light_dir) + 0.2).clamp(0.0, 1.0);
+    fragment.vertex_color * sun_light_color(fragment, uniforms) * haze_light
+}
+
+/// Opacidad de la capa: la de la atmósfera que atraviesa la visual (ver `atmosphere_transmittance`),
+/// solo donde no hay planeta detrás, porque sobre el disco la bruma ya la pone su shader.
+pub fn atmosphere_shell_alpha(fragment: &Fragment, uniforms: &Uniforms) -> f32 {
+    let (normal, _, view_dir) = lighting_vectors(fragment, uniforms);
+    // También se ve la cara trasera de la capa, al otro lado del limbo
+    let cos_view = normal.dot(view_dir).abs();
+    let optical_depth = uniforms.atmosphere_density * uniforms.atmosphere_height / cos_view.max(0.05);
+    // Coseno con el que corta la capa la visual tangente al planeta: por debajo, no hay disco detrás
+    let shell_scale = 1.0 + uniforms.atmosphere_height;
+    let disc_edge = (1.0 - 1.0 / (shell_scale * shell_scale)).sqrt();
+    let beyond_disc = 1.0 - smoothstep(disc_edge * 0.5, disc_edge, cos_view);
+    (1.0 - (-optical_depth).exp()) * beyond_disc
+}
+
 // 🚀 Nave
 pub fn nave_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
     let pos = fragment.world_position;
@@ -268,14 +1767,496 @@ pub fn nave_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3
     let panel_factor = (panel_pattern * 0.2 + 0.8).max(0.0).min(1.0);
     let textured_surface = base_color * (1.0 - pattern_factor) + panel_color * pattern_factor;
     let final_color = textured_surface * (1.0 - panel_factor * 0.2) + accent_color * panel_factor * 0.2;
-    let lighting = (pos.y * 0.4 + 0.6).max(0.3);
-    let lit_color = final_color * lighting;
+    let (normal, light_dir, view_dir) = lighting_vectors(fragment, uniforms);
+    let ambient = ambient_term(uniforms, final_color);
+    let lit_color = phong_light(normal, light_dir, view_dir, final_color, NAVE_SPECULAR, ambient, NAVE_SHININESS, sun_light_color(fragment, uniforms));
     let light_pulse = (time * 2.0).sin().abs() * 0.1 + 0.9;
     let pulsed_color = Vector3::new(0.9, 0.95, 1.0) * light_pulse * 0.1 + lit_color * (1.0 - 0.1);
+    // Color de vértice: oclusión ambiental precalculada (gris) o el tinte de la nave de reemplazo
+    let pulsed_color = pulsed_color * fragment.vertex_color;
     Vector3::new(pulsed_color.x.clamp(0.0, 1.0), pulsed_color.y.clamp(0.0, 1.0), pulsed_color.z.clamp(0.0, 1.0))
 }
 
+// 🚨 Luces de navegación de la nave: emisivas (sin iluminación) y parpadeantes
+pub fn nav_light_fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vector3 {
+    let blink = smoothstep(-0.2, 0.2, (uniforms.time * 8.0).sin());
+    let intensity = 0.15 + blink * 0.85;
+    fragment.vertex_color * intensity
+}
+
 // 🌟 Skybox
 pub fn skybox_fragment_shader(fragment: &Fragment, _uniforms: &Uniforms) -> Vector3 {
     Vector3::new(1.0, 1.0, 1.0)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exploration::{ExploredGrid, EXPLORATION_GRID_SIZE};
+
+    const GRID: ExploredGrid = [[true; EXPLORATION_GRID_SIZE]; EXPLORATION_GRID_SIZE];
+
+    // Fragmento en `position` (espacio de objeto; con la matriz de modelo identidad, también de mundo)
+    fn fragment_at(position: Vector3, normal: Vector3) -> Fragment {
+        Fragment::new(0.0, 0.0, Vector3::one(), 0.5, position, normal, Vector3::one())
+    }
+
+    #[test]
+    fn perspective_divide_rejects_vertices_on_or_behind_the_camera() {
+        assert_eq!(safe_perspective_divide(Vector4::new(1.0, 2.0, 3.0, 0.0)), DEGENERATE_NDC);
+        assert_eq!(safe_perspective_divide(Vector4::new(1.0, 2.0, 3.0, -2.0)), DEGENERATE_NDC);
+        assert_eq!(safe_perspective_divide(Vector4::new(1.0, 2.0, 3.0, 2.0)), Vector3::new(0.5, 1.0, 1.5));
+    }
+
+    #[test]
+    fn phong_specular_of_a_mirror_at_the_reflection_angle_is_ks_times_light() {
+        let normal = Vector3::new(0.0, 1.0, 0.0);
+        let light_dir = Vector3::new(1.0, 1.0, 0.0).normalized();
+        let view_dir = Vector3::new(-1.0, 1.0, 0.0).normalized();
+        let (ks, light_color) = (Vector3::new(0.8, 0.6, 0.4), Vector3::new(1.0, 0.9, 0.5));
+        let color = phong_light(normal, light_dir, view_dir, Vector3::zero(), ks, Vector3::zero(), 64.0, light_color);
+        let expected = ks * light_color;
+        assert!((color - expected).length() < 1e-5);
+    }
+
+    #[test]
+    fn fragment_facing_away_from_the_light_gets_only_ambient() {
+        let mut uniforms = Uniforms::for_tests(&GRID);
+        uniforms.lights = vec![Light::new(Vector3::new(10.0, 0.0, 0.0))];
+        uniforms.camera_pos = Vector3::new(0.0, 0.0, 10.0);
+        uniforms.ambient_intensity = 0.4;
+        uniforms.ambient_color = Vector3::new(1.0, 0.9, 0.8);
+        let surface_color = Vector3::new(0.5, 0.25, 1.0);
+        let fragment = fragment_at(Vector3::new(-1.0, 0.0, 0.0), Vector3::new(-1.0, 0.0, 0.0));
+        let color = planet_lighting(&fragment, &uniforms, surface_color);
+        let expected = surface_color * uniforms.ambient_color * (AMBIENT_REFLECTIVITY * 0.4);
+        assert!((color - expected).length() < 1e-6);
+    }
+
+    #[test]
+    fn value_noise_has_no_two_pi_period() {
+        let ys: Vec<f32> = (0..32).map(|i| 0.37 + i as f32 * 0.9).collect();
+        let differences: Vec<f32> = ys.iter().map(|&y| (value_noise_3d(0.0, y, 0.0) - value_noise_3d(0.0, y + std::f32::consts::TAU, 0.0)).abs()).collect();
+        let mean = differences.iter().sum::<f32>() / differences.len() as f32;
+        assert!(mean > 0.1, "diferencia media {}", mean);
+        // Un patrón con `sin` repetiría el valor en todos los pares; aquí la mayoría no se parece
+        assert!(differences.iter().filter(|&&d| d > 0.1).count() * 2 > differences.len());
+    }
+
+    #[test]
+    fn b_ring_shades_the_day_side_below_it() {
+        let normal = saturn_ring_normal();
+        let outward = normalize_vec3(normal.cross(Vector3::new(0.0, 0.0, 1.0)));
+        // Sol a 25° sobre el plano de los anillos, del lado de `outward`
+        let elevation = 25f32.to_radians();
+        let sun_dir = outward * elevation.cos() + normal * elevation.sin();
+        // Punto de la superficie cuyo rayo hacia el Sol cruza el centro del anillo B
+        let hit = outward * ((SATURN_B_RING_INNER + SATURN_CASSINI_INNER) * 0.5);
+        let along = hit.dot(sun_dir);
+        let t = along - (along * along - (hit.length_sqr() - 1.0)).sqrt();
+        let surface = hit - sun_dir * t;
+        assert!((surface.length() - 1.0).abs() < 1e-4);
+        assert!(surface.dot(sun_dir) > 0.0, "el punto tiene que estar en el lado de día");
+
+        let shadow = is_in_shadow(surface, normal, SATURN_B_RING_INNER, SATURN_A_RING_OUTER, sun_dir, 0.0, 1, 0);
+        assert!(shadow < 0.5);
+        // Sin anillos encima (el punto opuesto del ecuador) no hay sombra
+        assert_eq!(ring_shadow_factor(-outward, normal, SATURN_B_RING_INNER, SATURN_A_RING_OUTER, -sun_dir), 1.0);
+    }
+
+    #[test]
+    fn ceres_bright_spot_outshines_the_regolith() {
+        let mut uniforms = Uniforms::for_tests(&GRID);
+        uniforms.ambient_intensity = 1.0;
+        uniforms.ambient_color = Vector3::one();
+        // Solo luz ambiental: el brillo sale del color de la superficie
+        let brightness = |position: Vector3| {
+            let color = ceres_fragment_shader(&fragment_at(position, position), &uniforms);
+            color.x + color.y + color.z
+        };
+        let cerealia = normalize_vec3(Vector3::new(-0.45, 0.34, -0.82));
+        assert!(brightness(cerealia) > brightness(-cerealia));
+    }
+
+    #[test]
+    fn mars_ice_cap_edge_is_not_a_latitude_band() {
+        // Borde del casquete en grados a lo largo de un paralelo a 75° N, en la malla de radio 0.52
+        let latitude = 75f32.to_radians();
+        let edges: Vec<f32> = (0..360)
+            .map(|i| {
+                let longitude = (i as f32).to_radians();
+                let pos = Vector3::new(latitude.cos() * longitude.cos(), latitude.sin(), latitude.cos() * longitude.sin()) * 0.52;
+                mars_ice_cap_threshold(pos) * 90.0
+            })
+            .collect();
+        let (lowest, highest) = (edges.iter().cloned().fold(f32::INFINITY, f32::min), edges.iter().cloned().fold(0.0, f32::max));
+        assert!(highest - lowest >= 10.0, "el borde solo varía entre {}° y {}°", lowest, highest);
+    }
+
+    #[test]
+    fn earth_terminator_is_a_soft_nonlinear_transition() {
+        // Luz total relativa al color de la superficie: difusa más crepúsculo
+        let light_at = |elevation_deg: f32| {
+            let cos_sza = elevation_deg.to_radians().sin();
+            cos_sza.max(0.0) + earth_twilight_light(cos_sza)
+        };
+        let (below, horizon, above) = (light_at(-10.0), light_at(0.0), light_at(10.0));
+        // No es un corte: a -10° aún llega luz del crepúsculo, y sube hacia el día
+        assert!(below > 0.0 && below < horizon && horizon < above);
+        // Tampoco una rampa lineal entre -10° y +10°
+        assert!((horizon - (below + above) * 0.5).abs() > 0.01);
+        // Y nunca se oscurece al subir el Sol
+        let mut previous = light_at(-20.0);
+        for step in -199..=300 {
+            let light = light_at(step as f32 * 0.1);
+            assert!(light >= previous - 1e-6);
+            previous = light;
+        }
+    }
+
+    #[test]
+    fn still_ocean_glint_peaks_at_the_mirror_direction() {
+        let normal = Vector3::new(0.0, 1.0, 0.0);
+        let sun_dir = Vector3::new(0.6, 0.8, 0.0);
+        let mirror = normal * (2.0 * normal.dot(sun_dir)) - sun_dir;
+        let peak = ocean_specular(normal, sun_dir, mirror, Vector3::zero());
+        assert!((peak - 1.0).abs() < 1e-5);
+        for (dx, dz) in [(0.05, 0.0), (-0.05, 0.0), (0.0, 0.05), (0.2, 0.1)] {
+            let view_dir = normalize_vec3(mirror + Vector3::new(dx, 0.0, dz));
+            assert!(ocean_specular(normal, sun_dir, view_dir, Vector3::zero()) < peak);
+        }
+    }
+
+    #[test]
+    fn hexagon_contains_its_center_but_not_past_a_corner() {
+        let size = 0.2;
+        assert!(hexagon_distance(Vector2::new(0.0, 0.0), size) < 0.0);
+        // Con lados planos arriba y abajo, las esquinas quedan sobre el eje X a apotema / cos 30°
+        let circumradius = size / 30.0_f32.to_radians().cos();
+        for k in 0..6 {
+            let angle = (k as f32 * 60.0).to_radians();
+            let (sin_a, cos_a) = angle.sin_cos();
+            let corner = |r: f32| Vector2::new(cos_a * r, sin_a * r);
+            assert!(hexagon_distance(corner(circumradius - 1e-3), size) < 0.0, "esquina {}", k);
+            assert!(hexagon_distance(corner(circumradius + 1e-3), size) > 0.0, "esquina {}", k);
+        }
+    }
+
+    #[test]
+    fn turing_pattern_is_not_degenerate() {
+        // 100 puntos repartidos por la esfera (espiral de Fibonacci)
+        let samples: Vec<f32> = (0..100)
+            .map(|i| {
+                let y = 1.0 - (i as f32 + 0.5) / 50.0;
+                let ring = (1.0 - y * y).sqrt();
+                let (sin_a, cos_a) = (i as f32 * 2.399_963).sin_cos();
+                turing_pattern(Vector3::new(cos_a * ring, y, sin_a * ring), 0.0)
+            })
+            .collect();
+        let min = samples.iter().copied().fold(f32::MAX, f32::min);
+        let max = samples.iter().copied().fold(f32::MIN, f32::max);
+        assert!(min >= -1.0 && max <= 1.0);
+        assert!(max - min >= 0.6 * 2.0, "rango [{}, {}]", min, max);
+    }
+
+    #[test]
+    fn thick_cloud_shades_the_surface_below() {
+        // Un fragmento del océano con el Sol a 14° sobre el horizonte y la cámara en la vertical:
+        // al girar el Sol en acimut la difusa, el especular y el crepúsculo no cambian, solo la
+        // nube que cruza su luz. La cobertura de la nube sobre el propio fragmento tampoco cambia.
+        let latitude = -0.05_f32;
+        let longitude = 305.0_f32.to_radians();
+        let ring = (1.0 - latitude * latitude).sqrt();
+        let up = Vector3::new(longitude.cos() * ring, latitude, longitude.sin() * ring);
+        let east = normalize_vec3(Vector3::new(0.0, 1.0, 0.0).cross(up));
+        let north = up.cross(east);
+        let cos_zenith = 0.25_f32;
+        let sun_at = |azimuth: f32| up * cos_zenith + (east * azimuth.cos() + north * azimuth.sin()) * (1.0 - cos_zenith * cos_zenith).sqrt();
+        let shadows: Vec<(f32, f32)> = (0..36)
+            .map(|i| (i as f32 * 10.0).to_radians())
+            .map(|azimuth| (earth_cloud_shadow(up, sun_at(azimuth), 0.0), azimuth))
+            .collect();
+        let (thick, shaded) = shadows.iter().copied().fold((f32::MIN, 0.0), |a, b| if b.0 > a.0 { b } else { a });
+        let (thin, clear) = shadows.iter().copied().fold((f32::MAX, 0.0), |a, b| if b.0 < a.0 { b } else { a });
+        assert!(thick > 0.5 && thin == 0.0, "sombra entre {} y {}", thin, thick);
+
+        let brightness = |azimuth: f32| {
+            let mut uniforms = Uniforms::for_tests(&GRID);
+            uniforms.lights = vec![Light::new(up + sun_at(azimuth) * 1000.0)];
+            uniforms.camera_pos = up * 10.0;
+            let color = earth_fragment_shader(&fragment_at(up, up), &uniforms);
+            color.x + color.y + color.z
+        };
+        assert!(brightness(shaded) < brightness(clear), "bajo la nube {} despejado {}", brightness(shaded), brightness(clear));
+        // De noche no hay sombra de nubes
+        assert_eq!(earth_cloud_shadow(-up, up, 0.0), 0.0);
+    }
+
+    #[test]
+    fn nova_flash_peaks_early_and_decays() {
+        assert_eq!(nova_intensity(0.0), 1.0);
+        let (peak_phase, peak) = (0..=500)
+            .map(|i| i as f32 * 0.01)
+            .map(|phase| (phase, nova_intensity(phase)))
+            .fold((0.0, f32::MIN), |best, sample| if sample.1 > best.1 { sample } else { best });
+        assert!((0.0..=0.3).contains(&peak_phase), "máximo en {}", peak_phase);
+        assert!(peak > 1.5);
+        // A los 5 s de fase ya casi no queda destello
+        assert!(nova_intensity(5.0) < 1.01);
+    }
+
+    #[test]
+    fn hexagon_sdf_has_sixfold_symmetry() {
+        let size = 0.3;
+        assert!(hexagon_distance(Vector2::new(0.0, 0.0), size) < 0.0);
+        assert!(hexagon_distance(Vector2::new(0.0, size * 1.5), size) > 0.0);
+        // El centro del lado superior está a una apotema: distancia 0
+        assert!(hexagon_distance(Vector2::new(0.0, size), size).abs() < 1e-6);
+        let rotate = |p: Vector2, angle: f32| {
+            let (s, c) = angle.sin_cos();
+            Vector2::new(p.x * c - p.y * s, p.x * s + p.y * c)
+        };
+        for i in 0..40 {
+            let p = Vector2::new((i as f32 * 0.37).sin() * 0.5, (i as f32 * 0.91).cos() * 0.5);
+            let d = hexagon_distance(p, size);
+            for k in 1..6 {
+                let rotated = hexagon_distance(rotate(p, (k as f32 * 60.0).to_radians()), size);
+                assert!((rotated - d).abs() < 1e-5, "{:?} girado {}: {} vs {}", p, k * 60, rotated, d);
+            }
+        }
+        // Girar 30° no es una simetría: lleva el centro de un lado a una esquina
+        let off_axis = Vector2::new(0.0, size * 1.1);
+        assert!((hexagon_distance(rotate(off_axis, 30.0_f32.to_radians()), size) - hexagon_distance(off_axis, size)).abs() > 1e-3);
+    }
+
+    #[test]
+    fn soft_ring_shadow_has_a_penumbra_and_an_umbra() {
+        let normal = saturn_ring_normal();
+        let outward = normalize_vec3(normal.cross(Vector3::new(0.0, 0.0, 1.0)));
+        let elevation = 25f32.to_radians();
+        let sun_dir = outward * elevation.cos() + normal * elevation.sin();
+        // Punto de la superficie cuyo rayo central hacia el Sol cruza los anillos a radio `r`
+        let surface_below = |r: f32| {
+            let hit = outward * r;
+            let along = hit.dot(sun_dir);
+            hit - sun_dir * (along - (along * along - (hit.length_sqr() - 1.0)).sqrt())
+        };
+        let sun_angular_radius = 0.05;
+        let soft = |r: f32| is_in_shadow(surface_below(r), normal, SATURN_B_RING_INNER, SATURN_A_RING_OUTER, sun_dir, sun_angular_radius, 8, 406);
+
+        // Bajo el borde interior del anillo B, parte del disco solar queda tapada y parte no
+        let penumbra = soft(SATURN_B_RING_INNER);
+        assert!((0.1..=0.9).contains(&penumbra), "penumbra {}", penumbra);
+        // Bajo el centro del anillo B todo el disco queda detrás del anillo
+        let umbra = soft((SATURN_B_RING_INNER + SATURN_CASSINI_INNER) * 0.5);
+        assert!(umbra < 0.1, "umbra {}", umbra);
+        // Con un solo rayo el borde es duro
+        let hard = is_in_shadow(surface_below(SATURN_B_RING_INNER - 0.01), normal, SATURN_B_RING_INNER, SATURN_A_RING_OUTER, sun_dir, sun_angular_radius, 1, 406);
+        assert_eq!(hard, 1.0);
+    }
+
+    #[test]
+    fn lenticular_clouds_drift_slowly_and_band_by_latitude() {
+        let mut max_delta: f32 = 0.0;
+        for i in 0..200 {
+            let (longitude, latitude) = ((i as f32 * 0.731).sin() * 3.1, (i as f32 * 0.377).cos() * 1.4);
+            for t in [0.0, 10.0, 250.0] {
+                max_delta = max_delta.max((lenticular_cloud(longitude, latitude, 0.04, t + 1.0) - lenticular_cloud(longitude, latitude, 0.04, t)).abs());
+            }
+        }
+        assert!(max_delta < 0.01, "cambio por unidad de tiempo {}", max_delta);
+
+        // A lo largo de un meridiano las bandas van y vienen; a lo largo de un paralelo apenas cambian
+        let along = |f: &dyn Fn(f32) -> f32| (0..100).map(|i| (f(i as f32 * 0.01 + 0.01) - f(i as f32 * 0.01)).abs()).sum::<f32>();
+        let across_latitudes = along(&|x| lenticular_cloud(0.8, x, 0.04, 0.0));
+        let along_longitude = along(&|x| lenticular_cloud(x + 0.8, 0.6, 0.04, 0.0));
+        assert!(across_latitudes > 0.5, "{}", across_latitudes);
+        assert!(across_latitudes > along_longitude * 3.0, "{} vs {}", across_latitudes, along_longitude);
+    }
+
+    #[test]
+    fn altitude_palette_interpolates_linearly_between_stops() {
+        for palette in [&MARS_ALTITUDE_PALETTE, &MERCURY_ALTITUDE_PALETTE] {
+            for &(height, color) in palette.iter() {
+                assert!((terrain_palette_color(height, palette) - color).length() < 1e-5);
+            }
+            assert_eq!(terrain_palette_color(0.0, palette), palette[0].1);
+            assert_eq!(terrain_palette_color(1.0, palette), palette[3].1);
+            // Dentro de cada tramo cada canal avanza en línea recta y sin volver atrás
+            for pair in palette.windows(2) {
+                let ((h0, c0), (h1, c1)) = (pair[0], pair[1]);
+                let samples: Vec<Vector3> = (0..=10).map(|i| terrain_palette_color(h0 + (h1 - h0) * i as f32 / 10.0, palette)).collect();
+                for (i, sample) in samples.iter().enumerate() {
+                    let t = i as f32 / 10.0;
+                    assert!((*sample - (c0 * (1.0 - t) + c1 * t)).length() < 1e-5);
+                }
+                for w in samples.windows(2) {
+                    for (a, b, end_a, end_b) in [(w[0].x, w[1].x, c0.x, c1.x), (w[0].y, w[1].y, c0.y, c1.y), (w[0].z, w[1].z, c0.z, c1.z)] {
+                        assert!((b - a) * (end_b - end_a) >= -1e-6);
+                    }
+                }
+            }
+        }
+        // Mercurio es una rampa de grises: más alto, más claro
+        let brightness = |h: f32| { let c = terrain_palette_color(h, &MERCURY_ALTITUDE_PALETTE); c.x + c.y + c.z };
+        assert!((0..100).all(|i| brightness((i + 1) as f32 / 100.0) >= brightness(i as f32 / 100.0)));
+        // Con factor 0 la altitud no cambia el color
+        let rock = Vector3::new(0.5, 0.3, 0.2);
+        assert_eq!(apply_altitude_shading(rock, Vector3::new(0.3, 0.8, 0.1), &MARS_ALTITUDE_PALETTE, 0.0), rock);
+    }
+
+    #[test]
+    fn flat_height_field_keeps_the_sphere_normal() {
+        fn flat(_: Vector3) -> f32 {
+            0.7
+        }
+        for i in 0..50 {
+            let (a, b) = (i as f32 * 0.61, i as f32 * 1.37);
+            let pos = Vector3::new(a.cos() * b.sin(), b.cos(), a.sin() * b.sin()) * 1.3;
+            let normal = compute_displaced_normal(pos, flat, 0.04, DISPLACEMENT_NORMAL_EPSILON);
+            assert!((normal - normalize_vec3(pos)).length() < 1e-3, "{:?} en {:?}", normal, pos);
+        }
+        // Con relieve de verdad la normal se inclina
+        let bumpy = compute_displaced_normal(Vector3::new(0.3, 0.8, 0.52), compute_terrain_height, 0.04, DISPLACEMENT_NORMAL_EPSILON);
+        assert!((bumpy - normalize_vec3(Vector3::new(0.3, 0.8, 0.52))).length() > 1e-3);
+    }
+
+    #[test]
+    fn rings_on_the_anti_sun_half_are_more_shadowed() {
+        let normal = saturn_ring_normal();
+        let outward = normalize_vec3(normal.cross(Vector3::new(0.0, 0.0, 1.0)));
+        let elevation = 25f32.to_radians();
+        let sun_dir = outward * elevation.cos() + normal * elevation.sin();
+        let shadow_at = |r: f32, side: f32| ring_self_shadow(outward * (r * side), Vector3::zero(), normal, sun_dir, SATURN_C_RING_INNER, SATURN_A_RING_OUTER, 1.0);
+
+        // Justo por fuera del anillo B: del lado contrario al Sol el rayo sale sobre el anillo B
+        assert!(shadow_at(1.97, -1.0) < shadow_at(1.97, 1.0) * 0.5);
+        // De la división de Cassini hacia fuera, la mitad opuesta al Sol recibe más sombra en conjunto
+        let mean = |side: f32| (0..100).map(|i| shadow_at(SATURN_CASSINI_INNER + (SATURN_A_RING_OUTER - SATURN_CASSINI_INNER) * i as f32 / 100.0, side)).sum::<f32>() / 100.0;
+        assert!(mean(-1.0) < mean(1.0), "{} vs {}", mean(-1.0), mean(1.0));
+        // Con el Sol en el plano de los anillos no hay autosombra
+        assert_eq!(ring_self_shadow(outward * -1.97, Vector3::zero(), normal, outward, SATURN_C_RING_INNER, SATURN_A_RING_OUTER, 1.0), 1.0);
+    }
+
+    #[test]
+    fn uranus_rings_are_dark_except_epsilon() {
+        for (i, band) in URANUS_RINGS.iter().enumerate() {
+            let ring = crate::mesh::generate_ring_system(std::slice::from_ref(band), URANUS_RADIUS_KM, URANUS_RING_MIN_WIDTH, 32, URANUS_AXIAL_TILT);
+            let vertex = &ring[0];
+            let normal = normalize_vec3(vertex.normal);
+            // Sol de cara al anillo y lejos: el color es su albedo
+            let mut uniforms = Uniforms::for_tests(&GRID);
+            uniforms.lights = vec![Light::new(vertex.position + normal * 1000.0)];
+            let fragment = Fragment::new(0.0, 0.0, Vector3::one(), 0.5, vertex.position, normal, vertex.color);
+            let color = uranus_ring_fragment_shader(&fragment, &uniforms);
+            let brightest = color.x.max(color.y).max(color.z);
+            // El ε es el undécimo de la tabla, de dentro hacia fuera
+            if i == 10 {
+                assert!(brightest > 0.1, "ε: {}", brightest);
+            } else {
+                assert!(brightest < 0.08, "anillo {}: {}", i, brightest);
+            }
+        }
+    }
+
+    #[test]
+    fn ocean_world_glints_where_the_wave_normal_mirrors_the_sun() {
+        let up = Vector3::new(0.0, 0.0, 1.0);
+        let mut uniforms = Uniforms::for_tests(&GRID);
+        let (perturbation, _) = ocean_world_waves(up, uniforms.time);
+        let wave_normal = normalize_vec3(up + perturbation);
+        // Sol y cámara sobre la normal de la ola: la vista es justo el reflejo del Sol
+        uniforms.lights = vec![Light::new(up + wave_normal * 1000.0)];
+        uniforms.camera_pos = up + wave_normal * 50.0;
+        let fragment = fragment_at(up, up);
+        let aligned = ocean_world_fragment_shader(&fragment, &uniforms);
+        // Misma luz con la cámara a 60° del reflejo
+        let sideways = normalize_vec3(wave_normal.cross(Vector3::new(1.0, 0.0, 0.0)));
+        uniforms.camera_pos = up + (wave_normal * 0.5 + sideways * 0.75_f32.sqrt()) * 50.0;
+        let away = ocean_world_fragment_shader(&fragment, &uniforms);
+        assert!(aligned.x - away.x > 0.1, "reflejo {:?} frente a {:?}", aligned, away);
+    }
+
+    #[test]
+    fn heart_sdf_is_negative_at_its_center_and_positive_thirty_degrees_north() {
+        for (lat, lon) in [(PLUTO_HEART_LAT, PLUTO_HEART_LON), (0.0, 0.0), (-0.4, 3.0)] {
+            assert!(heart_sdf(lat, lon, lat, lon) < 0.0);
+            assert!(heart_sdf(lat + 30f32.to_radians(), lon, lat, lon) > 0.0);
+        }
+        // Las longitudes dan la vuelta: una vuelta completa más sigue siendo el centro
+        let tau = 2.0 * std::f32::consts::PI;
+        assert!((heart_sdf(0.2, 3.0 + tau, 0.2, 3.0) - heart_sdf(0.2, 3.0, 0.2, 3.0)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn sunspots_center_on_fifteen_degrees_at_a_quarter_cycle() {
+        let period = 400.0;
+        let time = period / 4.0;
+        let (band_min, band_max) = sunspot_latitude_band(time, period);
+        assert!(((band_min + band_max) * 0.5 - 15f32.to_radians()).abs() < 1e-4);
+        // Latitud media (ponderada por el oscurecimiento) de las manchas de cada hemisferio
+        let (mut north, mut north_weight, mut south, mut south_weight) = (0.0, 0.0, 0.0, 0.0);
+        for i in 0..180 {
+            let theta = (i as f32 + 0.5 - 90.0).to_radians();
+            for j in 0..720 {
+                let spot = sunspot_pattern(theta, (j as f32 * 0.5).to_radians(), time, period);
+                assert!(spot == 0.0 || theta.abs() <= band_max, "mancha a {}°", theta.to_degrees());
+                if theta > 0.0 {
+                    (north, north_weight) = (north + theta * spot, north_weight + spot);
+                } else {
+                    (south, south_weight) = (south + theta * spot, south_weight + spot);
+                }
+            }
+        }
+        assert!(north_weight > 0.0 && south_weight > 0.0);
+        let (north, south) = ((north / north_weight).to_degrees(), (south / south_weight).to_degrees());
+        assert!((north - 15.0).abs() < 3.0, "norte: {}°", north);
+        assert!((south + 15.0).abs() < 3.0, "sur: {}°", south);
+    }
+
+    #[test]
+    fn kelvin_helmholtz_waves_repeat_every_two_pi_over_k() {
+        for band_velocity in [0.56, -0.8, 1.2] {
+            let k = kelvin_helmholtz_wavenumber(band_velocity);
+            let period = std::f32::consts::TAU / k;
+            for i in 0..40 {
+                let (latitude, longitude, time) = (-1.2 + i as f32 * 0.06, -3.0 + i as f32 * 0.13, i as f32 * 0.7);
+                let here = kelvin_helmholtz_instability(latitude, longitude, band_velocity, time);
+                let next = kelvin_helmholtz_instability(latitude, longitude + period, band_velocity, time);
+                assert!((here - next).length() < 1e-4, "v = {}: {:?} frente a {:?}", band_velocity, here, next);
+                // Media longitud de onda después el desplazamiento cambia de signo
+                let half = kelvin_helmholtz_instability(latitude, longitude + period * 0.5, band_velocity, time);
+                assert!((here.x + half.x).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn gerstner_waves_are_periodic_with_crests_where_expected() {
+        // Un solo tren a favor del viento: periodo 2π/k en el espacio y 2π/ω en el tiempo
+        let (dir, k) = (normalize_vec3(BIOLUM_OCEAN_WIND_DIR), 24.0);
+        let train = [(dir, k, 0.6)];
+        let omega = (BIOLUM_OCEAN_GRAVITY * k).sqrt();
+        let height = |p: Vector3, time: f32| gerstner_wave_sum(&train, p, time, BIOLUM_OCEAN_WIND_SPEED).0;
+        let wavelength = std::f32::consts::TAU / k;
+        for i in 0..20 {
+            let p = Vector3::new(0.03 * i as f32, 0.5, -0.02 * i as f32);
+            let t = i as f32 * 0.37;
+            assert!((height(p, t) - height(p + dir * wavelength, t)).abs() < 1e-3);
+            assert!((height(p, t) - height(p, t + std::f32::consts::TAU / omega)).abs() < 1e-3);
+        }
+        // A t = 0, cresta (1) donde k·(D·p) = π/2 + 2πn y valle (-1) media onda después
+        for n in 0..3 {
+            let crest = dir * ((std::f32::consts::FRAC_PI_2 + std::f32::consts::TAU * n as f32) / k);
+            assert!((height(crest, 0.0) - 1.0).abs() < 1e-4, "cresta {}: {}", n, height(crest, 0.0));
+            assert!((height(crest + dir * (wavelength * 0.5), 0.0) + 1.0).abs() < 1e-4);
+        }
+
+        // Con los cuatro trenes la superficie es la misma tras una vuelta completa en longitud
+        for i in 0..10 {
+            let (latitude, longitude) = (-1.2 + i as f32 * 0.25, -2.0 + i as f32 * 0.4);
+            let at = |lon: f32| gerstner_ocean_waves(Vector3::new(latitude.cos() * lon.cos(), latitude.sin(), latitude.cos() * lon.sin()), 3.0, BIOLUM_OCEAN_WIND_SPEED).0;
+            assert!((at(longitude) - at(longitude + std::f32::consts::TAU)).abs() < 1e-3);
+        }
+    }
+}
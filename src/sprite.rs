@@ -0,0 +1,229 @@
+// sprite.rs
+// 🏷️ Sprites del HUD: imágenes RGBA pequeñas (iconos de planetas, avisos, brújula, indicadores)
+// que se copian al framebuffer con su canal alfa y un tinte (`Framebuffer::draw_sprite`). Se leen
+// de un PNG o se generan por código; `SpriteBatch` junta los dibujos del frame y los hace por Z.
+use crate::font::glyph_8x8;
+use crate::framebuffer::Framebuffer;
+use raylib::prelude::{Image, Vector3};
+use std::io;
+
+// Carpeta de la que se leen los sprites del HUD; los que falten se generan por código
+pub const SPRITE_DIR: &str = "./assets/sprites";
+
+pub struct Sprite {
+    pub image_data: Vec<[u8; 4]>, // RGBA por filas
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Sprite {
+    /// Sprite transparente de `width` x `height`.
+    pub fn new(width: u32, height: u32) -> Self {
+        Sprite { image_data: vec![[0, 0, 0, 0]; (width * height) as usize], width, height }
+    }
+
+    /// Lee el sprite (PNG) de `path`.
+    pub fn load(path: &str) -> io::Result<Self> {
+        let image = Image::load_image(path).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, err)))?;
+        let image_data = image.get_image_data().iter().map(|color| [color.r, color.g, color.b, color.a]).collect();
+        Ok(Sprite { image_data, width: image.width() as u32, height: image.height() as u32 })
+    }
+
+    fn set(&mut self, x: i32, y: i32, rgba: [u8; 4]) {
+        if x >= 0 && y >= 0 && (x as u32) < self.width && (y as u32) < self.height {
+            self.image_data[(y as u32 * self.width + x as u32) as usize] = rgba;
+        }
+    }
+
+    // Disco (o anillo, si `inner` > 0) centrado en (cx, cy); el borde exterior se suaviza en un texel
+    fn fill_disc(&mut self, cx: f32, cy: f32, inner: f32, outer: f32, color: Vector3) {
+        for y in 0..self.height as i32 {
+            for x in 0..self.width as i32 {
+                let d = ((x as f32 + 0.5 - cx).powi(2) + (y as f32 + 0.5 - cy).powi(2)).sqrt();
+                let coverage = (outer - d + 0.5).clamp(0.0, 1.0) * (d - inner + 0.5).clamp(0.0, 1.0);
+                if coverage > 0.0 {
+                    self.set(x, y, rgba(color, coverage));
+                }
+            }
+        }
+    }
+
+    // Glifo de la fuente del HUD con su esquina en (x0, y0), cada texel de la fuente `scale` x `scale`
+    fn stamp_glyph(&mut self, c: char, x0: i32, y0: i32, scale: i32, color: Vector3) {
+        for (row, bits) in glyph_8x8(c).iter().enumerate() {
+            for col in 0..8 {
+                if bits & (0x80 >> col) == 0 {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        self.set(x0 + col * scale + dx, y0 + row as i32 * scale + dy, rgba(color, 1.0));
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn rgba(color: Vector3, alpha: f32) -> [u8; 4] {
+    let to_byte = |v: f32| (v.clamp(0.0, 1.0) * 255.0) as u8;
+    [to_byte(color.x), to_byte(color.y), to_byte(color.z), to_byte(alpha)]
+}
+
+/// Icono de planeta: disco del color del cuerpo con un anillo de órbita alrededor y la letra
+/// de su tipo encima.
+pub fn planet_icon(color: Vector3, symbol: char) -> Sprite {
+    let mut sprite = Sprite::new(16, 16);
+    sprite.fill_disc(8.0, 8.0, 6.5, 7.5, Vector3::new(0.8, 0.85, 0.9));
+    sprite.fill_disc(8.0, 8.0, 0.0, 5.5, color);
+    // Letra oscura o clara según lo claro que sea el disco
+    let luminance = 0.3 * color.x + 0.59 * color.y + 0.11 * color.z;
+    let ink = if luminance > 0.5 { Vector3::new(0.05, 0.05, 0.1) } else { Vector3::new(1.0, 1.0, 1.0) };
+    sprite.stamp_glyph(symbol, 4, 4, 1, ink);
+    sprite
+}
+
+/// Triángulo de aviso con una exclamación (blanco: se tiñe al dibujarlo).
+pub fn warning_icon() -> Sprite {
+    let mut sprite = Sprite::new(16, 16);
+    for y in 1..15 {
+        // El triángulo se ensancha medio texel por fila desde el vértice superior
+        let half = (y as f32 - 1.0) * 0.55;
+        for x in 0..16 {
+            let d = (x as f32 + 0.5 - 8.0).abs();
+            let coverage = (half - d + 0.5).clamp(0.0, 1.0);
+            if coverage > 0.0 {
+                sprite.set(x, y, rgba(Vector3::new(1.0, 1.0, 1.0), coverage));
+            }
+        }
+    }
+    sprite.stamp_glyph('!', 4, 6, 1, Vector3::new(0.0, 0.0, 0.0));
+    sprite
+}
+
+/// Rosa de los vientos: círculo con las cuatro puntas y la N arriba.
+pub fn compass_rose() -> Sprite {
+    let mut sprite = Sprite::new(32, 32);
+    sprite.fill_disc(16.0, 16.0, 10.0, 11.0, Vector3::new(0.7, 0.75, 0.8));
+    for (dx, dy) in [(0_i32, -1_i32), (1, 0), (0, 1), (-1, 0)] {
+        for step in 3..10 {
+            // Puntas que se afinan hacia el anillo
+            let width = (10 - step) / 3;
+            for w in -width..=width {
+                let (x, y) = (16 + dx * step + dy.abs() * w, 16 + dy * step + dx.abs() * w);
+                sprite.set(x, y, rgba(Vector3::new(0.9, 0.9, 0.95), 1.0));
+            }
+        }
+    }
+    sprite.stamp_glyph('N', 12, 0, 1, Vector3::new(1.0, 0.4, 0.3));
+    sprite
+}
+
+/// Celda de la barra de carga del warp (blanca: se tiñe encendida o apagada).
+pub fn warp_charge_cell() -> Sprite {
+    let mut sprite = Sprite::new(6, 12);
+    for y in 0..12 {
+        for x in 0..6 {
+            // Esquinas recortadas
+            let corner = (x == 0 || x == 5) && (y == 0 || y == 11);
+            if !corner {
+                sprite.set(x, y, rgba(Vector3::new(1.0, 1.0, 1.0), 1.0));
+            }
+        }
+    }
+    sprite
+}
+
+/// Doble chevrón del impulso de la nave.
+pub fn boost_icon() -> Sprite {
+    let mut sprite = Sprite::new(16, 16);
+    for offset in [2, 8] {
+        for i in 0..6 {
+            for thickness in 0..2 {
+                let x = offset + i + thickness;
+                sprite.set(x, 2 + i, rgba(Vector3::new(1.0, 1.0, 1.0), 1.0));
+                sprite.set(x, 13 - i, rgba(Vector3::new(1.0, 1.0, 1.0), 1.0));
+            }
+        }
+    }
+    sprite
+}
+
+/// Cruz hueca de los puntos de Lagrange (blanca: se tiñe al dibujarla).
+pub fn lagrange_marker() -> Sprite {
+    let mut sprite = Sprite::new(16, 16);
+    for i in (1..6).chain(10..15) {
+        for thickness in 7..9 {
+            sprite.set(i, thickness, rgba(Vector3::new(1.0, 1.0, 1.0), 1.0));
+            sprite.set(thickness, i, rgba(Vector3::new(1.0, 1.0, 1.0), 1.0));
+        }
+    }
+    sprite
+}
+
+// Sprites fijos del HUD
+pub struct HudSprites {
+    pub warning: Sprite,
+    pub compass: Sprite,
+    pub warp_cell: Sprite,
+    pub boost: Sprite,
+    pub lagrange: Sprite,
+}
+
+impl HudSprites {
+    /// Lee cada sprite de `dir` (warning.png, compass.png, warp_cell.png, boost.png,
+    /// lagrange.png); los que no estén o no se puedan leer se generan por código.
+    pub fn load(dir: &str) -> Self {
+        let load_or = |name: &str, fallback: fn() -> Sprite| {
+            let path = format!("{}/{}", dir, name);
+            if std::path::Path::new(&path).exists() {
+                match Sprite::load(&path) {
+                    Ok(sprite) => return sprite,
+                    Err(e) => eprintln!("Warning: could not load sprite {}: {}", path, e),
+                }
+            }
+            fallback()
+        };
+        HudSprites {
+            warning: load_or("warning.png", warning_icon),
+            compass: load_or("compass.png", compass_rose),
+            warp_cell: load_or("warp_cell.png", warp_charge_cell),
+            boost: load_or("boost.png", boost_icon),
+            lagrange: load_or("lagrange.png", lagrange_marker),
+        }
+    }
+}
+
+// Un `draw_sprite` pendiente
+pub struct SpriteDraw<'a> {
+    pub z: i32,
+    pub x: i32,
+    pub y: i32,
+    pub sprite: &'a Sprite,
+    pub scale: f32,
+    pub tint: Vector3,
+}
+
+#[derive(Default)]
+pub struct SpriteBatch<'a> {
+    pub draws: Vec<SpriteDraw<'a>>,
+}
+
+impl<'a> SpriteBatch<'a> {
+    pub fn new() -> Self {
+        SpriteBatch { draws: Vec::new() }
+    }
+
+    pub fn push(&mut self, z: i32, x: i32, y: i32, sprite: &'a Sprite, scale: f32, tint: Vector3) {
+        self.draws.push(SpriteDraw { z, x, y, sprite, scale, tint });
+    }
+
+    /// Dibuja todo de menor a mayor Z (lo de mayor Z queda encima); con la misma Z, en orden
+    /// de llegada.
+    pub fn flush(mut self, framebuffer: &mut Framebuffer) {
+        self.draws.sort_by_key(|draw| draw.z);
+        for draw in &self.draws {
+            framebuffer.draw_sprite(draw.x, draw.y, draw.sprite, draw.scale, draw.tint);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+// texture.rs
+// 🖼️ Atlas de texturas de los planetas: una imagen con ATLAS_GRID x ATLAS_GRID casillas (una por
+// cuerpo) que un artista puede pintar para retocar el aspecto de un planeta por encima de su
+// shader procedural. Si no hay atlas, los shaders quedan como están.
+use raylib::prelude::{Image, Vector3};
+use std::io;
+
+pub const ATLAS_PATH: &str = "./assets/planet_atlas.png";
+// 4x4 casillas de 256x256: hasta 16 cuerpos
+pub const ATLAS_GRID: u32 = 4;
+pub const ATLAS_TILE_SIZE: u32 = 256;
+
+pub struct TextureAtlas {
+    pub width: u32,
+    pub height: u32,
+    // Casillas por lado; cada una ocupa width / grid x height / grid texels
+    pub grid: u32,
+    pub pixels: Vec<[u8; 4]>, // RGBA por filas
+}
+
+impl TextureAtlas {
+    pub fn from_pixels(width: u32, height: u32, grid: u32, pixels: Vec<[u8; 4]>) -> Self {
+        assert_eq!(pixels.len(), (width * height) as usize, "el atlas necesita width * height texels");
+        TextureAtlas { width, height, grid: grid.max(1), pixels }
+    }
+
+    /// Lee el atlas (PNG) de `path`; tiene que medir ATLAS_GRID * ATLAS_TILE_SIZE de lado.
+    pub fn load(path: &str) -> io::Result<Self> {
+        let image = Image::load_image(path).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, err)))?;
+        let side = (ATLAS_GRID * ATLAS_TILE_SIZE) as i32;
+        if image.width() != side || image.height() != side {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: expected {}x{} pixels, found {}x{}", path, side, side, image.width(), image.height()),
+            ));
+        }
+        let pixels = image.get_image_data().iter().map(|color| [color.r, color.g, color.b, color.a]).collect();
+        Ok(Self::from_pixels(side as u32, side as u32, ATLAS_GRID, pixels))
+    }
+
+    fn texel(&self, x: u32, y: u32) -> Vector3 {
+        let [r, g, b, _] = self.pixels[(y * self.width + x) as usize];
+        Vector3::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0)
+    }
+}
+
+/// Color (0-1) de la casilla `tile_index` (por filas) en (`u`, `v`) ∈ [0, 1]², interpolando los
+/// cuatro texels más cercanos. Los centros de texel están en medios enteros y no se lee fuera de
+/// la casilla, así no se cuela el borde de la vecina.
+pub fn sample_bilinear(atlas: &TextureAtlas, u: f32, v: f32, tile_index: u32) -> Vector3 {
+    let tile_width = atlas.width / atlas.grid;
+    let tile_height = atlas.height / atlas.grid;
+    let tile = tile_index % (atlas.grid * atlas.grid);
+    let (origin_x, origin_y) = ((tile % atlas.grid) * tile_width, (tile / atlas.grid) * tile_height);
+
+    let x = (u.clamp(0.0, 1.0) * tile_width as f32 - 0.5).clamp(0.0, (tile_width - 1) as f32);
+    let y = (v.clamp(0.0, 1.0) * tile_height as f32 - 0.5).clamp(0.0, (tile_height - 1) as f32);
+    let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+    let (x1, y1) = ((x0 + 1).min(tile_width - 1), (y0 + 1).min(tile_height - 1));
+    let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+
+    let at = |tx: u32, ty: u32| atlas.texel(origin_x + tx, origin_y + ty);
+    let top = at(x0, y0) * (1.0 - fx) + at(x1, y0) * fx;
+    let bottom = at(x0, y1) * (1.0 - fx) + at(x1, y1) * fx;
+    top * (1.0 - fy) + bottom * fy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bilinear_center_of_a_two_by_two_texture_is_the_average() {
+        let pixels = vec![[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255], [255, 255, 255, 255]];
+        let atlas = TextureAtlas::from_pixels(2, 2, 1, pixels);
+        let center = sample_bilinear(&atlas, 0.5, 0.5, 0);
+        assert!((center - Vector3::new(0.5, 0.5, 0.5)).length() < 1e-6, "{:?}", center);
+        // En el centro de un texel se lee el texel tal cual
+        assert!((sample_bilinear(&atlas, 0.25, 0.25, 0) - Vector3::new(1.0, 0.0, 0.0)).length() < 1e-6);
+        assert!((sample_bilinear(&atlas, 0.75, 0.75, 0) - Vector3::one()).length() < 1e-6);
+    }
+}
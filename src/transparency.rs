@@ -0,0 +1,71 @@
+// transparency.rs
+// 🔮 Pasada de translúcidos: los fragmentos con alfa (onda de la nova, nebulosa, órbitas, estelas)
+// no escriben profundidad. Se juntan durante la vista y se componen de atrás hacia delante
+// cuando ya está todo lo opaco, probando contra su profundidad.
+use crate::fragment::Fragment;
+use crate::framebuffer::Framebuffer;
+use crate::line::wu_line;
+use raylib::math::Vector3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlendMode {
+    Alpha,    // mezcla clásica: color * alfa + destino * (1 - alfa)
+    Additive, // suma color * alfa al destino (gas emisivo)
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TransparentItem {
+    pub depth: f32,
+    pub x: i32,
+    pub y: i32,
+    pub color: Vector3,
+    pub alpha: f32,
+    pub blend: BlendMode,
+}
+
+impl TransparentItem {
+    /// Elemento en la posición y profundidad del fragmento, aún sin color (blanco opaco). El
+    /// túnel del warp se suma a lo que hay detrás; el resto de `planet_type` se mezcla con alfa.
+    pub fn from_fragment(fragment: &Fragment, planet_type: &str) -> Self {
+        let blend = match planet_type {
+            "WarpTunnel" => BlendMode::Additive,
+            _ => BlendMode::Alpha,
+        };
+        TransparentItem {
+            depth: fragment.depth,
+            x: fragment.position.x.round() as i32,
+            y: fragment.position.y.round() as i32,
+            color: Vector3::one(),
+            alpha: 1.0,
+            blend,
+        }
+    }
+
+    pub fn with_color(mut self, color: Vector3, alpha: f32) -> Self {
+        self.color = color;
+        self.alpha = alpha;
+        self
+    }
+}
+
+/// Línea de (x0, y0, z0) a (x1, y1, z1) como elementos translúcidos, interpolando la profundidad
+/// a lo largo del segmento (así una órbita queda tapada solo donde de verdad pasa por detrás).
+/// Antialiasada con `wu_line`: la cobertura de cada píxel multiplica `alpha`.
+pub fn push_line(items: &mut Vec<TransparentItem>, (x0, y0, z0): (f32, f32, f32), (x1, y1, z1): (f32, f32, f32), color: Vector3, alpha: f32) {
+    wu_line(x0, y0, x1, y1, |x, y, coverage, t| {
+        let depth = z0 + (z1 - z0) * t;
+        items.push(TransparentItem { depth, x, y, color, alpha: alpha * coverage, blend: BlendMode::Alpha });
+    });
+}
+
+/// Ordena de atrás hacia delante (mayor profundidad primero) y compone sobre el framebuffer.
+/// Vacía `items` para reutilizar el vector en la siguiente vista.
+pub fn composite_transparent(framebuffer: &mut Framebuffer, items: &mut Vec<TransparentItem>) {
+    items.sort_unstable_by(|a, b| b.depth.total_cmp(&a.depth));
+    for item in items.drain(..) {
+        match item.blend {
+            BlendMode::Alpha => framebuffer.blend_point(item.x, item.y, item.color, item.alpha, item.depth),
+            BlendMode::Additive => framebuffer.add_point(item.x, item.y, item.color * item.alpha, item.depth),
+        }
+    }
+}
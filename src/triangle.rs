@@ -25,7 +25,11 @@ fn barycentric_coordinates(p_x: f32, p_y: f32, a: &Vertex, b: &Vertex, c: &Verte
     (w1, w2, w3)
 }
 
-pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex, light: &Light) -> Vec<Fragment> {
+/// Fragmentos del triángulo dentro de la pantalla de `viewport` (ancho, alto) píxeles. Con MSAA
+/// el píxel se emite si alguna de las muestras (desplazamientos respecto al centro en
+/// `sample_offsets`) cae dentro, y `coverage` marca cuáles; los atributos se siguen interpolando
+/// en el centro. Sin muestras se usa solo el centro.
+pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex, light: &Light, sample_offsets: &[(f32, f32)], viewport: (i32, i32)) -> Vec<Fragment> {
     let mut fragments = Vec::new();
     
 /*     //rgb colors demo
@@ -35,10 +39,12 @@ pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex, light: &Light) -> Vec<Fra
 
     let base_color = Vector3::new(0.5, 0.5, 0.5);
 
-    let min_x = v1.transformed_position.x.min(v2.transformed_position.x).min(v3.transformed_position.x).floor() as i32;
-    let max_x = v1.transformed_position.x.max(v2.transformed_position.x).max(v3.transformed_position.x).ceil() as i32;
-    let min_y = v1.transformed_position.y.min(v2.transformed_position.y).min(v3.transformed_position.y).floor() as i32;
-    let max_y = v1.transformed_position.y.max(v2.transformed_position.y).max(v3.transformed_position.y).ceil() as i32;
+    // Caja envolvente recortada a la pantalla: un triángulo enorme (muy cerca de la cámara) no
+    // recorre píxeles que nunca se van a dibujar
+    let min_x = (v1.transformed_position.x.min(v2.transformed_position.x).min(v3.transformed_position.x).floor() as i32).max(0);
+    let max_x = (v1.transformed_position.x.max(v2.transformed_position.x).max(v3.transformed_position.x).ceil() as i32).min(viewport.0 - 1);
+    let min_y = (v1.transformed_position.y.min(v2.transformed_position.y).min(v3.transformed_position.y).floor() as i32).max(0);
+    let max_y = (v1.transformed_position.y.max(v2.transformed_position.y).max(v3.transformed_position.y).ceil() as i32).min(viewport.1 - 1);
 
     for y in min_y..=max_y {
         for x in min_x..=max_x {
@@ -48,7 +54,16 @@ pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex, light: &Light) -> Vec<Fra
             // Calculate barycentric coordinates
             let (w1, w2, w3) = barycentric_coordinates(p_x, p_y, v1, v2, v3);
 
-            if w1 >= 0.0 && w2 >= 0.0 && w3 >= 0.0 {
+            let coverage = if sample_offsets.is_empty() {
+                if w1 >= 0.0 && w2 >= 0.0 && w3 >= 0.0 { u16::MAX } else { 0 }
+            } else {
+                sample_offsets.iter().enumerate().fold(0_u16, |mask, (i, (dx, dy))| {
+                    let (s1, s2, s3) = barycentric_coordinates(p_x + dx, p_y + dy, v1, v2, v3);
+                    if s1 >= 0.0 && s2 >= 0.0 && s3 >= 0.0 { mask | (1 << i) } else { mask }
+                })
+            };
+
+            if coverage != 0 {
 
 /*                 //Interpolate rgb demo
                 let interpolated_color = Vector3::new(
@@ -105,10 +120,19 @@ pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex, light: &Light) -> Vec<Fra
                     base_color.z * intensity,
                 );
 
+                // Interpolate vertex colors (e.g. baked ambient occlusion)
+                let vertex_color = Vector3::new(
+                    w1 * v1.color.x + w2 * v2.color.x + w3 * v3.color.x,
+                    w1 * v1.color.y + w2 * v2.color.y + w3 * v3.color.y,
+                    w1 * v1.color.z + w2 * v2.color.z + w3 * v3.color.z,
+                );
+
                 // Interpolate depth using barycentric coordinates
                 let depth = w1 * v1.transformed_position.z + w2 * v2.transformed_position.z + w3 * v3.transformed_position.z;
 
-                fragments.push(Fragment::new(p_x, p_y, shaded_color, depth, world_pos));
+                let mut fragment = Fragment::new(p_x, p_y, shaded_color, depth, world_pos, normalized_normal, vertex_color);
+                fragment.coverage = coverage;
+                fragments.push(fragment);
             }
         }
     }